@@ -0,0 +1,106 @@
+//! Procedural macros for AptosBB, re-exported from the `aptosbb` crate
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Expr, ExprArray, ItemFn, Lit, LitInt, LitStr};
+
+/// Fork configuration given as `#[aptosbb::test(...)]` arguments
+#[derive(Default)]
+struct TestArgs {
+    network: Option<LitStr>,
+    version: Option<LitInt>,
+    targets: Vec<LitStr>,
+    cache: Option<LitStr>,
+}
+
+fn string_literals(array: ExprArray) -> syn::Result<Vec<LitStr>> {
+    array
+        .elems
+        .into_iter()
+        .map(|elem| match elem {
+            Expr::Lit(lit) => match lit.lit {
+                Lit::Str(s) => Ok(s),
+                other => Err(syn::Error::new_spanned(other, "expected a string literal")),
+            },
+            other => Err(syn::Error::new_spanned(other, "expected a string literal")),
+        })
+        .collect()
+}
+
+/// Run a test against a forked network, injecting `&mut AptosBB`
+///
+/// ```ignore
+/// #[aptosbb::test(network = "mainnet", version = 2_000_000_000, targets = ["0xabc"], cache = ".aptosbb-cache")]
+/// fn drains_vault(bb: &mut AptosBB) -> anyhow::Result<()> {
+///     let attacker = bb.new_account();
+///     /* ... */
+///     Ok(())
+/// }
+/// ```
+///
+/// All arguments are optional: `network` defaults to mainnet and `version` to the latest
+/// one. Tests with the same arguments share one fork per test binary; each runs on a
+/// snapshot of it that is reverted afterwards, so tests cannot observe each other.
+#[proc_macro_attribute]
+pub fn test(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut args = TestArgs::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("network") {
+            args.network = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("version") {
+            args.version = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("targets") {
+            args.targets = string_literals(meta.value()?.parse()?)?;
+        } else if meta.path.is_ident("cache") {
+            args.cache = Some(meta.value()?.parse()?);
+        } else {
+            return Err(meta.error("expected `network`, `version`, `targets` or `cache`"));
+        }
+        Ok(())
+    });
+    parse_macro_input!(attr with parser);
+
+    let mut body = parse_macro_input!(item as ItemFn);
+    if body.sig.inputs.len() != 1 {
+        return syn::Error::new_spanned(&body.sig.inputs, "expected a single `&mut AptosBB` argument")
+            .to_compile_error()
+            .into();
+    }
+
+    let attrs = std::mem::take(&mut body.attrs);
+    let vis = body.vis.clone();
+    let name = body.sig.ident.clone();
+    let output = body.sig.output.clone();
+    body.sig.ident = format_ident!("__aptosbb_{}", name);
+    let inner = &body.sig.ident;
+
+    let network = args.network.map(|n| n.value()).unwrap_or_else(|| "mainnet".to_string());
+    let version = match args.version {
+        Some(version) => quote!(::core::option::Option::Some(#version)),
+        None => quote!(::core::option::Option::None),
+    };
+    let targets = &args.targets;
+    let cache = match args.cache {
+        Some(cache) => quote!(::core::option::Option::Some(#cache)),
+        None => quote!(::core::option::Option::None),
+    };
+
+    quote! {
+        #[::core::prelude::v1::test]
+        #(#attrs)*
+        #vis fn #name() #output {
+            #body
+
+            ::aptosbb::testing::with_fork(
+                &::aptosbb::testing::ForkSpec {
+                    network: #network,
+                    version: #version,
+                    targets: &[#(#targets),*],
+                    cache_dir: #cache,
+                },
+                #inner,
+            )
+        }
+    }
+    .into()
+}
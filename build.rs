@@ -0,0 +1,15 @@
+use std::process::Command;
+
+fn main() {
+    // Commit this build comes from, pinned by the projects `aptosbb init` generates
+    let rev = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok());
+    if let Some(rev) = rev {
+        println!("cargo:rustc-env=APTOSBB_GIT_REV={}", rev.trim());
+    }
+    println!("cargo:rerun-if-changed=.git/HEAD");
+}
@@ -0,0 +1,38 @@
+/* C interface of aptosbb, built with the `ffi` feature. See src/ffi.rs. */
+#ifndef APTOSBB_H
+#define APTOSBB_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct AptosBBHandle AptosBBHandle;
+
+/* Fork `network` ("mainnet", "testnet", "devnet" or a REST URL; NULL for mainnet) at
+ * `version` (0 for the latest). `api_key` may be NULL. Returns NULL on failure. */
+AptosBBHandle *aptosbb_fork(const char *network, uint64_t version, const char *api_key);
+
+void aptosbb_free(AptosBBHandle *handle);
+
+/* Run `function` ("0xabc::pool::swap") from `sender`, impersonated on the fork. Type and
+ * value arguments are JSON arrays (or NULL). Returns a JSON object with status, success,
+ * gas_used and events, or NULL on failure. */
+char *aptosbb_call(AptosBBHandle *handle, const char *sender, const char *function,
+                   const char *ty_args_json, const char *args_json);
+
+/* A resource as JSON ("null" if absent), or NULL on failure. */
+char *aptosbb_read_resource_json(AptosBBHandle *handle, const char *address, const char *resource_type);
+
+/* Message of the last failure on this thread, owned by the library. */
+const char *aptosbb_last_error(void);
+
+/* Release a string returned by aptosbb_call or aptosbb_read_resource_json. */
+void aptosbb_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* APTOSBB_H */
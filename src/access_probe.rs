@@ -0,0 +1,194 @@
+//! "Who can call this": try one call from a matrix of caller identities
+
+use crate::{
+    entry::parse_function_id,
+    export::status_name,
+    helpers::funding::apt,
+    state_keys::resource_account_address,
+    findings::{Category, Finding, Severity},
+    AptosBB,
+};
+use anyhow::Result;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{TransactionPayload, TransactionStatus},
+};
+use move_core_types::language_storage::TypeTag;
+use serde_json::Value;
+use std::fmt;
+
+/// Argument template placeholder replaced by the caller's address
+pub const CALLER_PLACEHOLDER: &str = "$caller";
+
+/// APT (in octas) impersonated callers are topped up to for gas
+const PROBE_GAS_BALANCE: u64 = 10_000_000_000;
+
+/// Seed of the resource account probed by default
+const PROBE_RESOURCE_ACCOUNT_SEED: &[u8] = b"aptosbb-access-probe";
+
+/// Who a probe call is sent from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CallerIdentity {
+    /// A new account with nothing but APT
+    Fresh,
+    /// An account holding the protocol's assets or positions, but no admin role
+    Holder(AccountAddress),
+    /// The account the function is supposed to be restricted to
+    Admin(AccountAddress),
+    /// A resource account, whose signer contracts obtain through a signer capability
+    ResourceAccount(AccountAddress),
+}
+
+impl CallerIdentity {
+    /// Whether the function should be callable by this identity without an access control bug
+    pub fn is_privileged(&self) -> bool {
+        matches!(self, Self::Admin(_))
+    }
+}
+
+impl fmt::Display for CallerIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fresh => write!(f, "fresh account"),
+            Self::Holder(addr) => write!(f, "holder {}", addr.to_hex_literal()),
+            Self::Admin(addr) => write!(f, "admin {}", addr.to_hex_literal()),
+            Self::ResourceAccount(addr) => write!(f, "resource account {}", addr.to_hex_literal()),
+        }
+    }
+}
+
+/// Outcome of the call from one identity
+#[derive(Clone, Debug)]
+pub struct AccessAttempt {
+    pub caller: CallerIdentity,
+    pub sender: AccountAddress,
+    pub status: TransactionStatus,
+}
+
+impl AccessAttempt {
+    pub fn succeeded(&self) -> bool {
+        self.status.status().is_ok_and(|status| status.is_success())
+    }
+}
+
+/// Which identities could call a function
+#[derive(Clone, Debug)]
+pub struct AccessReport {
+    pub function: String,
+    pub attempts: Vec<AccessAttempt>,
+}
+
+impl AccessReport {
+    /// Non-admin identities the call succeeded for, i.e. likely missing access control
+    pub fn exposed(&self) -> Vec<&AccessAttempt> {
+        self.attempts.iter().filter(|attempt| attempt.succeeded() && !attempt.caller.is_privileged()).collect()
+    }
+
+    /// Whether nobody could make the call, which usually means the argument template is wrong
+    pub fn nobody_succeeded(&self) -> bool {
+        !self.attempts.iter().any(AccessAttempt::succeeded)
+    }
+}
+
+impl fmt::Display for AccessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Access probe of {}:", self.function)?;
+        for attempt in &self.attempts {
+            let mark = match (attempt.succeeded(), attempt.caller.is_privileged()) {
+                (true, false) => "🚨",
+                (true, true) => "✅",
+                (false, _) => "🔒",
+            };
+            writeln!(f, "  {} {}: {}", mark, attempt.caller, status_name(&attempt.status))?;
+        }
+        if self.nobody_succeeded() {
+            write!(f, "no identity could call it; check the argument template")
+        } else {
+            write!(f, "{} non-admin identities can call it", self.exposed().len())
+        }
+    }
+}
+
+/// A fresh account getting through is worse than a holder, who at least needs a position
+impl From<&AccessReport> for Finding {
+    fn from(report: &AccessReport) -> Self {
+        let exposed = report.exposed();
+        let severity = if exposed.iter().any(|attempt| attempt.caller == CallerIdentity::Fresh) {
+            Severity::High
+        } else if !exposed.is_empty() {
+            Severity::Medium
+        } else {
+            Severity::Informational
+        };
+        let mut finding = Finding::new("Function callable without the admin role", severity, Category::AccessControl)
+            .affected(&report.function);
+        for attempt in &report.attempts {
+            finding = finding.evidence(format!("{}: {}", attempt.caller, status_name(&attempt.status)));
+        }
+        finding
+    }
+}
+
+/// Replace every `$caller` string in a template with the caller's address
+fn instantiate(template: &Value, caller: AccountAddress) -> Value {
+    match template {
+        Value::String(s) if s == CALLER_PLACEHOLDER => Value::String(caller.to_hex_literal()),
+        Value::Array(items) => Value::Array(items.iter().map(|item| instantiate(item, caller)).collect()),
+        Value::Object(fields) => {
+            Value::Object(fields.iter().map(|(name, value)| (name.clone(), instantiate(value, caller))).collect())
+        }
+        value => value.clone(),
+    }
+}
+
+impl AptosBB {
+    /// Try an entry function from a fresh account, an attacker-created resource account and
+    /// the module's own address as admin, and report who got through
+    ///
+    /// `arg_template` holds the arguments in the API's JSON representation; `"$caller"` stands
+    /// for the sender's address. Use `probe_access_as` to add holders or a different admin.
+    pub fn probe_access(&mut self, function: &str, ty_args: Vec<TypeTag>, arg_template: &[Value]) -> Result<AccessReport> {
+        let (module_id, _) = parse_function_id(function)?;
+        let creator = AccountAddress::random();
+        let callers = [
+            CallerIdentity::Fresh,
+            CallerIdentity::ResourceAccount(resource_account_address(&creator, PROBE_RESOURCE_ACCOUNT_SEED)),
+            CallerIdentity::Admin(*module_id.address()),
+        ];
+        self.probe_access_as(function, ty_args, arg_template, &callers)
+    }
+
+    /// Try an entry function from each of `callers`, every call on a copy of the current state
+    pub fn probe_access_as(
+        &mut self,
+        function: &str,
+        ty_args: Vec<TypeTag>,
+        arg_template: &[Value],
+        callers: &[CallerIdentity],
+    ) -> Result<AccessReport> {
+        let mut attempts = vec![];
+        for caller in callers {
+            let attempt = self.with_snapshot(|bb| -> Result<AccessAttempt> {
+                let account = match caller {
+                    CallerIdentity::Fresh => bb.new_account(),
+                    CallerIdentity::Holder(addr) | CallerIdentity::Admin(addr) | CallerIdentity::ResourceAccount(addr) => {
+                        let account = bb.impersonate(*addr)?;
+                        if bb.asset_balance(addr, &apt())? < PROBE_GAS_BALANCE {
+                            bb.fund(addr, &apt(), PROBE_GAS_BALANCE)?;
+                        }
+                        account
+                    }
+                };
+                let sender = *account.address();
+                let args: Vec<Value> = arg_template.iter().map(|arg| instantiate(arg, sender)).collect();
+                let entry = bb.encode_entry_function(function, ty_args.clone(), &args)?;
+                let (status, _) = bb.run_transaction_with_output(&account, TransactionPayload::EntryFunction(entry))?;
+                Ok(AccessAttempt { caller: *caller, sender, status })
+            })?;
+            attempts.push(attempt);
+        }
+        let report = AccessReport { function: function.to_string(), attempts };
+        println!("{}", report);
+        Ok(report)
+    }
+}
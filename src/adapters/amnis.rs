@@ -0,0 +1,39 @@
+//! Amnis Finance liquid staking (amAPT / stAPT)
+
+use super::{registry, LiquidStaking};
+use crate::{AptosBB, EntryCall};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{account_address::AccountAddress, transaction::TransactionStatus};
+
+pub struct Amnis {
+    pub address: AccountAddress,
+}
+
+impl Amnis {
+    pub fn new() -> Result<Self> {
+        Ok(Self { address: registry::protocol("amnis")?.address() })
+    }
+
+    fn function(&self, name: &str) -> String {
+        format!("{}::router::{}", self.address.to_hex_literal(), name)
+    }
+}
+
+impl LiquidStaking for Amnis {
+    /// Stake APT and receive stAPT at the caller's address
+    fn stake(&self, bb: &mut AptosBB, account: &Account, amount: u64) -> Result<TransactionStatus> {
+        let call = EntryCall::new(&self.function("deposit_and_stake_entry"))
+            .arg(amount)
+            .arg(*account.address());
+        bb.call(account, call)
+    }
+
+    /// Redeem stAPT for APT at the caller's address
+    fn unstake(&self, bb: &mut AptosBB, account: &Account, amount: u64) -> Result<TransactionStatus> {
+        let call = EntryCall::new(&self.function("unstake_entry"))
+            .arg(amount)
+            .arg(*account.address());
+        bb.call(account, call)
+    }
+}
@@ -0,0 +1,41 @@
+//! Echelon lending markets
+
+use super::{registry, Lending};
+use crate::{AptosBB, EntryCall};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{account_address::AccountAddress, transaction::TransactionStatus};
+use move_core_types::language_storage::TypeTag;
+
+/// Echelon's coin-based markets, addressed by their `Market` object
+pub struct Echelon {
+    pub address: AccountAddress,
+}
+
+impl Echelon {
+    pub fn new() -> Result<Self> {
+        Ok(Self { address: registry::protocol("echelon")?.address() })
+    }
+
+    fn function(&self, name: &str) -> String {
+        format!("{}::scripts::{}", self.address.to_hex_literal(), name)
+    }
+}
+
+impl Lending for Echelon {
+    fn deposit(&self, bb: &mut AptosBB, account: &Account, asset: &TypeTag, market: AccountAddress, amount: u64) -> Result<TransactionStatus> {
+        let call = EntryCall::new(&self.function("supply"))
+            .ty_tag(asset.clone())
+            .arg(market)
+            .arg(amount);
+        bb.call(account, call)
+    }
+
+    fn borrow(&self, bb: &mut AptosBB, account: &Account, asset: &TypeTag, market: AccountAddress, amount: u64) -> Result<TransactionStatus> {
+        let call = EntryCall::new(&self.function("borrow"))
+            .ty_tag(asset.clone())
+            .arg(market)
+            .arg(amount);
+        bb.call(account, call)
+    }
+}
@@ -0,0 +1,53 @@
+//! Typed wrappers around major mainnet protocols
+//!
+//! Adapters turn protocol entry points into calls like
+//! `thala.swap(&mut bb, &attacker, &apt, &usdc, amount, 0)`. Each protocol is looked up
+//! in the `registry`, so its deployment address lives in one place.
+
+pub mod amnis;
+pub mod echelon;
+pub mod registry;
+pub mod thala;
+
+use crate::AptosBB;
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{account_address::AccountAddress, transaction::TransactionStatus};
+use move_core_types::language_storage::TypeTag;
+
+pub use registry::{ProtocolInfo, ProtocolKind};
+
+/// Swaps and liquidity provision on an AMM
+pub trait Dex {
+    fn swap(
+        &self,
+        bb: &mut AptosBB,
+        account: &Account,
+        from: &TypeTag,
+        to: &TypeTag,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<TransactionStatus>;
+
+    fn add_liquidity(
+        &self,
+        bb: &mut AptosBB,
+        account: &Account,
+        amounts: &[u64],
+        min_lp_out: u64,
+    ) -> Result<TransactionStatus>;
+}
+
+/// Supplying and borrowing on a lending market
+pub trait Lending {
+    fn deposit(&self, bb: &mut AptosBB, account: &Account, asset: &TypeTag, market: AccountAddress, amount: u64) -> Result<TransactionStatus>;
+
+    fn borrow(&self, bb: &mut AptosBB, account: &Account, asset: &TypeTag, market: AccountAddress, amount: u64) -> Result<TransactionStatus>;
+}
+
+/// Staking APT for a liquid staking token
+pub trait LiquidStaking {
+    fn stake(&self, bb: &mut AptosBB, account: &Account, amount: u64) -> Result<TransactionStatus>;
+
+    fn unstake(&self, bb: &mut AptosBB, account: &Account, amount: u64) -> Result<TransactionStatus>;
+}
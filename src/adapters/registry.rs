@@ -0,0 +1,56 @@
+//! Known mainnet protocol deployments
+
+use anyhow::{anyhow, Result};
+use aptos_types::account_address::AccountAddress;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProtocolKind {
+    Dex,
+    Lending,
+    LiquidStaking,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ProtocolInfo {
+    pub name: &'static str,
+    pub kind: ProtocolKind,
+    pub address: &'static str,
+}
+
+impl ProtocolInfo {
+    pub fn address(&self) -> AccountAddress {
+        AccountAddress::from_hex_literal(self.address).expect("registry addresses are valid")
+    }
+}
+
+const PROTOCOLS: &[ProtocolInfo] = &[
+    ProtocolInfo {
+        name: "thala",
+        kind: ProtocolKind::Dex,
+        address: "0x48271d39d0b05bd6efca2278f22277d6fcc375504f9839fd73f74ace240861af",
+    },
+    ProtocolInfo {
+        name: "echelon",
+        kind: ProtocolKind::Lending,
+        address: "0xc6bc659f1649553c1a3fa05d9727433dc03843baac29473c817d06d39e7621ba",
+    },
+    ProtocolInfo {
+        name: "amnis",
+        kind: ProtocolKind::LiquidStaking,
+        address: "0x111ae3e5bc816a5e63c2da97d0aa3886519e0cd5e4b046659fa35796bd11542a",
+    },
+];
+
+/// All registered protocols
+pub fn protocols() -> &'static [ProtocolInfo] {
+    PROTOCOLS
+}
+
+/// Look up a protocol by name
+pub fn protocol(name: &str) -> Result<ProtocolInfo> {
+    PROTOCOLS
+        .iter()
+        .find(|protocol| protocol.name == name)
+        .copied()
+        .ok_or_else(|| anyhow!("Unknown protocol: {}", name))
+}
@@ -0,0 +1,91 @@
+//! ThalaSwap v1 stable and weighted pools
+
+use super::{registry, Dex};
+use crate::{AptosBB, EntryCall};
+use anyhow::{bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{account_address::AccountAddress, transaction::TransactionStatus};
+use move_core_types::language_storage::TypeTag;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PoolKind {
+    Stable,
+    Weighted,
+}
+
+/// A ThalaSwap v1 pool, identified by its (up to four) asset types
+///
+/// Unused asset slots are filled with `base_pool::Null`.
+#[derive(Clone, Debug)]
+pub struct ThalaPool {
+    pub address: AccountAddress,
+    pub kind: PoolKind,
+    pub assets: Vec<TypeTag>,
+}
+
+impl ThalaPool {
+    pub fn new(kind: PoolKind, assets: &[TypeTag]) -> Result<Self> {
+        if assets.len() < 2 || assets.len() > 4 {
+            bail!("Thala pools hold 2 to 4 assets, got {}", assets.len());
+        }
+        let address = registry::protocol("thala")?.address();
+        let null = TypeTag::from_str(&format!("{}::base_pool::Null", address.to_hex_literal()))?;
+
+        let mut padded = assets.to_vec();
+        padded.resize(4, null);
+        Ok(Self { address, kind, assets: padded })
+    }
+
+    fn scripts_module(&self) -> &'static str {
+        match self.kind {
+            PoolKind::Stable => "stable_pool_scripts",
+            PoolKind::Weighted => "weighted_pool_scripts",
+        }
+    }
+
+    fn function(&self, name: &str) -> String {
+        format!("{}::{}::{}", self.address.to_hex_literal(), self.scripts_module(), name)
+    }
+
+    fn with_pool_types(&self, mut call: EntryCall) -> EntryCall {
+        for asset in &self.assets {
+            call = call.ty_tag(asset.clone());
+        }
+        call
+    }
+}
+
+impl Dex for ThalaPool {
+    fn swap(
+        &self,
+        bb: &mut AptosBB,
+        account: &Account,
+        from: &TypeTag,
+        to: &TypeTag,
+        amount_in: u64,
+        min_amount_out: u64,
+    ) -> Result<TransactionStatus> {
+        let call = self
+            .with_pool_types(EntryCall::new(&self.function("swap_exact_in")))
+            .ty_tag(from.clone())
+            .ty_tag(to.clone())
+            .arg(amount_in)
+            .arg(min_amount_out);
+        bb.call(account, call)
+    }
+
+    fn add_liquidity(
+        &self,
+        bb: &mut AptosBB,
+        account: &Account,
+        amounts: &[u64],
+        min_lp_out: u64,
+    ) -> Result<TransactionStatus> {
+        let mut call = self.with_pool_types(EntryCall::new(&self.function("add_liquidity")));
+        for i in 0..4 {
+            call = call.arg(amounts.get(i).copied().unwrap_or(0));
+        }
+        bb.call(account, call.arg(min_lp_out))
+    }
+}
@@ -0,0 +1,204 @@
+use crate::AptosBB;
+use anyhow::{anyhow, bail, Result};
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Current value of an aggregator and the bound it may not exceed
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AggregatorValue {
+    pub value: u128,
+    pub max_value: u128,
+}
+
+/// Where an aggregator's value is stored
+enum AggregatorKind {
+    /// `aggregator_v2::Aggregator` (and `optional_aggregator::Integer`): inline in the resource
+    Inline { max_field: &'static str },
+    /// `aggregator::Aggregator` (v1): a `u128` table item keyed by the aggregator's `key`
+    Table { handle: AccountAddress, key: AccountAddress, limit: u128 },
+}
+
+fn json_u128(json: &Value) -> Result<u128> {
+    match json {
+        Value::Number(n) => n.as_u64().map(u128::from).ok_or_else(|| anyhow!("Expected an integer, got {}", n)),
+        Value::String(s) => Ok(s.parse()?),
+        _ => bail!("Expected an integer, got {}", json),
+    }
+}
+
+/// Follow a dot-separated field path, stepping through `Option`s (`{"vec": [..]}`) transparently
+fn field_mut<'v>(mut value: &'v mut Value, path: &str) -> Result<&'v mut Value> {
+    for key in path.split('.').filter(|key| !key.is_empty()) {
+        value = unwrap_option(value);
+        value = match value {
+            Value::Array(items) => {
+                let index: usize = key.parse().map_err(|_| anyhow!("Expected an index into an array, got {}", key))?;
+                items.get_mut(index)
+            }
+            other => other.get_mut(key),
+        }
+        .ok_or_else(|| anyhow!("Field {} not found in {}", key, path))?;
+    }
+    Ok(unwrap_option(value))
+}
+
+fn unwrap_option(value: &mut Value) -> &mut Value {
+    let is_some = value
+        .as_object()
+        .and_then(|object| object.get("vec"))
+        .and_then(Value::as_array)
+        .is_some_and(|items| items.len() == 1 && value.as_object().is_some_and(|object| object.len() == 1));
+    if is_some {
+        &mut value["vec"][0]
+    } else {
+        value
+    }
+}
+
+/// Recognize the aggregator flavour at `json`, descending into `OptionalAggregator`s
+fn aggregator_at(json: &mut Value) -> Result<(&mut Value, AggregatorKind)> {
+    let has = |json: &Value, field: &str| json.get(field).is_some();
+
+    if has(json, "aggregator") && has(json, "integer") {
+        // Exactly one of the two options is set, depending on whether the coin is parallelizable
+        let field = if json["aggregator"]["vec"].as_array().is_some_and(|items| !items.is_empty()) {
+            "aggregator"
+        } else {
+            "integer"
+        };
+        return aggregator_at(unwrap_option(&mut json[field]));
+    }
+    if has(json, "value") && has(json, "max_value") {
+        return Ok((json, AggregatorKind::Inline { max_field: "max_value" }));
+    }
+    if has(json, "value") && has(json, "limit") {
+        return Ok((json, AggregatorKind::Inline { max_field: "limit" }));
+    }
+    if has(json, "handle") && has(json, "key") && has(json, "limit") {
+        let address = |field: &str| -> Result<AccountAddress> {
+            let text = json[field].as_str().ok_or_else(|| anyhow!("Expected an address in {}", field))?;
+            Ok(AccountAddress::from_str(text)?)
+        };
+        let kind = AggregatorKind::Table {
+            handle: address("handle")?,
+            key: address("key")?,
+            limit: json_u128(&json["limit"])?,
+        };
+        return Ok((json, kind));
+    }
+    bail!("Not an aggregator: {}", json)
+}
+
+impl AptosBB {
+    fn aggregator_json(&self, addr: &AccountAddress, struct_tag: &StructTag) -> Result<Value> {
+        self.read_resource_json(addr, struct_tag)?
+            .ok_or_else(|| anyhow!("Resource {} not found at {}", struct_tag, addr))
+    }
+
+    /// Read an aggregator stored in a resource field, e.g. `current` of `ConcurrentSupply`
+    ///
+    /// `path` is dot-separated and steps through `Option`s. Both `aggregator_v2` aggregators and
+    /// v1 (`aggregator::Aggregator` / `OptionalAggregator`, as in `CoinInfo::supply`) are supported.
+    pub fn read_aggregator(&self, addr: &AccountAddress, struct_tag: &StructTag, path: &str) -> Result<AggregatorValue> {
+        let mut json = self.aggregator_json(addr, struct_tag)?;
+        let (aggregator, kind) = aggregator_at(field_mut(&mut json, path)?)?;
+        match kind {
+            AggregatorKind::Inline { max_field } => Ok(AggregatorValue {
+                value: json_u128(&aggregator["value"])?,
+                max_value: json_u128(&aggregator[max_field])?,
+            }),
+            AggregatorKind::Table { handle, key, limit } => {
+                let value = match self.read_table_item(handle, &key)? {
+                    Some(bytes) => bcs::from_bytes(&bytes)?,
+                    None => 0,
+                };
+                Ok(AggregatorValue { value, max_value: limit })
+            }
+        }
+    }
+
+    /// Overwrite the value of an aggregator stored in a resource field
+    ///
+    /// The new value must not exceed the aggregator's bound, since the VM would reject any
+    /// later update of an aggregator that is already out of range.
+    pub fn set_aggregator(&mut self, addr: &AccountAddress, struct_tag: &StructTag, path: &str, value: u128) -> Result<()> {
+        let mut json = self.aggregator_json(addr, struct_tag)?;
+        let (aggregator, kind) = aggregator_at(field_mut(&mut json, path)?)?;
+        match kind {
+            AggregatorKind::Inline { max_field } => {
+                let max_value = json_u128(&aggregator[max_field])?;
+                if value > max_value {
+                    bail!("Value {} exceeds the aggregator bound {}", value, max_value);
+                }
+                aggregator["value"] = Value::String(value.to_string());
+                self.write_resource_json(addr, struct_tag, &json)
+            }
+            AggregatorKind::Table { handle, key, limit } => {
+                if value > limit {
+                    bail!("Value {} exceeds the aggregator limit {}", value, limit);
+                }
+                self.write_table_item(handle, &key, &value)
+            }
+        }
+    }
+
+    /// Total supply of a fungible asset, from its metadata object's `ConcurrentSupply` or `Supply`
+    pub fn fa_supply(&self, metadata: AccountAddress) -> Result<AggregatorValue> {
+        let concurrent = StructTag::from_str("0x1::fungible_asset::ConcurrentSupply")?;
+        if self.read_resource_json(&metadata, &concurrent)?.is_some() {
+            return self.read_aggregator(&metadata, &concurrent, "current");
+        }
+
+        let supply = self.aggregator_json(&metadata, &StructTag::from_str("0x1::fungible_asset::Supply")?)?;
+        let max_value = match supply["maximum"]["vec"].get(0) {
+            Some(maximum) => json_u128(maximum)?,
+            None => u128::MAX,
+        };
+        Ok(AggregatorValue { value: json_u128(&supply["current"])?, max_value })
+    }
+
+    /// Overwrite the total supply of a fungible asset
+    pub fn set_fa_supply(&mut self, metadata: AccountAddress, value: u128) -> Result<()> {
+        let concurrent = StructTag::from_str("0x1::fungible_asset::ConcurrentSupply")?;
+        if self.read_resource_json(&metadata, &concurrent)?.is_some() {
+            return self.set_aggregator(&metadata, &concurrent, "current", value);
+        }
+
+        let supply_tag = StructTag::from_str("0x1::fungible_asset::Supply")?;
+        let mut supply = self.aggregator_json(&metadata, &supply_tag)?;
+        if let Some(maximum) = supply["maximum"]["vec"].get(0) {
+            let maximum = json_u128(maximum)?;
+            if value > maximum {
+                bail!("Value {} exceeds the maximum supply {}", value, maximum);
+            }
+        }
+        supply["current"] = Value::String(value.to_string());
+        self.write_resource_json(&metadata, &supply_tag, &supply)
+    }
+
+    /// Supply of a legacy coin as tracked by `CoinInfo<CoinType>::supply`
+    pub fn coin_supply(&self, coin_type: &str) -> Result<AggregatorValue> {
+        let (addr, coin_info) = coin_info_tag(coin_type)?;
+        self.read_aggregator(&addr, &coin_info, "supply")
+    }
+
+    /// Overwrite the supply tracked by `CoinInfo<CoinType>::supply`
+    pub fn set_coin_supply(&mut self, coin_type: &str, value: u128) -> Result<()> {
+        let (addr, coin_info) = coin_info_tag(coin_type)?;
+        self.set_aggregator(&addr, &coin_info, "supply", value)
+    }
+}
+
+/// `CoinInfo<CoinType>` and the address it is stored under (the coin type's module address)
+fn coin_info_tag(coin_type: &str) -> Result<(AccountAddress, StructTag)> {
+    let coin_type = TypeTag::from_str(coin_type)?;
+    let TypeTag::Struct(tag) = &coin_type else {
+        bail!("Coin type must be a struct, got {}", coin_type);
+    };
+    let addr = tag.address;
+    let mut coin_info = StructTag::from_str("0x1::coin::CoinInfo")?;
+    coin_info.type_args = vec![coin_type];
+    Ok((addr, coin_info))
+}
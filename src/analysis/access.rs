@@ -0,0 +1,163 @@
+//! Authorization map of a package's externally callable functions
+
+use super::{is_signer, patterns::CAPABILITY_SUFFIXES, FunctionView};
+use crate::AptosBB;
+use anyhow::Result;
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, StructDefinitionIndex, Visibility},
+    CompiledModule,
+};
+use move_core_types::language_storage::ModuleId;
+use std::{collections::BTreeSet, fmt};
+
+/// Authorization facts about one public or entry function
+#[derive(Clone, Debug)]
+pub struct AccessEntry {
+    pub module: ModuleId,
+    pub function: String,
+    pub is_entry: bool,
+    pub visibility: Visibility,
+    /// Indices of `signer`/`&signer` parameters
+    pub signers: Vec<usize>,
+    /// Whether the function derives an address from a signer (`signer::address_of`)
+    pub checks_signer_address: bool,
+    /// Capability-like resources borrowed or moved out of global storage
+    pub capabilities: BTreeSet<String>,
+    /// Other global resources read, typically admin/config state consulted for authorization
+    pub resources_read: BTreeSet<String>,
+    /// Global resources written (borrowed mutably, moved to or from)
+    pub resources_written: BTreeSet<String>,
+}
+
+impl AccessEntry {
+    /// Mutates global state without any signer or capability guarding it
+    pub fn is_unguarded(&self) -> bool {
+        self.signers.is_empty() && self.capabilities.is_empty() && !self.resources_written.is_empty()
+    }
+}
+
+/// Access-control matrix of a package
+#[derive(Clone, Debug, Default)]
+pub struct AccessMatrix {
+    pub entries: Vec<AccessEntry>,
+}
+
+impl AccessMatrix {
+    /// Entries that mutate state with no apparent authorization
+    pub fn unguarded(&self) -> impl Iterator<Item = &AccessEntry> {
+        self.entries.iter().filter(|entry| entry.is_unguarded())
+    }
+}
+
+fn join(set: &BTreeSet<String>) -> String {
+    if set.is_empty() {
+        "-".to_string()
+    } else {
+        set.iter().cloned().collect::<Vec<_>>().join(", ")
+    }
+}
+
+impl fmt::Display for AccessMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            let kind = match (entry.is_entry, entry.visibility) {
+                (true, Visibility::Public) => "public entry",
+                (true, _) => "entry",
+                _ => "public",
+            };
+            let signers = match (entry.signers.len(), entry.checks_signer_address) {
+                (0, _) => "none".to_string(),
+                (n, true) => format!("{} (address checked)", n),
+                (n, false) => n.to_string(),
+            };
+            writeln!(
+                f,
+                "{}{}::{} [{}] signers: {} | caps: {} | reads: {} | writes: {}",
+                if entry.is_unguarded() { "⚠️  " } else { "" },
+                entry.module.name(),
+                entry.function,
+                kind,
+                signers,
+                join(&entry.capabilities),
+                join(&entry.resources_read),
+                join(&entry.resources_written),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn struct_def_name(module: &CompiledModule, idx: StructDefinitionIndex) -> String {
+    let handle = module.struct_handle_at(module.struct_def_at(idx).struct_handle);
+    module.identifier_at(handle.name).to_string()
+}
+
+fn is_capability(name: &str) -> bool {
+    CAPABILITY_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+}
+
+/// Build the matrix entry of a single function
+fn access_entry(function: &FunctionView) -> AccessEntry {
+    let module = function.module;
+    let mut entry = AccessEntry {
+        module: module.self_id(),
+        function: function.name(),
+        is_entry: function.def.is_entry,
+        visibility: function.def.visibility,
+        signers: function
+            .parameters()
+            .iter()
+            .enumerate()
+            .filter(|(_, token)| is_signer(token))
+            .map(|(idx, _)| idx)
+            .collect(),
+        checks_signer_address: function.callees().iter().any(|callee| callee == "0x1::signer::address_of"),
+        capabilities: BTreeSet::new(),
+        resources_read: BTreeSet::new(),
+        resources_written: BTreeSet::new(),
+    };
+
+    for instr in function.code() {
+        let (def_idx, writes) = match instr {
+            Bytecode::ImmBorrowGlobal(idx) | Bytecode::Exists(idx) => (*idx, false),
+            Bytecode::ImmBorrowGlobalGeneric(idx) | Bytecode::ExistsGeneric(idx) => {
+                (module.struct_instantiation_at(*idx).def, false)
+            }
+            Bytecode::MutBorrowGlobal(idx) | Bytecode::MoveFrom(idx) | Bytecode::MoveTo(idx) => (*idx, true),
+            Bytecode::MutBorrowGlobalGeneric(idx) | Bytecode::MoveFromGeneric(idx) | Bytecode::MoveToGeneric(idx) => {
+                (module.struct_instantiation_at(*idx).def, true)
+            }
+            _ => continue,
+        };
+        let name = struct_def_name(module, def_idx);
+        if is_capability(&name) {
+            entry.capabilities.insert(name);
+        } else if writes {
+            entry.resources_written.insert(name);
+        } else {
+            entry.resources_read.insert(name);
+        }
+    }
+    entry
+}
+
+/// Access-control entries for every externally callable function of a module
+pub fn module_access(module: &CompiledModule) -> Vec<AccessEntry> {
+    FunctionView::all(module)
+        .filter(|function| function.is_externally_callable())
+        .map(|function| access_entry(&function))
+        .collect()
+}
+
+impl AptosBB {
+    /// Which signers, capabilities and admin resources each public/entry function of a package relies on
+    pub fn access_control_matrix(&mut self, addr: AccountAddress, package: &str) -> Result<AccessMatrix> {
+        let mut matrix = AccessMatrix::default();
+        for module in self.package_modules(addr, package)? {
+            matrix.entries.extend(module_access(&module));
+        }
+        Ok(matrix)
+    }
+}
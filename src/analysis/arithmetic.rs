@@ -0,0 +1,186 @@
+//! Truncation and rounding risks in integer arithmetic
+
+use super::{Finding, FunctionView};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, FieldHandleIndex, SignatureToken, StructFieldInformation},
+    CompiledModule,
+};
+use std::collections::BTreeMap;
+
+/// How far (in instructions) a related operation is looked for
+const WINDOW: usize = 12;
+
+fn finding(function: &FunctionView, check: &'static str, offset: usize, message: &str) -> Finding {
+    Finding::lead(check, &function.module.self_id(), Some(function.name()), format!("{} (offset {})", message, offset))
+}
+
+fn ends_block(instr: &Bytecode) -> bool {
+    matches!(
+        instr,
+        Bytecode::Branch(_) | Bytecode::BrTrue(_) | Bytecode::BrFalse(_) | Bytecode::Ret | Bytecode::Abort
+    )
+}
+
+/// Instructions following `offset` up to the end of its basic block, bounded by `WINDOW`
+fn following(code: &[Bytecode], offset: usize) -> impl Iterator<Item = &Bytecode> {
+    code[offset + 1..]
+        .iter()
+        .take(WINDOW)
+        .take_while(|instr| !ends_block(instr))
+}
+
+/// Instructions preceding `offset` back to the start of its basic block, bounded by `WINDOW`
+fn preceding(code: &[Bytecode], offset: usize) -> impl Iterator<Item = &Bytecode> {
+    code[..offset]
+        .iter()
+        .rev()
+        .take(WINDOW)
+        .take_while(|instr| !ends_block(instr))
+}
+
+fn int_width(token: &SignatureToken) -> Option<u16> {
+    match token {
+        SignatureToken::U8 => Some(8),
+        SignatureToken::U16 => Some(16),
+        SignatureToken::U32 => Some(32),
+        SignatureToken::U64 => Some(64),
+        SignatureToken::U128 => Some(128),
+        SignatureToken::U256 => Some(256),
+        _ => None,
+    }
+}
+
+fn cast_width(instr: &Bytecode) -> Option<u16> {
+    match instr {
+        Bytecode::CastU8 => Some(8),
+        Bytecode::CastU16 => Some(16),
+        Bytecode::CastU32 => Some(32),
+        Bytecode::CastU64 => Some(64),
+        Bytecode::CastU128 => Some(128),
+        Bytecode::CastU256 => Some(256),
+        _ => None,
+    }
+}
+
+fn field_width(module: &CompiledModule, idx: FieldHandleIndex) -> Option<u16> {
+    let handle = module.field_handle_at(idx);
+    match &module.struct_def_at(handle.owner).field_information {
+        StructFieldInformation::Declared(fields) => int_width(&fields.get(handle.field as usize)?.signature.0),
+        _ => None,
+    }
+}
+
+/// Width of the integer the instruction at `offset` leaves on top of the stack, when the
+/// instructions producing it tell; `None` for anything that needs real type inference
+fn produced_width(function: &FunctionView, offset: usize) -> Option<u16> {
+    let module = function.module;
+    let code = function.code();
+    let previous = || offset.checked_sub(1);
+    match &code[offset] {
+        Bytecode::LdU8(_) => Some(8),
+        Bytecode::LdU16(_) => Some(16),
+        Bytecode::LdU32(_) => Some(32),
+        Bytecode::LdU64(_) | Bytecode::VecLen(_) => Some(64),
+        Bytecode::LdU128(_) => Some(128),
+        Bytecode::LdU256(_) => Some(256),
+        Bytecode::CopyLoc(idx) | Bytecode::MoveLoc(idx) => int_width(function.local(*idx)?),
+        // Both operands share the result's type, and the right one was pushed last
+        Bytecode::Add
+        | Bytecode::Sub
+        | Bytecode::Mul
+        | Bytecode::Div
+        | Bytecode::Mod
+        | Bytecode::BitOr
+        | Bytecode::BitAnd
+        | Bytecode::Xor => produced_width(function, previous()?),
+        Bytecode::Call(idx) => int_width(module.signature_at(module.function_handle_at(*idx).return_).0.last()?),
+        Bytecode::CallGeneric(idx) => {
+            let handle = module.function_handle_at(module.function_instantiation_at(*idx).handle);
+            int_width(module.signature_at(handle.return_).0.last()?)
+        }
+        Bytecode::ReadRef => match &code[previous()?] {
+            Bytecode::ImmBorrowField(idx) | Bytecode::MutBorrowField(idx) => field_width(module, *idx),
+            Bytecode::ImmBorrowLoc(idx) | Bytecode::MutBorrowLoc(idx) => int_width(function.local(*idx)?),
+            _ => None,
+        },
+        instr => cast_width(instr),
+    }
+}
+
+fn function_findings(function: &FunctionView) -> Vec<Finding> {
+    let code = function.code();
+    let mut findings = vec![];
+
+    for (offset, instr) in code.iter().enumerate() {
+        match instr {
+            Bytecode::Div if following(code, offset).any(|next| matches!(next, Bytecode::Mul)) => {
+                findings.push(finding(
+                    function,
+                    "div-before-mul",
+                    offset,
+                    "division result is multiplied afterwards, amplifying rounding loss",
+                ));
+            }
+            Bytecode::Mul => {
+                let divides = following(code, offset).any(|next| matches!(next, Bytecode::Div));
+                let widened = preceding(code, offset)
+                    .any(|prev| matches!(prev, Bytecode::CastU128 | Bytecode::CastU256));
+                if divides && !widened {
+                    findings.push(finding(
+                        function,
+                        "unchecked-mul-div",
+                        offset,
+                        "mul-div share math without widening to u128/u256 may overflow and abort",
+                    ));
+                }
+            }
+            Bytecode::CastU8 | Bytecode::CastU16 | Bytecode::CastU32 | Bytecode::CastU64 | Bytecode::CastU128 => {
+                // Widening and same-width casts never abort; casts of operands whose width
+                // can't be told from the surrounding instructions are left out
+                let from = offset.checked_sub(1).and_then(|previous| produced_width(function, previous));
+                if let (Some(from), Some(to)) = (from, cast_width(instr)) {
+                    if to < from {
+                        findings.push(finding(
+                            function,
+                            "narrowing-cast",
+                            offset,
+                            &format!("u{} cast to u{} aborts on values that don't fit", from, to),
+                        ));
+                    }
+                }
+            }
+            Bytecode::Shl => {
+                findings.push(finding(
+                    function,
+                    "unchecked-shift",
+                    offset,
+                    "left shift silently drops overflowing bits",
+                ));
+            }
+            _ => {}
+        }
+    }
+    findings
+}
+
+/// Run the arithmetic checks on every function of a module
+pub fn run_all(module: &CompiledModule) -> Vec<Finding> {
+    FunctionView::all(module).flat_map(|function| function_findings(&function)).collect()
+}
+
+/// Externally callable functions ranked by the number of arithmetic findings in them,
+/// most suspicious first: a starting point for choosing fuzz targets
+pub fn fuzz_targets(module: &CompiledModule) -> Vec<(String, usize)> {
+    let mut counts = BTreeMap::<String, usize>::new();
+    for function in FunctionView::all(module).filter(|f| f.is_externally_callable()) {
+        let risks = function_findings(&function).len();
+        if risks > 0 {
+            let id = format!("{}::{}", module.self_id(), function.name());
+            counts.insert(id, risks);
+        }
+    }
+    let mut targets: Vec<_> = counts.into_iter().collect();
+    targets.sort_by(|a, b| b.1.cmp(&a.1));
+    targets
+}
@@ -0,0 +1,205 @@
+//! Static analyses over module bytecode fetched from the fork
+
+pub mod access;
+pub mod arithmetic;
+pub mod call_graph;
+pub mod patterns;
+pub mod pause;
+pub mod taint;
+
+use crate::AptosBB;
+use anyhow::{anyhow, Result};
+use aptos_framework::natives::code::PackageRegistry;
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, FunctionDefinition, FunctionHandle, SignatureToken},
+    CompiledModule,
+};
+use move_core_types::language_storage::ModuleId;
+use std::{collections::BTreeSet, sync::Arc};
+
+pub use crate::findings::Finding;
+
+/// Shorthand accessors used by the individual checks
+pub(crate) struct FunctionView<'a> {
+    pub module: &'a CompiledModule,
+    pub def: &'a FunctionDefinition,
+    pub handle: &'a FunctionHandle,
+}
+
+impl<'a> FunctionView<'a> {
+    pub fn all(module: &'a CompiledModule) -> impl Iterator<Item = FunctionView<'a>> {
+        module.function_defs().iter().map(move |def| FunctionView {
+            module,
+            def,
+            handle: module.function_handle_at(def.function),
+        })
+    }
+
+    pub fn name(&self) -> String {
+        self.module.identifier_at(self.handle.name).to_string()
+    }
+
+    pub fn parameters(&self) -> &'a [SignatureToken] {
+        &self.module.signature_at(self.handle.parameters).0
+    }
+
+    pub fn returns(&self) -> &'a [SignatureToken] {
+        &self.module.signature_at(self.handle.return_).0
+    }
+
+    /// Type of a local, counting parameters first
+    pub fn local(&self, idx: u8) -> Option<&'a SignatureToken> {
+        let parameters = self.parameters();
+        match parameters.get(idx as usize) {
+            Some(token) => Some(token),
+            None => {
+                let locals = self.def.code.as_ref()?.locals;
+                self.module.signature_at(locals).0.get(idx as usize - parameters.len())
+            }
+        }
+    }
+
+    pub fn code(&self) -> &'a [Bytecode] {
+        self.def.code.as_ref().map(|code| code.code.as_slice()).unwrap_or(&[])
+    }
+
+    /// Whether an external caller can invoke the function directly
+    pub fn is_externally_callable(&self) -> bool {
+        self.def.is_entry || self.def.visibility == move_binary_format::file_format::Visibility::Public
+    }
+
+    /// Fully qualified name, `0x1::module::function`
+    pub fn qualified_name(&self) -> String {
+        let id = self.module.self_id();
+        format!("{}::{}::{}", id.address().to_hex_literal(), id.name(), self.name())
+    }
+
+    /// Whether the function itself checks who its caller is
+    ///
+    /// Taking a `&signer` proves nothing on its own. The function must call
+    /// `signer::address_of` and either compare the address (`==`/`!=`) against stored state
+    /// or a constant address, or look up a capability resource (`exists`/`borrow_global` of a
+    /// `*Cap`-like struct), as `borrow_global<AdminCap>(signer::address_of(admin))` does.
+    pub fn checks_caller(&self) -> bool {
+        if !self.callees().iter().any(|callee| callee == "0x1::signer::address_of") {
+            return false;
+        }
+        let code = self.code();
+        let compares = code.iter().any(|instr| matches!(instr, Bytecode::Eq | Bytecode::Neq));
+        let mut reads_state = false;
+        let mut loads_address = false;
+        let mut reads_capability = false;
+        for instr in code {
+            let def_idx = match instr {
+                Bytecode::LdConst(idx) => {
+                    loads_address |= self.module.constant_at(*idx).type_ == SignatureToken::Address;
+                    continue;
+                }
+                Bytecode::ImmBorrowGlobal(idx)
+                | Bytecode::MutBorrowGlobal(idx)
+                | Bytecode::Exists(idx)
+                | Bytecode::MoveFrom(idx) => *idx,
+                Bytecode::ImmBorrowGlobalGeneric(idx)
+                | Bytecode::MutBorrowGlobalGeneric(idx)
+                | Bytecode::ExistsGeneric(idx)
+                | Bytecode::MoveFromGeneric(idx) => self.module.struct_instantiation_at(*idx).def,
+                _ => continue,
+            };
+            reads_state = true;
+            let handle = self.module.struct_handle_at(self.module.struct_def_at(def_idx).struct_handle);
+            let name = self.module.identifier_at(handle.name).as_str();
+            reads_capability |= patterns::CAPABILITY_SUFFIXES.iter().any(|suffix| name.ends_with(suffix));
+        }
+        reads_capability || (compares && (reads_state || loads_address))
+    }
+
+    /// Fully qualified names (`0x1::module::function`) of all functions this one calls
+    pub fn callees(&self) -> Vec<String> {
+        self.code()
+            .iter()
+            .filter_map(|instr| {
+                let handle_idx = match instr {
+                    Bytecode::Call(idx) => *idx,
+                    Bytecode::CallGeneric(idx) => self.module.function_instantiation_at(*idx).handle,
+                    _ => return None,
+                };
+                let handle = self.module.function_handle_at(handle_idx);
+                let owner = self.module.module_id_for_handle(self.module.module_handle_at(handle.module));
+                Some(format!("{}::{}::{}", owner.address().to_hex_literal(), owner.name(), self.module.identifier_at(handle.name)))
+            })
+            .collect()
+    }
+}
+
+/// Fully qualified names of the functions of `modules` that check their caller, themselves or
+/// through a callee among `modules` (e.g. an `assert_admin(&signer)` helper)
+pub(crate) fn authorizing_functions(modules: &[&CompiledModule]) -> BTreeSet<String> {
+    let functions: Vec<(String, Vec<String>, bool)> = modules
+        .iter()
+        .flat_map(|module| FunctionView::all(*module))
+        .map(|function| (function.qualified_name(), function.callees(), function.checks_caller()))
+        .collect();
+    let mut authorizing: BTreeSet<String> =
+        functions.iter().filter(|(_, _, checks)| *checks).map(|(name, _, _)| name.clone()).collect();
+    loop {
+        let before = authorizing.len();
+        for (name, callees, _) in &functions {
+            if callees.iter().any(|callee| authorizing.contains(callee)) {
+                authorizing.insert(name.clone());
+            }
+        }
+        if authorizing.len() == before {
+            return authorizing;
+        }
+    }
+}
+
+/// Whether a type is (a reference to) `signer`
+pub(crate) fn is_signer(token: &SignatureToken) -> bool {
+    match token {
+        SignatureToken::Signer => true,
+        SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => is_signer(inner),
+        _ => false,
+    }
+}
+
+impl AptosBB {
+    /// Compiled modules of a package published on the fork
+    pub(crate) fn package_modules(&mut self, addr: AccountAddress, package: &str) -> Result<Vec<Arc<CompiledModule>>> {
+        self.fetch_account(addr)?;
+        let registry: PackageRegistry = self.read_resource(&addr)
+            .ok_or_else(|| anyhow!("No packages published at {}", addr))?;
+        let package = registry.packages
+            .iter()
+            .find(|p| p.name == package)
+            .ok_or_else(|| anyhow!("Package {} not found at {}", package, addr))?;
+
+        package.modules
+            .iter()
+            .map(|module| self.get_module(&ModuleId::new(addr, module.name.parse()?)))
+            .collect()
+    }
+
+    /// Compiled modules of every package published at an address
+    pub(crate) fn account_modules(&mut self, addr: AccountAddress) -> Result<Vec<Arc<CompiledModule>>> {
+        self.fetch_account(addr)?;
+        let registry: PackageRegistry = self.read_resource(&addr)
+            .ok_or_else(|| anyhow!("No packages published at {}", addr))?;
+
+        registry.packages
+            .iter()
+            .flat_map(|package| &package.modules)
+            .map(|module| self.get_module(&ModuleId::new(addr, module.name.parse()?)))
+            .collect()
+    }
+
+    /// Run every bytecode pattern and arithmetic check on a module
+    pub fn analyze_module(&mut self, module_id: &ModuleId) -> Result<Vec<Finding>> {
+        let module = self.get_module(module_id)?;
+        let mut findings = patterns::run_all(&module);
+        findings.extend(arithmetic::run_all(&module));
+        Ok(findings)
+    }
+}
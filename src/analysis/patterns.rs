@@ -0,0 +1,172 @@
+//! Checks for well-known Move bug patterns
+
+use super::{is_signer, Finding, FunctionView};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, SignatureToken},
+    CompiledModule,
+};
+
+/// Struct names that grant privileges when held
+pub(crate) const CAPABILITY_SUFFIXES: &[&str] = &["Cap", "Capability", "Ref"];
+
+pub fn run_all(module: &CompiledModule) -> Vec<Finding> {
+    let mut findings = vec![];
+    for function in FunctionView::all(module) {
+        findings.extend(entry_without_authorization(&function));
+        findings.extend(returns_capability(&function));
+        findings.extend(unbound_type_parameters(&function));
+        findings.extend(loop_over_user_vector(&function));
+    }
+    findings
+}
+
+fn finding(function: &FunctionView, check: &'static str, message: String) -> Finding {
+    Finding::lead(check, &function.module.self_id(), Some(function.name()), message)
+}
+
+fn writes_global_state(code: &[Bytecode]) -> bool {
+    code.iter().any(|instr| {
+        matches!(
+            instr,
+            Bytecode::MutBorrowGlobal(_)
+                | Bytecode::MutBorrowGlobalGeneric(_)
+                | Bytecode::MoveFrom(_)
+                | Bytecode::MoveFromGeneric(_)
+        )
+    })
+}
+
+fn local_is_read(code: &[Bytecode], local: u8) -> bool {
+    code.iter().any(|instr| {
+        matches!(
+            instr,
+            Bytecode::CopyLoc(idx) | Bytecode::MoveLoc(idx) | Bytecode::ImmBorrowLoc(idx) | Bytecode::MutBorrowLoc(idx)
+                if *idx == local
+        )
+    })
+}
+
+/// Entry functions that mutate global state without a signer, or ignore the signer they take
+fn entry_without_authorization(function: &FunctionView) -> Option<Finding> {
+    if !function.def.is_entry {
+        return None;
+    }
+    let code = function.code();
+    let signers: Vec<u8> = function
+        .parameters()
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| is_signer(token))
+        .map(|(idx, _)| idx as u8)
+        .collect();
+
+    if signers.is_empty() && writes_global_state(code) {
+        return Some(finding(
+            function,
+            "entry-no-signer",
+            "entry function mutates global state but takes no signer".to_string(),
+        ));
+    }
+    if let Some(unused) = signers.iter().find(|idx| !local_is_read(code, **idx)) {
+        return Some(finding(
+            function,
+            "entry-unused-signer",
+            format!("signer parameter #{} is never used, so no authorization is derived from it", unused),
+        ));
+    }
+    None
+}
+
+fn capability_name(module: &CompiledModule, token: &SignatureToken) -> Option<String> {
+    match token {
+        SignatureToken::Signer => Some("signer".to_string()),
+        SignatureToken::Struct(idx) | SignatureToken::StructInstantiation(idx, _) => {
+            let name = module.identifier_at(module.struct_handle_at(*idx).name).to_string();
+            CAPABILITY_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)).then_some(name)
+        }
+        SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) | SignatureToken::Vector(inner) => {
+            capability_name(module, inner)
+        }
+        _ => None,
+    }
+}
+
+/// Public functions handing out capabilities (or signers) to any caller
+fn returns_capability(function: &FunctionView) -> Option<Finding> {
+    if function.def.visibility != move_binary_format::file_format::Visibility::Public {
+        return None;
+    }
+    let capability = function
+        .returns()
+        .iter()
+        .find_map(|token| capability_name(function.module, token))?;
+    Some(finding(
+        function,
+        "public-returns-capability",
+        format!("public function returns capability-like value `{}`", capability),
+    ))
+}
+
+fn mentions_type_parameter(token: &SignatureToken, param: u16) -> bool {
+    match token {
+        SignatureToken::TypeParameter(idx) => *idx == param,
+        SignatureToken::Vector(inner) | SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            mentions_type_parameter(inner, param)
+        }
+        SignatureToken::StructInstantiation(_, args) => args.iter().any(|arg| mentions_type_parameter(arg, param)),
+        _ => false,
+    }
+}
+
+/// Callable generic functions whose type parameter is not tied to any argument and never checked
+fn unbound_type_parameters(function: &FunctionView) -> Option<Finding> {
+    if !function.is_externally_callable() || function.handle.type_parameters.is_empty() {
+        return None;
+    }
+    let checks_type = function
+        .callees()
+        .iter()
+        .any(|callee| callee.starts_with("0x1::type_info::") || callee.ends_with("::object::address_to_object"));
+    if checks_type {
+        return None;
+    }
+
+    let unbound: Vec<String> = (0..function.handle.type_parameters.len() as u16)
+        .filter(|param| !function.parameters().iter().any(|token| mentions_type_parameter(token, *param)))
+        .map(|param| format!("T{}", param))
+        .collect();
+    if unbound.is_empty() {
+        return None;
+    }
+    Some(finding(
+        function,
+        "unchecked-type-parameter",
+        format!("caller-chosen type parameter(s) {} are not bound by any argument or type check", unbound.join(", ")),
+    ))
+}
+
+/// Callable functions looping over the length of a vector they receive as an argument
+fn loop_over_user_vector(function: &FunctionView) -> Option<Finding> {
+    if !function.is_externally_callable() {
+        return None;
+    }
+    let takes_vector = function.parameters().iter().any(|token| {
+        matches!(token, SignatureToken::Vector(_))
+            || matches!(token, SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) if matches!(**inner, SignatureToken::Vector(_)))
+    });
+    let code = function.code();
+    let has_loop = code.iter().enumerate().any(|(offset, instr)| match instr {
+        Bytecode::Branch(target) | Bytecode::BrTrue(target) | Bytecode::BrFalse(target) => (*target as usize) <= offset,
+        _ => false,
+    });
+    let uses_length = code.iter().any(|instr| matches!(instr, Bytecode::VecLen(_)));
+
+    (takes_vector && has_loop && uses_length).then(|| {
+        finding(
+            function,
+            "unbounded-loop",
+            "loops over a caller-supplied vector without an apparent length bound".to_string(),
+        )
+    })
+}
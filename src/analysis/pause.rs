@@ -0,0 +1,363 @@
+//! Pause and emergency switches of a package, with cheatcodes to flip them
+
+use super::{authorizing_functions, FunctionView};
+use crate::{view::struct_tag_for_handle, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_types::{account_address::AccountAddress, state_store::state_key::StateKey};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, SignatureToken, StructDefinitionIndex, StructFieldInformation},
+    CompiledModule,
+};
+use move_core_types::language_storage::StructTag;
+use serde_json::Value;
+use std::{collections::BTreeSet, fmt};
+
+/// Substrings of boolean field and marker names that usually mean "the protocol is stopped"
+const PAUSE_FIELD_PATTERNS: &[&str] = &["pause", "emergency", "halt", "freeze", "frozen", "shutdown", "stopped", "kill", "circuit"];
+
+/// Substrings of names that mean the opposite, "the protocol is running"; checked first, since
+/// `unpaused` also contains `pause`
+const RUNNING_FIELD_PATTERNS: &[&str] = &["unpause", "unfrozen", "enabled", "active", "running", "operational"];
+
+/// Nested structs followed when looking for flags, as a guard against pathological layouts
+const MAX_NESTING: usize = 4;
+
+/// A function that can change a pause flag, directly or by calling one that does
+#[derive(Clone, Debug)]
+pub struct PauseWriter {
+    pub function: String,
+    pub externally_callable: bool,
+    /// Checks its caller (see `FunctionView::checks_caller`), itself or through a callee
+    pub authorizes: bool,
+}
+
+/// A pause/emergency switch of a resource, and its state where the resource exists
+#[derive(Clone, Debug)]
+pub struct PauseFlag {
+    pub resource: StructTag,
+    /// Path of the boolean field, dot-separated for flags inside nested structs; `None` for a
+    /// marker resource whose existence is the switch
+    pub field: Option<String>,
+    /// Value of the field (or existence of the marker) that means paused; `false` for
+    /// `unpaused`/`enabled`-style names
+    pub paused_when: bool,
+    /// Address holding the resource, `None` if no instance was found
+    pub holder: Option<AccountAddress>,
+    /// Field value, or whether the marker exists at `holder`
+    pub value: Option<bool>,
+    pub writers: Vec<PauseWriter>,
+}
+
+impl PauseFlag {
+    /// Whether the switch currently stops the protocol
+    pub fn is_set(&self) -> bool {
+        self.value == Some(self.paused_when)
+    }
+
+    /// Externally callable writers with no detected caller check
+    ///
+    /// The check is a bytecode heuristic, so an empty list is not a proof that only the admin
+    /// can flip the flag, and a listed writer may still be guarded in a way it does not see.
+    pub fn unguarded_writers(&self) -> Vec<&PauseWriter> {
+        self.writers
+            .iter()
+            .filter(|writer| writer.externally_callable && !writer.authorizes)
+            .collect()
+    }
+
+    fn pointer(&self) -> Option<String> {
+        self.field.as_ref().map(|field| format!("/{}", field.replace('.', "/")))
+    }
+}
+
+impl fmt::Display for PauseFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = match (self.holder, self.value) {
+            (Some(holder), Some(value)) if self.field.is_none() => {
+                format!("{} at {}", if value { "present" } else { "absent" }, holder)
+            }
+            (Some(holder), Some(value)) => format!("{} at {}", value, holder),
+            _ => "no instance found".to_string(),
+        };
+        let name = match &self.field {
+            Some(field) => format!("{}.{}", self.resource, field),
+            None => format!("{} (marker)", self.resource),
+        };
+        write!(f, "{}{} = {}", if self.is_set() { "⏸️  " } else { "" }, name, value)?;
+        let writers: Vec<String> = self
+            .writers
+            .iter()
+            .map(|writer| {
+                let kind = match (writer.externally_callable, writer.authorizes) {
+                    (false, _) => "internal",
+                    (true, true) => "checked",
+                    (true, false) => "⚠️  no caller check",
+                };
+                format!("{} [{}]", writer.function, kind)
+            })
+            .collect();
+        if !writers.is_empty() {
+            write!(f, " | written by {}", writers.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Value of a switch named `name` that means paused, or `None` if the name is not a switch
+fn pause_polarity(name: &str) -> Option<bool> {
+    let name = name.to_lowercase();
+    if RUNNING_FIELD_PATTERNS.iter().any(|pattern| name.contains(pattern)) {
+        Some(false)
+    } else if PAUSE_FIELD_PATTERNS.iter().any(|pattern| name.contains(pattern)) {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// The `direct` functions plus every function of the module that calls one, directly or transitively
+fn with_callers(module: &CompiledModule, direct: BTreeSet<String>) -> Vec<FunctionView<'_>> {
+    let functions: Vec<_> = FunctionView::all(module).map(|function| (function.qualified_name(), function)).collect();
+    let mut reached = direct;
+    loop {
+        let before = reached.len();
+        for (name, function) in &functions {
+            if function.callees().iter().any(|callee| reached.contains(callee)) {
+                reached.insert(name.clone());
+            }
+        }
+        if reached.len() == before {
+            break;
+        }
+    }
+    functions
+        .into_iter()
+        .filter(|(name, _)| reached.contains(name))
+        .map(|(_, function)| function)
+        .collect()
+}
+
+/// Functions of the module that change the switch, directly (`is_write`) or through a callee
+fn writers(module: &CompiledModule, authorizing: &BTreeSet<String>, is_write: impl Fn(&Bytecode) -> bool) -> Vec<PauseWriter> {
+    let direct = FunctionView::all(module)
+        .filter(|function| function.code().iter().any(&is_write))
+        .map(|function| function.qualified_name())
+        .collect();
+    with_callers(module, direct)
+        .into_iter()
+        .map(|function| PauseWriter {
+            function: function.name(),
+            externally_callable: function.is_externally_callable(),
+            authorizes: authorizing.contains(&function.qualified_name()),
+        })
+        .collect()
+}
+
+/// Whether an instruction takes a mutable reference to field `field` of struct `def`
+fn borrows_field_mut(module: &CompiledModule, instr: &Bytecode, def: StructDefinitionIndex, field: usize) -> bool {
+    let handle_idx = match instr {
+        Bytecode::MutBorrowField(idx) => *idx,
+        Bytecode::MutBorrowFieldGeneric(idx) => module.field_instantiation_at(*idx).handle,
+        _ => return false,
+    };
+    let handle = module.field_handle_at(handle_idx);
+    handle.owner == def && handle.field as usize == field
+}
+
+/// Whether an instruction publishes or removes struct `def`
+fn moves_struct(instr: &Bytecode, def: StructDefinitionIndex) -> bool {
+    matches!(instr, Bytecode::MoveTo(idx) | Bytecode::MoveFrom(idx) if *idx == def)
+}
+
+/// Definition of a non-generic struct declared in the module
+fn local_struct(module: &CompiledModule, token: &SignatureToken) -> Option<StructDefinitionIndex> {
+    let SignatureToken::Struct(handle) = token else {
+        return None;
+    };
+    module
+        .struct_defs()
+        .iter()
+        .position(|def| def.struct_handle == *handle)
+        .map(|idx| StructDefinitionIndex(idx as u16))
+}
+
+/// Boolean switches in struct `def` and the module's structs nested in it, as
+/// (path, paused_when, writers)
+fn struct_switches(
+    module: &CompiledModule,
+    authorizing: &BTreeSet<String>,
+    def: StructDefinitionIndex,
+    prefix: &str,
+    depth: usize,
+) -> Vec<(String, bool, Vec<PauseWriter>)> {
+    let StructFieldInformation::Declared(declared) = &module.struct_def_at(def).field_information else {
+        return vec![];
+    };
+    let mut switches = vec![];
+    for (field_idx, field) in declared.iter().enumerate() {
+        let name = module.identifier_at(field.name).to_string();
+        let path = format!("{}{}", prefix, name);
+        if field.signature.0 == SignatureToken::Bool {
+            if let Some(paused_when) = pause_polarity(&name) {
+                let writers = writers(module, authorizing, |instr| borrows_field_mut(module, instr, def, field_idx));
+                switches.push((path, paused_when, writers));
+            }
+        } else if let Some(inner) = local_struct(module, &field.signature.0).filter(|_| depth < MAX_NESTING) {
+            switches.extend(struct_switches(module, authorizing, inner, &format!("{}.", path), depth + 1));
+        }
+    }
+    switches
+}
+
+/// Whether a struct has no fields of its own (Move gives those a single `dummy_field`)
+fn is_marker(module: &CompiledModule, def: StructDefinitionIndex) -> bool {
+    match &module.struct_def_at(def).field_information {
+        StructFieldInformation::Declared(fields) => {
+            fields.is_empty() || (fields.len() == 1 && module.identifier_at(fields[0].name).as_str() == "dummy_field")
+        }
+        _ => false,
+    }
+}
+
+/// Pause switches of the module's non-generic `key` structs: boolean fields, also inside
+/// nested structs, and marker resources such as `struct Paused has key {}`
+///
+/// `authorizing` comes from `authorizing_functions` over the package, so writers calling an
+/// admin check in another module are recognized.
+pub fn module_pause_flags(module: &CompiledModule, authorizing: &BTreeSet<String>) -> Vec<PauseFlag> {
+    let mut flags = vec![];
+    for (idx, def) in module.struct_defs().iter().enumerate() {
+        let handle = module.struct_handle_at(def.struct_handle);
+        if !handle.abilities.has_key() || !handle.type_parameters.is_empty() {
+            continue;
+        }
+        let def_idx = StructDefinitionIndex(idx as u16);
+        let resource = struct_tag_for_handle(module, def.struct_handle, vec![]);
+        let struct_name = module.identifier_at(handle.name).to_string();
+        if is_marker(module, def_idx) {
+            if let Some(paused_when) = pause_polarity(&struct_name) {
+                flags.push(PauseFlag {
+                    resource,
+                    field: None,
+                    paused_when,
+                    holder: None,
+                    value: None,
+                    writers: writers(module, authorizing, |instr| moves_struct(instr, def_idx)),
+                });
+            }
+            continue;
+        }
+        for (path, paused_when, writers) in struct_switches(module, authorizing, def_idx, "", 0) {
+            flags.push(PauseFlag { resource: resource.clone(), field: Some(path), paused_when, holder: None, value: None, writers });
+        }
+    }
+    flags
+}
+
+impl AptosBB {
+    /// Find pause/emergency switches in the resources of every package at `addr`
+    ///
+    /// Instances are looked up at `addr` itself and at `holders` (resource accounts, config
+    /// objects), reporting their current values and which functions can change them. A marker
+    /// resource found nowhere is reported as absent at `addr`, where `set_pause_flag` puts it.
+    pub fn find_pause_flags(&mut self, addr: AccountAddress, holders: &[AccountAddress]) -> Result<Vec<PauseFlag>> {
+        let modules = self.account_modules(addr)?;
+        let modules: Vec<&CompiledModule> = modules.iter().map(|m| m.as_ref()).collect();
+        let authorizing = authorizing_functions(&modules);
+
+        let mut flags = vec![];
+        for module in &modules {
+            for mut flag in module_pause_flags(module, &authorizing) {
+                for holder in std::iter::once(&addr).chain(holders) {
+                    if let Some(json) = self.read_resource_json(holder, &flag.resource)? {
+                        flag.holder = Some(*holder);
+                        flag.value = match flag.pointer() {
+                            Some(pointer) => json.pointer(&pointer).and_then(Value::as_bool),
+                            None => Some(true),
+                        };
+                        break;
+                    }
+                }
+                if flag.field.is_none() && flag.holder.is_none() {
+                    flag.holder = Some(addr);
+                    flag.value = Some(false);
+                }
+                flags.push(flag);
+            }
+        }
+        for flag in &flags {
+            println!("{}", flag);
+        }
+        Ok(flags)
+    }
+
+    /// Cheatcode: pause or unpause through a switch directly in storage, bypassing the
+    /// protocol's access control
+    ///
+    /// The written value follows the switch's polarity, so `paused: true` writes `false` to
+    /// an `enabled` field, and markers are published or removed.
+    pub fn set_pause_flag(&mut self, flag: &PauseFlag, paused: bool) -> Result<()> {
+        let holder = flag
+            .holder
+            .ok_or_else(|| anyhow!("No instance of {} to set the switch on", flag.resource))?;
+        let value = paused == flag.paused_when;
+        let Some(pointer) = flag.pointer() else {
+            let state_key = StateKey::resource(&holder, &flag.resource)?;
+            if value {
+                // A struct without fields has the single `dummy_field: bool`
+                self.write_state_bytes(state_key, bcs::to_bytes(&false)?);
+            } else {
+                self.delete_state_value(&state_key);
+            }
+            return Ok(());
+        };
+        let mut json = self
+            .read_resource_json(&holder, &flag.resource)?
+            .ok_or_else(|| anyhow!("{} not found at {}", flag.resource, holder))?;
+        *json
+            .pointer_mut(&pointer)
+            .ok_or_else(|| anyhow!("{} has no field {:?}", flag.resource, flag.field))? = Value::Bool(value);
+        self.write_resource_json(&holder, &flag.resource, &json)
+    }
+
+    /// Pause (or unpause) through every switch found by `find_pause_flags` that has an instance
+    pub fn set_all_pause_flags(&mut self, flags: &[PauseFlag], paused: bool) -> Result<()> {
+        for flag in flags.iter().filter(|flag| flag.holder.is_some()) {
+            self.set_pause_flag(flag, paused)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocking;
+    use std::str::FromStr;
+
+    #[test]
+    #[ignore = "forks mainnet"]
+    fn removed_markers_stay_removed() {
+        let mut bb = blocking::block_on(AptosBB::builder().build()).unwrap();
+        // A marker that exists on chain, standing in for a protocol's pause marker
+        let flag = PauseFlag {
+            resource: StructTag::from_str("0x1::chain_status::GenesisEndMarker").unwrap(),
+            field: None,
+            paused_when: true,
+            holder: Some(AccountAddress::ONE),
+            value: Some(true),
+            writers: vec![],
+        };
+        let state_key = StateKey::resource(&AccountAddress::ONE, &flag.resource).unwrap();
+        assert!(bb.read_state_value(&state_key).is_some());
+
+        bb.with_snapshot(|bb| {
+            bb.set_pause_flag(&flag, false).unwrap();
+            assert!(bb.read_state_value(&state_key).is_none());
+            assert!(bb.fetch_state_value(&state_key).unwrap().is_none());
+            assert!(bb.read_state_value(&state_key).is_none());
+        });
+        assert!(bb.read_state_value(&state_key).is_some());
+    }
+}
@@ -0,0 +1,159 @@
+//! Where signers and capabilities flow inside a package
+
+use super::{authorizing_functions, patterns::CAPABILITY_SUFFIXES, Finding, FunctionView};
+use crate::AptosBB;
+use anyhow::Result;
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{Bytecode, StructDefinitionIndex},
+    CompiledModule,
+};
+use move_core_types::language_storage::ModuleId;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Framework functions that turn a stored capability into authority
+const PRIVILEGED_CALLS: &[&str] = &[
+    "0x1::account::create_signer_with_capability",
+    "0x1::object::generate_signer",
+    "0x1::object::generate_signer_for_extending",
+    "0x1::resource_account::retrieve_resource_account_cap",
+    "0x1::coin::mint",
+    "0x1::coin::burn",
+    "0x1::coin::burn_from",
+    "0x1::coin::freeze_coin_store",
+    "0x1::fungible_asset::mint",
+    "0x1::fungible_asset::burn",
+    "0x1::fungible_asset::transfer_with_ref",
+    "0x1::fungible_asset::withdraw_with_ref",
+    "0x1::primary_fungible_store::mint",
+    "0x1::primary_fungible_store::burn",
+    "0x1::primary_fungible_store::transfer_with_ref",
+    "0x1::object::transfer_with_ref",
+];
+
+/// What a single function does with privileged values
+#[derive(Clone, Debug)]
+struct FunctionFacts {
+    module: ModuleId,
+    name: String,
+    externally_callable: bool,
+    /// Checks its caller, itself or through a callee (see `FunctionView::checks_caller`)
+    authorizes: bool,
+    /// Capability resources loaded from global storage and privileged framework calls made
+    sources: BTreeSet<String>,
+    callees: Vec<String>,
+}
+
+fn struct_def_name(module: &CompiledModule, idx: StructDefinitionIndex) -> String {
+    let handle = module.struct_handle_at(module.struct_def_at(idx).struct_handle);
+    module.identifier_at(handle.name).to_string()
+}
+
+fn function_facts(function: &FunctionView, authorizes: bool) -> FunctionFacts {
+    let module = function.module;
+    let callees = function.callees();
+    let mut sources: BTreeSet<String> = callees
+        .iter()
+        .filter(|callee| PRIVILEGED_CALLS.contains(&callee.as_str()))
+        .cloned()
+        .collect();
+
+    for instr in function.code() {
+        let def_idx = match instr {
+            Bytecode::ImmBorrowGlobal(idx) | Bytecode::MutBorrowGlobal(idx) | Bytecode::MoveFrom(idx) => *idx,
+            Bytecode::ImmBorrowGlobalGeneric(idx)
+            | Bytecode::MutBorrowGlobalGeneric(idx)
+            | Bytecode::MoveFromGeneric(idx) => module.struct_instantiation_at(*idx).def,
+            _ => continue,
+        };
+        let name = struct_def_name(module, def_idx);
+        if CAPABILITY_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+            sources.insert(format!("{}::{}", module.self_id(), name));
+        }
+    }
+
+    FunctionFacts {
+        module: module.self_id(),
+        name: function.name(),
+        externally_callable: function.is_externally_callable(),
+        authorizes,
+        sources,
+        callees,
+    }
+}
+
+/// Flag externally callable functions that reach a capability without the caller proving
+/// who they are
+///
+/// Starting from every public/entry function that does not check its caller, calls are
+/// followed within the analyzed modules (stopping at functions that do). Taking a `&signer`
+/// is not enough: the signer's address has to be checked against stored admin/owner state,
+/// or used to look up a capability. Any path that ends in a function loading a capability or
+/// making a privileged framework call is reported.
+pub fn capability_flows(modules: &[&CompiledModule]) -> Vec<Finding> {
+    let authorizing = authorizing_functions(modules);
+    let mut facts = BTreeMap::<String, FunctionFacts>::new();
+    for module in modules {
+        for function in FunctionView::all(module) {
+            let id = function.qualified_name();
+            let authorizes = authorizing.contains(&id);
+            facts.insert(id, function_facts(&function, authorizes));
+        }
+    }
+
+    let mut findings = vec![];
+    for (root, root_facts) in &facts {
+        if !root_facts.externally_callable || root_facts.authorizes {
+            continue;
+        }
+
+        let mut parents = BTreeMap::<&str, &str>::new();
+        let mut seen = BTreeSet::from([root.as_str()]);
+        let mut queue = VecDeque::from([root.as_str()]);
+        while let Some(current) = queue.pop_front() {
+            let current_facts = &facts[current];
+            if !current_facts.sources.is_empty() {
+                let mut path = vec![current.to_string()];
+                let mut node = current;
+                while let Some(parent) = parents.get(node) {
+                    path.push(parent.to_string());
+                    node = parent;
+                }
+                path.reverse();
+                let sources: Vec<_> = current_facts.sources.iter().cloned().collect();
+                findings.push(Finding::lead(
+                    "capability-reachable",
+                    &root_facts.module,
+                    Some(root_facts.name.clone()),
+                    format!(
+                        "callable without an authorization check yet reaches {} via {}",
+                        sources.join(", "),
+                        path.join(" -> ")
+                    ),
+                ));
+                break;
+            }
+            for callee in &current_facts.callees {
+                let Some((callee_id, callee_facts)) = facts.get_key_value(callee) else {
+                    continue;
+                };
+                if callee_facts.authorizes || !seen.insert(callee_id.as_str()) {
+                    continue;
+                }
+                parents.insert(callee_id.as_str(), current);
+                queue.push_back(callee_id.as_str());
+            }
+        }
+    }
+    findings
+}
+
+impl AptosBB {
+    /// Track signer and capability flow through a package and report capabilities reachable by anyone
+    pub fn capability_taint(&mut self, addr: AccountAddress, package: &str) -> Result<Vec<Finding>> {
+        let modules = self.package_modules(addr, package)?;
+        let modules: Vec<&CompiledModule> = modules.iter().map(|m| m.as_ref()).collect();
+        Ok(capability_flows(&modules))
+    }
+}
@@ -0,0 +1,321 @@
+use crate::{view::signature_to_type_tag, AptosBB};
+use anyhow::{anyhow, bail, Result};
+use aptos_api_types::MoveValue as JsonMoveValue;
+use aptos_resource_viewer::{AnnotatedMoveStruct, AnnotatedMoveValue, AptosValueAnnotator};
+use aptos_types::{account_address::AccountAddress, state_store::state_key::StateKey};
+use move_binary_format::{access::ModuleAccess, file_format::StructFieldInformation};
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    u256::U256,
+};
+use serde_json::Value;
+use std::str::FromStr;
+
+/// Type of a value to convert, either already parsed or as a Move type string
+/// such as `0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>`
+pub trait IntoTypeTag {
+    fn into_type_tag(self) -> Result<TypeTag>;
+}
+
+impl IntoTypeTag for TypeTag {
+    fn into_type_tag(self) -> Result<TypeTag> {
+        Ok(self)
+    }
+}
+
+impl IntoTypeTag for &TypeTag {
+    fn into_type_tag(self) -> Result<TypeTag> {
+        Ok(self.clone())
+    }
+}
+
+impl IntoTypeTag for StructTag {
+    fn into_type_tag(self) -> Result<TypeTag> {
+        Ok(TypeTag::Struct(Box::new(self)))
+    }
+}
+
+impl IntoTypeTag for &StructTag {
+    fn into_type_tag(self) -> Result<TypeTag> {
+        Ok(TypeTag::Struct(Box::new(self.clone())))
+    }
+}
+
+impl IntoTypeTag for &str {
+    fn into_type_tag(self) -> Result<TypeTag> {
+        TypeTag::from_str(self).map_err(|e| anyhow!("Invalid type {}: {}", self, e))
+    }
+}
+
+impl IntoTypeTag for &String {
+    fn into_type_tag(self) -> Result<TypeTag> {
+        self.as_str().into_type_tag()
+    }
+}
+
+impl AptosBB {
+    /// Annotator resolving type layouts against the fork's current state
+    pub(crate) fn annotator(&self) -> AptosValueAnnotator<'_, impl aptos_types::state_store::StateView + '_> {
+        AptosValueAnnotator::new(self.executor.get_state_view())
+    }
+
+    /// Decode BCS bytes of a value of the given type into an annotated value
+    pub fn annotate_value(&self, type_tag: &TypeTag, bytes: &[u8]) -> Result<AnnotatedMoveValue> {
+        self.annotator().view_value(type_tag, bytes)
+    }
+
+    /// Decode BCS bytes of a value of the given type into the API's JSON representation
+    pub fn value_to_json(&self, type_tag: &TypeTag, bytes: &[u8]) -> Result<serde_json::Value> {
+        JsonMoveValue::try_from(self.annotate_value(type_tag, bytes)?)?.json()
+    }
+
+    /// Decode BCS bytes into JSON, with the type given as a tag or a Move type string
+    ///
+    /// Struct layouts (field names, enum variants) are resolved against the fork, so this
+    /// works for resources, events and arguments of any deployed module.
+    pub fn bcs_to_json(&self, ty: impl IntoTypeTag, bytes: &[u8]) -> Result<Value> {
+        self.value_to_json(&ty.into_type_tag()?, bytes)
+    }
+}
+
+/// Field access into annotated values by a dotted path
+///
+/// Segments are field names for structs and indices for vectors, e.g. `"positions.0.debt"`;
+/// Move `Option`s are vectors under the field `vec`, so `"fee.vec.0"` reads a set option.
+pub trait AnnotatedPath {
+    fn get_path(&self, path: &str) -> Option<&AnnotatedMoveValue>;
+}
+
+fn struct_field<'a>(value: &'a AnnotatedMoveStruct, name: &str) -> Option<&'a AnnotatedMoveValue> {
+    value.value.iter().find(|(field, _)| field.as_str() == name).map(|(_, value)| value)
+}
+
+fn value_at<'a>(value: &'a AnnotatedMoveValue, segment: &str) -> Option<&'a AnnotatedMoveValue> {
+    match value {
+        AnnotatedMoveValue::Struct(inner) => struct_field(inner, segment),
+        AnnotatedMoveValue::Vector(_, items) => items.get(segment.parse::<usize>().ok()?),
+        _ => None,
+    }
+}
+
+impl AnnotatedPath for AnnotatedMoveStruct {
+    fn get_path(&self, path: &str) -> Option<&AnnotatedMoveValue> {
+        let mut segments = path.split('.');
+        let first = struct_field(self, segments.next()?)?;
+        segments.try_fold(first, value_at)
+    }
+}
+
+impl AnnotatedPath for AnnotatedMoveValue {
+    fn get_path(&self, path: &str) -> Option<&AnnotatedMoveValue> {
+        path.split('.').try_fold(self, value_at)
+    }
+}
+
+/// ULEB128 length prefix used by BCS for sequences
+fn write_uleb128(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// Integer from the API encoding: small integers are numbers, wide ones strings
+fn json_integer<T: FromStr>(json: &Value) -> Result<T>
+where
+    T::Err: std::fmt::Display,
+{
+    let text = match json {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        _ => bail!("Expected an integer, got {}", json),
+    };
+    text.parse().map_err(|e| anyhow!("Invalid integer {}: {}", text, e))
+}
+
+fn is_string_type(tag: &StructTag) -> bool {
+    tag.address == AccountAddress::ONE && tag.module.as_str() == "string" && tag.name.as_str() == "String"
+}
+
+/// Encode JSON as BCS, leaving structs other than strings to `encode_struct`, which needs
+/// their on-chain layouts
+fn encode_json_with(
+    type_tag: &TypeTag,
+    json: &Value,
+    out: &mut Vec<u8>,
+    encode_struct: &mut dyn FnMut(&StructTag, &Value, &mut Vec<u8>) -> Result<()>,
+) -> Result<()> {
+    match type_tag {
+        TypeTag::Bool => out.push(json.as_bool().ok_or_else(|| anyhow!("Expected a bool, got {}", json))? as u8),
+        TypeTag::U8 => out.extend(bcs::to_bytes(&json_integer::<u8>(json)?)?),
+        TypeTag::U16 => out.extend(bcs::to_bytes(&json_integer::<u16>(json)?)?),
+        TypeTag::U32 => out.extend(bcs::to_bytes(&json_integer::<u32>(json)?)?),
+        TypeTag::U64 => out.extend(bcs::to_bytes(&json_integer::<u64>(json)?)?),
+        TypeTag::U128 => out.extend(bcs::to_bytes(&json_integer::<u128>(json)?)?),
+        TypeTag::U256 => out.extend(bcs::to_bytes(&json_integer::<U256>(json)?)?),
+        TypeTag::Address => {
+            let text = json.as_str().ok_or_else(|| anyhow!("Expected an address, got {}", json))?;
+            out.extend(AccountAddress::from_str(text)?.to_vec());
+        }
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+            let text = json.as_str().ok_or_else(|| anyhow!("Expected hex bytes, got {}", json))?;
+            let bytes = hex::decode(text.trim_start_matches("0x"))?;
+            write_uleb128(out, bytes.len());
+            out.extend(bytes);
+        }
+        TypeTag::Vector(inner) => {
+            let items = json.as_array().ok_or_else(|| anyhow!("Expected an array, got {}", json))?;
+            write_uleb128(out, items.len());
+            for item in items {
+                encode_json_with(inner, item, out, encode_struct)?;
+            }
+        }
+        TypeTag::Struct(tag) if is_string_type(tag) => {
+            let text = json.as_str().ok_or_else(|| anyhow!("Expected a string, got {}", json))?;
+            write_uleb128(out, text.len());
+            out.extend(text.as_bytes());
+        }
+        TypeTag::Struct(tag) => encode_struct(tag, json, out)?,
+        other => bail!("Cannot encode values of type {}", other),
+    }
+    Ok(())
+}
+
+impl AptosBB {
+    /// Encode a value given in the API's JSON representation as BCS, using on-chain layouts
+    ///
+    /// This is the inverse of `bcs_to_json`, so a decoded value can be edited and written back,
+    /// or an entry function argument can be written as JSON.
+    pub fn json_to_bcs(&mut self, ty: impl IntoTypeTag, json: &Value) -> Result<Vec<u8>> {
+        let mut out = vec![];
+        self.encode_json(&ty.into_type_tag()?, json, &mut out)?;
+        Ok(out)
+    }
+
+    fn encode_json(&mut self, type_tag: &TypeTag, json: &Value, out: &mut Vec<u8>) -> Result<()> {
+        encode_json_with(type_tag, json, out, &mut |tag, json, out| self.encode_struct(tag, json, out))
+    }
+
+    fn encode_struct(&mut self, tag: &StructTag, json: &Value, out: &mut Vec<u8>) -> Result<()> {
+        let object = json.as_object().ok_or_else(|| anyhow!("Expected an object for {}, got {}", tag, json))?;
+        let module = self.get_module(&tag.module_id())?;
+        let def = module
+            .struct_defs()
+            .iter()
+            .find(|def| module.identifier_at(module.struct_handle_at(def.struct_handle).name) == tag.name.as_ident_str())
+            .ok_or_else(|| anyhow!("Struct {} not found", tag))?;
+
+        let fields = match &def.field_information {
+            StructFieldInformation::Native => bail!("Native struct {} has no JSON encoding", tag),
+            StructFieldInformation::Declared(fields) => fields,
+            StructFieldInformation::DeclaredVariants(variants) => {
+                let name = object
+                    .get("__variant__")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| anyhow!("Enum {} value needs a __variant__ name", tag))?;
+                let index = variants
+                    .iter()
+                    .position(|variant| module.identifier_at(variant.name).as_str() == name)
+                    .ok_or_else(|| anyhow!("Enum {} has no variant {}", tag, name))?;
+                write_uleb128(out, index);
+                &variants[index].fields
+            }
+        };
+
+        let mut layout = Vec::with_capacity(fields.len());
+        for field in fields {
+            let field_type = signature_to_type_tag(&module, &field.signature.0, &tag.type_args)?;
+            layout.push((module.identifier_at(field.name).to_string(), field_type));
+        }
+        for (name, field_type) in layout {
+            let value = object.get(&name).ok_or_else(|| anyhow!("Missing field {} of {}", name, tag))?;
+            self.encode_json(&field_type, value, out)?;
+        }
+        Ok(())
+    }
+
+    /// Raw bytes of a resource, taken out of its resource group's slot if it is a member
+    fn read_resource_bytes(&self, addr: &AccountAddress, struct_tag: &StructTag) -> Result<Option<Vec<u8>>> {
+        match self.read_resource_group_of(struct_tag)? {
+            Some(group) => Ok(self.read_resource_group(addr, &group)?.remove(struct_tag)),
+            None => Ok(self.read_state_value(&StateKey::resource(addr, struct_tag)?).map(|value| value.bytes().to_vec())),
+        }
+    }
+
+    /// Read a resource in the API's JSON representation, looking inside its resource group if needed
+    pub fn read_resource_json(&self, addr: &AccountAddress, struct_tag: &StructTag) -> Result<Option<Value>> {
+        let type_tag = TypeTag::Struct(Box::new(struct_tag.clone()));
+        self.read_resource_bytes(addr, struct_tag)?
+            .map(|bytes| self.value_to_json(&type_tag, &bytes))
+            .transpose()
+    }
+
+    /// Read a resource as an annotated struct, looking inside its resource group if needed
+    ///
+    /// The result prints as an indented tree with `{}` and nested fields (options, vectors
+    /// of structs, objects) are reached with `AnnotatedPath::get_path`, without defining
+    /// serde types for third-party structs.
+    pub fn annotate_resource(&self, addr: &AccountAddress, struct_tag: &StructTag) -> Result<Option<AnnotatedMoveStruct>> {
+        self.read_resource_bytes(addr, struct_tag)?
+            .map(|bytes| self.annotator().view_resource(struct_tag, &bytes))
+            .transpose()
+    }
+
+    /// Overwrite a resource from its JSON representation, e.g. an edited `read_resource_json` result
+    ///
+    /// Members of a resource group (such as everything stored at an object) are written back
+    /// into the group's slot; anything else is written as a standalone resource.
+    pub fn write_resource_json(&mut self, addr: &AccountAddress, struct_tag: &StructTag, json: &Value) -> Result<()> {
+        let bytes = self.json_to_bcs(&TypeTag::Struct(Box::new(struct_tag.clone())), json)?;
+        match self.resource_group_of(struct_tag)? {
+            Some(group) => {
+                let mut members = self.read_resource_group(addr, &group)?;
+                members.insert(struct_tag.clone(), bytes);
+                self.write_resource_group(addr, &group, &members)
+            }
+            None => {
+                self.write_state_bytes(StateKey::resource(addr, struct_tag)?, bytes);
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn encode(ty: &str, json: Value) -> Result<Vec<u8>> {
+        let mut out = vec![];
+        encode_json_with(&ty.into_type_tag()?, &json, &mut out, &mut |tag, _, _| bail!("no layout for {}", tag))?;
+        Ok(out)
+    }
+
+    #[test]
+    fn primitives_accept_numbers_and_strings() {
+        assert_eq!(encode("u64", json!(5)).unwrap(), bcs::to_bytes(&5u64).unwrap());
+        assert_eq!(encode("u128", json!(u128::MAX.to_string())).unwrap(), bcs::to_bytes(&u128::MAX).unwrap());
+        assert_eq!(encode("bool", json!(true)).unwrap(), [1]);
+        assert_eq!(encode("address", json!("0x1")).unwrap(), AccountAddress::ONE.to_vec());
+        assert!(encode("u8", json!(256)).is_err());
+        assert!(encode("u64", json!(-1)).is_err());
+    }
+
+    #[test]
+    fn vectors_and_strings_are_length_prefixed() {
+        assert_eq!(encode("vector<u8>", json!("0x0102")).unwrap(), [2, 1, 2]);
+        assert_eq!(encode("vector<u16>", json!([1, 2])).unwrap(), bcs::to_bytes(&vec![1u16, 2]).unwrap());
+        assert_eq!(encode("0x1::string::String", json!("hé")).unwrap(), bcs::to_bytes("hé").unwrap());
+        let long = "a".repeat(200);
+        assert_eq!(encode("0x1::string::String", json!(long)).unwrap(), bcs::to_bytes(&long).unwrap());
+    }
+
+    #[test]
+    fn other_structs_are_delegated() {
+        let err = encode("vector<0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin>>", json!([{}])).unwrap_err();
+        assert!(err.to_string().contains("no layout for 0x1::coin::CoinInfo"));
+        assert_eq!(encode("vector<0x1::coin::CoinInfo<0x1::aptos_coin::AptosCoin>>", json!([])).unwrap(), [0]);
+    }
+}
@@ -0,0 +1,217 @@
+use crate::{entry::EntryCall, AptosBB};
+use anyhow::{bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::transaction::{ExecutionStatus, TransactionPayload, TransactionStatus};
+#[cfg(feature = "criterion")]
+use criterion::Criterion;
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Whether iterations see each other's state changes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BenchMode {
+    /// Every iteration starts from the state after warm-up
+    #[default]
+    Isolated,
+    /// State accumulates across iterations, exposing costs that grow with state size
+    Cumulative,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct BenchOptions {
+    /// Untimed runs that pull every touched state key into the fork before measuring
+    pub warmup: usize,
+    pub iterations: usize,
+    pub mode: BenchMode,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self { warmup: 3, iterations: 50, mode: BenchMode::Isolated }
+    }
+}
+
+/// One measured execution
+#[derive(Clone, Debug)]
+pub struct BenchSample {
+    /// Time spent in the executor, excluding signing and AptosBB bookkeeping
+    pub elapsed: Duration,
+    pub gas_used: u64,
+    pub status: TransactionStatus,
+}
+
+/// Distribution of a measured quantity
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stats {
+    pub min: f64,
+    pub median: f64,
+    pub mean: f64,
+    pub max: f64,
+    pub std_dev: f64,
+}
+
+impl Stats {
+    fn of(values: impl IntoIterator<Item = f64>) -> Self {
+        let mut values: Vec<f64> = values.into_iter().collect();
+        if values.is_empty() {
+            return Self::default();
+        }
+        values.sort_by(f64::total_cmp);
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+        let mid = values.len() / 2;
+        let median = if values.len() % 2 == 0 { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] };
+        Self {
+            min: values[0],
+            median,
+            mean,
+            max: values[values.len() - 1],
+            std_dev: variance.sqrt(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BenchReport {
+    pub mode: BenchMode,
+    pub samples: Vec<BenchSample>,
+}
+
+impl BenchReport {
+    /// Executor time per iteration, in microseconds
+    pub fn time_us(&self) -> Stats {
+        Stats::of(self.samples.iter().map(|s| s.elapsed.as_secs_f64() * 1e6))
+    }
+
+    pub fn gas(&self) -> Stats {
+        Stats::of(self.samples.iter().map(|s| s.gas_used as f64))
+    }
+
+    /// Least-squares slope of gas used against iteration index
+    ///
+    /// Close to zero when the cost is flat. In `Cumulative` mode a clearly positive slope
+    /// means each call gets more expensive as state builds up, i.e. total cost is superlinear.
+    pub fn gas_slope(&self) -> f64 {
+        let n = self.samples.len() as f64;
+        if self.samples.len() < 2 {
+            return 0.0;
+        }
+        let mean_x = (n - 1.0) / 2.0;
+        let mean_y = self.gas().mean;
+        let (cov, var) = self.samples.iter().enumerate().fold((0.0, 0.0), |(cov, var), (i, s)| {
+            let dx = i as f64 - mean_x;
+            (cov + dx * (s.gas_used as f64 - mean_y), var + dx * dx)
+        });
+        cov / var
+    }
+
+    pub fn failures(&self) -> usize {
+        self.samples
+            .iter()
+            .filter(|s| s.status != TransactionStatus::Keep(ExecutionStatus::Success))
+            .count()
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = self.time_us();
+        let gas = self.gas();
+        writeln!(f, "{} iterations ({:?}), {} failed", self.samples.len(), self.mode, self.failures())?;
+        writeln!(
+            f,
+            "  time: median {:.1}µs, mean {:.1}µs ± {:.1}, min {:.1}µs, max {:.1}µs",
+            time.median, time.mean, time.std_dev, time.min, time.max
+        )?;
+        write!(
+            f,
+            "  gas:  median {:.0}, mean {:.1} ± {:.1}, min {:.0}, max {:.0}, slope {:+.2}/iter",
+            gas.median, gas.mean, gas.std_dev, gas.min, gas.max, self.gas_slope()
+        )
+    }
+}
+
+impl AptosBB {
+    /// Sign and execute a payload, timing only the executor
+    fn timed_execution(&mut self, account: &Account, payload: TransactionPayload) -> BenchSample {
+        let txn = self.sign_transaction(account, payload);
+        let started = Instant::now();
+        let output = self.executor.execute_and_apply(txn);
+        let elapsed = started.elapsed();
+        self.module_cache.invalidate(output.write_set());
+        self.view_cache.invalidate();
+        BenchSample {
+            elapsed,
+            gas_used: output.gas_used(),
+            status: output.status().clone(),
+        }
+    }
+
+    /// Measure executor time and gas of an entry call over many iterations
+    ///
+    /// Measurement starts from the state left by the warm-up runs, so measured iterations
+    /// never hit the network. The fork is restored to its pre-benchmark state afterwards.
+    /// Transactions executed here bypass event subscribers, exporters and session recording.
+    pub fn bench(&mut self, account: &Account, call: EntryCall, options: BenchOptions) -> Result<BenchReport> {
+        if options.iterations == 0 {
+            bail!("Benchmark needs at least one iteration");
+        }
+        let payload = call.into_payload()?;
+
+        let report = self.with_snapshot(|bb| {
+            for _ in 0..options.warmup {
+                bb.timed_execution(account, payload.clone());
+            }
+
+            let warm = bb.checkpoint();
+            let samples = (0..options.iterations)
+                .map(|_| {
+                    let sample = bb.timed_execution(account, payload.clone());
+                    if options.mode == BenchMode::Isolated {
+                        bb.revert(&warm);
+                    }
+                    sample
+                })
+                .collect();
+            BenchReport { mode: options.mode, samples }
+        });
+        println!("{}", report);
+        Ok(report)
+    }
+}
+
+/// Register an entry call as a Criterion benchmark measuring executor time
+///
+/// Each Criterion iteration starts from the state left by one warm-up run; the fork is
+/// restored to its original state once Criterion is done. Requires the `criterion` feature.
+///
+/// ```ignore
+/// fn benches(c: &mut Criterion) {
+///     let mut bb = blocking::block_on(AptosBB::builder().version(2_000_000_000).build()).unwrap();
+///     let user = bb.new_account();
+///     bench::criterion_bench(c, "vault::deposit", &mut bb, &user, deposit_call()).unwrap();
+/// }
+/// ```
+#[cfg(feature = "criterion")]
+pub fn criterion_bench(c: &mut Criterion, name: &str, bb: &mut AptosBB, account: &Account, call: EntryCall) -> Result<()> {
+    let payload = call.into_payload()?;
+    bb.with_snapshot(|bb| {
+        bb.timed_execution(account, payload.clone());
+        let warm = bb.checkpoint();
+        c.bench_function(name, |b| {
+            b.iter_custom(|iters| {
+                (0..iters)
+                    .map(|_| {
+                        let elapsed = bb.timed_execution(account, payload.clone()).elapsed;
+                        bb.revert(&warm);
+                        elapsed
+                    })
+                    .sum()
+            })
+        });
+    });
+    Ok(())
+}
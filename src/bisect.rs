@@ -0,0 +1,135 @@
+use crate::{blocking, AptosBB};
+use anyhow::{bail, Result};
+use aptos_api_types::TransactionData;
+use aptos_crypto::hash::CryptoHash;
+use aptos_types::transaction::Transaction;
+use std::ops::RangeInclusive;
+
+/// Transition point found by `AptosBB::bisect`
+#[derive(Clone, Debug)]
+pub struct BisectResult {
+    /// Last version with the original outcome
+    pub last_before: u64,
+    /// First version with the flipped outcome
+    pub first_after: u64,
+    /// Outcome at `last_before`; `true` means the bug was fixed at `first_after`,
+    /// `false` that it was introduced there
+    pub outcome_before: bool,
+    /// Short description of the transaction at `first_after`
+    pub culprit: String,
+    /// Number of forks executed
+    pub steps: usize,
+}
+
+fn describe(txn: &Transaction) -> String {
+    match txn {
+        Transaction::UserTransaction(signed) => {
+            let hash = txn.hash();
+            match signed.payload() {
+                aptos_types::transaction::TransactionPayload::EntryFunction(entry) => format!(
+                    "user transaction {} from {} calling {}::{}",
+                    hash.to_hex_literal(),
+                    signed.sender(),
+                    entry.module(),
+                    entry.function()
+                ),
+                _ => format!("user transaction {} from {}", hash.to_hex_literal(), signed.sender()),
+            }
+        }
+        Transaction::GenesisTransaction(_) => "write-set (governance/genesis) transaction".to_string(),
+        Transaction::BlockMetadata(_) | Transaction::BlockMetadataExt(_) => "block metadata".to_string(),
+        _ => "system transaction".to_string(),
+    }
+}
+
+/// Binary search for adjacent versions with different `probe` outcomes: the last version
+/// with the original outcome, the first with the flipped one, the original outcome and the
+/// number of probes
+fn find_flip(
+    versions: RangeInclusive<u64>,
+    mut probe: impl FnMut(u64) -> Result<bool>,
+) -> Result<(u64, u64, bool, usize)> {
+    let (mut low, mut high) = versions.into_inner();
+    let outcome_before = probe(low)?;
+    if probe(high)? == outcome_before {
+        bail!("Scenario outcome is the same at versions {} and {}", low, high);
+    }
+    let mut steps = 2;
+
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if probe(mid)? == outcome_before {
+            low = mid;
+        } else {
+            high = mid;
+        }
+        steps += 1;
+    }
+    Ok((low, high, outcome_before, steps))
+}
+
+impl AptosBB {
+    /// Binary-search the ledger for the version at which a scenario's outcome flips
+    ///
+    /// `scenario` runs on a fresh fork at each probed version and reports whether the bug
+    /// reproduces. The outcomes at both (inclusive) ends of `versions` must differ; each probe costs
+    /// one fork, so a range of `n` versions needs about `log2(n)` forks.
+    pub fn bisect(
+        &self,
+        mut scenario: impl FnMut(&mut AptosBB) -> Result<bool>,
+        versions: RangeInclusive<u64>,
+    ) -> Result<BisectResult> {
+        let probe = |version: u64| -> Result<bool> {
+            let mut bb = self.fork_at(version)?;
+            let reproduced = scenario(&mut bb)?;
+            println!("  version {}: {}", version, if reproduced { "reproduces" } else { "does not reproduce" });
+            Ok(reproduced)
+        };
+
+        let (low, high, outcome_before, steps) = find_flip(versions, probe)?;
+
+        let data = blocking::block_on(async { anyhow::Ok(self.remote.client().await?.get_transaction_by_version_bcs(high).await?) })?
+            .into_inner();
+        let culprit = match data {
+            TransactionData::OnChain(txn) => describe(&txn.transaction),
+            TransactionData::Pending(_) => "pending transaction".to_string(),
+        };
+
+        println!(
+            "Outcome flips at version {} ({}) after {} forks: {}",
+            high,
+            if outcome_before { "fixed" } else { "introduced" },
+            steps,
+            culprit
+        );
+        Ok(BisectResult {
+            last_before: low,
+            first_after: high,
+            outcome_before,
+            culprit,
+            steps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_flip_locates_the_transition_in_log_steps() {
+        let (low, high, before, steps) = find_flip(0..=1_000_000, |version| Ok(version >= 777_777)).unwrap();
+        assert_eq!((low, high, before), (777_776, 777_777, false));
+        assert!(steps <= 2 + 20);
+
+        let (low, high, before, _) = find_flip(10..=11, |version| Ok(version < 11)).unwrap();
+        assert_eq!((low, high, before), (10, 11, true));
+    }
+
+    #[test]
+    fn find_flip_needs_different_outcomes_at_the_ends() {
+        assert!(find_flip(0..=100, |_| Ok(true)).is_err());
+        let failing = |version| if version == 50 { Err(anyhow::anyhow!("fork failed")) } else { Ok(version > 50) };
+        assert!(find_flip(0..=100, failing).is_err());
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{calls::DecodedCall, export::function_name, AptosBB};
+use anyhow::{anyhow, bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{
+        signature_verified_transaction::into_signature_verified_block, ExecutionStatus, SignedTransaction,
+        Transaction, TransactionPayload, TransactionStatus,
+    },
+};
+use std::fmt;
+
+/// What became of one transaction of a block
+#[derive(Clone, Debug)]
+pub struct BlockTxnOutcome {
+    /// Position in the block
+    pub index: usize,
+    pub sender: AccountAddress,
+    pub function: String,
+    pub call: Option<DecodedCall>,
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    /// Gas fee charged to the sender, in octas; aborted transactions pay it too
+    pub gas_fee: u64,
+}
+
+impl BlockTxnOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.status == TransactionStatus::Keep(ExecutionStatus::Success)
+    }
+
+    /// Kept on chain but failed: only gas and the sequence number were charged
+    pub fn aborted(&self) -> bool {
+        matches!(self.status, TransactionStatus::Keep(ref status) if *status != ExecutionStatus::Success)
+    }
+
+    /// Rejected before execution, leaving no trace on chain
+    pub fn discarded(&self) -> bool {
+        matches!(self.status, TransactionStatus::Discard(_))
+    }
+
+    /// Not executed because the block gas limit was reached; it would go into a later block
+    pub fn retried(&self) -> bool {
+        self.status == TransactionStatus::Retry
+    }
+}
+
+impl fmt::Display for BlockTxnOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = if self.succeeded() {
+            "✅"
+        } else if self.aborted() {
+            "↩️ "
+        } else if self.retried() {
+            "⏭️ "
+        } else {
+            "🚫"
+        };
+        let call = self.call.as_ref().map_or_else(|| self.function.clone(), |call| call.to_string());
+        write!(f, "{} #{} {} {} -> {:?} ({} gas)", mark, self.index, self.sender, call, self.status, self.gas_used)
+    }
+}
+
+/// Per-transaction outcomes of a block run through the block executor
+#[derive(Clone, Debug)]
+pub struct BlockReport {
+    pub txns: Vec<BlockTxnOutcome>,
+}
+
+impl BlockReport {
+    pub fn succeeded(&self) -> Vec<&BlockTxnOutcome> {
+        self.txns.iter().filter(|txn| txn.succeeded()).collect()
+    }
+
+    pub fn aborted(&self) -> Vec<&BlockTxnOutcome> {
+        self.txns.iter().filter(|txn| txn.aborted()).collect()
+    }
+
+    pub fn discarded(&self) -> Vec<&BlockTxnOutcome> {
+        self.txns.iter().filter(|txn| txn.discarded()).collect()
+    }
+
+    pub fn retried(&self) -> Vec<&BlockTxnOutcome> {
+        self.txns.iter().filter(|txn| txn.retried()).collect()
+    }
+
+    /// Gas used by the transactions the block committed
+    ///
+    /// Plain gas units; the block gas limit is weighed against the executor's effective gas,
+    /// which can be higher when transactions conflict.
+    pub fn gas_used(&self) -> u64 {
+        self.txns.iter().map(|txn| txn.gas_used).sum()
+    }
+
+    /// Transactions sent by `sender`, in block order
+    pub fn sent_by(&self, sender: &AccountAddress) -> Vec<&BlockTxnOutcome> {
+        self.txns.iter().filter(|txn| txn.sender == *sender).collect()
+    }
+
+    /// Gas fees `sender` paid for transactions that aborted
+    ///
+    /// For a revert-shielded attack (each attempt aborts unless it turns a profit) this is
+    /// the full cost of failing: compare it with the profit of the attempts that succeeded.
+    pub fn shielded_cost(&self, sender: &AccountAddress) -> u64 {
+        self.sent_by(sender).into_iter().filter(|txn| txn.aborted()).map(|txn| txn.gas_fee).sum()
+    }
+}
+
+impl fmt::Display for BlockReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for txn in &self.txns {
+            writeln!(f, "{}", txn)?;
+        }
+        write!(
+            f,
+            "{} succeeded, {} aborted, {} discarded, {} retried ({} gas)",
+            self.succeeded().len(),
+            self.aborted().len(),
+            self.discarded().len(),
+            self.retried().len(),
+            self.gas_used()
+        )
+    }
+}
+
+impl AptosBB {
+    /// Execute transactions as one block through the block executor
+    ///
+    /// As on chain, a failing transaction does not stop the block: an aborted one only
+    /// charges gas and its sequence number, while a discarded one (e.g. unable to pay gas)
+    /// leaves no trace and its sender's next transaction reuses the sequence number. Later
+    /// transactions always execute, so attackers can pad a block with attempts that revert
+    /// themselves when unprofitable.
+    ///
+    /// With a block gas limit set (`set_block_gas_limit`), the executor stops after the
+    /// transaction that reaches it and the rest come back as retried. Blocks run on the
+    /// main-branch VM. Event subscribers, scenario limits, storage tracking and summaries see
+    /// their transactions; exports, session reports and staleness checks capture state before
+    /// each transaction, so they only record transactions executed one at a time.
+    pub fn execute_block(&mut self, txns: &[(Account, TransactionPayload)]) -> Result<BlockReport> {
+        let report = self.run_block(txns)?;
+        println!("{}", report);
+        Ok(report)
+    }
+
+    /// `execute_block` without printing the report
+    pub(crate) fn run_block(&mut self, txns: &[(Account, TransactionPayload)]) -> Result<BlockReport> {
+        if let Some(exceeded) = self.check_limits() {
+            bail!("Block refused: scenario {}", exceeded);
+        }
+        let config = self.block_executor_config()?;
+        let signed: Vec<SignedTransaction> = txns
+            .iter()
+            .map(|(account, payload)| self.sign_transaction(account, payload.clone()))
+            .collect();
+        let block = into_signature_verified_block(signed.iter().cloned().map(Transaction::UserTransaction).collect());
+        let outputs = match self.executor.execute_transaction_block_impl_with_state_view(
+            &block,
+            config,
+            false,
+            self.executor.data_store(),
+        ) {
+            Ok(outputs) if outputs.len() == signed.len() => outputs,
+            result => {
+                for txn in signed.iter().rev() {
+                    self.release_sequence_number(txn.sender(), txn.sequence_number());
+                }
+                return Err(match result {
+                    Ok(outputs) => anyhow!("Executor returned {} outputs for {} transactions", outputs.len(), signed.len()),
+                    Err(status) => anyhow!("Block execution failed: {:?}", status),
+                });
+            }
+        };
+
+        let mut outcomes = Vec::with_capacity(txns.len());
+        for (index, (((account, payload), txn), output)) in txns.iter().zip(&signed).zip(&outputs).enumerate() {
+            let gas_fee = match output.status() {
+                TransactionStatus::Keep(_) => {
+                    self.executor.apply_write_set(output.write_set());
+                    self.module_cache.invalidate(output.write_set());
+                    self.notify_applied(txn, output);
+                    output.gas_used() * self.gas_defaults.gas_unit_price
+                }
+                _ => 0,
+            };
+            outcomes.push(BlockTxnOutcome {
+                index,
+                sender: *account.address(),
+                function: function_name(payload),
+                call: self.decode_payload(payload),
+                status: output.status().clone(),
+                gas_used: output.gas_used(),
+                gas_fee,
+            });
+        }
+        self.view_cache.invalidate();
+        // Newest first, so a sender's discarded run of transactions gives back every number
+        for (txn, output) in signed.iter().zip(&outputs).rev() {
+            if !matches!(output.status(), TransactionStatus::Keep(_)) {
+                self.release_sequence_number(txn.sender(), txn.sequence_number());
+            }
+        }
+
+        Ok(BlockReport { txns: outcomes })
+    }
+}
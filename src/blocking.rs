@@ -0,0 +1,149 @@
+use crate::{remote::RemoteState, AptosBB};
+use anyhow::Result;
+use aptos_language_e2e_tests::data_store::FakeDataStore;
+use aptos_types::state_store::{
+    errors::StateViewError, state_key::StateKey, state_storage_usage::StateStorageUsage,
+    state_value::StateValue, StateViewResult, TStateView,
+};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use tokio::runtime::{Builder, Handle, Runtime, RuntimeFlavor};
+
+/// Runtime driving remote reads issued from synchronous code when the caller's runtime cannot:
+/// outside of any runtime, or on a current-thread runtime whose only thread is the one waiting
+static IO_RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    Builder::new_multi_thread()
+        .worker_threads(2)
+        .thread_name("aptosbb-io")
+        .enable_all()
+        .build()
+        .expect("failed to create tokio runtime")
+});
+
+/// Run a closure that may block on remote state reads without stalling the tokio runtime
+///
+/// `FakeExecutor` fetches missing state keys synchronously over HTTP. On a multi-threaded
+/// runtime the current worker is handed over to the blocking pool for the duration of the
+/// closure so other tasks keep making progress. Outside of a runtime (or on a current-thread
+/// runtime, where that hand-off is not supported) the closure simply runs inline; the async
+/// methods of `AptosBB` fetch what a transaction reads up front so it does not block there.
+pub fn run_blocking<R>(f: impl FnOnce() -> R) -> R {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        _ => f(),
+    }
+}
+
+/// Drive a future to completion from synchronous code
+///
+/// Inside a multi-threaded runtime the future runs on the current runtime after handing the
+/// worker over to the blocking pool. Anywhere else, including inside a current-thread runtime,
+/// its I/O and timers are driven by a dedicated background runtime while the calling thread
+/// waits for it.
+pub fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    match Handle::try_current() {
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(|| handle.block_on(future))
+        }
+        _ => {
+            let _io = IO_RUNTIME.enter();
+            futures::executor::block_on(future)
+        }
+    }
+}
+
+/// State view that never waits on the network
+///
+/// Keys neither held by the fork nor already fetched are recorded as missing and fail the
+/// read, so they can be fetched asynchronously before execution is retried.
+pub(crate) struct DeferredView<'a> {
+    local: &'a FakeDataStore,
+    remote: &'a RemoteState,
+    missing: Mutex<Vec<StateKey>>,
+}
+
+impl<'a> DeferredView<'a> {
+    pub(crate) fn new(local: &'a FakeDataStore, remote: &'a RemoteState) -> Self {
+        Self { local, remote, missing: Mutex::new(vec![]) }
+    }
+
+    pub(crate) fn into_missing(self) -> Vec<StateKey> {
+        self.missing.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl TStateView for DeferredView<'_> {
+    type Key = StateKey;
+
+    fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+        if let Some(value) = self.local.inner().get(state_key) {
+            return Ok(Some(value.clone()));
+        }
+        match self.remote.get_state_value_without_request(state_key) {
+            Ok(Some(value)) => Ok(value),
+            Ok(None) => {
+                self.missing
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .push(state_key.clone());
+                Err(StateViewError::Other(format!("{:?} is not fetched yet", state_key)))
+            }
+            Err(e) => Err(StateViewError::Other(format!("{:#}", e))),
+        }
+    }
+
+    fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+        Ok(StateStorageUsage::new_untracked())
+    }
+}
+
+impl AptosBB {
+    /// Fetch every state key `execute` reads that the fork does not hold yet, awaiting the
+    /// requests instead of blocking on them
+    ///
+    /// `execute` runs against a `DeferredView` and is retried until it reads nothing new, after
+    /// which executing the same work on the fork needs no network request.
+    pub(crate) async fn fetch_reads(&mut self, execute: impl Fn(&AptosBB, &DeferredView<'_>)) -> Result<()> {
+        loop {
+            let missing = {
+                let view = DeferredView::new(self.executor.data_store(), &self.remote);
+                execute(self, &view);
+                view.into_missing()
+            };
+            if missing.is_empty() {
+                return Ok(());
+            }
+            let values = self.remote.get_state_values(&missing).await?;
+            self.load_missing(values.into_iter().filter_map(|(state_key, value)| Some((state_key, value?))));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    async fn sleep_then(value: u32) -> u32 {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        value
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn block_on_inside_a_current_thread_runtime() {
+        assert_eq!(block_on(sleep_then(1)), 1);
+        assert_eq!(run_blocking(|| block_on(sleep_then(2))), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn block_on_inside_a_multi_thread_runtime() {
+        assert_eq!(block_on(sleep_then(3)), 3);
+    }
+
+    #[test]
+    fn block_on_outside_a_runtime() {
+        assert_eq!(block_on(sleep_then(4)), 4);
+    }
+}
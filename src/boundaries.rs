@@ -0,0 +1,215 @@
+//! Canonical edge-case argument values per Move type, for systematic boundary testing
+//!
+//! Values are in the JSON representation `json_to_bcs` and `encode_entry_function` take, so
+//! they slot into `call_json`, access probe templates and hand-written scenarios alike.
+
+use crate::{export::status_name, AptosBB};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::transaction::{ExecutionStatus, TransactionPayload, TransactionStatus};
+use move_core_types::{
+    account_address::AccountAddress,
+    language_storage::{StructTag, TypeTag},
+    u256::U256,
+};
+use serde_json::{json, Value};
+use std::fmt;
+
+/// Length of the long byte vectors and strings, well inside the transaction size limit
+pub const LONG_BYTES: usize = 32 * 1024;
+
+/// Length of the long vectors of other element types
+pub const LONG_VECTOR_LEN: usize = 1024;
+
+/// Argument values longer than this are cut off in reports
+const MAX_VALUE_CHARS: usize = 80;
+
+fn is_framework_struct(tag: &StructTag, module: &str, name: &str) -> bool {
+    tag.address == AccountAddress::ONE && tag.module.as_str() == module && tag.name.as_str() == name
+}
+
+/// 0, 1, 2, the square root of the maximum (its square overflows), half the maximum (doubling
+/// it overflows), and the maximum and one below it
+fn unsigned(bits: u32) -> Vec<Value> {
+    if bits == 256 {
+        let max = U256::max_value();
+        let half = max >> 1;
+        let root = U256::one() << 128;
+        return [U256::zero(), U256::one(), U256::from(2u8), root, half, half + U256::one(), max - U256::one(), max]
+            .iter()
+            .map(|value| json!(value.to_string()))
+            .collect();
+    }
+    let max = if bits == 128 { u128::MAX } else { (1u128 << bits) - 1 };
+    let half = max >> 1;
+    [0, 1, 2, 1u128 << (bits / 2), half, half + 1, max - 1, max]
+        .into_iter()
+        .map(|value| if bits <= 32 { json!(value as u64) } else { json!(value.to_string()) })
+        .collect()
+}
+
+/// Edge-case values of a Move type; empty for structs other than strings, options and objects
+///
+/// Vectors get the empty vector, one holding every edge case of the element type and a long
+/// one; options get `none` and `some` of every edge case.
+pub fn values(ty: &TypeTag) -> Vec<Value> {
+    match ty {
+        TypeTag::Bool => vec![json!(false), json!(true)],
+        TypeTag::U8 => unsigned(8),
+        TypeTag::U16 => unsigned(16),
+        TypeTag::U32 => unsigned(32),
+        TypeTag::U64 => unsigned(64),
+        TypeTag::U128 => unsigned(128),
+        TypeTag::U256 => unsigned(256),
+        TypeTag::Address => addresses(),
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+            vec![json!("0x"), json!("0x00"), json!(format!("0x{}", "ff".repeat(LONG_BYTES)))]
+        }
+        TypeTag::Vector(inner) => {
+            let items = values(inner);
+            let Some(first) = items.first().cloned() else {
+                return vec![json!([])];
+            };
+            vec![json!([]), Value::Array(items), Value::Array(vec![first; LONG_VECTOR_LEN])]
+        }
+        TypeTag::Struct(tag) if is_framework_struct(tag, "string", "String") => {
+            vec![json!(""), json!("é"), json!("a".repeat(LONG_BYTES))]
+        }
+        TypeTag::Struct(tag) if is_framework_struct(tag, "option", "Option") => {
+            let mut options = vec![json!({ "vec": [] })];
+            if let Some(inner) = tag.type_args.first() {
+                options.extend(values(inner).into_iter().map(|value| json!({ "vec": [value] })));
+            }
+            options
+        }
+        TypeTag::Struct(tag) if is_framework_struct(tag, "object", "Object") => {
+            addresses().into_iter().map(|addr| json!({ "inner": addr })).collect()
+        }
+        _ => vec![],
+    }
+}
+
+/// 0x0, the framework at 0x1 and 0xf..f
+pub fn addresses() -> Vec<Value> {
+    [AccountAddress::ZERO, AccountAddress::ONE, AccountAddress::new([0xff; AccountAddress::LENGTH])]
+        .iter()
+        .map(|addr| json!(addr.to_hex_literal()))
+        .collect()
+}
+
+/// One call with a single argument set to an edge case
+#[derive(Clone, Debug)]
+pub struct BoundaryCase {
+    /// Index of the replaced argument, not counting signers
+    pub param: usize,
+    pub ty: TypeTag,
+    pub value: Value,
+    pub status: TransactionStatus,
+}
+
+impl BoundaryCase {
+    /// Failures other than a deliberate abort: arithmetic errors, out-of-bounds accesses, running
+    /// out of gas, or the transaction being discarded
+    pub fn is_anomaly(&self) -> bool {
+        !matches!(self.status, TransactionStatus::Keep(ExecutionStatus::Success | ExecutionStatus::MoveAbort { .. }))
+    }
+}
+
+impl fmt::Display for BoundaryCase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut value = self.value.to_string();
+        let chars = value.chars().count();
+        if chars > MAX_VALUE_CHARS {
+            value = format!("{}… ({} chars)", value.chars().take(MAX_VALUE_CHARS).collect::<String>(), chars);
+        }
+        write!(f, "arg {} ({}) = {}: {}", self.param, self.ty, value, status_name(&self.status))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BoundaryReport {
+    pub function: String,
+    pub cases: Vec<BoundaryCase>,
+}
+
+impl BoundaryReport {
+    pub fn anomalies(&self) -> Vec<&BoundaryCase> {
+        self.cases.iter().filter(|case| case.is_anomaly()).collect()
+    }
+}
+
+impl fmt::Display for BoundaryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Boundary cases of {}:", self.function)?;
+        for case in &self.cases {
+            writeln!(f, "  {} {}", if case.is_anomaly() { "🚨" } else { "  " }, case)?;
+        }
+        write!(f, "{} cases, {} anomalies", self.cases.len(), self.anomalies().len())
+    }
+}
+
+impl AptosBB {
+    /// Call an entry function once per edge case of each parameter, the other arguments kept
+    /// at `base_args`, each call on a copy of the current state
+    ///
+    /// Parameters without presets (arbitrary structs) keep their base value.
+    pub fn probe_boundaries(
+        &mut self,
+        account: &Account,
+        function: &str,
+        ty_args: Vec<TypeTag>,
+        base_args: &[Value],
+    ) -> Result<BoundaryReport> {
+        let params = self.entry_param_types(function, &ty_args)?;
+        let mut cases = vec![];
+        for (param, ty) in params.iter().enumerate() {
+            for value in values(ty) {
+                let mut args = base_args.to_vec();
+                if let Some(arg) = args.get_mut(param) {
+                    *arg = value.clone();
+                }
+                let entry = self.encode_entry_function(function, ty_args.clone(), &args)?;
+                let (status, _) = self.with_snapshot(|bb| bb.run_transaction_with_output(account, TransactionPayload::EntryFunction(entry)))?;
+                cases.push(BoundaryCase { param, ty: ty.clone(), value, status });
+            }
+        }
+        let report = BoundaryReport { function: function.to_string(), cases };
+        println!("{}", report);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn unsigned_edges_are_numbers_up_to_u32_and_strings_above() {
+        assert_eq!(values(&TypeTag::U8), [0, 1, 2, 16, 127, 128, 254, 255].map(|v| json!(v)));
+        assert_eq!(values(&TypeTag::U32).last(), Some(&json!(u32::MAX)));
+        assert_eq!(values(&TypeTag::U64)[3], json!((1u64 << 32).to_string()));
+        assert_eq!(values(&TypeTag::U128).last(), Some(&json!(u128::MAX.to_string())));
+        assert_eq!(values(&TypeTag::U256).last(), Some(&json!(U256::max_value().to_string())));
+    }
+
+    #[test]
+    fn vectors_hold_empty_every_edge_and_long() {
+        let cases = values(&TypeTag::Vector(Box::new(TypeTag::Bool)));
+        assert_eq!(cases[0], json!([]));
+        assert_eq!(cases[1], json!([false, true]));
+        assert_eq!(cases[2].as_array().unwrap().len(), LONG_VECTOR_LEN);
+
+        let bytes = values(&TypeTag::Vector(Box::new(TypeTag::U8)));
+        assert_eq!(bytes[2].as_str().unwrap().len(), 2 + 2 * LONG_BYTES);
+    }
+
+    #[test]
+    fn framework_structs_get_their_json_shapes() {
+        let option = TypeTag::from_str("0x1::option::Option<bool>").unwrap();
+        assert_eq!(values(&option), [json!({ "vec": [] }), json!({ "vec": [false] }), json!({ "vec": [true] })]);
+        let object = TypeTag::from_str("0x1::object::Object<0x1::fungible_asset::Metadata>").unwrap();
+        assert_eq!(values(&object)[1], json!({ "inner": "0x1" }));
+        assert!(values(&TypeTag::from_str("0x1::coin::Coin<0x1::aptos_coin::AptosCoin>").unwrap()).is_empty());
+    }
+}
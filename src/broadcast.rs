@@ -0,0 +1,99 @@
+//! Testnet broadcast of a PoC validated on the fork
+//!
+//! Only compiled with `--features broadcast`; default builds cannot submit transactions
+//! anywhere (see `sandbox::ReadOnlyClient`).
+
+use crate::{blocking, sandbox::ensure_broadcast_allowed, GasDefaults};
+use anyhow::{anyhow, bail, Result};
+use aptos_crypto::HashValue;
+use aptos_language_e2e_tests::account::Account;
+use aptos_rest_client::{AptosBaseUrl, Client};
+use aptos_types::{
+    chain_id::ChainId,
+    transaction::{SignedTransaction, TransactionPayload},
+};
+use std::{
+    io::{BufRead, Write},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Must be set to `1` before anything can be broadcast
+pub const ALLOW_BROADCAST_VAR: &str = "APTOSBB_ALLOW_TESTNET_BROADCAST";
+
+/// Testnet API key used for broadcasting, deliberately separate from `APTOSBB_KEY`
+pub const TESTNET_KEY_VAR: &str = "APTOSBB_TESTNET_KEY";
+
+/// Answer the confirmation prompt expects
+const CONFIRMATION: &str = "broadcast";
+
+/// Write-capable testnet client, only handed out once every gate has passed
+fn testnet_client() -> Result<Client> {
+    ensure_broadcast_allowed()?;
+    if std::env::var(ALLOW_BROADCAST_VAR).as_deref() != Ok("1") {
+        bail!("Refusing to broadcast: set {}=1 to allow testnet broadcasts", ALLOW_BROADCAST_VAR);
+    }
+    let mut builder = Client::builder(AptosBaseUrl::Testnet);
+    if let Ok(api_key) = std::env::var(TESTNET_KEY_VAR) {
+        if !api_key.is_empty() {
+            builder = builder.api_key(&api_key)?;
+        }
+    }
+    Ok(builder.build())
+}
+
+/// Sign `payload` for testnet with the account's current testnet sequence number
+///
+/// Fork accounts are fresh keypairs, so the account must first be funded on testnet
+/// (e.g. from the faucet).
+pub fn testnet_transaction(account: &Account, payload: TransactionPayload, gas: GasDefaults) -> Result<SignedTransaction> {
+    let client = testnet_client()?;
+    let sequence_number = blocking::block_on(client.get_account(*account.address()))
+        .map_err(|e| anyhow!("Account {} not found on testnet: {}", account.address(), e))?
+        .into_inner()
+        .sequence_number;
+    let expiration = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs() + gas.expiration_secs;
+    Ok(account
+        .transaction()
+        .payload(payload)
+        .sequence_number(sequence_number)
+        .max_gas_amount(gas.max_gas_amount)
+        .gas_unit_price(gas.gas_unit_price)
+        .ttl(expiration)
+        .chain_id(ChainId::testnet())
+        .sign())
+}
+
+/// Submit a transaction that succeeded on the fork to testnet and wait for it to commit
+///
+/// Meant for triagers who ask for an on-network demonstration against a protocol's testnet
+/// deployment. Gated four times: the crate must be built with `--features broadcast`,
+/// no-broadcast mode must be off, `APTOSBB_ALLOW_TESTNET_BROADCAST=1` must be set, and the
+/// transaction is only sent after typing `broadcast` at the prompt.
+/// Transactions not signed for the testnet chain ID are rejected outright, so a mainnet
+/// transaction can never be replayed by mistake. Returns the committed transaction's hash.
+pub fn broadcast_to_testnet(txn: SignedTransaction) -> Result<HashValue> {
+    if txn.chain_id() != ChainId::testnet() {
+        bail!("Refusing to broadcast: transaction is signed for chain {}, not testnet", txn.chain_id());
+    }
+    let client = testnet_client()?;
+
+    println!("⚠️  About to broadcast to TESTNET:");
+    println!("   sender:          {}", txn.sender());
+    println!("   sequence number: {}", txn.sequence_number());
+    println!("   payload:         {:?}", txn.payload());
+    print!("Type '{}' to submit: ", CONFIRMATION);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    if answer.trim() != CONFIRMATION {
+        bail!("Broadcast aborted");
+    }
+
+    let committed = blocking::block_on(client.submit_and_wait_bcs(&txn))?.into_inner();
+    let hash = committed.info.transaction_hash();
+    if !committed.info.status().is_success() {
+        bail!("Transaction {} committed on testnet but failed: {:?}", hash, committed.info.status());
+    }
+    println!("📡 Broadcast {} committed at testnet version {}", hash, committed.version);
+    Ok(hash)
+}
@@ -0,0 +1,290 @@
+use crate::{
+    blocking::run_blocking,
+    cache::StateCache,
+    fetch_log::FetchLog,
+    rate_limit::{RateLimit, RateLimiter},
+    remote::{rest_client, RemoteState},
+    vm_version::{VmSelection, VmVersion},
+    AptosBB, AptosBBError,
+};
+use anyhow::Result;
+use aptos_language_e2e_tests::executor::FakeExecutor;
+use aptos_rest_client::AptosBaseUrl;
+use aptos_types::{account_address::AccountAddress, chain_id::ChainId};
+use std::{collections::HashMap, path::PathBuf};
+use url::Url;
+
+/// Gas parameters applied to every transaction signed by AptosBB
+#[derive(Clone, Copy, Debug)]
+pub struct GasDefaults {
+    pub max_gas_amount: u64,
+    pub gas_unit_price: u64,
+    /// Seconds from now after which a transaction expires
+    pub expiration_secs: u64,
+}
+
+impl Default for GasDefaults {
+    fn default() -> Self {
+        Self {
+            max_gas_amount: 2_000_000,
+            gas_unit_price: 100,
+            expiration_secs: 300,
+        }
+    }
+}
+
+/// Parse a network given as `mainnet`, `testnet`, `devnet` or a REST URL
+pub fn parse_network(network: &str) -> Result<AptosBaseUrl> {
+    Ok(match network {
+        "mainnet" => AptosBaseUrl::Mainnet,
+        "testnet" => AptosBaseUrl::Testnet,
+        "devnet" => AptosBaseUrl::Devnet,
+        url => AptosBaseUrl::Custom(Url::parse(url)?),
+    })
+}
+
+/// Inverse of `parse_network`
+pub fn network_name(network: &AptosBaseUrl) -> String {
+    match network {
+        AptosBaseUrl::Mainnet => "mainnet".to_string(),
+        AptosBaseUrl::Testnet => "testnet".to_string(),
+        AptosBaseUrl::Devnet => "devnet".to_string(),
+        other => other.to_url().to_string(),
+    }
+}
+
+/// How remote state is brought into the fork
+#[derive(Clone, Debug, Default)]
+pub enum ForkMode {
+    /// Fetch state keys on demand as execution touches them
+    #[default]
+    Lazy,
+    /// Download the listed accounts' modules and resources at fork time, so later
+    /// execution against them does not depend on the network
+    Eager { accounts: Vec<AccountAddress> },
+    /// Pre-seed exactly the keys an earlier session used (see `AptosBB::write_fetch_log`),
+    /// pinned to that session's version, for deterministic and network-free reruns
+    Replay { log: PathBuf },
+}
+
+impl ForkMode {
+    /// Eager mode covering the framework accounts (0x1, 0x3, 0x4) plus the given targets
+    pub fn eager_with_framework(targets: &[AccountAddress]) -> Self {
+        let mut accounts = vec![
+            AccountAddress::ONE,
+            AccountAddress::THREE,
+            AccountAddress::FOUR,
+        ];
+        accounts.extend_from_slice(targets);
+        ForkMode::Eager { accounts }
+    }
+}
+
+/// Builder for configuring and forking an AptosBB environment
+///
+/// ```ignore
+/// let bb = AptosBB::builder()
+///     .network(AptosBaseUrl::Mainnet)
+///     .version(2_000_000_000)
+///     .api_key(&api_key)
+///     .build()
+///     .await?;
+/// ```
+pub struct AptosBBBuilder {
+    network: AptosBaseUrl,
+    version: Option<u64>,
+    api_key: Option<String>,
+    cache_dir: Option<PathBuf>,
+    gas_defaults: GasDefaults,
+    rate_limit: Option<RateLimit>,
+    fork_mode: ForkMode,
+    vm_version: VmVersion,
+}
+
+impl Default for AptosBBBuilder {
+    fn default() -> Self {
+        Self {
+            network: AptosBaseUrl::Mainnet,
+            version: None,
+            api_key: None,
+            cache_dir: None,
+            gas_defaults: GasDefaults::default(),
+            rate_limit: None,
+            fork_mode: ForkMode::Lazy,
+            vm_version: VmVersion::Main,
+        }
+    }
+}
+
+impl AptosBBBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Network to fork from (defaults to mainnet)
+    pub fn network(mut self, network: AptosBaseUrl) -> Self {
+        self.network = network;
+        self
+    }
+
+    /// Ledger version to fork at (defaults to the latest version)
+    pub fn version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// API key used for the remote state connection (higher rate limits)
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Directory where fetched remote state is persisted between runs
+    pub fn cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Gas parameters used for every transaction signed by the environment
+    pub fn gas_defaults(mut self, gas_defaults: GasDefaults) -> Self {
+        self.gas_defaults = gas_defaults;
+        self
+    }
+
+    /// Request budget for remote reads (defaults depend on whether an API key is set)
+    ///
+    /// An invalid budget (see `RateLimit::validate`) makes `build` fail.
+    pub fn rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    /// Whether state is fetched lazily or downloaded up front (defaults to lazy)
+    pub fn fork_mode(mut self, fork_mode: ForkMode) -> Self {
+        self.fork_mode = fork_mode;
+        self
+    }
+
+    /// VM release transactions are executed with (defaults to the main branch)
+    ///
+    /// `VmVersion::MainnetRelease` needs the `vm-mainnet` feature, which vendors the
+    /// mainnet release of aptos-core next to the main branch.
+    pub fn vm_version(mut self, vm_version: VmVersion) -> Self {
+        self.vm_version = vm_version;
+        self
+    }
+
+    /// Connect to the network and fork its state
+    pub async fn build(self) -> crate::error::Result<AptosBB> {
+        self.fork().await.map_err(AptosBBError::RemoteStateError)
+    }
+
+    async fn fork(self) -> Result<AptosBB> {
+        if self.vm_version == VmVersion::MainnetRelease && !cfg!(feature = "vm-mainnet") {
+            anyhow::bail!("VmVersion::MainnetRelease requires building AptosBB with the `vm-mainnet` feature");
+        }
+        let fetch_log = match &self.fork_mode {
+            ForkMode::Replay { log } => Some(FetchLog::read(log)?),
+            _ => None,
+        };
+        let pinned_version = match (&fetch_log, self.version) {
+            (Some(log), Some(version)) if log.version != version => {
+                anyhow::bail!("Fetch log was recorded at version {} but version {} was requested", log.version, version)
+            }
+            (Some(log), _) => Some(log.version),
+            (None, version) => version,
+        };
+        let client = rest_client(&self.network, self.api_key.as_deref())?;
+        let rate_limit = self.rate_limit.unwrap_or_else(|| match self.api_key {
+            Some(_) => RateLimit::with_api_key(),
+            None => RateLimit::anonymous(),
+        });
+        let limiter = RateLimiter::new(rate_limit)?;
+
+        let cache = match &self.cache_dir {
+            Some(dir) => {
+                eprintln!("Serving remote state from the cache at {} when possible", dir.display());
+                Some(StateCache::open(dir)?)
+            }
+            None => None,
+        };
+
+        let network = network_name(&self.network);
+        let cached = match (&cache, pinned_version) {
+            (Some(cache), Some(version)) => cache.fork_info(&network, version)?.map(|info| (version, info)),
+            _ => None,
+        };
+        let (chain_id, version, timestamp_usecs) = match (cached, pinned_version) {
+            // A pinned version never changes, so an earlier run's answer is as good as a request
+            (Some((version, (chain_id, timestamp_usecs))), _) => (chain_id, version, timestamp_usecs),
+            // The response headers carry the chain id, so a pinned fork needs no ledger info
+            (None, Some(version)) => {
+                limiter.acquire().await?;
+                let response = client.get_block_by_version(version, false).await?;
+                let chain_id = ChainId::new(response.state().chain_id);
+                let timestamp_usecs = response.into_inner().block_timestamp.0;
+                if let Some(cache) = &cache {
+                    cache.put_fork_info(&network, version, chain_id, timestamp_usecs)?;
+                }
+                (chain_id, version, timestamp_usecs)
+            }
+            (None, None) => {
+                limiter.acquire().await?;
+                let ledger_info = client.get_ledger_information().await?.into_inner();
+                (ChainId::new(ledger_info.chain_id), ledger_info.version, ledger_info.timestamp_usecs)
+            }
+        };
+
+        match self.api_key {
+            Some(_) => eprintln!("Connecting at version: {} (with API key)", version),
+            None => eprintln!("Connecting at version: {}", version),
+        }
+        eprintln!("Chain ID: {}", chain_id);
+
+        let remote = RemoteState::new(client, chain_id, version, cache, limiter);
+
+        // Lazy reads go through `remote` as well, so they hit the cache and the rate limiter
+        let view = remote.clone();
+        let mut executor = run_blocking(|| FakeExecutor::from_state_view(view));
+
+        let timestamp_secs = timestamp_usecs / 1_000_000;
+        executor.set_block_time(timestamp_secs);
+        eprintln!("Set executor block time to: {}", timestamp_secs);
+
+        let mut bb = AptosBB {
+            executor,
+            sequence_numbers: HashMap::new(),
+            chain_id,
+            remote,
+            module_cache: Default::default(),
+            view_cache: Default::default(),
+            vm: VmSelection { version: self.vm_version, mainnet: None },
+            network: self.network,
+            api_key: self.api_key,
+            version,
+            cache_dir: self.cache_dir,
+            gas_defaults: self.gas_defaults,
+            events: Default::default(),
+            exporter: None,
+            report: None,
+            storage: None,
+            limits: None,
+            block_gas_limit: Default::default(),
+            staleness: None,
+            history: None,
+            txn_summaries: None,
+            findings: vec![],
+        };
+
+        if let ForkMode::Eager { accounts } = &self.fork_mode {
+            let started = std::time::Instant::now();
+            let loaded = bb.prefetch_accounts(accounts).await?;
+            eprintln!("Eagerly loaded {} state keys from {} accounts in {:.1?}", loaded, accounts.len(), started.elapsed());
+        }
+        if let Some(log) = fetch_log {
+            bb.seed_from_fetch_log(&log).await?;
+        }
+
+        Ok(bb)
+    }
+}
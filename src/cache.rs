@@ -0,0 +1,151 @@
+use anyhow::{Context, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_types::{
+    chain_id::ChainId,
+    state_store::{state_key::StateKey, state_value::StateValue},
+};
+use rocksdb::{IteratorMode, Options, WriteBatch, DB};
+use std::path::Path;
+
+const INDEX_PREFIX: &[u8] = b"k/";
+const BLOB_PREFIX: &[u8] = b"v/";
+const FORK_PREFIX: &[u8] = b"f/";
+
+/// Persistent, content-addressed cache of remote state shared across runs
+///
+/// Every fetched key is indexed under `(chain id, version, key hash)` and points at a blob
+/// stored under the hash of its contents, so values that did not change between pinned
+/// versions are only stored once. Keys known to be absent on chain are cached as well, and
+/// so are the chain id and block timestamp of every pinned version forked so far.
+pub struct StateCache {
+    db: DB,
+}
+
+impl StateCache {
+    /// Open (or create) the cache in the given directory
+    pub fn open(dir: &Path) -> Result<Self> {
+        let mut options = Options::default();
+        options.create_if_missing(true);
+        let db = DB::open(&options, dir)
+            .with_context(|| format!("Failed to open state cache at {}", dir.display()))?;
+        Ok(Self { db })
+    }
+
+    fn version_prefix(chain_id: ChainId, version: u64) -> Vec<u8> {
+        let mut prefix = INDEX_PREFIX.to_vec();
+        prefix.push(chain_id.id());
+        prefix.extend_from_slice(&version.to_be_bytes());
+        prefix
+    }
+
+    fn index_key(chain_id: ChainId, version: u64, state_key: &StateKey) -> Vec<u8> {
+        let mut key = Self::version_prefix(chain_id, version);
+        key.extend_from_slice(state_key.hash().as_ref());
+        key
+    }
+
+    fn blob_key(hash: &HashValue) -> Vec<u8> {
+        [BLOB_PREFIX, hash.as_ref()].concat()
+    }
+
+    fn read_blob(&self, hash: &HashValue) -> Result<Option<StateValue>> {
+        match self.db.get(Self::blob_key(hash))? {
+            Some(bytes) => Ok(Some(bcs::from_bytes(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Look up a key; `Some(None)` means the key is cached as absent on chain
+    pub fn get(&self, chain_id: ChainId, version: u64, state_key: &StateKey) -> Result<Option<Option<StateValue>>> {
+        let Some(entry) = self.db.get(Self::index_key(chain_id, version, state_key))? else {
+            return Ok(None);
+        };
+        let (_, blob_hash): (StateKey, Option<HashValue>) = bcs::from_bytes(&entry)?;
+        match blob_hash {
+            Some(hash) => Ok(Some(self.read_blob(&hash)?)),
+            None => Ok(Some(None)),
+        }
+    }
+
+    /// Record a fetched key and its value (or absence)
+    pub fn put(&self, chain_id: ChainId, version: u64, state_key: &StateKey, value: Option<&StateValue>) -> Result<()> {
+        let mut batch = WriteBatch::default();
+        let blob_hash = match value {
+            Some(value) => {
+                let bytes = bcs::to_bytes(value)?;
+                let hash = HashValue::sha3_256_of(&bytes);
+                batch.put(Self::blob_key(&hash), bytes);
+                Some(hash)
+            }
+            None => None,
+        };
+        batch.put(
+            Self::index_key(chain_id, version, state_key),
+            bcs::to_bytes(&(state_key, blob_hash))?,
+        );
+        self.db.write(batch)?;
+        Ok(())
+    }
+
+    /// All cached entries for a version
+    pub fn entries(&self, chain_id: ChainId, version: u64) -> Result<Vec<(StateKey, Option<StateValue>)>> {
+        let prefix = Self::version_prefix(chain_id, version);
+        let mut entries = Vec::new();
+
+        for item in self.db.iterator(IteratorMode::From(&prefix, rocksdb::Direction::Forward)) {
+            let (key, entry) = item?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let (state_key, blob_hash): (StateKey, Option<HashValue>) = bcs::from_bytes(&entry)?;
+            let value = match blob_hash {
+                Some(hash) => self.read_blob(&hash)?,
+                None => None,
+            };
+            entries.push((state_key, value));
+        }
+
+        Ok(entries)
+    }
+
+    /// Keyed by the network's name or URL rather than its chain id, which is what is looked up
+    fn fork_key(network: &str, version: u64) -> Vec<u8> {
+        let mut key = FORK_PREFIX.to_vec();
+        key.extend_from_slice(network.as_bytes());
+        key.push(0);
+        key.extend_from_slice(&version.to_be_bytes());
+        key
+    }
+
+    /// Chain id and block timestamp (in microseconds) of a network at a version, if cached
+    pub fn fork_info(&self, network: &str, version: u64) -> Result<Option<(ChainId, u64)>> {
+        let Some(entry) = self.db.get(Self::fork_key(network, version))? else {
+            return Ok(None);
+        };
+        let (chain_id, timestamp_usecs): (u8, u64) = bcs::from_bytes(&entry)?;
+        Ok(Some((ChainId::new(chain_id), timestamp_usecs)))
+    }
+
+    /// Record the chain id and block timestamp of a network at a version
+    pub fn put_fork_info(&self, network: &str, version: u64, chain_id: ChainId, timestamp_usecs: u64) -> Result<()> {
+        self.db.put(Self::fork_key(network, version), bcs::to_bytes(&(chain_id.id(), timestamp_usecs))?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fork_info_is_kept_per_network_and_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = StateCache::open(dir.path()).unwrap();
+        assert_eq!(cache.fork_info("mainnet", 7).unwrap(), None);
+
+        cache.put_fork_info("mainnet", 7, ChainId::new(1), 1_700_000_000_000_000).unwrap();
+        assert_eq!(cache.fork_info("mainnet", 7).unwrap(), Some((ChainId::new(1), 1_700_000_000_000_000)));
+        assert_eq!(cache.fork_info("mainnet", 8).unwrap(), None);
+        assert_eq!(cache.fork_info("testnet", 7).unwrap(), None);
+    }
+}
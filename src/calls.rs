@@ -0,0 +1,186 @@
+use crate::{entry::parse_function_id, export::status_name, view::signature_to_type_tag, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    state_store::state_key::StateKey,
+    transaction::{
+        EntryFunction, MultisigTransactionPayload, TransactionExecutable, TransactionPayload, TransactionPayloadInner,
+    },
+};
+use move_binary_format::{access::ModuleAccess, file_format::SignatureToken, CompiledModule};
+use move_core_types::{
+    identifier::IdentStr,
+    language_storage::{ModuleId, TypeTag},
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{fmt, sync::Arc};
+
+/// An entry function call with its arguments decoded through the module's ABI
+#[derive(Clone, Debug, Serialize)]
+pub struct DecodedCall {
+    /// `address::module::function`
+    pub function: String,
+    pub ty_args: Vec<TypeTag>,
+    /// Argument types and values, without the leading signer parameters
+    pub args: Vec<(TypeTag, Value)>,
+}
+
+/// Integers and addresses are shown bare, everything else as compact JSON
+fn format_arg(ty: &TypeTag, value: &Value) -> String {
+    match (ty, value) {
+        (
+            TypeTag::U8 | TypeTag::U16 | TypeTag::U32 | TypeTag::U64 | TypeTag::U128 | TypeTag::U256 | TypeTag::Address,
+            Value::String(value),
+        ) => value.clone(),
+        _ => value.to_string(),
+    }
+}
+
+impl fmt::Display for DecodedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.function)?;
+        if !self.ty_args.is_empty() {
+            let ty_args: Vec<String> = self.ty_args.iter().map(|ty| ty.to_string()).collect();
+            write!(f, "<{}>", ty_args.join(", "))?;
+        }
+        let args: Vec<String> = self.args.iter().map(|(ty, value)| format_arg(ty, value)).collect();
+        write!(f, "({})", args.join(", "))
+    }
+}
+
+/// Entry function a payload executes, if any
+fn entry_function(payload: &TransactionPayload) -> Option<&EntryFunction> {
+    match payload {
+        TransactionPayload::EntryFunction(entry) => Some(entry),
+        TransactionPayload::Multisig(multisig) => match &multisig.transaction_payload {
+            Some(MultisigTransactionPayload::EntryFunction(entry)) => Some(entry),
+            None => None,
+        },
+        TransactionPayload::Payload(TransactionPayloadInner::V1 {
+            executable: TransactionExecutable::EntryFunction(entry),
+            ..
+        }) => Some(entry),
+        _ => None,
+    }
+}
+
+impl AptosBB {
+    /// Module from the module cache, or read from the fork without caching it
+    fn module_for_decoding(&self, module_id: &ModuleId) -> Result<Arc<CompiledModule>> {
+        if let Some(module) = self.module_cache.get(module_id) {
+            return Ok(module);
+        }
+        let state_value = self
+            .read_state_value(&StateKey::module_id(module_id))
+            .ok_or_else(|| anyhow!("Module {} not found", module_id))?;
+        let module = CompiledModule::deserialize(state_value.bytes())
+            .map_err(|e| anyhow!("Failed to deserialize {}: {:?}", module_id, e))?;
+        Ok(Arc::new(module))
+    }
+
+    /// Non-signer parameter types of a function, as the transaction passes arguments for them
+    fn entry_params(module: &CompiledModule, function: &str, name: &IdentStr) -> Result<Vec<SignatureToken>> {
+        let handle = module
+            .function_handles()
+            .iter()
+            .find(|handle| module.identifier_at(handle.name) == name && handle.module == module.self_handle_idx())
+            .ok_or_else(|| anyhow!("Function {} not found", function))?;
+        Ok(module
+            .signature_at(handle.parameters)
+            .0
+            .iter()
+            .skip_while(|token| match token {
+                SignatureToken::Reference(inner) => matches!(**inner, SignatureToken::Signer),
+                token => matches!(token, SignatureToken::Signer),
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Decode the BCS arguments of an entry function call using the function's ABI
+    ///
+    /// Parameter types are resolved against the fork's current module, so decoding a
+    /// historical call assumes the function's signature has not changed since.
+    pub fn decode_entry_function(&self, entry: &EntryFunction) -> Result<DecodedCall> {
+        let module = self.module_for_decoding(entry.module())?;
+        let function = format!("{}::{}", entry.module(), entry.function());
+        let params = Self::entry_params(&module, &function, entry.function())?;
+        if params.len() != entry.args().len() {
+            return Err(anyhow!("{} takes {} arguments, got {}", function, params.len(), entry.args().len()));
+        }
+
+        let args = params
+            .iter()
+            .zip(entry.args())
+            .map(|(token, bytes)| {
+                let ty = signature_to_type_tag(&module, token, entry.ty_args())?;
+                let value = self.value_to_json(&ty, bytes)?;
+                Ok((ty, value))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(DecodedCall { function, ty_args: entry.ty_args().to_vec(), args })
+    }
+
+    /// Build an entry function call from arguments in the API's JSON representation
+    ///
+    /// The inverse of `decode_entry_function`: each argument is encoded as the type the
+    /// function's ABI declares for it, so callers outside Rust never deal with BCS.
+    pub fn encode_entry_function(&mut self, function: &str, ty_args: Vec<TypeTag>, args: &[Value]) -> Result<EntryFunction> {
+        let (module_id, name) = parse_function_id(function)?;
+        let module = self.get_module(&module_id)?;
+        let params = Self::entry_params(&module, function, &name)?;
+        if params.len() != args.len() {
+            return Err(anyhow!("{} takes {} arguments, got {}", function, params.len(), args.len()));
+        }
+
+        let args = params
+            .iter()
+            .zip(args)
+            .map(|(token, json)| {
+                let ty = signature_to_type_tag(&module, token, &ty_args)?;
+                self.json_to_bcs(ty, json)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(EntryFunction::new(module_id, name, ty_args, args))
+    }
+
+    /// Types of an entry function's non-signer parameters, instantiated with `ty_args`
+    pub fn entry_param_types(&mut self, function: &str, ty_args: &[TypeTag]) -> Result<Vec<TypeTag>> {
+        let (module_id, name) = parse_function_id(function)?;
+        let module = self.get_module(&module_id)?;
+        Self::entry_params(&module, function, &name)?
+            .iter()
+            .map(|token| signature_to_type_tag(&module, token, ty_args))
+            .collect()
+    }
+
+    /// Run an entry function with JSON arguments and describe the outcome as JSON
+    ///
+    /// The result has the `status`, whether it was a `success`, `gas_used` and the decoded
+    /// `events`, for callers driving the fork from outside Rust.
+    pub fn call_json(&mut self, account: &Account, function: &str, ty_args: Vec<TypeTag>, args: &[Value]) -> Result<Value> {
+        let entry = self.encode_entry_function(function, ty_args, args)?;
+        let (status, output) = self.run_transaction_with_output(account, TransactionPayload::EntryFunction(entry))?;
+        let events: Vec<Value> = output
+            .events()
+            .iter()
+            .map(|event| {
+                let data = self.value_to_json(event.type_tag(), event.event_data()).unwrap_or(Value::Null);
+                json!({ "type": event.type_tag().to_canonical_string(), "data": data })
+            })
+            .collect();
+        Ok(json!({
+            "status": status_name(&status),
+            "success": status.status().is_ok_and(|status| status.is_success()),
+            "gas_used": output.gas_used(),
+            "events": events,
+        }))
+    }
+
+    /// Decoded call of an entry function, multisig or v2 payload; `None` for scripts
+    /// or when the arguments do not match the ABI
+    pub fn decode_payload(&self, payload: &TransactionPayload) -> Option<DecodedCall> {
+        self.decode_entry_function(entry_function(payload)?).ok()
+    }
+}
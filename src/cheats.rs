@@ -0,0 +1,305 @@
+use crate::{module_cache::module_id_of, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_crypto::{ed25519::Ed25519PrivateKey, PrivateKey, Uniform};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    access_path::Path,
+    account_address::AccountAddress,
+    account_config::AccountResource,
+    event::{EventHandle, EventKey},
+    state_store::{
+        state_key::{inner::StateKeyInner, StateKey},
+        state_value::StateValue,
+        table::TableHandle,
+    },
+};
+use move_binary_format::CompiledModule;
+use move_core_types::{
+    language_storage::{ModuleId, StructTag, TypeTag},
+    move_resource::MoveStructType,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, str::FromStr};
+
+impl AptosBB {
+    /// Overwrite the raw bytes stored under a state key, keeping an existing value's metadata
+    pub fn write_state_bytes(&mut self, state_key: StateKey, bytes: Vec<u8>) {
+        let value = match self.executor.read_state_value(&state_key) {
+            Some(old) => StateValue::new_with_metadata(bytes.into(), old.into_metadata()),
+            None => StateValue::new_legacy(bytes.into()),
+        };
+        self.executor.data_store_mut().set(state_key, value);
+        self.view_cache.invalidate();
+    }
+
+    /// Delete a state key from the fork
+    ///
+    /// The key then reads as absent even if it exists on chain: the executor does not fetch
+    /// it again from the network.
+    pub fn delete_state_value(&mut self, state_key: &StateKey) {
+        self.executor.data_store_mut().remove(state_key);
+        self.remote.delete(state_key);
+        if let Some(module_id) = module_id_of(state_key) {
+            self.module_cache.remove(&module_id);
+        }
+        self.view_cache.invalidate();
+    }
+
+    /// Overwrite (or create) a resource with the BCS encoding of `value`
+    ///
+    /// `value` must mirror the Move struct's field layout exactly.
+    pub fn write_resource<T: Serialize>(&mut self, addr: &AccountAddress, struct_tag: &StructTag, value: &T) -> Result<()> {
+        let state_key = StateKey::resource(addr, struct_tag)?;
+        self.write_state_bytes(state_key, bcs::to_bytes(value)?);
+        Ok(())
+    }
+
+    /// Read a table item's raw bytes, with `key` given as a BCS-serializable value
+    pub fn read_table_item<K: Serialize>(&self, handle: AccountAddress, key: &K) -> Result<Option<Vec<u8>>> {
+        let state_key = StateKey::table_item(&TableHandle(handle), &bcs::to_bytes(key)?);
+        Ok(self.read_state_value(&state_key).map(|value| value.bytes().to_vec()))
+    }
+
+    /// Overwrite a table item with the BCS encoding of `value`
+    pub fn write_table_item<K: Serialize, V: Serialize>(&mut self, handle: AccountAddress, key: &K, value: &V) -> Result<()> {
+        let state_key = StateKey::table_item(&TableHandle(handle), &bcs::to_bytes(key)?);
+        self.write_state_bytes(state_key, bcs::to_bytes(value)?);
+        Ok(())
+    }
+
+    /// Members of a resource group (e.g. `0x1::object::ObjectGroup`), as raw bytes per struct
+    pub fn read_resource_group(&self, addr: &AccountAddress, group: &StructTag) -> Result<BTreeMap<StructTag, Vec<u8>>> {
+        let state_key = StateKey::resource_group(addr, group);
+        match self.read_state_value(&state_key) {
+            Some(value) => Ok(bcs::from_bytes(value.bytes())?),
+            None => Ok(BTreeMap::new()),
+        }
+    }
+
+    /// Overwrite a resource group with the given members
+    pub fn write_resource_group(&mut self, addr: &AccountAddress, group: &StructTag, members: &BTreeMap<StructTag, Vec<u8>>) -> Result<()> {
+        let state_key = StateKey::resource_group(addr, group);
+        self.write_state_bytes(state_key, bcs::to_bytes(members)?);
+        Ok(())
+    }
+
+    /// Overwrite (or add) one member of a resource group with the BCS encoding of `value`
+    pub fn write_group_member<T: Serialize>(&mut self, addr: &AccountAddress, group: &StructTag, member: &StructTag, value: &T) -> Result<()> {
+        let mut members = self.read_resource_group(addr, group)?;
+        members.insert(member.clone(), bcs::to_bytes(value)?);
+        self.write_resource_group(addr, group, &members)
+    }
+
+    /// Current on-chain time (`0x1::timestamp::CurrentTimeMicroseconds`) as a unix time in seconds
+    pub fn now_secs(&self) -> Result<u64> {
+        let struct_tag = StructTag::from_str("0x1::timestamp::CurrentTimeMicroseconds")?;
+        let micros: u64 = self
+            .read_state_value(&StateKey::resource(&AccountAddress::ONE, &struct_tag)?)
+            .map(|value| bcs::from_bytes(value.bytes()))
+            .transpose()?
+            .ok_or_else(|| anyhow!("CurrentTimeMicroseconds not found"))?;
+        Ok(micros / 1_000_000)
+    }
+
+    /// Move the on-chain clock (`0x1::timestamp::CurrentTimeMicroseconds`) to a unix time in seconds
+    pub fn set_time(&mut self, unix_secs: u64) -> Result<()> {
+        let struct_tag = StructTag::from_str("0x1::timestamp::CurrentTimeMicroseconds")?;
+        self.write_resource(&AccountAddress::ONE, &struct_tag, &(unix_secs * 1_000_000))
+    }
+
+    /// Copy every resource, resource group and module of `src` to `dst`
+    ///
+    /// With `rewrite_self_address`, occurrences of `src`'s address inside the copied values are
+    /// replaced with `dst` (a byte-level rewrite, so values that merely happen to contain the
+    /// same 32 bytes are rewritten too). Modules are always republished under `dst`, with
+    /// references to `src` in their address pool pointing at `dst` so they call each other.
+    /// Resources and resource group members of types declared at `src` are stored under the
+    /// same types declared at `dst`, so the republished modules find them. Table items are
+    /// not reachable by enumeration and keep pointing at the original handles. Returns the
+    /// number of state keys written.
+    pub fn clone_account_state(&mut self, src: AccountAddress, dst: AccountAddress, rewrite_self_address: bool) -> Result<usize> {
+        self.fetch_account(src)?;
+
+        let mut written = 0;
+        for (state_key, value) in self.local_state() {
+            let StateKeyInner::AccessPath(access_path) = state_key.inner() else {
+                continue;
+            };
+            if access_path.address != src {
+                continue;
+            }
+            let mut bytes = value.bytes().to_vec();
+            let dst_key = match access_path.get_path() {
+                Path::Resource(struct_tag) => StateKey::resource(&dst, &retarget_struct_tag(&struct_tag, &src, &dst))?,
+                Path::ResourceGroup(group) => {
+                    bytes = retarget_group_members(&bytes, &src, &dst)?;
+                    StateKey::resource_group(&dst, &retarget_struct_tag(&group, &src, &dst))
+                }
+                Path::Code(module_id) => {
+                    let dst_id = ModuleId::new(dst, module_id.name().to_owned());
+                    self.module_cache.remove(&dst_id);
+                    self.write_state_bytes(StateKey::module_id(&dst_id), retarget_module(value.bytes(), &src, &dst)?);
+                    written += 1;
+                    continue;
+                }
+            };
+
+            if rewrite_self_address {
+                replace_address(&mut bytes, &src, &dst);
+            }
+            self.write_state_bytes(dst_key, bytes);
+            written += 1;
+        }
+        println!("Cloned {} state keys from {} to {}", written, src, dst);
+        Ok(written)
+    }
+
+    /// Overwrite one field of an account's `AccountResource`, keeping every other field
+    /// (GUID counter, rotation and signer-capability offers) as it is
+    fn patch_account_resource(&mut self, addr: AccountAddress, field: &str, value: Value) -> Result<()> {
+        let struct_tag = AccountResource::struct_tag();
+        let mut resource = self
+            .read_resource_json(&addr, &struct_tag)?
+            .ok_or_else(|| anyhow!("No AccountResource at {}", addr))?;
+        resource[field] = value;
+        self.write_resource_json(&addr, &struct_tag, &resource)
+    }
+
+    /// Take control of an existing address by rotating its authentication key to a fresh key
+    ///
+    /// Only the authentication key changes; the sequence number, GUID counter and everything
+    /// else the account holds are kept. An address without an `AccountResource` (e.g. a
+    /// stateless account) gets one. Returns an `Account` that signs for the address on this fork.
+    pub fn impersonate(&mut self, addr: AccountAddress) -> Result<Account> {
+        self.fetch_account(addr)?;
+        let private_key = Ed25519PrivateKey::generate(&mut rand::rngs::OsRng);
+        let public_key = private_key.public_key();
+        let account = Account::new_from_addr(addr, private_key, public_key);
+
+        let auth_key = json!(format!("0x{}", hex::encode(account.auth_key())));
+        match self.read_account_resource_at_address(&addr) {
+            Some(_) => self.patch_account_resource(addr, "authentication_key", auth_key)?,
+            None => self.write_account_resource(&account)?,
+        }
+        let sequence_number = self
+            .read_account_resource_at_address(&addr)
+            .map(|resource| resource.sequence_number())
+            .unwrap_or_default();
+        self.sequence_numbers.insert(addr, sequence_number);
+        Ok(account)
+    }
+
+    /// Give `account`'s address a fresh `AccountResource` authenticating its key
+    pub(crate) fn write_account_resource(&mut self, account: &Account) -> Result<()> {
+        let addr = *account.address();
+        let resource = AccountResource::new(
+            0,
+            account.auth_key(),
+            EventHandle::new(EventKey::new(0, addr), 0),
+            EventHandle::new(EventKey::new(1, addr), 0),
+        );
+        self.write_resource(&addr, &AccountResource::struct_tag(), &resource)?;
+        // The two event handles above used GUIDs 0 and 1
+        self.patch_account_resource(addr, "guid_creation_num", json!("2"))
+    }
+
+    /// Overwrite an account's on-chain sequence number, e.g. to rewind it below transactions
+    /// it already sent
+    pub fn set_sequence_number(&mut self, addr: AccountAddress, sequence_number: u64) -> Result<()> {
+        self.patch_account_resource(addr, "sequence_number", json!(sequence_number.to_string()))?;
+        self.sequence_numbers.insert(addr, sequence_number);
+        Ok(())
+    }
+}
+
+/// Module bytes with `from` replaced by `to` in the address pool, moving the module (and
+/// its references to modules published alongside it) to `to`
+fn retarget_module(bytes: &[u8], from: &AccountAddress, to: &AccountAddress) -> Result<Vec<u8>> {
+    let mut module = CompiledModule::deserialize(bytes).map_err(|e| anyhow!("Failed to deserialize module: {:?}", e))?;
+    for addr in module.address_identifiers.iter_mut().filter(|addr| **addr == *from) {
+        *addr = *to;
+    }
+    let mut retargeted = vec![];
+    module
+        .serialize(&mut retargeted)
+        .map_err(|e| anyhow!("Failed to serialize module: {:?}", e))?;
+    Ok(retargeted)
+}
+
+/// A struct tag with every type declared at `from`, including type arguments, moved to `to`
+fn retarget_struct_tag(struct_tag: &StructTag, from: &AccountAddress, to: &AccountAddress) -> StructTag {
+    StructTag {
+        address: if struct_tag.address == *from { *to } else { struct_tag.address },
+        module: struct_tag.module.clone(),
+        name: struct_tag.name.clone(),
+        type_args: struct_tag.type_args.iter().map(|ty| retarget_type_tag(ty, from, to)).collect(),
+    }
+}
+
+fn retarget_type_tag(type_tag: &TypeTag, from: &AccountAddress, to: &AccountAddress) -> TypeTag {
+    match type_tag {
+        TypeTag::Struct(struct_tag) => TypeTag::Struct(Box::new(retarget_struct_tag(struct_tag, from, to))),
+        TypeTag::Vector(elem) => TypeTag::Vector(Box::new(retarget_type_tag(elem, from, to))),
+        other => other.clone(),
+    }
+}
+
+/// Resource group bytes with every member's struct tag retargeted from `from` to `to`
+fn retarget_group_members(bytes: &[u8], from: &AccountAddress, to: &AccountAddress) -> Result<Vec<u8>> {
+    let members: BTreeMap<StructTag, Vec<u8>> = bcs::from_bytes(bytes)?;
+    let members: BTreeMap<StructTag, Vec<u8>> = members
+        .into_iter()
+        .map(|(member, value)| (retarget_struct_tag(&member, from, to), value))
+        .collect();
+    Ok(bcs::to_bytes(&members)?)
+}
+
+/// Replace every occurrence of one address' bytes with another's
+fn replace_address(bytes: &mut [u8], from: &AccountAddress, to: &AccountAddress) {
+    let (from, to) = (from.into_bytes(), to.into_bytes());
+    let mut offset = 0;
+    while offset + AccountAddress::LENGTH <= bytes.len() {
+        if bytes[offset..offset + AccountAddress::LENGTH] == from {
+            bytes[offset..offset + AccountAddress::LENGTH].copy_from_slice(&to);
+            offset += AccountAddress::LENGTH;
+        } else {
+            offset += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retargets_types_declared_at_the_source() {
+        let (src, dst) = (AccountAddress::from_hex_literal("0xa").unwrap(), AccountAddress::from_hex_literal("0xb").unwrap());
+        let tag = StructTag::from_str("0x1::coin::CoinStore<0xa::pool::LP<0x1::aptos_coin::AptosCoin, vector<0xa::pool::Share>>>").unwrap();
+        assert_eq!(
+            retarget_struct_tag(&tag, &src, &dst),
+            StructTag::from_str("0x1::coin::CoinStore<0xb::pool::LP<0x1::aptos_coin::AptosCoin, vector<0xb::pool::Share>>>").unwrap()
+        );
+        let own = StructTag::from_str("0xa::pool::Config").unwrap();
+        assert_eq!(retarget_struct_tag(&own, &src, &dst), StructTag::from_str("0xb::pool::Config").unwrap());
+    }
+
+    #[test]
+    fn retargets_resource_group_members() {
+        let (src, dst) = (AccountAddress::from_hex_literal("0xa").unwrap(), AccountAddress::from_hex_literal("0xb").unwrap());
+        let members = BTreeMap::from([
+            (StructTag::from_str("0x1::object::ObjectCore").unwrap(), vec![1]),
+            (StructTag::from_str("0xa::vault::Vault").unwrap(), vec![2]),
+        ]);
+        let retargeted: BTreeMap<StructTag, Vec<u8>> =
+            bcs::from_bytes(&retarget_group_members(&bcs::to_bytes(&members).unwrap(), &src, &dst).unwrap()).unwrap();
+        assert_eq!(
+            retargeted,
+            BTreeMap::from([
+                (StructTag::from_str("0x1::object::ObjectCore").unwrap(), vec![1]),
+                (StructTag::from_str("0xb::vault::Vault").unwrap(), vec![2]),
+            ])
+        );
+    }
+}
@@ -0,0 +1,357 @@
+//! Rust types for on-chain Move structs, so `read_resource::<T>` works for any protocol
+
+use crate::{stubgen::render_type, AptosBB};
+use anyhow::{bail, Result};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{FieldDefinition, SignatureToken, StructDefinition, StructFieldInformation, StructHandleIndex},
+    CompiledModule,
+};
+use move_core_types::{account_address::AccountAddress, identifier::Identifier, language_storage::ModuleId};
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    sync::Arc,
+};
+
+/// Rust keywords a Move identifier may collide with
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "priv", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "try", "type", "unsafe", "use", "where", "while", "yield",
+];
+
+/// A struct by defining module and name
+type StructRef = (ModuleId, Identifier);
+
+/// Rust identifier for a Move field, variant or module name
+fn rust_ident(name: &str) -> String {
+    match name {
+        "self" | "Self" | "super" | "crate" => format!("{}_", name),
+        _ if RUST_KEYWORDS.contains(&name) => format!("r#{}", name),
+        _ => name.to_string(),
+    }
+}
+
+fn struct_ref(module: &CompiledModule, idx: StructHandleIndex) -> StructRef {
+    let handle = module.struct_handle_at(idx);
+    let owner = module.module_id_for_handle(module.module_handle_at(handle.module));
+    (owner, module.identifier_at(handle.name).to_owned())
+}
+
+/// Framework structs with a Rust counterpart of the same BCS encoding
+fn builtin(owner: &ModuleId, name: &str) -> Option<&'static str> {
+    if owner.address() != &AccountAddress::ONE {
+        return None;
+    }
+    match (owner.name().as_str(), name) {
+        ("string", "String") => Some("String"),
+        ("option", "Option") => Some("Option"),
+        ("object", "Object") => Some("AccountAddress"),
+        _ => None,
+    }
+}
+
+/// Structs a type refers to that need generated definitions
+fn referenced_structs(module: &CompiledModule, token: &SignatureToken, out: &mut Vec<StructRef>) {
+    match token {
+        SignatureToken::Vector(inner) | SignatureToken::Reference(inner) | SignatureToken::MutableReference(inner) => {
+            referenced_structs(module, inner, out)
+        }
+        SignatureToken::Struct(idx) | SignatureToken::StructInstantiation(idx, _) => {
+            let (owner, name) = struct_ref(module, *idx);
+            if builtin(&owner, name.as_str()).is_none() {
+                out.push((owner, name));
+            }
+            if let SignatureToken::StructInstantiation(_, ty_args) = token {
+                for ty_arg in ty_args {
+                    referenced_structs(module, ty_arg, out);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn struct_fields(def: &StructDefinition) -> Vec<&FieldDefinition> {
+    match &def.field_information {
+        StructFieldInformation::Native => vec![],
+        StructFieldInformation::Declared(fields) => fields.iter().collect(),
+        StructFieldInformation::DeclaredVariants(variants) => variants.iter().flat_map(|variant| &variant.fields).collect(),
+    }
+}
+
+fn find_struct<'a>(module: &'a CompiledModule, name: &Identifier) -> Option<&'a StructDefinition> {
+    module
+        .struct_defs()
+        .iter()
+        .find(|def| module.identifier_at(module.struct_handle_at(def.struct_handle).name) == name.as_ident_str())
+}
+
+fn address_literal(addr: &AccountAddress) -> String {
+    let bytes: Vec<String> = addr.into_bytes().iter().map(|byte| format!("{:#04x}", byte)).collect();
+    format!("AccountAddress::new([{}])", bytes.join(", "))
+}
+
+/// Renders the collected structs, the target module's at the top level and every other
+/// module's in a nested Rust module of its own
+struct Codegen {
+    target: ModuleId,
+    /// Rust module name of every non-target Move module
+    mod_names: BTreeMap<ModuleId, String>,
+}
+
+impl Codegen {
+    /// Path to a struct as seen from the Rust module generated for `from`
+    fn struct_path(&self, from: &ModuleId, owner: &ModuleId, name: &str) -> String {
+        if owner == from {
+            name.to_string()
+        } else if owner == &self.target {
+            format!("super::{}", name)
+        } else if from == &self.target {
+            format!("{}::{}", self.mod_names[owner], name)
+        } else {
+            format!("super::{}::{}", self.mod_names[owner], name)
+        }
+    }
+
+    fn rust_type(&self, module: &CompiledModule, token: &SignatureToken) -> Result<String> {
+        Ok(match token {
+            SignatureToken::Bool => "bool".to_string(),
+            SignatureToken::U8 => "u8".to_string(),
+            SignatureToken::U16 => "u16".to_string(),
+            SignatureToken::U32 => "u32".to_string(),
+            SignatureToken::U64 => "u64".to_string(),
+            SignatureToken::U128 => "u128".to_string(),
+            SignatureToken::U256 => "move_core_types::u256::U256".to_string(),
+            SignatureToken::Address | SignatureToken::Signer => "AccountAddress".to_string(),
+            SignatureToken::Vector(inner) => format!("Vec<{}>", self.rust_type(module, inner)?),
+            SignatureToken::Struct(idx) => self.struct_type(module, *idx, &[])?,
+            SignatureToken::StructInstantiation(idx, ty_args) => self.struct_type(module, *idx, ty_args)?,
+            SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+            SignatureToken::Reference(_) | SignatureToken::MutableReference(_) | SignatureToken::Function(..) => {
+                bail!("`{}` has no Rust representation", render_type(module, token))
+            }
+        })
+    }
+
+    /// A struct type with its non-phantom type arguments; phantom ones do not affect the layout
+    fn struct_type(&self, module: &CompiledModule, idx: StructHandleIndex, ty_args: &[SignatureToken]) -> Result<String> {
+        let (owner, name) = struct_ref(module, idx);
+        let base = match builtin(&owner, name.as_str()) {
+            Some(builtin) => builtin.to_string(),
+            None => self.struct_path(&module.self_id(), &owner, name.as_str()),
+        };
+        let ty_args: Vec<String> = ty_args
+            .iter()
+            .zip(&module.struct_handle_at(idx).type_parameters)
+            .filter(|(_, param)| !param.is_phantom)
+            .map(|(ty_arg, _)| self.rust_type(module, ty_arg))
+            .collect::<Result<_>>()?;
+        Ok(if ty_args.is_empty() { base } else { format!("{}<{}>", base, ty_args.join(", ")) })
+    }
+
+    fn render_fields(&self, module: &CompiledModule, fields: &[FieldDefinition], indent: &str) -> Result<String> {
+        fields
+            .iter()
+            .map(|field| {
+                Ok(format!(
+                    "{}{}: {},\n",
+                    indent,
+                    rust_ident(module.identifier_at(field.name).as_str()),
+                    self.rust_type(module, &field.signature.0)?
+                ))
+            })
+            .collect()
+    }
+
+    /// Rust definition of a struct or enum, with `MoveStructType` and (for `key` structs)
+    /// `MoveResource` impls when it has no type parameters
+    fn render_struct(&self, module: &CompiledModule, def: &StructDefinition) -> Result<String> {
+        let handle = module.struct_handle_at(def.struct_handle);
+        let self_id = module.self_id();
+        let name = module.identifier_at(handle.name);
+        let params: Vec<String> = handle
+            .type_parameters
+            .iter()
+            .enumerate()
+            .filter(|(_, param)| !param.is_phantom)
+            .map(|(i, _)| format!("T{}", i))
+            .collect();
+        let generics = if params.is_empty() { String::new() } else { format!("<{}>", params.join(", ")) };
+
+        let mut out = format!("/// `{}::{}::{}`\n", self_id.address().to_hex_literal(), self_id.name(), name);
+        out.push_str("#[derive(Clone, Debug, Deserialize, Serialize)]\n");
+        match &def.field_information {
+            StructFieldInformation::Native => bail!("{} is a native struct", name),
+            StructFieldInformation::Declared(fields) => {
+                out.push_str(&format!("pub struct {}{} {{\n", name, generics));
+                out.push_str(&self.render_fields(module, fields, "    pub ")?);
+                out.push_str("}\n");
+            }
+            StructFieldInformation::DeclaredVariants(variants) => {
+                out.push_str(&format!("pub enum {}{} {{\n", name, generics));
+                for variant in variants {
+                    out.push_str(&format!("    {} {{\n", rust_ident(module.identifier_at(variant.name).as_str())));
+                    out.push_str(&self.render_fields(module, &variant.fields, "        ")?);
+                    out.push_str("    },\n");
+                }
+                out.push_str("}\n");
+            }
+        }
+
+        if handle.type_parameters.is_empty() {
+            out.push_str(&format!(
+                "\nimpl MoveStructType for {} {{\n    const ADDRESS: AccountAddress = {};\n    const MODULE_NAME: &'static IdentStr = ident_str!(\"{}\");\n    const STRUCT_NAME: &'static IdentStr = ident_str!(\"{}\");\n}}\n",
+                name,
+                address_literal(self_id.address()),
+                self_id.name(),
+                name
+            ));
+            if handle.abilities.has_key() {
+                out.push_str(&format!("\nimpl MoveResource for {} {{}}\n", name));
+            }
+        }
+        Ok(out)
+    }
+}
+
+impl AptosBB {
+    /// Rust source for every struct of `module_id`, to use with `read_resource::<T>`
+    ///
+    /// Structs of other modules that appear in fields are generated too, in a nested Rust
+    /// module each; `String`, `Option` and `Object` map to their natural Rust counterparts.
+    /// Phantom type parameters are dropped, and only structs without type parameters get
+    /// `MoveStructType` impls since a generic one needs its instantiation to name its tag.
+    pub fn generate_rust_types(&mut self, module_id: &ModuleId) -> Result<String> {
+        let target = self.get_module(module_id)?;
+        let mut modules: BTreeMap<ModuleId, Arc<CompiledModule>> = BTreeMap::new();
+        modules.insert(module_id.clone(), target.clone());
+
+        let mut queue: VecDeque<StructRef> = target
+            .struct_defs()
+            .iter()
+            .filter(|def| !matches!(def.field_information, StructFieldInformation::Native))
+            .map(|def| struct_ref(&target, def.struct_handle))
+            .collect();
+        let mut order = vec![];
+        let mut seen = BTreeSet::new();
+        while let Some(struct_ref) = queue.pop_front() {
+            if !seen.insert(struct_ref.clone()) {
+                continue;
+            }
+            let module = match modules.get(&struct_ref.0) {
+                Some(module) => module.clone(),
+                None => {
+                    let module = self.get_module(&struct_ref.0)?;
+                    modules.insert(struct_ref.0.clone(), module.clone());
+                    module
+                }
+            };
+            let Some(def) = find_struct(&module, &struct_ref.1) else {
+                bail!("{}::{} not found", struct_ref.0, struct_ref.1);
+            };
+            let mut referenced = vec![];
+            for field in struct_fields(def) {
+                referenced_structs(&module, &field.signature.0, &mut referenced);
+            }
+            queue.extend(referenced);
+            order.push(struct_ref);
+        }
+
+        let mut mod_names = BTreeMap::new();
+        for owner in modules.keys().filter(|owner| *owner != module_id) {
+            let shared_name = modules.keys().filter(|other| other.name() == owner.name()).count() > 1;
+            let name = if shared_name {
+                format!("{}_{}", owner.name(), owner.address().short_str_lossless())
+            } else {
+                owner.name().to_string()
+            };
+            mod_names.insert(owner.clone(), rust_ident(&name));
+        }
+        let codegen = Codegen { target: module_id.clone(), mod_names };
+
+        let mut rendered: BTreeMap<&ModuleId, Vec<String>> = BTreeMap::new();
+        for (owner, name) in &order {
+            let module = &modules[owner];
+            let def = find_struct(module, name).expect("struct was found while collecting");
+            let source = codegen
+                .render_struct(module, def)
+                .unwrap_or_else(|e| format!("// Skipped `{}::{}`: {:#}\n", owner, name, e));
+            rendered.entry(owner).or_default().push(source);
+        }
+
+        let mut out = format!(
+            "//! Rust types for `{}::{}`, generated by `aptosbb codegen` at version {}\n#![allow(dead_code, unused_imports)]\n\n",
+            module_id.address().to_hex_literal(),
+            module_id.name(),
+            self.version
+        );
+        out.push_str("use move_core_types::{\n    account_address::AccountAddress,\n    ident_str,\n    identifier::IdentStr,\n    move_resource::{MoveResource, MoveStructType},\n};\nuse serde::{Deserialize, Serialize};\n");
+        for source in rendered.remove(module_id).unwrap_or_default() {
+            out.push('\n');
+            out.push_str(&source);
+        }
+        for (owner, sources) in rendered {
+            out.push_str(&format!("\n/// Structs of `{}::{}`\npub mod {} {{\n    use super::*;\n",
+                owner.address().to_hex_literal(),
+                owner.name(),
+                codegen.mod_names[owner]));
+            for source in sources {
+                out.push('\n');
+                for line in source.lines() {
+                    if line.is_empty() {
+                        out.push('\n');
+                    } else {
+                        out.push_str(&format!("    {}\n", line));
+                    }
+                }
+            }
+            out.push_str("}\n");
+        }
+        println!("Generated {} Rust types for {} ({} modules)", order.len(), module_id, modules.len());
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use move_binary_format::file_format::basic_test_module;
+
+    #[test]
+    fn rust_ident_escapes_keywords() {
+        assert_eq!(rust_ident("type"), "r#type");
+        assert_eq!(rust_ident("self"), "self_");
+        assert_eq!(rust_ident("amount"), "amount");
+    }
+
+    #[test]
+    fn address_literal_lists_every_byte() {
+        let literal = address_literal(&AccountAddress::ONE);
+        assert!(literal.starts_with("AccountAddress::new([0x00, "));
+        assert!(literal.ends_with(", 0x01])"));
+        assert_eq!(literal.matches("0x").count(), AccountAddress::LENGTH);
+    }
+
+    #[test]
+    fn struct_paths_are_relative_to_the_rendering_module() {
+        let target = ModuleId::new(AccountAddress::ONE, Identifier::new("vault").unwrap());
+        let other = ModuleId::new(AccountAddress::TWO, Identifier::new("pool").unwrap());
+        let codegen = Codegen { target: target.clone(), mod_names: BTreeMap::from([(other.clone(), "pool".to_string())]) };
+        assert_eq!(codegen.struct_path(&target, &target, "Vault"), "Vault");
+        assert_eq!(codegen.struct_path(&target, &other, "Pool"), "pool::Pool");
+        assert_eq!(codegen.struct_path(&other, &target, "Vault"), "super::Vault");
+    }
+
+    #[test]
+    fn render_struct_emits_fields_and_struct_type_impl() {
+        let module = basic_test_module();
+        let codegen = Codegen { target: module.self_id(), mod_names: BTreeMap::new() };
+        let source = codegen.render_struct(&module, &module.struct_defs()[0]).unwrap();
+        let name = module.identifier_at(module.struct_handle_at(module.struct_defs()[0].struct_handle).name);
+        assert!(source.contains(&format!("pub struct {} {{\n", name)));
+        assert!(source.contains(": u64,\n"));
+        assert!(source.contains(&format!("impl MoveStructType for {} {{", name)));
+        assert!(!source.contains("impl MoveResource"));
+    }
+}
@@ -0,0 +1,268 @@
+use crate::{export::function_name, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_gas_profiling::{CallFrame, ExecutionGasEvent};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    access_path::Path as AccessPathKind,
+    account_address::AccountAddress,
+    state_store::state_key::{inner::StateKeyInner, StateKey},
+    transaction::TransactionPayload,
+};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// Granularity at which accesses are compared
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Location {
+    /// A resource, including members of resource groups
+    Resource { addr: AccountAddress, struct_tag: StructTag },
+    /// Any other state key: table items, module code, ...
+    Key(StateKey),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resource { addr, struct_tag } => write!(f, "{}::{}", addr.to_hex_literal(), struct_tag),
+            Self::Key(key) => write!(f, "{:?}", key),
+        }
+    }
+}
+
+/// Locations a transaction reads and writes when executed on the current fork state
+///
+/// Reads come from what the VM loads: resources (a resource group member is loaded as its
+/// own resource) and the code of every module the transaction depends on. Table items are
+/// invisible to the gas profiler the reads are taken from, so a table read is only seen when
+/// the same transaction writes the item, which counts as a read too.
+#[derive(Clone, Debug, Default)]
+pub struct AccessSet {
+    pub function: String,
+    pub reads: BTreeSet<Location>,
+    pub writes: BTreeSet<Location>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Both transactions write the location; the later one decides its final value
+    WriteWrite,
+    /// The first transaction reads what the second writes, so its outcome depends on order
+    ReadWrite,
+    /// The first transaction writes what the second reads
+    WriteRead,
+    /// Both read and then write the location: executed against the same snapshot, one
+    /// transaction's update would be computed from a stale value
+    LostUpdate,
+}
+
+/// An access conflict between two of the candidate transactions
+#[derive(Clone, Debug)]
+pub struct Conflict {
+    /// Indices into the candidate list, `a < b`
+    pub a: usize,
+    pub b: usize,
+    pub location: Location,
+    pub kind: ConflictKind,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConflictReport {
+    /// Access set of each candidate, in input order
+    pub access_sets: Vec<AccessSet>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl ConflictReport {
+    pub fn lost_updates(&self) -> Vec<&Conflict> {
+        self.conflicts.iter().filter(|c| c.kind == ConflictKind::LostUpdate).collect()
+    }
+
+    /// Pairs of candidates with at least one conflict
+    pub fn conflicting_pairs(&self) -> BTreeSet<(usize, usize)> {
+        self.conflicts.iter().map(|c| (c.a, c.b)).collect()
+    }
+
+    /// Whether every pair of candidates touches disjoint state
+    pub fn is_conflict_free(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+impl fmt::Display for ConflictReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, access) in self.access_sets.iter().enumerate() {
+            writeln!(f, "#{} {}: {} reads, {} writes", i, access.function, access.reads.len(), access.writes.len())?;
+        }
+        if self.conflicts.is_empty() {
+            return write!(f, "no conflicts");
+        }
+        for conflict in &self.conflicts {
+            writeln!(f, "#{} <-> #{} {:?} on {}", conflict.a, conflict.b, conflict.kind, conflict.location)?;
+        }
+        write!(f, "{} conflicts, {} lost-update candidates", self.conflicts.len(), self.lost_updates().len())
+    }
+}
+
+/// Resources loaded anywhere in the call graph
+fn loaded_resources(frame: &CallFrame, reads: &mut BTreeSet<Location>) {
+    for event in &frame.events {
+        match event {
+            ExecutionGasEvent::Call(frame) => loaded_resources(frame, reads),
+            ExecutionGasEvent::LoadResource { addr, ty: TypeTag::Struct(struct_tag), .. } => {
+                reads.insert(Location::Resource { addr: *addr, struct_tag: *struct_tag.clone() });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Conflicts between two access sets; a lost update subsumes the plain conflicts on its location
+fn pair_conflicts(a: usize, b: usize, first: &AccessSet, second: &AccessSet) -> Vec<Conflict> {
+    let locations: BTreeSet<&Location> = first
+        .writes
+        .intersection(&second.writes)
+        .chain(first.reads.intersection(&second.writes))
+        .chain(first.writes.intersection(&second.reads))
+        .collect();
+
+    let mut conflicts = vec![];
+    for location in locations {
+        let (first_reads, first_writes) = (first.reads.contains(location), first.writes.contains(location));
+        let (second_reads, second_writes) = (second.reads.contains(location), second.writes.contains(location));
+        let kinds = if first_reads && first_writes && second_reads && second_writes {
+            vec![ConflictKind::LostUpdate]
+        } else {
+            [
+                (first_writes && second_writes, ConflictKind::WriteWrite),
+                (first_reads && second_writes, ConflictKind::ReadWrite),
+                (first_writes && second_reads, ConflictKind::WriteRead),
+            ]
+            .into_iter()
+            .filter_map(|(applies, kind)| applies.then_some(kind))
+            .collect()
+        };
+        conflicts.extend(kinds.into_iter().map(|kind| Conflict { a, b, location: location.clone(), kind }));
+    }
+    conflicts
+}
+
+impl AptosBB {
+    /// Locations written by a write set, with resource groups broken down into changed members
+    fn written_locations(&self, key: &StateKey, after: Option<&[u8]>) -> Vec<Location> {
+        let StateKeyInner::AccessPath(access_path) = key.inner() else {
+            return vec![Location::Key(key.clone())];
+        };
+        let addr = access_path.address;
+        match access_path.get_path() {
+            AccessPathKind::Resource(struct_tag) => vec![Location::Resource { addr, struct_tag }],
+            AccessPathKind::ResourceGroup(group) => {
+                let before = self.read_resource_group(&addr, &group).unwrap_or_default();
+                let after: BTreeMap<StructTag, Vec<u8>> =
+                    after.and_then(|bytes| bcs::from_bytes(bytes).ok()).unwrap_or_default();
+                before
+                    .keys()
+                    .chain(after.keys())
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .filter(|member| before.get(*member) != after.get(*member))
+                    .map(|member| Location::Resource { addr, struct_tag: member.clone() })
+                    .collect()
+            }
+            _ => vec![Location::Key(key.clone())],
+        }
+    }
+
+    /// Read and write locations of a transaction, simulated without applying it
+    pub fn access_set(&mut self, account: &Account, payload: TransactionPayload) -> Result<AccessSet> {
+        let function = function_name(&payload);
+        let txn = self.sign_dry_run_transaction(account, payload);
+        let (output, log) = self
+            .executor
+            .execute_transaction_with_gas_profiler(txn)
+            .map_err(|e| anyhow!("Failed to simulate {}: {:?}", function, e))?;
+
+        let mut reads = BTreeSet::new();
+        loaded_resources(&log.exec_io.call_graph, &mut reads);
+        for dependency in &log.exec_io.dependencies {
+            reads.insert(Location::Key(StateKey::module_id(&dependency.id)));
+        }
+        let mut writes = BTreeSet::new();
+        for (key, op) in output.write_set().iter() {
+            for location in self.written_locations(key, op.bytes().map(|bytes| bytes.as_ref())) {
+                if matches!(location, Location::Key(_)) {
+                    reads.insert(location.clone());
+                }
+                writes.insert(location);
+            }
+        }
+        Ok(AccessSet { function, reads, writes })
+    }
+
+    /// Compute read/write sets of candidate transactions and report pairwise conflicts
+    ///
+    /// Every candidate is simulated on its own against the current fork state, i.e. as if
+    /// they all landed in the same block; nothing is applied. Gas fee and sequence number
+    /// bookkeeping is included, so candidates sharing a sender always conflict.
+    pub fn find_conflicts(&mut self, txns: &[(Account, TransactionPayload)]) -> Result<ConflictReport> {
+        let access_sets = txns
+            .iter()
+            .map(|(account, payload)| self.access_set(account, payload.clone()))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut conflicts = vec![];
+        for a in 0..access_sets.len() {
+            for b in a + 1..access_sets.len() {
+                conflicts.extend(pair_conflicts(a, b, &access_sets[a], &access_sets[b]));
+            }
+        }
+        let report = ConflictReport { access_sets, conflicts };
+        println!("{}", report);
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn resource(name: &str) -> Location {
+        Location::Resource { addr: AccountAddress::ONE, struct_tag: StructTag::from_str(&format!("0x1::m::{}", name)).unwrap() }
+    }
+
+    fn access(reads: &[&str], writes: &[&str]) -> AccessSet {
+        AccessSet {
+            function: String::new(),
+            reads: reads.iter().map(|name| resource(name)).collect(),
+            writes: writes.iter().map(|name| resource(name)).collect(),
+        }
+    }
+
+    #[test]
+    fn pair_conflicts_classify_each_shared_location() {
+        let first = access(&["Pool", "Config"], &["Pool", "Log"]);
+        let second = access(&["Pool", "Log"], &["Pool", "Config"]);
+        let mut kinds: Vec<(String, ConflictKind)> =
+            pair_conflicts(0, 1, &first, &second).into_iter().map(|c| (c.location.to_string(), c.kind)).collect();
+        kinds.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            kinds,
+            [
+                (resource("Config").to_string(), ConflictKind::ReadWrite),
+                (resource("Log").to_string(), ConflictKind::WriteRead),
+                (resource("Pool").to_string(), ConflictKind::LostUpdate),
+            ]
+        );
+    }
+
+    #[test]
+    fn disjoint_or_read_only_sets_do_not_conflict() {
+        assert!(pair_conflicts(0, 1, &access(&["A"], &["B"]), &access(&["A"], &["C"])).is_empty());
+        let blind = pair_conflicts(0, 1, &access(&[], &["A"]), &access(&[], &["A"]));
+        assert_eq!(blind.len(), 1);
+        assert_eq!(blind[0].kind, ConflictKind::WriteWrite);
+    }
+}
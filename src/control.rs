@@ -0,0 +1,296 @@
+//! JSON-RPC control plane over HTTP, mirroring anvil's cheatcode RPCs for external test frameworks
+//!
+//! Methods (params are positional):
+//! - `aptosbb_snapshot []` returns a snapshot id
+//! - `aptosbb_revert [id]` restores it, dropping it and every later snapshot
+//! - `aptosbb_setBalance [address, amount, asset?]` sets a balance; `asset` is a coin type or
+//!   fungible asset metadata address, APT by default
+//! - `aptosbb_impersonate [address]` lets `aptosbb_call` send as the address
+//! - `aptosbb_warpTime [unix_secs]` moves the on-chain clock
+//! - `aptosbb_call [sender, function, ty_args, args]` runs an entry function from an
+//!   impersonated sender, with JSON arguments
+//! - `aptosbb_readResource [address, resource_type]` returns a resource as JSON, or null
+//! - `aptosbb_view [function, ty_args, args]` returns a view function's results as JSON
+
+use crate::{
+    blocking::run_blocking,
+    helpers::funding::{apt, Asset},
+    limits::panic_message,
+    snapshot::Checkpoint,
+    AptosBB,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    panic::{catch_unwind, AssertUnwindSafe},
+    str::FromStr,
+    time::Duration,
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// Methods of the control namespace
+const METHODS: &[&str] = &[
+    "aptosbb_snapshot",
+    "aptosbb_revert",
+    "aptosbb_setBalance",
+    "aptosbb_impersonate",
+    "aptosbb_warpTime",
+    "aptosbb_call",
+    "aptosbb_readResource",
+    "aptosbb_view",
+];
+
+/// Largest request body accepted
+const MAX_REQUEST_BYTES: usize = 16 * 1024 * 1024;
+
+/// Time a client gets to send its whole request before the connection is dropped
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A fork driven by JSON-RPC requests
+pub struct ControlPlane {
+    bb: AptosBB,
+    /// Checkpoints with the impersonations that were valid when they were taken
+    snapshots: Vec<(Checkpoint, HashMap<AccountAddress, Account>)>,
+    impersonated: HashMap<AccountAddress, Account>,
+}
+
+fn param<'a>(params: &'a [Value], index: usize, name: &str) -> Result<&'a Value> {
+    params.get(index).ok_or_else(|| anyhow!("Missing parameter {} ({})", index, name))
+}
+
+fn str_param<'a>(params: &'a [Value], index: usize, name: &str) -> Result<&'a str> {
+    param(params, index, name)?
+        .as_str()
+        .ok_or_else(|| anyhow!("Parameter {} ({}) must be a string", index, name))
+}
+
+/// Integer given as a JSON number, decimal string or 0x-prefixed hex string
+fn u64_param(params: &[Value], index: usize, name: &str) -> Result<u64> {
+    let value = param(params, index, name)?;
+    let parsed = match value {
+        Value::Number(number) => number.as_u64(),
+        Value::String(s) => match s.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16).ok(),
+            None => s.parse().ok(),
+        },
+        _ => None,
+    };
+    parsed.ok_or_else(|| anyhow!("Parameter {} ({}) must be an unsigned integer, got {}", index, name, value))
+}
+
+fn address_param(params: &[Value], index: usize, name: &str) -> Result<AccountAddress> {
+    Ok(AccountAddress::from_str(str_param(params, index, name)?)?)
+}
+
+/// Optional array of type strings
+fn ty_args_param(params: &[Value], index: usize) -> Result<Vec<TypeTag>> {
+    match params.get(index) {
+        Some(Value::Array(ty_args)) => ty_args
+            .iter()
+            .map(|ty| TypeTag::from_str(ty.as_str().ok_or_else(|| anyhow!("Type arguments must be strings"))?))
+            .collect(),
+        _ => Ok(vec![]),
+    }
+}
+
+/// Optional array of JSON arguments
+fn args_param(params: &[Value], index: usize) -> Vec<Value> {
+    match params.get(index) {
+        Some(Value::Array(args)) => args.clone(),
+        _ => vec![],
+    }
+}
+
+impl ControlPlane {
+    pub fn new(bb: AptosBB) -> Self {
+        Self { bb, snapshots: vec![], impersonated: HashMap::new() }
+    }
+
+    /// The fork, e.g. to prepare state before serving
+    pub fn bb(&mut self) -> &mut AptosBB {
+        &mut self.bb
+    }
+
+    fn dispatch(&mut self, method: &str, params: &[Value]) -> Result<Value> {
+        match method {
+            "aptosbb_snapshot" => {
+                self.snapshots.push((self.bb.checkpoint(), self.impersonated.clone()));
+                Ok(json!(format!("{:#x}", self.snapshots.len() - 1)))
+            }
+            "aptosbb_revert" => {
+                let id = u64_param(params, 0, "id")? as usize;
+                let Some((checkpoint, impersonated)) = self.snapshots.get(id) else {
+                    return Ok(json!(false));
+                };
+                // Impersonations rotated authentication keys, which the revert restores
+                self.bb.revert(checkpoint);
+                self.impersonated = impersonated.clone();
+                self.snapshots.truncate(id);
+                Ok(json!(true))
+            }
+            "aptosbb_setBalance" => {
+                let address = address_param(params, 0, "address")?;
+                let amount = u64_param(params, 1, "amount")?;
+                let asset = match params.get(2).and_then(Value::as_str) {
+                    None => apt(),
+                    Some(asset) if asset.contains("::") => Asset::Coin(TypeTag::from_str(asset)?),
+                    Some(metadata) => Asset::FungibleAsset(AccountAddress::from_str(metadata)?),
+                };
+                self.bb.fund(&address, &asset, amount)?;
+                Ok(json!(true))
+            }
+            "aptosbb_impersonate" => {
+                let address = address_param(params, 0, "address")?;
+                if !self.impersonated.contains_key(&address) {
+                    let account = self.bb.impersonate(address)?;
+                    self.impersonated.insert(address, account);
+                }
+                Ok(json!(true))
+            }
+            "aptosbb_warpTime" => {
+                let unix_secs = u64_param(params, 0, "unix_secs")?;
+                self.bb.set_time(unix_secs)?;
+                Ok(json!(self.bb.now_secs()?))
+            }
+            "aptosbb_call" => {
+                let sender = address_param(params, 0, "sender")?;
+                let function = str_param(params, 1, "function")?;
+                let ty_args = ty_args_param(params, 2)?;
+                let args = args_param(params, 3);
+                let account = self
+                    .impersonated
+                    .get(&sender)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("{} is not impersonated, call aptosbb_impersonate first", sender))?;
+                self.bb.call_json(&account, function, ty_args, &args)
+            }
+            "aptosbb_readResource" => {
+                let address = address_param(params, 0, "address")?;
+                let resource_type = StructTag::from_str(str_param(params, 1, "resource_type")?)?;
+                Ok(self.bb.read_resource_json(&address, &resource_type)?.unwrap_or(Value::Null))
+            }
+            "aptosbb_view" => {
+                let function = str_param(params, 0, "function")?;
+                let ty_args = ty_args_param(params, 1)?;
+                let args = args_param(params, 2);
+                let types = self.bb.entry_param_types(function, &ty_args)?;
+                if types.len() != args.len() {
+                    bail!("{} takes {} arguments, got {}", function, types.len(), args.len());
+                }
+                let args = types
+                    .iter()
+                    .zip(&args)
+                    .map(|(ty, json)| self.bb.json_to_bcs(ty, json))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(json!(self.bb.view(function, ty_args, args)?.json))
+            }
+            _ => bail!("Method {} not found", method),
+        }
+    }
+
+    /// Answer one JSON-RPC 2.0 request, or a batch of them
+    pub fn handle(&mut self, request: &Value) -> Value {
+        if let Value::Array(batch) = request {
+            return Value::Array(batch.iter().map(|request| self.handle(request)).collect());
+        }
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        let Some(method) = request.get("method").and_then(Value::as_str) else {
+            return json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32600, "message": "Invalid request" } });
+        };
+        if !METHODS.contains(&method) {
+            return json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32601, "message": format!("Method {} not found", method) } });
+        }
+        let params = match request.get("params") {
+            Some(Value::Array(params)) => params.as_slice(),
+            _ => &[],
+        };
+        match self.dispatch(method, params) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(e) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": -32000, "message": format!("{:#}", e) } }),
+        }
+    }
+
+    /// Serve JSON-RPC over HTTP POST on `addr` until the process is stopped
+    ///
+    /// Requests are executed one at a time, so they see the fork in a consistent state. A
+    /// client gets `READ_TIMEOUT` to send its request, so an idle connection cannot hold up
+    /// the others, and a panicking request is answered with an internal error.
+    pub async fn serve(mut self, addr: SocketAddr) -> Result<()> {
+        let listener = TcpListener::bind(addr).await.with_context(|| format!("Binding {}", addr))?;
+        println!("🛰️  Control plane listening on http://{}", listener.local_addr()?);
+        loop {
+            let (mut stream, peer) = listener.accept().await?;
+            if let Err(e) = self.serve_connection(&mut stream).await {
+                eprintln!("⚠️  Request from {} failed: {:#}", peer, e);
+            }
+        }
+    }
+
+    async fn serve_connection(&mut self, stream: &mut TcpStream) -> Result<()> {
+        let body = tokio::time::timeout(READ_TIMEOUT, read_request(stream))
+            .await
+            .map_err(|_| anyhow!("No complete request within {:?}", READ_TIMEOUT))??;
+        let response = match serde_json::from_slice::<Value>(&body) {
+            Ok(request) => run_blocking(|| catch_unwind(AssertUnwindSafe(|| self.handle(&request))))
+                .unwrap_or_else(|panic| {
+                    let message = format!("Internal error: panicked: {}", panic_message(panic.as_ref()));
+                    json!({ "jsonrpc": "2.0", "id": request.get("id").cloned().unwrap_or(Value::Null), "error": { "code": -32603, "message": message } })
+                }),
+            Err(e) => json!({ "jsonrpc": "2.0", "id": null, "error": { "code": -32700, "message": format!("Parse error: {}", e) } }),
+        };
+        let response = response.to_string();
+        let http = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.len(),
+            response
+        );
+        stream.write_all(http.as_bytes()).await?;
+        Ok(())
+    }
+}
+
+/// Read one HTTP request and return its body
+async fn read_request(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut buffer = vec![];
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            bail!("Connection closed before the request headers ended");
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if buffer.len() > MAX_REQUEST_BYTES {
+            bail!("Request headers too large");
+        }
+    };
+    let headers = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let content_length = headers
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .map(|(_, value)| value.trim().parse::<usize>())
+        .transpose()?
+        .unwrap_or(0);
+    if content_length > MAX_REQUEST_BYTES {
+        bail!("Request body of {} bytes is too large", content_length);
+    }
+    while buffer.len() < header_end + content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            bail!("Connection closed before the request body ended");
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+    }
+    Ok(buffer[header_end..header_end + content_length].to_vec())
+}
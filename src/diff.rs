@@ -0,0 +1,264 @@
+use crate::{
+    blocking,
+    stubgen::{render_function_signature, render_qualified_type},
+    AptosBB,
+};
+use anyhow::{anyhow, Result};
+use aptos_framework::natives::code::PackageRegistry;
+use aptos_types::{account_address::AccountAddress, state_store::state_key::StateKey};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{
+        Bytecode, FieldDefinition, FunctionDefinition, FunctionHandleIndex, MemberCount, SignatureIndex,
+        SignatureToken, StructDefinitionIndex, StructFieldInformation, StructVariantHandleIndex,
+        VariantFieldHandleIndex, VariantIndex,
+    },
+    CompiledModule,
+};
+use move_core_types::{language_storage::ModuleId, move_resource::MoveStructType};
+use similar::TextDiff;
+use std::collections::BTreeMap;
+
+/// A function-level change between two package versions
+#[derive(Clone, Debug)]
+pub enum FunctionChange {
+    Added { module: String, signature: String },
+    Removed { module: String, signature: String },
+    SignatureChanged { module: String, before: String, after: String },
+    /// Unified diff of the function's disassembly
+    BodyChanged { module: String, function: String, diff: String },
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PackageDiff {
+    pub modules_added: Vec<String>,
+    pub modules_removed: Vec<String>,
+    pub upgrade_numbers: (u64, u64),
+    pub changes: Vec<FunctionChange>,
+}
+
+/// Type arguments of a signature, e.g. `<u64, 0x1::aptos_coin::AptosCoin>`
+fn type_args(module: &CompiledModule, idx: SignatureIndex) -> String {
+    let tokens = &module.signature_at(idx).0;
+    if tokens.is_empty() {
+        return String::new();
+    }
+    let rendered: Vec<String> = tokens.iter().map(|token| render_qualified_type(module, token)).collect();
+    format!("<{}>", rendered.join(", "))
+}
+
+fn function_name(module: &CompiledModule, idx: FunctionHandleIndex) -> String {
+    let handle = module.function_handle_at(idx);
+    let owner = module.module_id_for_handle(module.module_handle_at(handle.module));
+    format!("{}::{}", owner, module.identifier_at(handle.name))
+}
+
+fn struct_name(module: &CompiledModule, idx: StructDefinitionIndex) -> String {
+    render_qualified_type(module, &SignatureToken::Struct(module.struct_def_at(idx).struct_handle))
+}
+
+/// Fields of a struct, or of one of its variants
+fn fields(module: &CompiledModule, idx: StructDefinitionIndex, variant: Option<VariantIndex>) -> &[FieldDefinition] {
+    match (&module.struct_def_at(idx).field_information, variant) {
+        (StructFieldInformation::Declared(fields), _) => fields,
+        (StructFieldInformation::DeclaredVariants(variants), Some(variant)) => {
+            variants.get(variant as usize).map_or(&[], |variant| variant.fields.as_slice())
+        }
+        _ => &[],
+    }
+}
+
+fn field_name(module: &CompiledModule, idx: StructDefinitionIndex, variant: Option<VariantIndex>, field: MemberCount) -> String {
+    let name = fields(module, idx, variant)
+        .get(field as usize)
+        .map_or_else(|| format!("#{}", field), |def| module.identifier_at(def.name).to_string());
+    format!("{}.{}", struct_name(module, idx), name)
+}
+
+fn variant_name(module: &CompiledModule, idx: StructVariantHandleIndex) -> String {
+    let handle = module.struct_variant_handle_at(idx);
+    let variant = match &module.struct_def_at(handle.struct_index).field_information {
+        StructFieldInformation::DeclaredVariants(variants) => variants
+            .get(handle.variant as usize)
+            .map_or_else(|| format!("#{}", handle.variant), |variant| module.identifier_at(variant.name).to_string()),
+        _ => format!("#{}", handle.variant),
+    };
+    format!("{}::{}", struct_name(module, handle.struct_index), variant)
+}
+
+fn variant_field_name(module: &CompiledModule, idx: VariantFieldHandleIndex) -> String {
+    let handle = module.variant_field_handle_at(idx);
+    field_name(module, handle.struct_index, handle.variants.first().copied(), handle.field)
+}
+
+/// An instruction with every pool index it carries (functions, structs, fields, variants,
+/// constants, vector element types) replaced by what it points to
+fn render_instr(module: &CompiledModule, instr: &Bytecode) -> String {
+    use Bytecode::*;
+    let name = format!("{:?}", instr);
+    let name = name.split('(').next().unwrap_or_default();
+    let operand = match instr {
+        Call(idx) | PackClosure(idx, _) => function_name(module, *idx),
+        CallGeneric(idx) | PackClosureGeneric(idx, _) => {
+            let inst = module.function_instantiation_at(*idx);
+            format!("{}{}", function_name(module, inst.handle), type_args(module, inst.type_parameters))
+        }
+        Pack(idx) | Unpack(idx) | Exists(idx) | MoveFrom(idx) | MoveTo(idx) | ImmBorrowGlobal(idx)
+        | MutBorrowGlobal(idx) => struct_name(module, *idx),
+        PackGeneric(idx) | UnpackGeneric(idx) | ExistsGeneric(idx) | MoveFromGeneric(idx) | MoveToGeneric(idx)
+        | ImmBorrowGlobalGeneric(idx) | MutBorrowGlobalGeneric(idx) => {
+            let inst = module.struct_instantiation_at(*idx);
+            format!("{}{}", struct_name(module, inst.def), type_args(module, inst.type_parameters))
+        }
+        ImmBorrowField(idx) | MutBorrowField(idx) => {
+            let handle = module.field_handle_at(*idx);
+            field_name(module, handle.owner, None, handle.field)
+        }
+        ImmBorrowFieldGeneric(idx) | MutBorrowFieldGeneric(idx) => {
+            let inst = module.field_instantiation_at(*idx);
+            let handle = module.field_handle_at(inst.handle);
+            format!("{}{}", field_name(module, handle.owner, None, handle.field), type_args(module, inst.type_parameters))
+        }
+        PackVariant(idx) | UnpackVariant(idx) | TestVariant(idx) => variant_name(module, *idx),
+        PackVariantGeneric(idx) | UnpackVariantGeneric(idx) | TestVariantGeneric(idx) => {
+            let inst = module.struct_variant_instantiation_at(*idx);
+            format!("{}{}", variant_name(module, inst.handle), type_args(module, inst.type_parameters))
+        }
+        ImmBorrowVariantField(idx) | MutBorrowVariantField(idx) => variant_field_name(module, *idx),
+        ImmBorrowVariantFieldGeneric(idx) | MutBorrowVariantFieldGeneric(idx) => {
+            let inst = module.variant_field_instantiation_at(*idx);
+            format!("{}{}", variant_field_name(module, inst.handle), type_args(module, inst.type_parameters))
+        }
+        LdConst(idx) => {
+            let constant = module.constant_at(*idx);
+            format!("{} 0x{}", render_qualified_type(module, &constant.type_), hex::encode(&constant.data))
+        }
+        VecPack(idx, count) | VecUnpack(idx, count) => format!("{}, {}", type_args(module, *idx), count),
+        VecLen(idx) | VecImmBorrow(idx) | VecMutBorrow(idx) | VecPushBack(idx) | VecPopBack(idx) | VecSwap(idx)
+        | CallClosure(idx) => type_args(module, *idx),
+        _ => return format!("{:?}\n", instr),
+    };
+    let mask = match instr {
+        PackClosure(_, mask) | PackClosureGeneric(_, mask) => format!(", {:?}", mask),
+        _ => String::new(),
+    };
+    format!("{}({}{})\n", name, operand, mask)
+}
+
+/// One instruction per line, with pool indices resolved to names so that pool reshuffling
+/// between versions does not show up as a change
+fn disassemble(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    let Some(code) = &def.code else {
+        return "native\n".to_string();
+    };
+    code.code.iter().map(|instr| render_instr(module, instr)).collect()
+}
+
+fn functions_by_name(module: &CompiledModule) -> BTreeMap<String, &FunctionDefinition> {
+    module
+        .function_defs()
+        .iter()
+        .map(|def| (module.identifier_at(module.function_handle_at(def.function).name).to_string(), def))
+        .collect()
+}
+
+impl AptosBB {
+    /// Modules of a package as they were at a historical version
+    fn package_modules_at(&self, addr: AccountAddress, name: &str, version: u64) -> Result<(u64, BTreeMap<String, CompiledModule>)> {
+        let remote = self.remote.at_version(version);
+        blocking::block_on(async {
+            let registry_key = StateKey::resource(&addr, &PackageRegistry::struct_tag())?;
+            let registry: PackageRegistry = match remote.get_state_value(&registry_key).await? {
+                Some(value) => bcs::from_bytes(value.bytes())?,
+                None => return Err(anyhow!("No packages at {} at version {}", addr, version)),
+            };
+            let package = registry.packages
+                .into_iter()
+                .find(|package| package.name == name)
+                .ok_or_else(|| anyhow!("Package {} not found at {} at version {}", name, addr, version))?;
+
+            let mut modules = BTreeMap::new();
+            for module in &package.modules {
+                let module_id = ModuleId::new(addr, module.name.parse()?);
+                let value = remote.get_state_value(&StateKey::module_id(&module_id)).await?
+                    .ok_or_else(|| anyhow!("Module {} missing at version {}", module_id, version))?;
+                let compiled = CompiledModule::deserialize(value.bytes())
+                    .map_err(|e| anyhow!("Failed to deserialize {}: {:?}", module_id, e))?;
+                modules.insert(module.name.clone(), compiled);
+            }
+            Ok((package.upgrade_number, modules))
+        })
+    }
+
+    /// Function-level diff of a package between two ledger versions
+    pub fn diff_package_versions(&self, addr: AccountAddress, name: &str, v1: u64, v2: u64) -> Result<PackageDiff> {
+        let (upgrade_before, before) = self.package_modules_at(addr, name, v1)?;
+        let (upgrade_after, after) = self.package_modules_at(addr, name, v2)?;
+
+        let mut diff = PackageDiff {
+            upgrade_numbers: (upgrade_before, upgrade_after),
+            ..Default::default()
+        };
+        diff.modules_removed = before.keys().filter(|m| !after.contains_key(*m)).cloned().collect();
+        diff.modules_added = after.keys().filter(|m| !before.contains_key(*m)).cloned().collect();
+
+        for (module_name, new_module) in &after {
+            let new_functions = functions_by_name(new_module);
+            let Some(old_module) = before.get(module_name) else {
+                for def in new_functions.values() {
+                    diff.changes.push(FunctionChange::Added {
+                        module: module_name.clone(),
+                        signature: render_function_signature(new_module, def),
+                    });
+                }
+                continue;
+            };
+            let old_functions = functions_by_name(old_module);
+
+            for (function, old_def) in &old_functions {
+                if !new_functions.contains_key(function) {
+                    diff.changes.push(FunctionChange::Removed {
+                        module: module_name.clone(),
+                        signature: render_function_signature(old_module, old_def),
+                    });
+                }
+            }
+
+            for (function, new_def) in &new_functions {
+                let Some(old_def) = old_functions.get(function) else {
+                    diff.changes.push(FunctionChange::Added {
+                        module: module_name.clone(),
+                        signature: render_function_signature(new_module, new_def),
+                    });
+                    continue;
+                };
+
+                let before_sig = render_function_signature(old_module, old_def);
+                let after_sig = render_function_signature(new_module, new_def);
+                if before_sig != after_sig {
+                    diff.changes.push(FunctionChange::SignatureChanged {
+                        module: module_name.clone(),
+                        before: before_sig,
+                        after: after_sig,
+                    });
+                }
+
+                let old_code = disassemble(old_module, old_def);
+                let new_code = disassemble(new_module, new_def);
+                if old_code != new_code {
+                    let unified = TextDiff::from_lines(&old_code, &new_code)
+                        .unified_diff()
+                        .header(&format!("{}@{}", function, v1), &format!("{}@{}", function, v2))
+                        .to_string();
+                    diff.changes.push(FunctionChange::BodyChanged {
+                        module: module_name.clone(),
+                        function: function.clone(),
+                        diff: unified,
+                    });
+                }
+            }
+        }
+
+        Ok(diff)
+    }
+}
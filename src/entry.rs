@@ -0,0 +1,213 @@
+use crate::AptosBB;
+use anyhow::{anyhow, bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{EntryFunction, TransactionPayload, TransactionStatus},
+};
+use move_core_types::{
+    identifier::Identifier,
+    language_storage::{ModuleId, TypeTag},
+    u256::U256,
+};
+use std::str::FromStr;
+
+/// Values that can be passed as entry function arguments
+pub trait MoveArg {
+    /// BCS encoding of the value as the Move VM expects it
+    fn to_move_arg(&self) -> Vec<u8>;
+}
+
+macro_rules! impl_move_arg_bcs {
+    ($($ty:ty),*) => {
+        $(
+            impl MoveArg for $ty {
+                fn to_move_arg(&self) -> Vec<u8> {
+                    bcs::to_bytes(self).expect("primitive values are BCS serializable")
+                }
+            }
+        )*
+    };
+}
+
+impl_move_arg_bcs!(bool, u8, u16, u32, u64, u128, U256, AccountAddress, String);
+
+impl MoveArg for &str {
+    fn to_move_arg(&self) -> Vec<u8> {
+        bcs::to_bytes(self).expect("strings are BCS serializable")
+    }
+}
+
+fn encode_uleb128(mut value: usize, out: &mut Vec<u8>) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+impl<T: MoveArg> MoveArg for Vec<T> {
+    fn to_move_arg(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_uleb128(self.len(), &mut out);
+        for item in self {
+            out.extend(item.to_move_arg());
+        }
+        out
+    }
+}
+
+/// `0x1::option::Option<T>` is encoded as a vector of zero or one element
+impl<T: MoveArg> MoveArg for Option<T> {
+    fn to_move_arg(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        encode_uleb128(self.is_some() as usize, &mut out);
+        if let Some(value) = self {
+            out.extend(value.to_move_arg());
+        }
+        out
+    }
+}
+
+/// Pre-encoded argument bytes, passed through unchanged
+pub struct RawArg(pub Vec<u8>);
+
+impl MoveArg for RawArg {
+    fn to_move_arg(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+}
+
+/// Fluent builder for entry function calls
+///
+/// ```ignore
+/// let call = EntryCall::new("0xabc::pool::swap")
+///     .ty("0x1::aptos_coin::AptosCoin")
+///     .arg(100u64)
+///     .arg(*attacker.address());
+/// let status = bb.call(&attacker, call)?;
+/// ```
+///
+/// Parse errors are collected and reported by `build`, so chains never need `unwrap`.
+pub struct EntryCall {
+    function: Result<(ModuleId, Identifier)>,
+    ty_args: Result<Vec<TypeTag>>,
+    args: Vec<Vec<u8>>,
+}
+
+/// Parse `address::module::function`
+pub fn parse_function_id(function: &str) -> Result<(ModuleId, Identifier)> {
+    let parts: Vec<&str> = function.split("::").collect();
+    let [address, module, name] = parts.as_slice() else {
+        return Err(anyhow!("Expected `address::module::function`, got `{}`", function));
+    };
+    let address = AccountAddress::from_str(address)
+        .map_err(|e| anyhow!("Invalid address in `{}`: {}", function, e))?;
+    Ok((
+        ModuleId::new(address, Identifier::new(*module)?),
+        Identifier::new(*name)?,
+    ))
+}
+
+/// Parse `address::module`
+pub fn parse_module_id(module: &str) -> Result<ModuleId> {
+    let parts: Vec<&str> = module.split("::").collect();
+    let [address, name] = parts.as_slice() else {
+        return Err(anyhow!("Expected `address::module`, got `{}`", module));
+    };
+    let address = AccountAddress::from_str(address)
+        .map_err(|e| anyhow!("Invalid address in `{}`: {}", module, e))?;
+    Ok(ModuleId::new(address, Identifier::new(*name)?))
+}
+
+impl EntryCall {
+    pub fn new(function: &str) -> Self {
+        Self {
+            function: parse_function_id(function),
+            ty_args: Ok(vec![]),
+            args: vec![],
+        }
+    }
+
+    /// Add a type argument, e.g. `"0x1::aptos_coin::AptosCoin"`
+    pub fn ty(mut self, type_tag: &str) -> Self {
+        if let Ok(ty_args) = &mut self.ty_args {
+            match TypeTag::from_str(type_tag) {
+                Ok(tag) => ty_args.push(tag),
+                Err(e) => self.ty_args = Err(anyhow!("Invalid type argument `{}`: {}", type_tag, e)),
+            }
+        }
+        self
+    }
+
+    /// Add an already parsed type argument
+    pub fn ty_tag(mut self, type_tag: TypeTag) -> Self {
+        if let Ok(ty_args) = &mut self.ty_args {
+            ty_args.push(type_tag);
+        }
+        self
+    }
+
+    /// Add an argument
+    pub fn arg(mut self, arg: impl MoveArg) -> Self {
+        self.args.push(arg.to_move_arg());
+        self
+    }
+
+    pub fn build(self) -> Result<EntryFunction> {
+        let (module, function) = self.function?;
+        Ok(EntryFunction::new(module, function, self.ty_args?, self.args))
+    }
+
+    pub fn into_payload(self) -> Result<TransactionPayload> {
+        Ok(TransactionPayload::EntryFunction(self.build()?))
+    }
+}
+
+impl AptosBB {
+    /// Run an entry function described by an `EntryCall`
+    pub fn call(&mut self, account: &Account, call: EntryCall) -> Result<TransactionStatus> {
+        let payload = call.into_payload()?;
+        Ok(self.run_transaction(account, payload)?)
+    }
+
+    /// Run an entry function with several `&signer` parameters, signed in order by `signers`
+    pub fn call_multi(&mut self, signers: &[&Account], call: EntryCall) -> Result<TransactionStatus> {
+        let Some((sender, secondary_signers)) = signers.split_first() else {
+            bail!("At least one signer is required");
+        };
+        let payload = call.into_payload()?;
+        let (status, _) = self.run_multi_agent_transaction_with_output(sender, secondary_signers, payload)?;
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_args_match_bcs() {
+        assert_eq!(7u64.to_move_arg(), bcs::to_bytes(&7u64).unwrap());
+        assert_eq!("hi".to_move_arg(), bcs::to_bytes("hi").unwrap());
+        assert_eq!(vec![1u16, 2].to_move_arg(), bcs::to_bytes(&vec![1u16, 2]).unwrap());
+        let long = vec![0u8; 300];
+        assert_eq!(long.to_move_arg(), bcs::to_bytes(&long).unwrap());
+    }
+
+    #[test]
+    fn options_encode_as_vectors_of_at_most_one() {
+        assert_eq!(None::<u8>.to_move_arg(), [0]);
+        assert_eq!(Some(5u8).to_move_arg(), [1, 5]);
+        assert_eq!(RawArg(vec![9, 9]).to_move_arg(), [9, 9]);
+    }
+
+    #[test]
+    fn entry_call_reports_the_first_parse_error_on_build() {
+        assert!(EntryCall::new("0x1::coin::transfer").ty("0x1::aptos_coin::AptosCoin").arg(1u64).build().is_ok());
+        assert!(EntryCall::new("0x1::coin").build().is_err());
+        assert!(EntryCall::new("0x1::coin::transfer").ty("not a type").ty_tag(TypeTag::U8).build().is_err());
+        assert!(parse_module_id("0x1::coin").is_ok());
+        assert!(parse_module_id("nothex::coin").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use std::fmt;
+
+/// Result type returned by the fallible `AptosBB` APIs
+pub type Result<T, E = AptosBBError> = std::result::Result<T, E>;
+
+/// Why an `AptosBB` operation failed
+///
+/// Transactions that abort or run out of gas are not errors: they still produce a
+/// `TransactionStatus`, which is returned in the `Ok` case for the caller to inspect.
+#[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum AptosBBError {
+    /// Preparing or compiling a Move package failed
+    BuildError(anyhow::Error),
+    /// An identifier, function ID or payload could not be encoded
+    EncodingError(String),
+    /// Fetching state from the forked network (or the local state cache) failed
+    RemoteStateError(anyhow::Error),
+    /// The VM failed to evaluate a view function or to execute a transaction
+    ExecutionError(String),
+}
+
+impl AptosBBError {
+    pub(crate) fn encoding(what: &str, e: impl fmt::Display) -> Self {
+        Self::EncodingError(format!("{}: {}", what, e))
+    }
+}
+
+impl fmt::Display for AptosBBError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BuildError(e) => write!(f, "failed to build package: {:#}", e),
+            Self::EncodingError(msg) => write!(f, "encoding failed: {}", msg),
+            Self::RemoteStateError(e) => write!(f, "failed to load remote state: {:#}", e),
+            Self::ExecutionError(msg) => write!(f, "execution failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AptosBBError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::BuildError(e) | Self::RemoteStateError(e) => Some(e.as_ref()),
+            Self::EncodingError(_) | Self::ExecutionError(_) => None,
+        }
+    }
+}
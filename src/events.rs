@@ -0,0 +1,261 @@
+use crate::{observer::TxnObserver, AptosBB};
+use anyhow::{anyhow, bail, Result};
+use aptos_types::{
+    contract_event::ContractEvent,
+    transaction::{SignedTransaction, TransactionOutput},
+};
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use move_core_types::language_storage::TypeTag;
+use serde_json::Value;
+use std::{fmt, str::FromStr};
+
+/// Condition on a decoded event field
+pub enum Predicate {
+    Eq(Value),
+    Ne(Value),
+    Gt(u128),
+    Ge(u128),
+    Lt(u128),
+    Le(u128),
+    Custom(String, Box<dyn Fn(&Value) -> bool>),
+}
+
+pub fn eq(value: impl Into<Value>) -> Predicate {
+    Predicate::Eq(value.into())
+}
+
+pub fn ne(value: impl Into<Value>) -> Predicate {
+    Predicate::Ne(value.into())
+}
+
+pub fn gt(value: u128) -> Predicate {
+    Predicate::Gt(value)
+}
+
+pub fn ge(value: u128) -> Predicate {
+    Predicate::Ge(value)
+}
+
+pub fn lt(value: u128) -> Predicate {
+    Predicate::Lt(value)
+}
+
+pub fn le(value: u128) -> Predicate {
+    Predicate::Le(value)
+}
+
+/// Arbitrary check on the field's JSON value, described by `name` in failure messages
+pub fn satisfies(name: impl Into<String>, check: impl Fn(&Value) -> bool + 'static) -> Predicate {
+    Predicate::Custom(name.into(), Box::new(check))
+}
+
+/// Integers are rendered as JSON strings by the API encoding when wider than 32 bits
+fn as_u128(value: &Value) -> Option<u128> {
+    match value {
+        Value::Number(n) => n.as_u64().map(u128::from),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Compare allowing `eq(5)` to match the API's string-encoded `"5"`
+fn json_eq(actual: &Value, expected: &Value) -> bool {
+    actual == expected || matches!((as_u128(actual), as_u128(expected)), (Some(a), Some(b)) if a == b)
+}
+
+impl Predicate {
+    pub fn test(&self, value: &Value) -> bool {
+        match self {
+            Predicate::Eq(expected) => json_eq(value, expected),
+            Predicate::Ne(expected) => !json_eq(value, expected),
+            Predicate::Gt(bound) => as_u128(value).is_some_and(|v| v > *bound),
+            Predicate::Ge(bound) => as_u128(value).is_some_and(|v| v >= *bound),
+            Predicate::Lt(bound) => as_u128(value).is_some_and(|v| v < *bound),
+            Predicate::Le(bound) => as_u128(value).is_some_and(|v| v <= *bound),
+            Predicate::Custom(_, check) => check(value),
+        }
+    }
+}
+
+impl fmt::Display for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Predicate::Eq(v) => write!(f, "== {}", v),
+            Predicate::Ne(v) => write!(f, "!= {}", v),
+            Predicate::Gt(v) => write!(f, "> {}", v),
+            Predicate::Ge(v) => write!(f, ">= {}", v),
+            Predicate::Lt(v) => write!(f, "< {}", v),
+            Predicate::Le(v) => write!(f, "<= {}", v),
+            Predicate::Custom(name, _) => write!(f, "satisfies {}", name),
+        }
+    }
+}
+
+/// Look up a dotted path (`"metadata.inner"`) in a decoded event
+fn field<'v>(value: &'v Value, path: &str) -> Option<&'v Value> {
+    path.split('.').try_fold(value, |value, key| value.get(key))
+}
+
+/// An event emitted by a transaction executed on the fork
+#[derive(Clone, Debug)]
+pub struct DecodedEvent {
+    /// Position of the emitting transaction among those executed on the fork
+    pub txn_index: u64,
+    pub type_tag: TypeTag,
+    /// Decoded fields, or the hex-encoded payload if the layout could not be resolved
+    pub data: Value,
+}
+
+impl fmt::Display for DecodedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[txn {}] {} {}", self.txn_index, self.type_tag, self.data)
+    }
+}
+
+/// Subscribers to the fork's events, each with an optional type prefix filter
+#[derive(Default)]
+pub(crate) struct EventBus {
+    subscribers: Vec<(Option<String>, UnboundedSender<DecodedEvent>)>,
+    txn_count: u64,
+}
+
+/// Expected occurrence of an event, built with `AptosBB::expect_event`
+pub struct EventMatcher<'a> {
+    bb: &'a AptosBB,
+    event_type: String,
+    fields: Vec<(String, Predicate)>,
+    times: Option<usize>,
+}
+
+impl<'a> EventMatcher<'a> {
+    /// Require a (dotted path) field of the event to satisfy a predicate
+    pub fn field(mut self, path: &str, predicate: Predicate) -> Self {
+        self.fields.push((path.to_string(), predicate));
+        self
+    }
+
+    /// Require exactly `n` matching events instead of at least one
+    pub fn times(mut self, n: usize) -> Self {
+        self.times = Some(n);
+        self
+    }
+
+    fn type_tag(&self) -> Result<TypeTag> {
+        TypeTag::from_str(&self.event_type).map_err(|e| anyhow!("Invalid event type {}: {}", self.event_type, e))
+    }
+
+    /// Decoded events of the expected type that satisfy every field predicate
+    pub fn matching(&self, events: &[ContractEvent]) -> Result<Vec<Value>> {
+        let type_tag = self.type_tag()?;
+        let mut matched = vec![];
+        for event in events.iter().filter(|event| *event.type_tag() == type_tag) {
+            let json = self.bb.value_to_json(event.type_tag(), event.event_data())?;
+            let satisfied = self
+                .fields
+                .iter()
+                .all(|(path, predicate)| field(&json, path).is_some_and(|value| predicate.test(value)));
+            if satisfied {
+                matched.push(json);
+            }
+        }
+        Ok(matched)
+    }
+
+    /// Check the transaction's events, returning the matching ones decoded
+    pub fn assert(&self, output: &TransactionOutput) -> Result<Vec<Value>> {
+        let matched = self.matching(output.events())?;
+        let ok = match self.times {
+            Some(n) => matched.len() == n,
+            None => !matched.is_empty(),
+        };
+        if ok {
+            return Ok(matched);
+        }
+
+        let type_tag = self.type_tag()?;
+        let seen: Vec<String> = output
+            .events()
+            .iter()
+            .filter(|event| *event.type_tag() == type_tag)
+            .filter_map(|event| self.bb.value_to_json(event.type_tag(), event.event_data()).ok())
+            .map(|json| json.to_string())
+            .collect();
+        let conditions: Vec<String> = self.fields.iter().map(|(path, p)| format!("{} {}", path, p)).collect();
+        let expected = match self.times {
+            Some(n) => format!("exactly {}", n),
+            None => "at least one".to_string(),
+        };
+        bail!(
+            "Expected {} {} event(s) where [{}], found {} matching; emitted of that type: [{}]",
+            expected,
+            self.event_type,
+            conditions.join(", "),
+            matched.len(),
+            seen.join(", ")
+        )
+    }
+}
+
+impl AptosBB {
+    /// Stream of decoded events from every transaction subsequently executed on the fork
+    ///
+    /// `filter` keeps only events whose type starts with the given prefix, e.g.
+    /// `"0xabc::vault"` or `"0x1::coin::DepositEvent"`.
+    pub fn event_stream(&mut self, filter: Option<&str>) -> UnboundedReceiver<DecodedEvent> {
+        let (sender, receiver) = unbounded();
+        self.events.subscribers.push((filter.map(str::to_string), sender));
+        receiver
+    }
+
+    /// Decode a transaction's events and hand them to subscribers
+    pub(crate) fn publish_events(&mut self, output: &TransactionOutput) {
+        let txn_index = self.events.txn_count;
+        self.events.txn_count += 1;
+        if self.events.subscribers.is_empty() {
+            return;
+        }
+
+        for event in output.events() {
+            let type_name = event.type_tag().to_string();
+            let data = self
+                .value_to_json(event.type_tag(), event.event_data())
+                .unwrap_or_else(|_| Value::String(format!("0x{}", hex::encode(event.event_data()))));
+            let decoded = DecodedEvent {
+                txn_index,
+                type_tag: event.type_tag().clone(),
+                data,
+            };
+            self.events.subscribers.retain(|(filter, sender)| {
+                if filter.as_ref().is_some_and(|prefix| !type_name.starts_with(prefix.as_str())) {
+                    return !sender.is_closed();
+                }
+                sender.unbounded_send(decoded.clone()).is_ok()
+            });
+        }
+    }
+
+    /// Start an assertion on emitted events of a type, decoded with on-chain layouts
+    ///
+    /// ```ignore
+    /// bb.expect_event("0xabc::vault::WithdrawEvent")
+    ///     .field("amount", gt(1_000_000))
+    ///     .assert(&output)?;
+    /// ```
+    pub fn expect_event(&self, event_type: &str) -> EventMatcher<'_> {
+        EventMatcher {
+            bb: self,
+            event_type: event_type.to_string(),
+            fields: vec![],
+            times: None,
+        }
+    }
+}
+
+/// Hands each transaction's decoded events to `event_stream` subscribers
+pub(crate) struct EventObserver;
+
+impl TxnObserver for EventObserver {
+    fn applied(&self, bb: &mut AptosBB, _txn: &SignedTransaction, output: &TransactionOutput) {
+        bb.publish_events(output);
+    }
+}
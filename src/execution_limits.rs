@@ -0,0 +1,143 @@
+//! Block and per-transaction limit knobs, for probing behavior at the limit boundaries
+
+use crate::AptosBB;
+use anyhow::{anyhow, Result};
+use aptos_types::{
+    block_executor::config::BlockExecutorConfigFromOnchain,
+    on_chain_config::{BlockGasLimitType, GasScheduleV2, OnChainConfig, OnChainExecutionConfig},
+    state_store::state_key::StateKey,
+};
+
+/// Block gas limit `execute_block` enforces
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlockGasLimit {
+    /// Every transaction of a block executes, however much gas the block uses
+    #[default]
+    Unlimited,
+    /// The limit of the network's on-chain execution config, with its conflict penalties
+    /// and output size limit
+    OnChain,
+    /// A custom limit, in effective gas units
+    Limit(u64),
+}
+
+/// A per-transaction limit of the gas schedule
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxnLimit {
+    /// Most gas units a transaction may be charged (its `max_gas_amount` may not exceed it)
+    MaxGasUnits,
+    MaxExecutionGas,
+    MaxIoGas,
+    /// Most storage fee, in octas
+    MaxStorageFee,
+    MaxTransactionSize,
+    MaxWriteOps,
+    MaxBytesPerWriteOp,
+    MaxBytesAllWriteOps,
+    MaxBytesPerEvent,
+    MaxBytesAllEvents,
+}
+
+impl TxnLimit {
+    /// Name of the limit's entry in `0x1::gas_schedule::GasScheduleV2`
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::MaxGasUnits => "txn.maximum_number_of_gas_units",
+            Self::MaxExecutionGas => "txn.max_execution_gas",
+            Self::MaxIoGas => "txn.max_io_gas",
+            Self::MaxStorageFee => "txn.max_storage_fee",
+            Self::MaxTransactionSize => "txn.max_transaction_size_in_bytes",
+            Self::MaxWriteOps => "txn.max_write_ops_per_transaction",
+            Self::MaxBytesPerWriteOp => "txn.max_bytes_per_write_op",
+            Self::MaxBytesAllWriteOps => "txn.max_bytes_all_write_ops_per_transaction",
+            Self::MaxBytesPerEvent => "txn.max_bytes_per_event",
+            Self::MaxBytesAllEvents => "txn.max_bytes_all_events_per_transaction",
+        }
+    }
+}
+
+impl AptosBB {
+    /// The network's execution config
+    pub fn on_chain_execution_config(&self) -> Result<OnChainExecutionConfig> {
+        let value = self
+            .read_state_value(&StateKey::on_chain_config::<OnChainExecutionConfig>()?)
+            .ok_or_else(|| anyhow!("0x1::execution_config::ExecutionConfig not found"))?;
+        Ok(OnChainExecutionConfig::deserialize_into_config(value.bytes())?)
+    }
+
+    /// Block gas limit of the network's execution config, `None` if blocks are unlimited
+    pub fn on_chain_block_gas_limit(&self) -> Result<Option<u64>> {
+        Ok(self.on_chain_execution_config()?.block_gas_limit_type().block_gas_limit())
+    }
+
+    /// Set the block gas limit `execute_block` enforces
+    ///
+    /// The limit goes into the block executor's config, so the executor decides when a block
+    /// is full exactly as validators do: weighing effective gas (execution and IO gas with
+    /// conflict penalties, under `OnChain`) and stopping after the transaction that crosses
+    /// the limit. The rest come back with `TransactionStatus::Retry`.
+    pub fn set_block_gas_limit(&mut self, limit: BlockGasLimit) {
+        self.block_gas_limit = limit;
+    }
+
+    /// The block gas limit type in effect
+    pub fn block_gas_limit_type(&self) -> Result<BlockGasLimitType> {
+        match self.block_gas_limit {
+            BlockGasLimit::Unlimited => Ok(BlockGasLimitType::NoLimit),
+            BlockGasLimit::OnChain => Ok(self.on_chain_execution_config()?.block_gas_limit_type()),
+            BlockGasLimit::Limit(limit) => Ok(BlockGasLimitType::Limit(limit)),
+        }
+    }
+
+    /// Block executor config carrying the block gas limit type in effect
+    pub(crate) fn block_executor_config(&self) -> Result<BlockExecutorConfigFromOnchain> {
+        Ok(match self.block_gas_limit {
+            BlockGasLimit::OnChain => {
+                let config = self.on_chain_execution_config()?;
+                BlockExecutorConfigFromOnchain::new(
+                    config.block_gas_limit_type(),
+                    config.enable_per_block_gas_limit(),
+                    config.gas_price_to_burn(),
+                )
+            }
+            _ => BlockExecutorConfigFromOnchain::new(self.block_gas_limit_type()?, false, None),
+        })
+    }
+
+    /// On-chain gas schedule of the fork
+    pub fn gas_schedule(&self) -> Result<GasScheduleV2> {
+        let value = self
+            .read_state_value(&StateKey::on_chain_config::<GasScheduleV2>()?)
+            .ok_or_else(|| anyhow!("0x1::gas_schedule::GasScheduleV2 not found"))?;
+        Ok(bcs::from_bytes(value.bytes())?)
+    }
+
+    /// Overwrite the on-chain gas schedule, taking effect from the next transaction
+    pub fn set_gas_schedule(&mut self, schedule: &GasScheduleV2) -> Result<()> {
+        self.write_state_bytes(StateKey::on_chain_config::<GasScheduleV2>()?, bcs::to_bytes(schedule)?);
+        Ok(())
+    }
+
+    /// Current value of a per-transaction limit
+    pub fn txn_limit(&self, limit: TxnLimit) -> Result<u64> {
+        self.gas_schedule()?
+            .entries
+            .iter()
+            .find(|(key, _)| key == limit.key())
+            .map(|(_, value)| *value)
+            .ok_or_else(|| anyhow!("Gas schedule has no {} entry", limit.key()))
+    }
+
+    /// Change a per-transaction limit, e.g. lower `MaxExecutionGas` to find where a protocol
+    /// operation stops fitting in a transaction
+    pub fn set_txn_limit(&mut self, limit: TxnLimit, value: u64) -> Result<()> {
+        let mut schedule = self.gas_schedule()?;
+        let entry = schedule
+            .entries
+            .iter_mut()
+            .find(|(key, _)| key == limit.key())
+            .ok_or_else(|| anyhow!("Gas schedule has no {} entry", limit.key()))?;
+        entry.1 = value;
+        self.set_gas_schedule(&schedule)
+    }
+}
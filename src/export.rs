@@ -0,0 +1,430 @@
+use crate::{
+    observer::{recorder, Recorder, TxnObserver},
+    AptosBB,
+};
+use anyhow::{bail, Context, Result};
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{
+        ExecutionStatus, SignedTransaction, TransactionExecutable, TransactionOutput, TransactionPayload,
+        TransactionPayloadInner, TransactionStatus,
+    },
+};
+#[cfg(feature = "parquet")]
+use parquet::{
+    data_type::{BoolType, ByteArray, ByteArrayType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+use std::{
+    collections::HashSet,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::Path,
+};
+#[cfg(feature = "parquet")]
+use std::sync::Arc;
+
+/// Rows buffered in memory before a Parquet row group is written
+#[cfg(feature = "parquet")]
+const PARQUET_ROW_GROUP_SIZE: usize = 10_000;
+
+/// On-disk format of exported execution results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Appended to if the file already exists with the same columns
+    Csv,
+    /// Replaces any existing file; readable once the exporter is finished. Requires the
+    /// `parquet` feature
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Pick the format from a `.csv` / `.parquet` file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => Ok(Self::Csv),
+            #[cfg(feature = "parquet")]
+            Some("parquet") => Ok(Self::Parquet),
+            #[cfg(not(feature = "parquet"))]
+            Some("parquet") => bail!("Parquet export of {} requires the `parquet` feature", path.display()),
+            _ => bail!("Cannot infer export format of {}; use a .csv or .parquet extension", path.display()),
+        }
+    }
+}
+
+/// Metadata of one executed transaction, as written by the exporter
+#[derive(Clone, Debug)]
+pub struct TxnRecord {
+    /// Position among the transactions exported by this exporter
+    pub txn_index: u64,
+    /// `address::module::function` for entry functions, otherwise the payload kind
+    pub function: String,
+    pub sender: AccountAddress,
+    pub status: String,
+    pub success: bool,
+    pub gas_used: u64,
+    /// APT balance change of each watched address, in watch order
+    pub balance_deltas: Vec<i128>,
+}
+
+pub(crate) fn function_name(payload: &TransactionPayload) -> String {
+    match payload {
+        TransactionPayload::EntryFunction(entry) => format!("{}::{}", entry.module(), entry.function()),
+        TransactionPayload::Script(_) => "script".to_string(),
+        TransactionPayload::Multisig(_) => "multisig".to_string(),
+        TransactionPayload::Payload(TransactionPayloadInner::V1 { executable, .. }) => match executable {
+            TransactionExecutable::EntryFunction(entry) => format!("{}::{}", entry.module(), entry.function()),
+            TransactionExecutable::Script(_) => "script".to_string(),
+            TransactionExecutable::Empty => "empty".to_string(),
+        },
+        _ => "other".to_string(),
+    }
+}
+
+pub(crate) fn status_name(status: &TransactionStatus) -> String {
+    match status {
+        TransactionStatus::Keep(status) => format!("{:?}", status),
+        TransactionStatus::Discard(code) => format!("Discard({:?})", code),
+        TransactionStatus::Retry => "Retry".to_string(),
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+enum Sink {
+    Csv(BufWriter<File>),
+    #[cfg(feature = "parquet")]
+    Parquet {
+        writer: Option<SerializedFileWriter<File>>,
+        pending: Vec<TxnRecord>,
+    },
+}
+
+/// Writes one row per executed transaction to a CSV or Parquet file
+///
+/// Columns: `txn_index, function, sender, status, success, gas_used`, followed by one
+/// `delta_<address>` column per watched address holding its APT balance change.
+pub struct ResultExporter {
+    watch: Vec<AccountAddress>,
+    sink: Sink,
+    exported: u64,
+}
+
+impl ResultExporter {
+    /// Open an exporter; watching an address twice records it once
+    ///
+    /// Appending to a CSV file is refused unless its header has the same columns, i.e. the
+    /// same watched addresses in the same order.
+    pub fn create(path: &Path, format: ExportFormat, watch: &[AccountAddress]) -> Result<Self> {
+        let mut seen = HashSet::new();
+        let watch: Vec<AccountAddress> = watch.iter().copied().filter(|addr| seen.insert(*addr)).collect();
+        let columns: Vec<String> = watch.iter().map(|addr| format!("delta_{}", addr.to_hex())).collect();
+
+        let sink = match format {
+            ExportFormat::Csv => {
+                let mut header = vec!["txn_index", "function", "sender", "status", "success", "gas_used"];
+                header.extend(columns.iter().map(String::as_str));
+                let header = header.join(",");
+
+                let file = OpenOptions::new()
+                    .create(true)
+                    .read(true)
+                    .append(true)
+                    .open(path)
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                let is_new = file.metadata()?.len() == 0;
+                if !is_new {
+                    let mut existing = String::new();
+                    BufReader::new(&file).read_line(&mut existing)?;
+                    if existing.trim_end() != header {
+                        bail!(
+                            "{} was written with different columns ({}); export to a new file or watch the same addresses",
+                            path.display(),
+                            existing.trim_end()
+                        );
+                    }
+                }
+                let mut writer = BufWriter::new(file);
+                if is_new {
+                    writeln!(writer, "{}", header)?;
+                }
+                Sink::Csv(writer)
+            }
+            #[cfg(feature = "parquet")]
+            ExportFormat::Parquet => {
+                let deltas: String = columns
+                    .iter()
+                    .map(|column| format!("  REQUIRED INT64 {};\n", column))
+                    .collect();
+                let schema = parse_message_type(&format!(
+                    "message aptosbb_txn {{
+  REQUIRED INT64 txn_index;
+  REQUIRED BYTE_ARRAY function (UTF8);
+  REQUIRED BYTE_ARRAY sender (UTF8);
+  REQUIRED BYTE_ARRAY status (UTF8);
+  REQUIRED BOOLEAN success;
+  REQUIRED INT64 gas_used;
+{}}}",
+                    deltas
+                ))?;
+                let file = File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+                let writer = SerializedFileWriter::new(file, Arc::new(schema), Arc::new(WriterProperties::builder().build()))?;
+                Sink::Parquet { writer: Some(writer), pending: vec![] }
+            }
+        };
+
+        Ok(Self { watch, sink, exported: 0 })
+    }
+
+    /// Addresses whose balance deltas are recorded
+    pub fn watched(&self) -> &[AccountAddress] {
+        &self.watch
+    }
+
+    /// Number of transactions exported so far
+    pub fn exported(&self) -> u64 {
+        self.exported
+    }
+
+    pub fn write(&mut self, record: TxnRecord) -> Result<()> {
+        self.exported += 1;
+        match &mut self.sink {
+            Sink::Csv(writer) => {
+                let mut fields = vec![
+                    record.txn_index.to_string(),
+                    csv_field(&record.function),
+                    record.sender.to_hex_literal(),
+                    csv_field(&record.status),
+                    record.success.to_string(),
+                    record.gas_used.to_string(),
+                ];
+                fields.extend(record.balance_deltas.iter().map(i128::to_string));
+                writeln!(writer, "{}", fields.join(","))?;
+                Ok(())
+            }
+            #[cfg(feature = "parquet")]
+            Sink::Parquet { pending, .. } => {
+                pending.push(record);
+                if pending.len() >= PARQUET_ROW_GROUP_SIZE {
+                    self.write_row_group()?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    fn write_row_group(&mut self) -> Result<()> {
+        let Sink::Parquet { writer: Some(writer), pending } = &mut self.sink else {
+            return Ok(());
+        };
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let rows = std::mem::take(pending);
+
+        let int64 = |f: &dyn Fn(&TxnRecord) -> i64| rows.iter().map(f).collect::<Vec<_>>();
+        let text = |f: &dyn Fn(&TxnRecord) -> String| rows.iter().map(|r| ByteArray::from(f(r).into_bytes())).collect::<Vec<_>>();
+
+        let mut row_group = writer.next_row_group()?;
+        let mut column_index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match column_index {
+                0 => column.typed::<Int64Type>().write_batch(&int64(&|r| r.txn_index as i64), None, None)?,
+                1 => column.typed::<ByteArrayType>().write_batch(&text(&|r| r.function.clone()), None, None)?,
+                2 => column.typed::<ByteArrayType>().write_batch(&text(&|r| r.sender.to_hex_literal()), None, None)?,
+                3 => column.typed::<ByteArrayType>().write_batch(&text(&|r| r.status.clone()), None, None)?,
+                4 => column.typed::<BoolType>().write_batch(&rows.iter().map(|r| r.success).collect::<Vec<_>>(), None, None)?,
+                5 => column.typed::<Int64Type>().write_batch(&int64(&|r| r.gas_used as i64), None, None)?,
+                n => {
+                    let watched = n - 6;
+                    let deltas = int64(&|r| r.balance_deltas[watched].clamp(i64::MIN as i128, i64::MAX as i128) as i64);
+                    column.typed::<Int64Type>().write_batch(&deltas, None, None)?
+                }
+            };
+            column.close()?;
+            column_index += 1;
+        }
+        row_group.close()?;
+        Ok(())
+    }
+
+    /// Flush buffered rows and, for Parquet, write the file footer
+    pub fn finish(mut self) -> Result<()> {
+        self.flush()
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        #[cfg(feature = "parquet")]
+        self.write_row_group()?;
+        match &mut self.sink {
+            Sink::Csv(writer) => writer.flush()?,
+            #[cfg(feature = "parquet")]
+            Sink::Parquet { writer, .. } => {
+                if let Some(writer) = writer.take() {
+                    writer.close()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for ResultExporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            eprintln!("Warning: failed to finish result export: {}", e);
+        }
+    }
+}
+
+/// Pre-execution half of a `TxnRecord`, captured before the transaction runs
+pub(crate) struct PendingRecord {
+    function: String,
+    sender: AccountAddress,
+    balances_before: Vec<u64>,
+}
+
+impl AptosBB {
+    /// Append metadata of every subsequently executed transaction to `path`
+    ///
+    /// Each row records the called function, sender, status, gas used and the APT balance
+    /// change of every address in `watch`. Replaces any exporter already attached; rows are
+    /// only guaranteed to be on disk after `finish_export` (or when the fork is dropped).
+    pub fn export_results(&mut self, path: &Path, format: ExportFormat, watch: &[AccountAddress]) -> Result<()> {
+        let exporter = ResultExporter::create(path, format, watch)?;
+        if let Some(previous) = self.exporter.replace(exporter) {
+            previous.finish()?;
+        }
+        Ok(())
+    }
+
+    /// Detach the exporter and flush everything it has written
+    pub fn finish_export(&mut self) -> Result<u64> {
+        match self.exporter.take() {
+            Some(exporter) => {
+                let exported = exporter.exported();
+                exporter.finish()?;
+                Ok(exported)
+            }
+            None => Ok(0),
+        }
+    }
+
+    pub(crate) fn begin_export_record(&self, txn: &SignedTransaction) -> Option<PendingRecord> {
+        let exporter = self.exporter.as_ref()?;
+        Some(PendingRecord {
+            function: function_name(txn.payload()),
+            sender: txn.sender(),
+            balances_before: exporter.watched().iter().map(|addr| self.read_aptos_balance(addr)).collect(),
+        })
+    }
+
+    pub(crate) fn export_record(&mut self, pending: PendingRecord, output: &TransactionOutput) {
+        let Some(watched) = self.exporter.as_ref().map(|exporter| exporter.watched().to_vec()) else {
+            return;
+        };
+        let balance_deltas = watched
+            .iter()
+            .zip(&pending.balances_before)
+            .map(|(addr, before)| self.read_aptos_balance(addr) as i128 - *before as i128)
+            .collect();
+
+        let Some(exporter) = self.exporter.as_mut() else {
+            return;
+        };
+        let record = TxnRecord {
+            txn_index: exporter.exported(),
+            function: pending.function,
+            sender: pending.sender,
+            status: status_name(output.status()),
+            success: matches!(output.status(), TransactionStatus::Keep(ExecutionStatus::Success)),
+            gas_used: output.gas_used(),
+            balance_deltas,
+        };
+        if let Err(e) = exporter.write(record) {
+            eprintln!("Warning: failed to export transaction result: {}", e);
+        }
+    }
+}
+
+/// Writes a row per transaction to the file set up by `export_results`
+pub(crate) struct ExportObserver;
+
+impl TxnObserver for ExportObserver {
+    fn begin(&self, bb: &AptosBB, txn: &SignedTransaction) -> Result<Option<Recorder>> {
+        Ok(bb
+            .begin_export_record(txn)
+            .map(|pending| recorder(move |bb, executed| bb.export_record(pending, executed.output))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(txn_index: u64, function: &str, deltas: Vec<i128>) -> TxnRecord {
+        TxnRecord {
+            txn_index,
+            function: function.to_string(),
+            sender: AccountAddress::ONE,
+            status: "Success".to_string(),
+            success: true,
+            gas_used: 7,
+            balance_deltas: deltas,
+        }
+    }
+
+    #[test]
+    fn csv_field_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+
+    #[test]
+    fn csv_appends_under_the_same_header_and_dedupes_watches() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.csv");
+        let watch = [AccountAddress::ONE, AccountAddress::TWO, AccountAddress::ONE];
+
+        let mut exporter = ResultExporter::create(&path, ExportFormat::Csv, &watch).unwrap();
+        assert_eq!(exporter.watched(), [AccountAddress::ONE, AccountAddress::TWO]);
+        exporter.write(record(0, "0x1::m::f", vec![-5, 5])).unwrap();
+        exporter.finish().unwrap();
+        let mut exporter = ResultExporter::create(&path, ExportFormat::Csv, &watch).unwrap();
+        exporter.write(record(1, "a,b", vec![0, 1])).unwrap();
+        exporter.finish().unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = contents.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0].split(',').count(), 8);
+        assert!(lines[1].starts_with("0,0x1::m::f,0x1,Success,true,7,-5,5"));
+        assert!(lines[2].starts_with("1,\"a,b\","));
+    }
+
+    #[test]
+    fn csv_refuses_to_append_with_different_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("results.csv");
+        ResultExporter::create(&path, ExportFormat::Csv, &[AccountAddress::ONE]).unwrap().finish().unwrap();
+        assert!(ResultExporter::create(&path, ExportFormat::Csv, &[AccountAddress::TWO]).is_err());
+        assert!(ResultExporter::create(&path, ExportFormat::Csv, &[AccountAddress::ONE]).is_ok());
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(ExportFormat::from_path(Path::new("out.csv")).unwrap(), ExportFormat::Csv);
+        assert!(ExportFormat::from_path(Path::new("out.json")).is_err());
+    }
+}
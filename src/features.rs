@@ -0,0 +1,179 @@
+use crate::{replay::TxnOutcome, AptosBB};
+use anyhow::{anyhow, bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    on_chain_config::{FeatureFlag, Features},
+    state_store::state_key::StateKey,
+    transaction::TransactionPayload,
+};
+use move_core_types::language_storage::StructTag;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    str::FromStr,
+};
+
+/// Every combination doubles the work, so keep matrices to a size that finishes
+const MAX_MATRIX_FLAGS: usize = 10;
+
+/// Outcome of the transaction with one on/off assignment of the flags
+#[derive(Clone, Debug)]
+pub struct FeatureOutcome {
+    /// Flag states, in the order the flags were given
+    pub flags: Vec<(FeatureFlag, bool)>,
+    pub outcome: TxnOutcome,
+    /// Value written to each state key, `None` for deletions
+    pub writes: BTreeMap<StateKey, Option<Vec<u8>>>,
+}
+
+impl FeatureOutcome {
+    fn differs_from(&self, other: &FeatureOutcome) -> bool {
+        self.outcome.status != other.outcome.status
+            || self.outcome.gas_used != other.outcome.gas_used
+            || self.outcome.events != other.outcome.events
+            || self.writes != other.writes
+    }
+}
+
+/// Results of running a transaction under every combination of a set of feature flags
+#[derive(Clone, Debug)]
+pub struct FeatureMatrixReport {
+    pub outcomes: Vec<FeatureOutcome>,
+    /// Index of the combination matching the fork's current flags
+    pub current: usize,
+}
+
+impl FeatureMatrixReport {
+    pub fn baseline(&self) -> &FeatureOutcome {
+        &self.outcomes[self.current]
+    }
+
+    /// Whether every combination produced the same status, gas, events and writes
+    pub fn is_flag_independent(&self) -> bool {
+        self.divergences().is_empty()
+    }
+
+    /// Combinations whose outcome differs from the current flags' outcome
+    pub fn divergences(&self) -> Vec<&FeatureOutcome> {
+        let baseline = self.baseline();
+        self.outcomes.iter().filter(|outcome| outcome.differs_from(baseline)).collect()
+    }
+
+    /// Combinations whose status differs from the current flags' status
+    pub fn status_divergences(&self) -> Vec<&FeatureOutcome> {
+        let baseline = self.baseline();
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.outcome.status != baseline.outcome.status)
+            .collect()
+    }
+
+    /// State keys written differently by any combination than by the current flags
+    pub fn differing_keys(&self) -> BTreeSet<StateKey> {
+        let baseline = self.baseline();
+        let mut keys = BTreeSet::new();
+        for outcome in &self.outcomes {
+            for key in baseline.writes.keys().chain(outcome.writes.keys()) {
+                if baseline.writes.get(key) != outcome.writes.get(key) {
+                    keys.insert(key.clone());
+                }
+            }
+        }
+        keys
+    }
+}
+
+fn features_tag() -> Result<StructTag> {
+    StructTag::from_str("0x1::features::Features")
+}
+
+impl AptosBB {
+    /// On-chain feature flags of the fork
+    pub fn features(&self) -> Result<Features> {
+        let key = StateKey::resource(&AccountAddress::ONE, &features_tag()?)?;
+        let value = self
+            .read_state_value(&key)
+            .ok_or_else(|| anyhow!("0x1::features::Features not found"))?;
+        Ok(bcs::from_bytes(value.bytes())?)
+    }
+
+    /// Overwrite the on-chain feature flags, taking effect from the next transaction
+    pub fn set_features(&mut self, features: &Features) -> Result<()> {
+        self.write_resource(&AccountAddress::ONE, &features_tag()?, features)
+    }
+
+    /// Turn a single feature flag on or off
+    pub fn set_feature(&mut self, flag: FeatureFlag, enabled: bool) -> Result<()> {
+        let mut features = self.features()?;
+        if enabled {
+            features.enable(flag);
+        } else {
+            features.disable(flag);
+        }
+        self.set_features(&features)
+    }
+
+    /// Execute the same transaction under every on/off combination of `flags` and diff outcomes
+    ///
+    /// Each combination starts from the current fork state, which is restored afterwards.
+    /// Outcomes are compared against the combination matching the fork's current flags, so
+    /// divergences show what would change if a gated feature flipped on chain.
+    pub fn run_under_feature_matrix(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+        flags: &[FeatureFlag],
+    ) -> Result<FeatureMatrixReport> {
+        if flags.len() > MAX_MATRIX_FLAGS {
+            bail!("{} flags give too many combinations, at most {} are supported", flags.len(), MAX_MATRIX_FLAGS);
+        }
+        let original = self.features()?;
+        let current_mask = flags
+            .iter()
+            .enumerate()
+            .filter(|(_, flag)| original.is_enabled(**flag))
+            .fold(0usize, |mask, (i, _)| mask | (1 << i));
+
+        let mut outcomes = vec![];
+        for mask in 0..1usize << flags.len() {
+            let assignment: Vec<(FeatureFlag, bool)> =
+                flags.iter().enumerate().map(|(i, flag)| (*flag, mask & (1 << i) != 0)).collect();
+            let mut features = original.clone();
+            for (flag, enabled) in &assignment {
+                if *enabled {
+                    features.enable(*flag);
+                } else {
+                    features.disable(*flag);
+                }
+            }
+
+            let outcome = self.with_snapshot(|bb| -> Result<FeatureOutcome> {
+                bb.set_features(&features)?;
+                let (_, output) = bb.run_transaction_with_output(account, payload.clone())?;
+                Ok(FeatureOutcome {
+                    flags: assignment,
+                    outcome: TxnOutcome::from(&output),
+                    writes: output
+                        .write_set()
+                        .iter()
+                        .map(|(key, op)| (key.clone(), op.bytes().map(|bytes| bytes.to_vec())))
+                        .collect(),
+                })
+            })?;
+            outcomes.push(outcome);
+        }
+
+        let report = FeatureMatrixReport { outcomes, current: current_mask };
+        if report.is_flag_independent() {
+            println!("✅ {} flag combinations produced identical outcomes", report.outcomes.len());
+        } else {
+            println!(
+                "⚠️  Flag-dependent outcome: {} combinations diverge ({} with differing statuses), {} differing state keys",
+                report.divergences().len(),
+                report.status_divergences().len(),
+                report.differing_keys().len()
+            );
+        }
+        Ok(report)
+    }
+}
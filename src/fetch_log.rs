@@ -0,0 +1,81 @@
+use crate::AptosBB;
+use anyhow::{bail, Context, Result};
+use aptos_types::state_store::state_key::StateKey;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// State keys a session read from the network, so a rerun can pre-seed exactly those
+///
+/// Every read at the fork version is logged, the executor's lazy reads included, as are keys
+/// found absent on chain, so a seeded rerun never needs to go back to the network. Stored as
+/// JSON so logs can be diffed and audited.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FetchLog {
+    pub chain_id: u8,
+    pub version: u64,
+    /// Remote requests the session made, a budget estimate for reruns without a cache
+    pub remote_requests: u64,
+    pub keys: Vec<StateKey>,
+}
+
+impl FetchLog {
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Reading fetch log {}", path.display()))?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    pub fn write(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Writing fetch log {}", path.display()))
+    }
+}
+
+impl AptosBB {
+    /// Every state key read from the network so far, with the remote requests spent on them
+    pub fn fetch_log(&self) -> FetchLog {
+        let mut keys = self.remote.fetched_keys();
+        keys.sort();
+        FetchLog {
+            chain_id: self.chain_id.id(),
+            version: self.version,
+            remote_requests: self.remote.limiter().requests(),
+            keys,
+        }
+    }
+
+    /// Write the session's fetch log, for `ForkMode::Replay` or `seed_from_fetch_log` next time
+    pub fn write_fetch_log(&self, path: &Path) -> Result<FetchLog> {
+        let log = self.fetch_log();
+        log.write(path)?;
+        println!("Logged {} state keys ({} remote requests) to {}", log.keys.len(), log.remote_requests, path.display());
+        Ok(log)
+    }
+
+    /// Load every key of a fetch log into the fork up front
+    ///
+    /// Keys are fetched in batches (one request per account for resources and modules) and
+    /// served from the state cache when configured, so a rerun with a warm cache needs no
+    /// network at all. Logged absent keys are remembered as absent, so lazy reads of them do
+    /// not go to the network either. Returns the number of keys loaded.
+    pub async fn seed_from_fetch_log(&mut self, log: &FetchLog) -> Result<usize> {
+        if log.chain_id != self.chain_id.id() || log.version != self.version {
+            bail!(
+                "Fetch log is for chain {} at version {}, but the fork is on chain {} at version {}",
+                log.chain_id,
+                log.version,
+                self.chain_id,
+                self.version
+            );
+        }
+        let requests_before = self.remote.limiter().requests();
+        let values = self.remote.get_state_values(&log.keys).await?;
+        let loaded = self.load_missing(values.into_iter().filter_map(|(state_key, value)| Some((state_key, value?))));
+        println!(
+            "Seeded {} of {} logged state keys with {} remote requests",
+            loaded,
+            log.keys.len(),
+            self.remote.limiter().requests() - requests_before
+        );
+        Ok(loaded)
+    }
+}
@@ -0,0 +1,219 @@
+//! C ABI for driving a fork from non-Rust tooling (Go, TypeScript, Python harnesses)
+//!
+//! Functions return null on failure, with the message available from `aptosbb_last_error`
+//! on the same thread. Panics are caught at the boundary and reported the same way, so they
+//! never unwind into the host. Strings returned by the library belong to the caller and must be
+//! released with `aptosbb_string_free`; `include/aptosbb.h` declares the interface.
+
+use crate::{builder::parse_network, limits::panic_message, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde_json::Value;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    ffi::{c_char, CStr, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+    ptr,
+    str::FromStr,
+};
+use tokio::runtime::Runtime;
+
+/// A fork and the runtime its remote reads run on
+pub struct AptosBBHandle {
+    runtime: Runtime,
+    bb: AptosBB,
+    /// Senders impersonated so far, so each address keeps one signing key
+    accounts: HashMap<AccountAddress, Account>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(error: &anyhow::Error) {
+    let message = CString::new(format!("{:#}", error).replace('\0', " ")).expect("nul bytes were replaced");
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// Run an FFI body, turning a panic into an error instead of unwinding across the C ABI
+fn guarded<R>(f: impl FnOnce() -> Result<R>) -> Result<R> {
+    catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|panic| Err(anyhow!("panicked: {}", panic_message(panic.as_ref()))))
+}
+
+/// Borrow a C string argument; null is `None`
+///
+/// # Safety
+/// `ptr` must be null or point to a nul-terminated string that outlives the call.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<Option<&'a str>> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    CStr::from_ptr(ptr)
+        .to_str()
+        .map(Some)
+        .map_err(|_| anyhow!("{} is not valid UTF-8", name))
+}
+
+unsafe fn required_str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str> {
+    str_arg(ptr, name)?.ok_or_else(|| anyhow!("{} must not be null", name))
+}
+
+/// Hand a string to the caller, or record the error and return null
+fn into_c_string(result: Result<String>) -> *mut c_char {
+    match result.and_then(|s| Ok(CString::new(s)?)) {
+        Ok(s) => s.into_raw(),
+        Err(e) => {
+            set_last_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+impl AptosBBHandle {
+    /// Run `f` on the fork inside the handle's runtime, where blocking remote reads are allowed
+    fn with_bb<R>(&mut self, f: impl FnOnce(&mut AptosBB, &mut HashMap<AccountAddress, Account>) -> R) -> R {
+        let _guard = self.runtime.enter();
+        f(&mut self.bb, &mut self.accounts)
+    }
+}
+
+/// Fork a network at a ledger version
+///
+/// `network` is `mainnet`, `testnet`, `devnet` or a REST URL (null means mainnet),
+/// `version` 0 means the latest version and `api_key` may be null.
+///
+/// # Safety
+/// String arguments must be null or valid nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn aptosbb_fork(network: *const c_char, version: u64, api_key: *const c_char) -> *mut AptosBBHandle {
+    let fork = || -> Result<AptosBBHandle> {
+        let mut builder = AptosBB::builder();
+        if let Some(network) = str_arg(network, "network")? {
+            builder = builder.network(parse_network(network)?);
+        }
+        if version != 0 {
+            builder = builder.version(version);
+        }
+        if let Some(api_key) = str_arg(api_key, "api_key")?.filter(|key| !key.is_empty()) {
+            builder = builder.api_key(api_key);
+        }
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+        let bb = runtime.block_on(builder.build())?;
+        Ok(AptosBBHandle { runtime, bb, accounts: HashMap::new() })
+    };
+    match guarded(fork) {
+        Ok(handle) => Box::into_raw(Box::new(handle)),
+        Err(e) => {
+            set_last_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Release a fork created by `aptosbb_fork`
+///
+/// # Safety
+/// `handle` must be null or come from `aptosbb_fork`, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aptosbb_free(handle: *mut AptosBBHandle) {
+    if !handle.is_null() {
+        if let Err(e) = guarded(|| {
+            drop(Box::from_raw(handle));
+            Ok(())
+        }) {
+            set_last_error(&e);
+        }
+    }
+}
+
+/// Execute an entry function from `sender`, which is impersonated on the fork
+///
+/// `ty_args_json` is a JSON array of type strings and `args_json` a JSON array of arguments
+/// in the API's representation (either may be null for none). Returns a JSON object with
+/// `status`, `success`, `gas_used` and the emitted `events`.
+///
+/// # Safety
+/// `handle` must come from `aptosbb_fork`; string arguments must be null or valid
+/// nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn aptosbb_call(
+    handle: *mut AptosBBHandle,
+    sender: *const c_char,
+    function: *const c_char,
+    ty_args_json: *const c_char,
+    args_json: *const c_char,
+) -> *mut c_char {
+    let call = || -> Result<String> {
+        let handle = handle.as_mut().ok_or_else(|| anyhow!("handle must not be null"))?;
+        let sender = AccountAddress::from_str(required_str_arg(sender, "sender")?)?;
+        let function = required_str_arg(function, "function")?;
+        let ty_args: Vec<String> = match str_arg(ty_args_json, "ty_args_json")? {
+            Some(json) => serde_json::from_str(json)?,
+            None => vec![],
+        };
+        let args: Vec<Value> = match str_arg(args_json, "args_json")? {
+            Some(json) => serde_json::from_str(json)?,
+            None => vec![],
+        };
+
+        handle.with_bb(|bb, accounts| {
+            let ty_args = ty_args.iter().map(|ty| TypeTag::from_str(ty)).collect::<Result<Vec<_>, _>>()?;
+            let account = match accounts.get(&sender) {
+                Some(account) => account.clone(),
+                None => {
+                    let account = bb.impersonate(sender)?;
+                    accounts.insert(sender, account.clone());
+                    account
+                }
+            };
+            Ok(bb.call_json(&account, function, ty_args, &args)?.to_string())
+        })
+    };
+    into_c_string(guarded(call))
+}
+
+/// A resource as JSON, or the string `null` if `address` does not hold it
+///
+/// # Safety
+/// `handle` must come from `aptosbb_fork`; string arguments must be valid nul-terminated
+/// strings.
+#[no_mangle]
+pub unsafe extern "C" fn aptosbb_read_resource_json(
+    handle: *mut AptosBBHandle,
+    address: *const c_char,
+    resource_type: *const c_char,
+) -> *mut c_char {
+    let read = || -> Result<String> {
+        let handle = handle.as_mut().ok_or_else(|| anyhow!("handle must not be null"))?;
+        let address = AccountAddress::from_str(required_str_arg(address, "address")?)?;
+        let resource_type = StructTag::from_str(required_str_arg(resource_type, "resource_type")?)?;
+        handle.with_bb(|bb, _| Ok(bb.read_resource_json(&address, &resource_type)?.unwrap_or(Value::Null).to_string()))
+    };
+    into_c_string(guarded(read))
+}
+
+/// Message of the last error on this thread, or null; valid until the next failing call
+#[no_mangle]
+pub extern "C" fn aptosbb_last_error() -> *const c_char {
+    catch_unwind(|| LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr())))
+        .unwrap_or(ptr::null())
+}
+
+/// Release a string returned by the library
+///
+/// # Safety
+/// `s` must be null or a string returned by this library, and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn aptosbb_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        if let Err(e) = guarded(|| {
+            drop(CString::from_raw(s));
+            Ok(())
+        }) {
+            set_last_error(&e);
+        }
+    }
+}
@@ -0,0 +1,205 @@
+//! The one finding type every subsystem reports: scenario runs, static checks, the
+//! taint and pause analyses, griefing, access and replay probes, the prover, staleness checks
+//! and the publish monitor
+
+use move_core_types::language_storage::ModuleId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How bad a finding is, ordered from least to most severe
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Severity {
+    Informational,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Informational => "informational",
+            Self::Low => "low",
+            Self::Medium => "medium",
+            Self::High => "high",
+            Self::Critical => "critical",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How likely an attacker is to be able to trigger an issue
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Likelihood {
+    /// Needs a compromised privileged key or an implausible state
+    Rare,
+    /// Needs specific market conditions or victim behavior
+    Unlikely,
+    Possible,
+    /// Anyone can trigger it at some point
+    Likely,
+    /// Anyone can trigger it right now, e.g. a working PoC on a fork
+    Certain,
+}
+
+/// Kind of weakness a finding describes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Category {
+    AccessControl,
+    Arithmetic,
+    DenialOfService,
+    Oracle,
+    Economic,
+    Logic,
+    Other,
+}
+
+impl fmt::Display for Category {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::AccessControl => "access control",
+            Self::Arithmetic => "arithmetic",
+            Self::DenialOfService => "denial of service",
+            Self::Oracle => "oracle",
+            Self::Economic => "economic",
+            Self::Logic => "logic",
+            Self::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Severity from the funds an issue puts at risk and how likely it is to be exploited
+///
+/// A risk matrix: funds are bucketed by order of magnitude (none, < $10k, < $100k, < $1M,
+/// more) and added to the likelihood's rank, so a certain exploit of a few thousand dollars
+/// rates the same as a possible one of a few hundred thousand.
+pub fn score(funds_at_risk_usd: f64, likelihood: Likelihood) -> Severity {
+    let impact = match funds_at_risk_usd {
+        usd if usd >= 1_000_000.0 => 4,
+        usd if usd >= 100_000.0 => 3,
+        usd if usd >= 10_000.0 => 2,
+        usd if usd > 0.0 => 1,
+        _ => 0,
+    };
+    match impact + likelihood as u8 {
+        0 => Severity::Informational,
+        1..=2 => Severity::Low,
+        3..=4 => Severity::Medium,
+        5..=6 => Severity::High,
+        _ => Severity::Critical,
+    }
+}
+
+/// An issue reported by any AptosBB subsystem, in one comparable shape
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    pub title: String,
+    pub severity: Severity,
+    pub category: Category,
+    /// Affected modules, functions or addresses, e.g. `0xabc::vault::withdraw`
+    pub affected: Vec<String>,
+    /// Observations backing the finding: statuses, balance changes, offending bytecode
+    pub evidence: Vec<String>,
+    /// How to reproduce it, e.g. the scenario or command that triggers it
+    pub reproduction: Option<String>,
+    /// Identifier of the static check that produced it, e.g. `narrowing-cast`
+    #[serde(default)]
+    pub check: Option<String>,
+}
+
+impl Finding {
+    pub fn new(title: &str, severity: Severity, category: Category) -> Self {
+        Self {
+            title: title.to_string(),
+            severity,
+            category,
+            affected: vec![],
+            evidence: vec![],
+            reproduction: None,
+            check: None,
+        }
+    }
+
+    /// Lead from a static check; unconfirmed, so it rates low until a PoC shows otherwise
+    pub fn lead(check: &str, module: &ModuleId, function: Option<String>, message: impl Into<String>) -> Self {
+        let affected = match function {
+            Some(function) => format!("{}::{}", module, function),
+            None => module.to_string(),
+        };
+        let mut finding = Self::new(&message.into(), Severity::Low, check_category(check)).affected(&affected);
+        finding.check = Some(check.to_string());
+        finding
+    }
+
+    /// Finding with its severity scored from funds at risk and likelihood
+    pub fn scored(title: &str, category: Category, funds_at_risk_usd: f64, likelihood: Likelihood) -> Self {
+        Self::new(title, score(funds_at_risk_usd, likelihood), category)
+    }
+
+    pub fn affected(mut self, affected: &str) -> Self {
+        self.affected.push(affected.to_string());
+        self
+    }
+
+    pub fn evidence(mut self, evidence: impl Into<String>) -> Self {
+        self.evidence.push(evidence.into());
+        self
+    }
+
+    pub fn reproduction(mut self, reproduction: &str) -> Self {
+        self.reproduction = Some(reproduction.to_string());
+        self
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] ", self.severity)?;
+        if let Some(check) = &self.check {
+            write!(f, "[{}] ", check)?;
+        }
+        write!(f, "{} ({})", self.title, self.category)?;
+        if !self.affected.is_empty() {
+            write!(f, " in {}", self.affected.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Category of a static check, by its identifier
+fn check_category(check: &str) -> Category {
+    match check {
+        "div-before-mul" | "unchecked-mul-div" | "narrowing-cast" | "unchecked-shift" => Category::Arithmetic,
+        "unbounded-loop" => Category::DenialOfService,
+        "entry-no-signer" | "entry-unused-signer" | "public-returns-capability" | "unchecked-type-parameter"
+        | "capability-reachable" => Category::AccessControl,
+        _ => Category::Other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn score_combines_impact_and_likelihood() {
+        assert_eq!(score(0.0, Likelihood::Rare), Severity::Informational);
+        assert_eq!(score(0.0, Likelihood::Certain), Severity::Medium);
+        assert_eq!(score(5_000.0, Likelihood::Certain), Severity::High);
+        assert_eq!(score(500_000.0, Likelihood::Possible), Severity::High);
+        assert_eq!(score(2_000_000.0, Likelihood::Likely), Severity::Critical);
+        assert_eq!(score(2_000_000.0, Likelihood::Rare), Severity::Medium);
+        assert_eq!(score(-1.0, Likelihood::Unlikely), Severity::Low);
+    }
+
+    #[test]
+    fn score_is_monotonic_in_funds() {
+        let funds = [0.0, 1.0, 10_000.0, 100_000.0, 1_000_000.0, 1e12];
+        for likelihood in [Likelihood::Rare, Likelihood::Possible, Likelihood::Certain] {
+            let severities: Vec<Severity> = funds.iter().map(|usd| score(*usd, likelihood)).collect();
+            assert!(severities.windows(2).all(|pair| pair[0] <= pair[1]));
+        }
+    }
+}
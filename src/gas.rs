@@ -0,0 +1,121 @@
+use crate::{entry::EntryCall, export::function_name, helpers::ensure_success, AptosBB};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{ExecutionStatus, TransactionStatus},
+};
+use std::{fmt, path::Path};
+
+/// Gas of one call of the scenario under both package variants
+#[derive(Clone, Debug)]
+pub struct GasCall {
+    pub function: String,
+    pub gas_a: u64,
+    pub gas_b: u64,
+    pub status_a: TransactionStatus,
+    pub status_b: TransactionStatus,
+}
+
+impl GasCall {
+    /// Gas of variant B minus gas of variant A
+    pub fn delta(&self) -> i128 {
+        self.gas_b as i128 - self.gas_a as i128
+    }
+
+    /// Whether the call succeeded under one variant but not the other
+    pub fn status_differs(&self) -> bool {
+        let success = |status: &TransactionStatus| *status == TransactionStatus::Keep(ExecutionStatus::Success);
+        success(&self.status_a) != success(&self.status_b)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct GasComparison {
+    pub calls: Vec<GasCall>,
+}
+
+impl GasComparison {
+    pub fn total_a(&self) -> u64 {
+        self.calls.iter().map(|call| call.gas_a).sum()
+    }
+
+    pub fn total_b(&self) -> u64 {
+        self.calls.iter().map(|call| call.gas_b).sum()
+    }
+
+    pub fn total_delta(&self) -> i128 {
+        self.calls.iter().map(GasCall::delta).sum()
+    }
+
+    pub fn status_divergences(&self) -> Vec<&GasCall> {
+        self.calls.iter().filter(|call| call.status_differs()).collect()
+    }
+}
+
+impl fmt::Display for GasComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:>4}  {:<60} {:>10} {:>10} {:>10}", "#", "function", "gas A", "gas B", "delta")?;
+        for (i, call) in self.calls.iter().enumerate() {
+            let marker = if call.status_differs() { "  ⚠️ status differs" } else { "" };
+            writeln!(
+                f,
+                "{:>4}  {:<60} {:>10} {:>10} {:>+10}{}",
+                i, call.function, call.gas_a, call.gas_b, call.delta(), marker
+            )?;
+        }
+        write!(f, "{:>4}  {:<60} {:>10} {:>10} {:>+10}", "", "total", self.total_a(), self.total_b(), self.total_delta())
+    }
+}
+
+impl AptosBB {
+    /// Publish a package from `account`, run the scenario's calls and collect their gas and status
+    fn run_gas_variant(
+        &mut self,
+        account: &Account,
+        package: &Path,
+        scenario: &impl Fn(AccountAddress) -> Vec<EntryCall>,
+    ) -> Result<Vec<(String, u64, TransactionStatus)>> {
+        ensure_success(self.publish_package(account, package)?, &format!("publishing {}", package.display()))?;
+        scenario(*account.address())
+            .into_iter()
+            .map(|call| {
+                let payload = call.into_payload()?;
+                let function = function_name(&payload);
+                let (status, output) = self.run_transaction_with_output(account, payload)?;
+                Ok((function, output.gas_used(), status))
+            })
+            .collect()
+    }
+
+    /// Run the same call sequence against two variants of a package and compare gas per call
+    ///
+    /// Each variant is published from `account` on its own snapshot of the fork, so both live
+    /// at the same address; `scenario` receives that address and returns the calls to make,
+    /// all sent from `account`. The fork is left unchanged.
+    pub fn compare_gas(
+        &mut self,
+        account: &Account,
+        scenario: impl Fn(AccountAddress) -> Vec<EntryCall>,
+        package_a: &Path,
+        package_b: &Path,
+    ) -> Result<GasComparison> {
+        let a = self.with_snapshot(|bb| bb.run_gas_variant(account, package_a, &scenario))?;
+        let b = self.with_snapshot(|bb| bb.run_gas_variant(account, package_b, &scenario))?;
+
+        let calls = a
+            .into_iter()
+            .zip(b)
+            .map(|((function, gas_a, status_a), (_, gas_b, status_b))| GasCall {
+                function,
+                gas_a,
+                gas_b,
+                status_a,
+                status_b,
+            })
+            .collect();
+        let comparison = GasComparison { calls };
+        println!("{}", comparison);
+        Ok(comparison)
+    }
+}
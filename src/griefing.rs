@@ -0,0 +1,155 @@
+use crate::{
+    findings::{Category, Finding, Severity},
+    AptosBB,
+};
+use anyhow::{bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::transaction::{TransactionPayload, TransactionStatus};
+use std::fmt;
+
+/// Gas amplification `assert_griefing` treats as griefing
+pub const GRIEFING_GAS_FACTOR: f64 = 5.0;
+
+/// Status and gas of one victim execution
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VictimRun {
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+}
+
+impl VictimRun {
+    pub fn succeeded(&self) -> bool {
+        self.status.status().is_ok_and(|status| status.is_success())
+    }
+}
+
+/// The victim's transaction with and without the attacker's transactions in front of it
+#[derive(Clone, Debug)]
+pub struct GriefingReport {
+    /// Victim run on the untouched fork
+    pub baseline: VictimRun,
+    /// Victim run right after the attacker's transactions
+    pub attacked: VictimRun,
+    /// Victim resubmitting after an attacked run that failed, without further attacker action
+    pub retry: Option<VictimRun>,
+    /// Gas the attacker paid in total
+    pub attacker_gas: u64,
+}
+
+impl GriefingReport {
+    /// Whether the attacker turned a succeeding victim transaction into a failing one
+    pub fn forces_abort(&self) -> bool {
+        self.baseline.succeeded() && !self.attacked.succeeded()
+    }
+
+    /// Whether the victim keeps failing when it simply retries, i.e. a lasting denial of service
+    pub fn persists_after_retry(&self) -> bool {
+        self.forces_abort() && self.retry.as_ref().is_some_and(|retry| !retry.succeeded())
+    }
+
+    /// Victim gas with the attack relative to without it
+    pub fn gas_amplification(&self) -> f64 {
+        self.attacked.gas_used as f64 / self.baseline.gas_used.max(1) as f64
+    }
+
+    /// Extra victim gas per unit of gas the attacker paid
+    pub fn cost_ratio(&self) -> f64 {
+        self.attacked.gas_used.saturating_sub(self.baseline.gas_used) as f64 / self.attacker_gas.max(1) as f64
+    }
+
+    /// Whether the attack forces an abort or inflates the victim's gas by at least `min_amplification`
+    pub fn is_griefing(&self, min_amplification: f64) -> bool {
+        self.forces_abort() || self.gas_amplification() >= min_amplification
+    }
+}
+
+impl fmt::Display for GriefingReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "victim {:?} ({} gas) -> {:?} ({} gas, {:.1}x) after {} attacker gas",
+            self.baseline.status,
+            self.baseline.gas_used,
+            self.attacked.status,
+            self.attacked.gas_used,
+            self.gas_amplification(),
+            self.attacker_gas
+        )?;
+        if let Some(retry) = &self.retry {
+            write!(f, "; retry {:?} ({} gas)", retry.status, retry.gas_used)?;
+        }
+        Ok(())
+    }
+}
+
+/// A lasting denial of service outranks a one-off abort or inflated gas
+impl From<&GriefingReport> for Finding {
+    fn from(report: &GriefingReport) -> Self {
+        let severity = if report.persists_after_retry() {
+            Severity::Medium
+        } else if report.is_griefing(GRIEFING_GAS_FACTOR) {
+            Severity::Low
+        } else {
+            Severity::Informational
+        };
+        Finding::new("Attacker transactions grief the victim", severity, Category::DenialOfService)
+            .evidence(report.to_string())
+    }
+}
+
+impl AptosBB {
+    fn run_victim(&mut self, account: &Account, payload: &TransactionPayload) -> Result<VictimRun> {
+        let (status, output) = self.run_transaction_with_output(account, payload.clone())?;
+        Ok(VictimRun { status, gas_used: output.gas_used() })
+    }
+
+    /// Run the victim's transaction with and without the attacker's transactions executed first
+    ///
+    /// Both runs start from the current fork state, which is restored afterwards. When the
+    /// attacked run fails, the victim's transaction is submitted once more to tell a one-off
+    /// abort from a lasting denial of service.
+    pub fn griefing_report(
+        &mut self,
+        victim_call: (&Account, TransactionPayload),
+        attacker_setup: &[(Account, TransactionPayload)],
+    ) -> Result<GriefingReport> {
+        let (victim, payload) = victim_call;
+
+        let baseline = self.with_snapshot(|bb| bb.run_victim(victim, &payload))?;
+        self.with_snapshot(|bb| -> Result<GriefingReport> {
+            let mut attacker_gas = 0;
+            for (account, attack) in attacker_setup {
+                let (_, output) = bb.run_transaction_with_output(account, attack.clone())?;
+                attacker_gas += output.gas_used();
+            }
+            let attacked = bb.run_victim(victim, &payload)?;
+            let retry = if attacked.succeeded() { None } else { Some(bb.run_victim(victim, &payload)?) };
+            Ok(GriefingReport { baseline, attacked, retry, attacker_gas })
+        })
+    }
+
+    /// Fail unless the attacker's transactions make the victim's transaction abort or use
+    /// at least `GRIEFING_GAS_FACTOR` times more gas
+    ///
+    /// Meant as the check of a griefing/DoS PoC; use `griefing_report` to evaluate vectors
+    /// without failing. The fork state is left as it was.
+    pub fn assert_griefing(
+        &mut self,
+        victim_call: (&Account, TransactionPayload),
+        attacker_setup: &[(Account, TransactionPayload)],
+    ) -> Result<GriefingReport> {
+        let report = self.griefing_report(victim_call, attacker_setup)?;
+        if !report.baseline.succeeded() {
+            bail!("Victim transaction fails even without the attack: {:?}", report.baseline.status);
+        }
+        if !report.is_griefing(GRIEFING_GAS_FACTOR) {
+            bail!("No griefing: {}", report);
+        }
+        if report.persists_after_retry() {
+            println!("🚨 Lasting denial of service: {}", report);
+        } else {
+            println!("⚠️  Griefing: {}", report);
+        }
+        Ok(report)
+    }
+}
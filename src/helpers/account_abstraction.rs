@@ -0,0 +1,142 @@
+//! Account abstraction: custom (dispatchable) and derivable authentication functions
+
+use super::ensure_success;
+use crate::{entry::parse_function_id, AptosBB, EntryCall};
+use anyhow::Result;
+use aptos_crypto::{signing_message, HashValue};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    function_info::FunctionInfo,
+    on_chain_config::FeatureFlag,
+    transaction::{
+        authenticator::{AASigningData, AccountAuthenticator, AuthenticationKey, TransactionAuthenticator},
+        RawTransaction, SignedTransaction, TransactionOutput, TransactionPayload, TransactionStatus,
+    },
+};
+
+/// Parse an authentication function such as `0xabc::auth::authenticate`
+pub fn auth_function(function: &str) -> Result<FunctionInfo> {
+    let (module, name) = parse_function_id(function)?;
+    Ok(FunctionInfo::new(*module.address(), module.name().to_string(), name.to_string()))
+}
+
+/// Address of the account a derivable authentication function derives for `abstract_public_key`
+pub fn derivable_account_address(function: &FunctionInfo, abstract_public_key: &[u8]) -> Result<AccountAddress> {
+    Ok(AuthenticationKey::domain_abstraction_address(bcs::to_bytes(function)?, abstract_public_key).account_address())
+}
+
+/// Digest an authentication function is given to check, binding the transaction to the function
+fn signing_message_digest(raw_txn: &RawTransaction, function: &FunctionInfo) -> Result<Vec<u8>> {
+    let data = AASigningData::V1 {
+        original_signing_message: signing_message(raw_txn)?,
+        function_info: function.clone(),
+    };
+    Ok(HashValue::sha3_256_of(&signing_message(&data)?).to_vec())
+}
+
+impl AptosBB {
+    /// Turn on the account abstraction feature flags, in case the fork predates them
+    pub fn enable_account_abstraction(&mut self) -> Result<()> {
+        self.set_feature(FeatureFlag::ACCOUNT_ABSTRACTION, true)?;
+        self.set_feature(FeatureFlag::DERIVABLE_ACCOUNT_ABSTRACTION, true)
+    }
+
+    /// Let `function` authenticate transactions from `account`, in addition to its key
+    pub fn add_authentication_function(&mut self, account: &Account, function: &FunctionInfo) -> Result<()> {
+        let call = EntryCall::new("0x1::account_abstraction::add_authentication_function")
+            .arg(function.module_address)
+            .arg(function.module_name.clone())
+            .arg(function.function_name.clone());
+        let status = self.call(account, call)?;
+        ensure_success(status, "Adding authentication function")
+    }
+
+    /// Publish an authenticator module under `account` and register `module::function` for it
+    ///
+    /// `source` must declare `module <account>::<module>` (the named address `module` is
+    /// bound to the account) with a `public fun <function>(account: signer, auth_data:
+    /// AbstractionAuthData): signer`, the signature the framework dispatches to.
+    pub fn install_authenticator(&mut self, account: &Account, module: &str, source: &str, function: &str) -> Result<FunctionInfo> {
+        self.enable_account_abstraction()?;
+        let status = self.publish_source(account, module, &[(module, source)], &[(module, *account.address())])?;
+        ensure_success(status, "Publishing authenticator")?;
+        let function = FunctionInfo::new(*account.address(), module.to_string(), function.to_string());
+        self.add_authentication_function(account, &function)?;
+        Ok(function)
+    }
+
+    /// Register a derivable authentication function, as governance would
+    ///
+    /// Runs `account_abstraction::register_derivable_authentication_function` with the
+    /// framework signer (see `run_as_framework`), bypassing the proposal flow; an abort is
+    /// returned as an error.
+    pub fn register_derivable_authentication_function(&mut self, function: &FunctionInfo) -> Result<()> {
+        self.enable_account_abstraction()?;
+        self.run_as_framework(
+            "account_abstraction",
+            "register_derivable_authentication_function",
+            vec![],
+            vec![
+                bcs::to_bytes(&function.module_address)?,
+                bcs::to_bytes(&function.module_name)?,
+                bcs::to_bytes(&function.function_name)?,
+            ],
+        )
+    }
+
+    fn raw_transaction(&mut self, sender: AccountAddress, payload: TransactionPayload) -> RawTransaction {
+        RawTransaction::new(
+            sender,
+            self.next_sequence_number(sender),
+            payload,
+            self.gas_defaults.max_gas_amount,
+            self.gas_defaults.gas_unit_price,
+            self.expiration_timestamp(),
+            self.chain_id,
+        )
+    }
+
+    /// Execute a transaction authenticated by `function` instead of a key signature
+    ///
+    /// `authenticator` receives the signing message digest and returns the bytes passed to
+    /// the function as `AbstractionAuthData::authenticator`, e.g. a signature over the digest,
+    /// or anything at all when probing what the function accepts.
+    pub fn run_abstracted_transaction(
+        &mut self,
+        sender: &Account,
+        function: &FunctionInfo,
+        payload: TransactionPayload,
+        authenticator: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> Result<(TransactionStatus, TransactionOutput)> {
+        let raw_txn = self.raw_transaction(*sender.address(), payload);
+        let digest = signing_message_digest(&raw_txn, function)?;
+        let auth = AccountAuthenticator::abstraction(function.clone(), digest.clone(), authenticator(&digest));
+        let txn = SignedTransaction::new_signed_transaction(raw_txn, TransactionAuthenticator::single_sender(auth));
+        Ok(self.run_signed_transaction_with_output(txn)?)
+    }
+
+    /// Execute a transaction from the account `function` derives for `abstract_public_key`
+    ///
+    /// The function must have been registered (see `register_derivable_authentication_function`);
+    /// `sign` receives the signing message digest and returns the abstract signature.
+    pub fn run_derivable_transaction(
+        &mut self,
+        function: &FunctionInfo,
+        abstract_public_key: &[u8],
+        payload: TransactionPayload,
+        sign: impl FnOnce(&[u8]) -> Vec<u8>,
+    ) -> Result<(TransactionStatus, TransactionOutput)> {
+        let sender = derivable_account_address(function, abstract_public_key)?;
+        let raw_txn = self.raw_transaction(sender, payload);
+        let digest = signing_message_digest(&raw_txn, function)?;
+        let auth = AccountAuthenticator::derivable_abstraction(
+            function.clone(),
+            digest.clone(),
+            sign(&digest),
+            abstract_public_key.to_vec(),
+        );
+        let txn = SignedTransaction::new_signed_transaction(raw_txn, TransactionAuthenticator::single_sender(auth));
+        Ok(self.run_signed_transaction_with_output(txn)?)
+    }
+}
@@ -0,0 +1,178 @@
+//! Dispatchable fungible assets: custom withdraw/deposit/balance hooks on `0x1::dispatchable_fungible_asset`
+
+use super::{ensure_success, funding::primary_store_address};
+use crate::{AptosBB, EntryCall};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::{create_object_address, AccountAddress},
+    transaction::TransactionStatus,
+};
+use move_core_types::language_storage::TypeTag;
+use std::str::FromStr;
+
+const METADATA_TYPE: &str = "0x1::fungible_asset::Metadata";
+const STORE_TYPE: &str = "0x1::fungible_asset::FungibleStore";
+
+/// Module published next to the hooks, creating the asset and registering them
+const SETUP_MODULE: &str = "aptosbb_dispatch";
+const SETUP_SOURCE: &str = r#"module deployer::aptosbb_dispatch {
+    use std::option::{Self, Option};
+    use std::signer;
+    use std::string::{Self, String};
+    use aptos_framework::dispatchable_fungible_asset;
+    use aptos_framework::function_info::{Self, FunctionInfo};
+    use aptos_framework::fungible_asset;
+    use aptos_framework::object;
+    use aptos_framework::primary_fungible_store;
+
+    fun hook(deployer: &signer, function: Option<String>): Option<FunctionInfo> {
+        if (option::is_none(&function)) {
+            return option::none()
+        };
+        option::some(function_info::new_function_info(deployer, string::utf8(b"{module}"), option::destroy_some(function)))
+    }
+
+    public entry fun create(
+        deployer: &signer,
+        name: String,
+        symbol: String,
+        decimals: u8,
+        supply: u64,
+        withdraw: Option<String>,
+        deposit: Option<String>,
+        derived_balance: Option<String>,
+    ) {
+        let constructor_ref = &object::create_named_object(deployer, *string::bytes(&symbol));
+        primary_fungible_store::create_primary_store_enabled_fungible_asset(
+            constructor_ref,
+            option::none(),
+            name,
+            symbol,
+            decimals,
+            string::utf8(b""),
+            string::utf8(b""),
+        );
+        dispatchable_fungible_asset::register_dispatch_functions(
+            constructor_ref,
+            hook(deployer, withdraw),
+            hook(deployer, deposit),
+            hook(deployer, derived_balance),
+        );
+        let mint_ref = fungible_asset::generate_mint_ref(constructor_ref);
+        primary_fungible_store::mint(&mint_ref, signer::address_of(deployer), supply);
+    }
+}
+"#;
+
+/// Settings for a test dispatchable asset
+///
+/// Hooks are function names in the hooks module; unset hooks keep the default behavior.
+/// They must have the signatures the framework dispatches to:
+/// - withdraw: `public fun f<T: key>(store: Object<T>, amount: u64, transfer_ref: &TransferRef): FungibleAsset`
+/// - deposit: `public fun f<T: key>(store: Object<T>, fa: FungibleAsset, transfer_ref: &TransferRef)`
+/// - derived balance: `public fun f<T: key>(store: Object<T>): u64`
+#[derive(Clone, Debug)]
+pub struct DispatchableAssetConfig {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Minted to the deployer's primary store on creation
+    pub supply: u64,
+    pub withdraw: Option<String>,
+    pub deposit: Option<String>,
+    pub derived_balance: Option<String>,
+}
+
+impl DispatchableAssetConfig {
+    pub fn new(symbol: &str) -> Self {
+        Self {
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 8,
+            supply: 1_000_000_000,
+            withdraw: None,
+            deposit: None,
+            derived_balance: None,
+        }
+    }
+
+    pub fn supply(mut self, supply: u64) -> Self {
+        self.supply = supply;
+        self
+    }
+
+    pub fn withdraw(mut self, function: &str) -> Self {
+        self.withdraw = Some(function.to_string());
+        self
+    }
+
+    pub fn deposit(mut self, function: &str) -> Self {
+        self.deposit = Some(function.to_string());
+        self
+    }
+
+    pub fn derived_balance(mut self, function: &str) -> Self {
+        self.derived_balance = Some(function.to_string());
+        self
+    }
+}
+
+/// Metadata address of the asset `create_asset` creates for `symbol`
+pub fn asset_address(deployer: &AccountAddress, symbol: &str) -> AccountAddress {
+    create_object_address(*deployer, symbol.as_bytes())
+}
+
+/// Publish a hooks module under `deployer` and create a dispatchable asset using its hooks
+///
+/// `hooks_source` declares `module deployer::<hooks_module>`; the named address `deployer`
+/// is bound to the deployer's address. The configured supply is minted to the deployer.
+/// Returns the asset's metadata address.
+pub fn create_asset(
+    bb: &mut AptosBB,
+    deployer: &Account,
+    hooks_module: &str,
+    hooks_source: &str,
+    config: &DispatchableAssetConfig,
+) -> Result<AccountAddress> {
+    let setup_source = SETUP_SOURCE.replace("{module}", hooks_module);
+    let status = bb.publish_source(
+        deployer,
+        "dispatch_hooks",
+        &[(hooks_module, hooks_source), (SETUP_MODULE, &setup_source)],
+        &[("deployer", *deployer.address())],
+    )?;
+    ensure_success(status, "Publishing dispatch hooks")?;
+
+    let call = EntryCall::new(&format!("{}::{}::create", deployer.address().to_hex_literal(), SETUP_MODULE))
+        .arg(config.name.clone())
+        .arg(config.symbol.clone())
+        .arg(config.decimals)
+        .arg(config.supply)
+        .arg(config.withdraw.clone())
+        .arg(config.deposit.clone())
+        .arg(config.derived_balance.clone());
+    ensure_success(bb.call(deployer, call)?, "Creating dispatchable asset")?;
+    Ok(asset_address(deployer.address(), &config.symbol))
+}
+
+/// Transfer through the primary stores, dispatching to the asset's withdraw and deposit hooks
+///
+/// The status is returned rather than checked, since making hooks abort is usually the point.
+pub fn transfer(bb: &mut AptosBB, from: &Account, metadata: AccountAddress, to: AccountAddress, amount: u64) -> Result<TransactionStatus> {
+    let call = EntryCall::new("0x1::primary_fungible_store::transfer")
+        .ty(METADATA_TYPE)
+        .arg(metadata)
+        .arg(to)
+        .arg(amount);
+    bb.call(from, call)
+}
+
+/// Balance of `owner`'s primary store as reported by the derived balance hook
+pub fn derived_balance(bb: &mut AptosBB, owner: &AccountAddress, metadata: &AccountAddress) -> Result<u64> {
+    bb.view_as(
+        "0x1::dispatchable_fungible_asset::derived_balance",
+        vec![TypeTag::from_str(STORE_TYPE)?],
+        vec![bcs::to_bytes(&primary_store_address(owner, metadata))?],
+    )
+}
@@ -0,0 +1,113 @@
+//! Typed payloads for the framework entry functions scenarios call most
+
+use super::funding::Asset;
+use crate::{entry::MoveArg, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{EntryFunction, TransactionPayload},
+};
+use move_core_types::{
+    ident_str,
+    identifier::IdentStr,
+    language_storage::{ModuleId, StructTag, TypeTag},
+    value::MoveValue,
+};
+
+fn framework_call(module: &IdentStr, function: &IdentStr, ty_args: Vec<TypeTag>, args: Vec<Vec<u8>>) -> TransactionPayload {
+    TransactionPayload::EntryFunction(EntryFunction::new(
+        ModuleId::new(AccountAddress::ONE, module.to_owned()),
+        function.to_owned(),
+        ty_args,
+        args,
+    ))
+}
+
+fn framework_type(module: &IdentStr, name: &IdentStr) -> TypeTag {
+    TypeTag::Struct(Box::new(StructTag {
+        address: AccountAddress::ONE,
+        module: module.to_owned(),
+        name: name.to_owned(),
+        type_args: vec![],
+    }))
+}
+
+/// `0x1::aptos_account::transfer`: send APT, creating the recipient's account if needed
+pub fn transfer_apt(to: AccountAddress, amount: u64) -> TransactionPayload {
+    framework_call(
+        ident_str!("aptos_account"),
+        ident_str!("transfer"),
+        vec![],
+        vec![to.to_move_arg(), amount.to_move_arg()],
+    )
+}
+
+/// `0x1::aptos_account::transfer_coins<CoinType>`: send a legacy coin, registering the recipient if needed
+pub fn transfer_coins(coin_type: TypeTag, to: AccountAddress, amount: u64) -> TransactionPayload {
+    framework_call(
+        ident_str!("aptos_account"),
+        ident_str!("transfer_coins"),
+        vec![coin_type],
+        vec![to.to_move_arg(), amount.to_move_arg()],
+    )
+}
+
+/// `0x1::primary_fungible_store::transfer<Metadata>`: send a fungible asset between primary stores
+pub fn transfer_fungible_asset(metadata: AccountAddress, to: AccountAddress, amount: u64) -> TransactionPayload {
+    framework_call(
+        ident_str!("primary_fungible_store"),
+        ident_str!("transfer"),
+        vec![framework_type(ident_str!("fungible_asset"), ident_str!("Metadata"))],
+        vec![metadata.to_move_arg(), to.to_move_arg(), amount.to_move_arg()],
+    )
+}
+
+/// Send any `Asset` the way a wallet would: coins through `aptos_account`, fungible assets
+/// through their primary stores
+pub fn transfer_asset(asset: &Asset, to: AccountAddress, amount: u64) -> TransactionPayload {
+    match asset {
+        Asset::Coin(coin_type) => transfer_coins(coin_type.clone(), to, amount),
+        Asset::FungibleAsset(metadata) => transfer_fungible_asset(*metadata, to, amount),
+    }
+}
+
+/// `0x1::object::transfer<ObjectCore>`: hand an object (token, store, position) to `to`
+pub fn transfer_object(object: AccountAddress, to: AccountAddress) -> TransactionPayload {
+    framework_call(
+        ident_str!("object"),
+        ident_str!("transfer"),
+        vec![framework_type(ident_str!("object"), ident_str!("ObjectCore"))],
+        vec![object.to_move_arg(), to.to_move_arg()],
+    )
+}
+
+/// Register a `CoinStore<CoinType>` for the sender
+///
+/// `0x1::coin::register` is not an entry function, so this goes through
+/// `0x1::managed_coin::register`, which calls it.
+pub fn register_coin(coin_type: TypeTag) -> TransactionPayload {
+    framework_call(ident_str!("managed_coin"), ident_str!("register"), vec![coin_type], vec![])
+}
+
+impl AptosBB {
+    /// Run a `0x1` function with the framework signer as its first argument, the way genesis
+    /// and governance do
+    ///
+    /// Executes directly in a VM session: no account is created or modified at 0x1, no
+    /// transaction or sequence number is involved, and an abort is returned as an error
+    /// without applying anything.
+    pub fn run_as_framework(&mut self, module: &str, function: &str, ty_args: Vec<TypeTag>, args: Vec<Vec<u8>>) -> Result<()> {
+        let framework = MoveValue::Signer(AccountAddress::ONE)
+            .simple_serialize()
+            .ok_or_else(|| anyhow!("Failed to serialize framework signer"))?;
+        let args = std::iter::once(framework).chain(args).collect();
+        let (write_set, _events) = self
+            .executor
+            .try_exec(module, function, ty_args, args)
+            .map_err(|status| anyhow!("0x1::{}::{} failed: {:?}", module, function, status))?;
+        self.executor.apply_write_set(&write_set);
+        self.module_cache.invalidate(&write_set);
+        self.view_cache.invalidate();
+        Ok(())
+    }
+}
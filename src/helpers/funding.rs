@@ -0,0 +1,219 @@
+//! Funding cheatcodes for the assets mainnet protocols actually use
+
+use crate::AptosBB;
+use anyhow::Result;
+use aptos_crypto::HashValue;
+use aptos_types::account_address::AccountAddress;
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    move_resource::MoveStructType,
+};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// An asset held either as a fungible asset or as a legacy coin
+#[derive(Clone, Debug)]
+pub enum Asset {
+    /// Fungible asset, identified by its metadata object address
+    FungibleAsset(AccountAddress),
+    /// Legacy `0x1::coin` type
+    Coin(TypeTag),
+}
+
+fn fa(metadata: &str) -> Asset {
+    Asset::FungibleAsset(AccountAddress::from_hex_literal(metadata).expect("valid metadata address"))
+}
+
+fn coin(type_tag: &str) -> Asset {
+    Asset::Coin(TypeTag::from_str(type_tag).expect("valid coin type"))
+}
+
+/// APT (as a fungible asset)
+pub fn apt() -> Asset {
+    fa("0xa")
+}
+
+/// Native Tether USDt
+pub fn usdt() -> Asset {
+    fa("0x357b0b74bc833e95a115ad22604854d6b0fca151cecd94111770e5d6ffc9dc2b")
+}
+
+/// Native Circle USDC
+pub fn usdc() -> Asset {
+    fa("0xbae207659db88bea0cbead6da0ed00aac12edcdda169e591cd41c94180b46f3b")
+}
+
+/// LayerZero bridged USDC
+pub fn lz_usdc() -> Asset {
+    coin("0xf22bede237a07e121b56d91a491eb7bcdfd1f5907926a9e58338f964a01b17fa::asset::USDC")
+}
+
+/// LayerZero bridged USDT
+pub fn lz_usdt() -> Asset {
+    coin("0xf22bede237a07e121b56d91a491eb7bcdfd1f5907926a9e58338f964a01b17fa::asset::USDT")
+}
+
+/// LayerZero bridged WETH
+pub fn lz_weth() -> Asset {
+    coin("0xf22bede237a07e121b56d91a491eb7bcdfd1f5907926a9e58338f964a01b17fa::asset::WETH")
+}
+
+/// Wormhole bridged USDC
+pub fn wh_usdc() -> Asset {
+    coin("0x5e156f1207d0ebfa19a9eeff00d62a282278fb8719f4fab3a586a0a2c0fffbea::coin::T")
+}
+
+/// Address of an owner's primary fungible store for `metadata`
+pub fn primary_store_address(owner: &AccountAddress, metadata: &AccountAddress) -> AccountAddress {
+    let mut bytes = owner.to_vec();
+    bytes.extend_from_slice(metadata.as_ref());
+    bytes.push(0xFC);
+    AccountAddress::from_bytes(HashValue::sha3_256_of(&bytes).to_vec()).expect("32-byte hash")
+}
+
+/// `0x1::guid::GUID` (its nested `ID` struct has the same BCS layout flattened)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Guid {
+    creation_num: u64,
+    addr: AccountAddress,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct EventHandle {
+    counter: u64,
+    guid: Guid,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ObjectCore {
+    guid_creation_num: u64,
+    owner: AccountAddress,
+    allow_ungated_transfer: bool,
+    transfer_events: EventHandle,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct FungibleStore {
+    metadata: AccountAddress,
+    balance: u64,
+    frozen: bool,
+}
+
+/// Aggregator v2 layout: `{ value, max_value }`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ConcurrentFungibleBalance {
+    value: u64,
+    max_value: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct CoinStore {
+    coin: u64,
+    frozen: bool,
+    deposit_events: EventHandle,
+    withdraw_events: EventHandle,
+}
+
+/// First GUID creation number handed out to objects (`object::INIT_GUID_CREATION_NUM`)
+const INIT_GUID_CREATION_NUM: u64 = 0x4000000000000000;
+
+pub(super) fn framework_tag(module: &str, name: &str, type_args: Vec<TypeTag>) -> Result<StructTag> {
+    Ok(StructTag {
+        address: AccountAddress::ONE,
+        module: module.parse()?,
+        name: name.parse()?,
+        type_args,
+    })
+}
+
+impl AptosBB {
+    /// Set `owner`'s balance of an asset, creating its primary store / coin store if needed
+    ///
+    /// Balances are written directly; total supply is not adjusted.
+    pub fn fund(&mut self, owner: &AccountAddress, asset: &Asset, amount: u64) -> Result<()> {
+        match asset {
+            Asset::FungibleAsset(metadata) => self.fund_fungible_asset(owner, metadata, amount),
+            Asset::Coin(coin_type) => self.fund_coin(owner, coin_type, amount),
+        }
+    }
+
+    fn fund_fungible_asset(&mut self, owner: &AccountAddress, metadata: &AccountAddress, amount: u64) -> Result<()> {
+        use aptos_types::account_config::ObjectGroupResource;
+
+        let group = ObjectGroupResource::struct_tag();
+        let store = primary_store_address(owner, metadata);
+        let mut members = self.read_resource_group(&store, &group)?;
+
+        let core_tag = framework_tag("object", "ObjectCore", vec![])?;
+        if !members.contains_key(&core_tag) {
+            let core = ObjectCore {
+                guid_creation_num: INIT_GUID_CREATION_NUM + 1,
+                owner: *owner,
+                allow_ungated_transfer: false,
+                transfer_events: EventHandle {
+                    counter: 0,
+                    guid: Guid { creation_num: INIT_GUID_CREATION_NUM, addr: store },
+                },
+            };
+            members.insert(core_tag, bcs::to_bytes(&core)?);
+        }
+
+        let concurrent_tag = framework_tag("fungible_asset", "ConcurrentFungibleBalance", vec![])?;
+        let store_tag = framework_tag("fungible_asset", "FungibleStore", vec![])?;
+        if members.contains_key(&concurrent_tag) {
+            let balance = ConcurrentFungibleBalance { value: amount, max_value: u64::MAX };
+            members.insert(concurrent_tag, bcs::to_bytes(&balance)?);
+        } else {
+            let frozen = match members.get(&store_tag) {
+                Some(bytes) => bcs::from_bytes::<FungibleStore>(bytes)?.frozen,
+                None => false,
+            };
+            let fungible_store = FungibleStore { metadata: *metadata, balance: amount, frozen };
+            members.insert(store_tag, bcs::to_bytes(&fungible_store)?);
+        }
+
+        self.write_resource_group(&store, &group, &members)
+    }
+
+    fn fund_coin(&mut self, owner: &AccountAddress, coin_type: &TypeTag, amount: u64) -> Result<()> {
+        let tag = framework_tag("coin", "CoinStore", vec![coin_type.clone()])?;
+        let state_key = aptos_types::state_store::state_key::StateKey::resource(owner, &tag)?;
+
+        let coin_store = match self.read_state_value(&state_key) {
+            Some(value) => CoinStore { coin: amount, ..bcs::from_bytes(value.bytes())? },
+            None => CoinStore {
+                coin: amount,
+                frozen: false,
+                // Event handles are never emitted to on the fork, so any unused GUIDs will do
+                deposit_events: EventHandle { counter: 0, guid: Guid { creation_num: u64::MAX - 1, addr: *owner } },
+                withdraw_events: EventHandle { counter: 0, guid: Guid { creation_num: u64::MAX, addr: *owner } },
+            },
+        };
+        self.write_resource(owner, &tag, &coin_store)
+    }
+
+    /// Current balance of an asset held by `owner` (primary store or coin store)
+    pub fn asset_balance(&self, owner: &AccountAddress, asset: &Asset) -> Result<u64> {
+        match asset {
+            Asset::FungibleAsset(metadata) => {
+                use aptos_types::account_config::ObjectGroupResource;
+                let members = self.read_resource_group(&primary_store_address(owner, metadata), &ObjectGroupResource::struct_tag())?;
+                if let Some(bytes) = members.get(&framework_tag("fungible_asset", "ConcurrentFungibleBalance", vec![])?) {
+                    return Ok(bcs::from_bytes::<ConcurrentFungibleBalance>(bytes)?.value);
+                }
+                match members.get(&framework_tag("fungible_asset", "FungibleStore", vec![])?) {
+                    Some(bytes) => Ok(bcs::from_bytes::<FungibleStore>(bytes)?.balance),
+                    None => Ok(0),
+                }
+            }
+            Asset::Coin(coin_type) => {
+                let tag = framework_tag("coin", "CoinStore", vec![coin_type.clone()])?;
+                let state_key = aptos_types::state_store::state_key::StateKey::resource(owner, &tag)?;
+                match self.read_state_value(&state_key) {
+                    Some(value) => Ok(bcs::from_bytes::<CoinStore>(value.bytes())?.coin),
+                    None => Ok(0),
+                }
+            }
+        }
+    }
+}
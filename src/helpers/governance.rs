@@ -0,0 +1,115 @@
+//! Fast-forwarding on-chain governance proposals
+
+use super::ensure_success;
+use crate::AptosBB;
+use anyhow::{anyhow, bail, Result};
+use aptos_crypto::HashValue;
+use aptos_types::{
+    account_address::AccountAddress,
+    state_store::{state_key::StateKey, table::TableHandle},
+    transaction::{Script, TransactionArgument, TransactionOutput, TransactionPayload},
+};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde_json::Value;
+use std::str::FromStr;
+
+const VOTING_FORUM: &str = "0x1::voting::VotingForum<0x1::governance_proposal::GovernanceProposal>";
+const PROPOSAL: &str = "0x1::voting::Proposal<0x1::governance_proposal::GovernanceProposal>";
+
+fn json_u128(value: &Value, field: &str) -> Result<u128> {
+    let text = value[field].as_str().ok_or_else(|| anyhow!("Proposal has no {} field", field))?;
+    Ok(text.parse()?)
+}
+
+/// A governance proposal as stored in the voting forum
+#[derive(Clone, Debug)]
+pub struct GovernanceProposal {
+    pub proposal_id: u64,
+    pub execution_hash: Vec<u8>,
+    pub expiration_secs: u64,
+    pub yes_votes: u128,
+    pub no_votes: u128,
+    pub min_vote_threshold: u128,
+    pub is_resolved: bool,
+    /// Decoded `0x1::voting::Proposal`, including its metadata map
+    pub json: Value,
+}
+
+impl AptosBB {
+    fn proposal_key(&mut self, proposal_id: u64) -> Result<StateKey> {
+        let forum_tag = StructTag::from_str(VOTING_FORUM)?;
+        let forum = self
+            .read_resource_json(&AccountAddress::ONE, &forum_tag)?
+            .ok_or_else(|| anyhow!("Governance voting forum not found"))?;
+        let handle = forum["proposals"]["handle"]
+            .as_str()
+            .ok_or_else(|| anyhow!("Voting forum has no proposals table"))?;
+        Ok(StateKey::table_item(
+            &TableHandle(AccountAddress::from_str(handle)?),
+            &bcs::to_bytes(&proposal_id)?,
+        ))
+    }
+
+    /// Read a governance proposal from the voting forum
+    pub fn governance_proposal(&mut self, proposal_id: u64) -> Result<GovernanceProposal> {
+        let key = self.proposal_key(proposal_id)?;
+        let bytes = self
+            .read_state_value(&key)
+            .ok_or_else(|| anyhow!("Proposal {} not found", proposal_id))?;
+        let json = self.value_to_json(&TypeTag::from_str(PROPOSAL)?, bytes.bytes())?;
+
+        let execution_hash = json["execution_hash"].as_str().unwrap_or_default();
+        Ok(GovernanceProposal {
+            proposal_id,
+            execution_hash: hex::decode(execution_hash.trim_start_matches("0x"))?,
+            expiration_secs: json_u128(&json, "expiration_secs")? as u64,
+            yes_votes: json_u128(&json, "yes_votes")?,
+            no_votes: json_u128(&json, "no_votes")?,
+            min_vote_threshold: json_u128(&json, "min_vote_threshold")?,
+            is_resolved: json["is_resolved"].as_bool().unwrap_or(false),
+            json,
+        })
+    }
+
+    /// Pass a pending proposal and execute its script on the fork
+    ///
+    /// Only the script's hash is stored on chain, so the compiled execution script must be
+    /// supplied (e.g. built from the proposal's published source); it is checked against the
+    /// proposal's execution hash. The proposal's vote tally is rewritten so it succeeds, the
+    /// clock is moved past its voting period, and the script is run with the proposal id as
+    /// its only argument.
+    pub fn execute_pending_proposal(&mut self, proposal_id: u64, script: &[u8]) -> Result<TransactionOutput> {
+        let proposal = self.governance_proposal(proposal_id)?;
+        if proposal.is_resolved {
+            bail!("Proposal {} is already resolved", proposal_id);
+        }
+        let script_hash = HashValue::sha3_256_of(script).to_vec();
+        if script_hash != proposal.execution_hash {
+            bail!(
+                "Script hash 0x{} does not match proposal execution hash 0x{}",
+                hex::encode(&script_hash),
+                hex::encode(&proposal.execution_hash)
+            );
+        }
+
+        let mut json = proposal.json.clone();
+        let yes_votes = proposal.min_vote_threshold.max(proposal.no_votes) + 1;
+        json["yes_votes"] = Value::String(yes_votes.to_string());
+        let bytes = self.json_to_bcs(&TypeTag::from_str(PROPOSAL)?, &json)?;
+        let key = self.proposal_key(proposal_id)?;
+        self.write_state_bytes(key, bytes);
+
+        self.set_time(proposal.expiration_secs + 1)?;
+        println!("Proposal {} passed with {} yes votes, clock moved to {}", proposal_id, yes_votes, proposal.expiration_secs + 1);
+
+        let executor = self.new_account();
+        let payload = TransactionPayload::Script(Script::new(
+            script.to_vec(),
+            vec![],
+            vec![TransactionArgument::U64(proposal_id)],
+        ));
+        let (status, output) = self.run_transaction_with_output(&executor, payload)?;
+        ensure_success(status, &format!("Executing proposal {}", proposal_id))?;
+        Ok(output)
+    }
+}
@@ -0,0 +1,102 @@
+//! Coin to fungible asset migration: toggling the framework defaults and migrating stores
+
+use super::funding::{framework_tag, primary_store_address, Asset};
+use crate::{AptosBB, EntryCall};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::ObjectGroupResource,
+    on_chain_config::FeatureFlag,
+    state_store::state_key::StateKey,
+    transaction::TransactionStatus,
+};
+use move_core_types::{language_storage::TypeTag, move_resource::MoveStructType};
+
+/// Both representations of an owner's balance of a coin type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoinBalances {
+    /// Balance of the `0x1::coin::CoinStore`, `None` if the owner has none
+    pub coin_store: Option<u64>,
+    /// Balance of the primary store of the paired fungible asset, `None` if there is none
+    pub fungible_store: Option<u64>,
+    /// What `0x1::coin::balance` reports, which should be the sum of both
+    pub reported: u64,
+}
+
+impl CoinBalances {
+    /// Whether the framework's view of the balance matches the two stores
+    pub fn is_consistent(&self) -> bool {
+        self.coin_store.unwrap_or(0) as u128 + self.fungible_store.unwrap_or(0) as u128 == self.reported as u128
+    }
+
+    /// Whether the balance now lives only in the fungible store
+    pub fn is_migrated(&self) -> bool {
+        self.coin_store.is_none() && self.fungible_store.is_some()
+    }
+}
+
+impl AptosBB {
+    /// Turn the framework's migration defaults on or off
+    ///
+    /// When enabled, new accounts and coin operations default to primary fungible stores and
+    /// coins are converted on deposit; when disabled, the legacy `CoinStore` paths are taken.
+    pub fn set_coin_migration_defaults(&mut self, enabled: bool) -> Result<()> {
+        for flag in [
+            FeatureFlag::COIN_TO_FUNGIBLE_ASSET_MIGRATION,
+            FeatureFlag::NEW_ACCOUNTS_DEFAULT_TO_FA_APT_STORE,
+            FeatureFlag::OPERATIONS_DEFAULT_TO_FA_APT_STORE,
+            FeatureFlag::NEW_ACCOUNTS_DEFAULT_TO_FA_STORE,
+        ] {
+            self.set_feature(flag, enabled)?;
+        }
+        Ok(())
+    }
+
+    /// Metadata address of the fungible asset paired with a coin type, if it has one yet
+    pub fn paired_metadata(&mut self, coin_type: &TypeTag) -> Result<Option<AccountAddress>> {
+        self.view_as("0x1::coin::paired_metadata", vec![coin_type.clone()], vec![])
+    }
+
+    /// Migrate `account`'s `CoinStore` into the paired fungible store, as the owner would
+    ///
+    /// The status is returned rather than checked, since migration edge cases are the point.
+    pub fn migrate_to_fungible_store(&mut self, account: &Account, coin_type: &TypeTag) -> Result<TransactionStatus> {
+        let call = EntryCall::new("0x1::coin::migrate_to_fungible_store").ty_tag(coin_type.clone());
+        self.call(account, call)
+    }
+
+    /// Migrate other accounts' `CoinStore`s in bulk through the permissionless entry function
+    pub fn migrate_coin_stores(&mut self, caller: &Account, coin_type: &TypeTag, owners: &[AccountAddress]) -> Result<TransactionStatus> {
+        let call = EntryCall::new("0x1::coin::migrate_coin_store_to_fungible_store")
+            .ty_tag(coin_type.clone())
+            .arg(owners.to_vec());
+        self.call(caller, call)
+    }
+
+    /// Read `owner`'s balance of a coin type in the coin store, the paired fungible store and
+    /// as reported by the framework
+    pub fn coin_balances(&mut self, owner: &AccountAddress, coin_type: &TypeTag) -> Result<CoinBalances> {
+        let coin_store_tag = framework_tag("coin", "CoinStore", vec![coin_type.clone()])?;
+        let coin_store = match self.read_state_value(&StateKey::resource(owner, &coin_store_tag)?) {
+            Some(_) => Some(self.asset_balance(owner, &Asset::Coin(coin_type.clone()))?),
+            None => None,
+        };
+
+        let fungible_store = match self.paired_metadata(coin_type)? {
+            Some(metadata) => {
+                let store = primary_store_address(owner, &metadata);
+                let members = self.read_resource_group(&store, &ObjectGroupResource::struct_tag())?;
+                if members.contains_key(&framework_tag("fungible_asset", "FungibleStore", vec![])?) {
+                    Some(self.asset_balance(owner, &Asset::FungibleAsset(metadata))?)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        };
+
+        let reported = self.view_as("0x1::coin::balance", vec![coin_type.clone()], vec![bcs::to_bytes(owner)?])?;
+        Ok(CoinBalances { coin_store, fungible_store, reported })
+    }
+}
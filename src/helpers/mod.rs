@@ -0,0 +1,24 @@
+//! High-level helpers for setting up common on-chain situations on the fork
+
+pub mod account_abstraction;
+pub mod dispatchable;
+pub mod framework;
+pub mod funding;
+pub mod governance;
+pub mod migration;
+pub mod oracle;
+pub mod staking;
+pub mod token;
+pub mod vesting;
+pub mod whales;
+
+use anyhow::{bail, Result};
+use aptos_types::transaction::{ExecutionStatus, TransactionStatus};
+
+/// Turn a non-successful transaction status into an error naming the failed step
+pub(crate) fn ensure_success(status: TransactionStatus, step: &str) -> Result<()> {
+    match status {
+        TransactionStatus::Keep(ExecutionStatus::Success) => Ok(()),
+        status => bail!("{} failed: {:?}", step, status),
+    }
+}
@@ -0,0 +1,260 @@
+//! Price feed manipulation for oracle-dependent protocols
+//!
+//! Pyth is built in, with its storage layout mirrored. Any other oracle that keeps a feed in
+//! a resource (Switchboard aggregators, Chainlink-style feeds, a protocol's own price
+//! store) is driven through `ResourceFeed` by naming the fields to overwrite.
+
+use crate::AptosBB;
+use anyhow::{anyhow, bail, Result};
+use aptos_rest_client::AptosBaseUrl;
+use aptos_types::account_address::AccountAddress;
+use move_core_types::{identifier::Identifier, language_storage::StructTag};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Pyth deployment on Aptos mainnet
+pub const PYTH_ADDRESS: &str = "0x7e783b349d3e89cf5931af376ebeadbfab855b3fa239b7ada8f5a92fbea6b387";
+
+/// A price as published by an oracle: `price * 10^expo`, with `confidence` in the same units
+#[derive(Clone, Copy, Debug)]
+pub struct PriceUpdate {
+    pub price: i64,
+    pub confidence: u64,
+    /// Unix timestamp (seconds) the price claims to be from
+    pub timestamp: u64,
+}
+
+/// Oracles whose stored feeds can be overwritten on the fork
+pub trait PriceOracle {
+    /// Overwrite a feed's current price; `feed` is the oracle-specific feed identifier
+    fn set_price(&self, bb: &mut AptosBB, feed: &[u8], update: PriceUpdate) -> Result<()>;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct I64 {
+    negative: bool,
+    magnitude: u64,
+}
+
+impl From<i64> for I64 {
+    fn from(value: i64) -> Self {
+        Self { negative: value < 0, magnitude: value.unsigned_abs() }
+    }
+}
+
+impl I64 {
+    fn to_f64(&self) -> f64 {
+        if self.negative { -(self.magnitude as f64) } else { self.magnitude as f64 }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PythPrice {
+    price: I64,
+    conf: u64,
+    expo: I64,
+    timestamp: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PriceIdentifier {
+    bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PriceFeed {
+    price_identifier: PriceIdentifier,
+    price: PythPrice,
+    ema_price: PythPrice,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct PriceInfo {
+    attestation_time: u64,
+    arrival_time: u64,
+    price_feed: PriceFeed,
+}
+
+/// `state::LatestPriceInfo { info: Table<PriceIdentifier, PriceInfo> }`
+#[derive(Debug, Deserialize)]
+struct LatestPriceInfo {
+    info: AccountAddress,
+}
+
+/// Pyth price feeds, keyed by their 32-byte price identifier
+pub struct Pyth {
+    pub address: AccountAddress,
+}
+
+impl Pyth {
+    pub fn mainnet() -> Self {
+        Self { address: AccountAddress::from_hex_literal(PYTH_ADDRESS).expect("valid address") }
+    }
+
+    /// The deployment on the network a fork was taken from; only mainnet's is built in, on
+    /// other networks construct `Pyth { address }` with the deployment's address
+    pub fn for_network(network: &AptosBaseUrl) -> Result<Self> {
+        match network {
+            AptosBaseUrl::Mainnet => Ok(Self::mainnet()),
+            network => Err(anyhow!("No known Pyth deployment on {}", network.to_url())),
+        }
+    }
+
+    fn price_table(&self, bb: &mut AptosBB) -> Result<AccountAddress> {
+        let tag = StructTag {
+            address: self.address,
+            module: Identifier::new("state")?,
+            name: Identifier::new("LatestPriceInfo")?,
+            type_args: vec![],
+        };
+        let state_key = aptos_types::state_store::state_key::StateKey::resource(&self.address, &tag)?;
+        let value = bb.read_state_value(&state_key)
+            .ok_or_else(|| anyhow!("Pyth LatestPriceInfo not found at {}", self.address))?;
+        Ok(bcs::from_bytes::<LatestPriceInfo>(value.bytes())?.info)
+    }
+}
+
+impl Pyth {
+    /// Price table and its value type (`price_info::PriceInfo`), for
+    /// `StalenessConfig::watch_table`
+    pub fn staleness_table(&self, bb: &mut AptosBB) -> Result<(AccountAddress, StructTag)> {
+        let value_type = StructTag {
+            address: self.address,
+            module: Identifier::new("price_info")?,
+            name: Identifier::new("PriceInfo")?,
+            type_args: vec![],
+        };
+        Ok((self.price_table(bb)?, value_type))
+    }
+
+    /// Current spot price of a feed as stored on the fork, scaled by its exponent
+    pub fn price(&self, bb: &mut AptosBB, feed: &[u8]) -> Result<f64> {
+        let table = self.price_table(bb)?;
+        let key = PriceIdentifier { bytes: feed.to_vec() };
+        let bytes = bb.read_table_item(table, &key)?
+            .ok_or_else(|| anyhow!("Pyth feed 0x{} not found", hex::encode(feed)))?;
+        let price = bcs::from_bytes::<PriceInfo>(&bytes)?.price_feed.price;
+        Ok(price.price.to_f64() * 10f64.powf(price.expo.to_f64()))
+    }
+}
+
+impl PriceOracle for Pyth {
+    /// Rewrite both the spot and EMA price of a feed, keeping its exponent
+    fn set_price(&self, bb: &mut AptosBB, feed: &[u8], update: PriceUpdate) -> Result<()> {
+        let table = self.price_table(bb)?;
+        let key = PriceIdentifier { bytes: feed.to_vec() };
+        let bytes = bb.read_table_item(table, &key)?
+            .ok_or_else(|| anyhow!("Pyth feed 0x{} not found", hex::encode(feed)))?;
+
+        let mut info: PriceInfo = bcs::from_bytes(&bytes)?;
+        for price in [&mut info.price_feed.price, &mut info.price_feed.ema_price] {
+            price.price = update.price.into();
+            price.conf = update.confidence;
+            price.timestamp = update.timestamp;
+        }
+        info.attestation_time = update.timestamp;
+        info.arrival_time = update.timestamp;
+
+        bb.write_table_item(table, &key, &info)
+    }
+}
+
+impl AptosBB {
+    /// Overwrite a price feed on the given oracle
+    pub fn set_price(&mut self, oracle: &impl PriceOracle, feed: &[u8], price: i64, confidence: u64, timestamp: u64) -> Result<()> {
+        oracle.set_price(self, feed, PriceUpdate { price, confidence, timestamp })
+    }
+}
+
+/// A feed stored in fields of a resource, overwritten through the resource's JSON form so
+/// its layout does not have to be mirrored
+///
+/// The feed identifier passed to `set_price` is the address holding the resource, e.g. a
+/// Switchboard aggregator's object address. Field paths are dotted, as in
+/// `current_result.result.value`; values are written in the resource's own units.
+#[derive(Clone, Debug)]
+pub struct ResourceFeed {
+    pub resource: StructTag,
+    /// Unsigned magnitude of the price
+    pub price_field: String,
+    /// Sign flag (`true` for negative), for decimal types that keep it apart from the magnitude
+    pub negative_field: Option<String>,
+    pub confidence_field: Option<String>,
+    pub timestamp_field: Option<String>,
+    /// Units of the timestamp field per second, e.g. 1_000_000 for microseconds
+    pub timestamp_scale: u64,
+}
+
+impl ResourceFeed {
+    /// A feed with only a price field, timestamped in seconds
+    pub fn new(resource: StructTag, price_field: &str) -> Self {
+        Self {
+            resource,
+            price_field: price_field.to_string(),
+            negative_field: None,
+            confidence_field: None,
+            timestamp_field: None,
+            timestamp_scale: 1,
+        }
+    }
+
+    pub fn negative_field(mut self, field: &str) -> Self {
+        self.negative_field = Some(field.to_string());
+        self
+    }
+
+    pub fn confidence_field(mut self, field: &str) -> Self {
+        self.confidence_field = Some(field.to_string());
+        self
+    }
+
+    pub fn timestamp_field(mut self, field: &str, scale: u64) -> Self {
+        self.timestamp_field = Some(field.to_string());
+        self.timestamp_scale = scale;
+        self
+    }
+}
+
+/// The JSON value at a dotted field path, which must already exist
+fn field_mut<'a>(value: &'a mut Value, path: &str) -> Result<&'a mut Value> {
+    path.split('.').try_fold(value, |value, field| {
+        value.get_mut(field).ok_or_else(|| anyhow!("No field {} in {}", field, path))
+    })
+}
+
+/// Overwrite an integer field, keeping its JSON form (numbers for u8-u32, strings above)
+fn set_integer(value: &mut Value, path: &str, integer: u128) -> Result<()> {
+    let field = field_mut(value, path)?;
+    *field = match field {
+        Value::Number(_) => Value::Number(
+            u64::try_from(integer)
+                .map_err(|_| anyhow!("{} does not fit in {}", integer, path))?
+                .into(),
+        ),
+        _ => Value::String(integer.to_string()),
+    };
+    Ok(())
+}
+
+impl PriceOracle for ResourceFeed {
+    fn set_price(&self, bb: &mut AptosBB, feed: &[u8], update: PriceUpdate) -> Result<()> {
+        let addr = AccountAddress::from_bytes(feed)?;
+        let mut json = bb
+            .read_resource_json(&addr, &self.resource)?
+            .ok_or_else(|| anyhow!("{} not found at {}", self.resource, addr))?;
+
+        set_integer(&mut json, &self.price_field, update.price.unsigned_abs().into())?;
+        match &self.negative_field {
+            Some(path) => *field_mut(&mut json, path)? = Value::Bool(update.price < 0),
+            None if update.price < 0 => bail!("{} has no sign field for a negative price", self.resource),
+            None => {}
+        }
+        if let Some(path) = &self.confidence_field {
+            set_integer(&mut json, path, update.confidence.into())?;
+        }
+        if let Some(path) = &self.timestamp_field {
+            set_integer(&mut json, path, update.timestamp as u128 * self.timestamp_scale as u128)?;
+        }
+        bb.write_resource_json(&addr, &self.resource, &json)
+    }
+}
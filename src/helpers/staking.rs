@@ -0,0 +1,133 @@
+//! Delegation pool lifecycle: delegate, accrue rewards over epochs, unlock and withdraw
+
+use super::ensure_success;
+use crate::{AptosBB, EntryCall};
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::StructTag;
+use serde_json::Value;
+use std::str::FromStr;
+
+/// A delegator's stake in a pool, as reported by `delegation_pool::get_stake`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DelegatorStake {
+    /// Earning rewards, can be unlocked
+    pub active: u64,
+    /// Unlocked and past the lockup, can be withdrawn
+    pub inactive: u64,
+    /// Unlocked, waiting for the pool's lockup to expire
+    pub pending_inactive: u64,
+}
+
+fn json_u64(value: &Value, field: &str) -> Result<u64> {
+    match &value[field] {
+        Value::String(text) => Ok(text.parse()?),
+        Value::Number(n) => n.as_u64().ok_or_else(|| anyhow!("{} is not a u64", field)),
+        _ => Err(anyhow!("Missing field {}", field)),
+    }
+}
+
+/// Add `amount` octas of stake to a delegation pool
+pub fn delegate(bb: &mut AptosBB, delegator: &Account, pool: AccountAddress, amount: u64) -> Result<()> {
+    let call = EntryCall::new("0x1::delegation_pool::add_stake").arg(pool).arg(amount);
+    ensure_success(bb.call(delegator, call)?, "delegation_pool::add_stake")
+}
+
+/// Move `amount` octas of active stake to pending inactive
+pub fn unlock(bb: &mut AptosBB, delegator: &Account, pool: AccountAddress, amount: u64) -> Result<()> {
+    let call = EntryCall::new("0x1::delegation_pool::unlock").arg(pool).arg(amount);
+    ensure_success(bb.call(delegator, call)?, "delegation_pool::unlock")
+}
+
+/// Withdraw up to `amount` octas of inactive stake back to the delegator
+pub fn withdraw(bb: &mut AptosBB, delegator: &Account, pool: AccountAddress, amount: u64) -> Result<()> {
+    let call = EntryCall::new("0x1::delegation_pool::withdraw").arg(pool).arg(amount);
+    ensure_success(bb.call(delegator, call)?, "delegation_pool::withdraw")
+}
+
+/// Stake of `delegator` in `pool`, including rewards not yet synchronized into the pool
+pub fn stake(bb: &mut AptosBB, pool: AccountAddress, delegator: AccountAddress) -> Result<DelegatorStake> {
+    let result = bb.view(
+        "0x1::delegation_pool::get_stake",
+        vec![],
+        vec![bcs::to_bytes(&pool)?, bcs::to_bytes(&delegator)?],
+    )?;
+    Ok(DelegatorStake {
+        active: result.value_as(0)?,
+        inactive: result.value_as(1)?,
+        pending_inactive: result.value_as(2)?,
+    })
+}
+
+/// Unix time (seconds) until which the pool's stake is locked
+pub fn locked_until(bb: &mut AptosBB, pool: AccountAddress) -> Result<u64> {
+    bb.view_as("0x1::stake::get_lockup_secs", vec![], vec![bcs::to_bytes(&pool)?])
+}
+
+/// Configured epoch length in seconds
+fn epoch_interval_secs(bb: &AptosBB) -> Result<u64> {
+    let struct_tag = StructTag::from_str("0x1::block::BlockResource")?;
+    let block = bb
+        .read_resource_json(&AccountAddress::ONE, &struct_tag)?
+        .ok_or_else(|| anyhow!("BlockResource not found"))?;
+    Ok(json_u64(&block, "epoch_interval")? / 1_000_000)
+}
+
+/// Record a perfect proposal history for every active validator in the current epoch
+///
+/// No blocks are proposed on the fork, and `stake` pays no rewards to validators without
+/// successful proposals, so rewards would never accrue otherwise.
+fn set_perfect_performance(bb: &mut AptosBB) -> Result<()> {
+    let struct_tag = StructTag::from_str("0x1::stake::ValidatorPerformance")?;
+    let mut performance = bb
+        .read_resource_json(&AccountAddress::ONE, &struct_tag)?
+        .ok_or_else(|| anyhow!("ValidatorPerformance not found"))?;
+    let validators = performance["validators"]
+        .as_array_mut()
+        .ok_or_else(|| anyhow!("ValidatorPerformance has no validators"))?;
+    for validator in validators {
+        validator["successful_proposals"] = Value::String("1".to_string());
+        validator["failed_proposals"] = Value::String("0".to_string());
+    }
+    bb.write_resource_json(&AccountAddress::ONE, &struct_tag, &performance)
+}
+
+/// End the current epoch, distributing a full epoch of rewards to every active validator
+///
+/// The clock is moved forward by one epoch interval and `aptos_governance::force_end_epoch`
+/// is run with the framework signer (without touching 0x1's account), which bypasses the DKG
+/// wait of a regular reconfiguration.
+/// Delegation pools pick up their rewards lazily, on the next operation touching them.
+pub fn advance_epoch(bb: &mut AptosBB) -> Result<()> {
+    let now = bb.now_secs()?;
+    bb.set_time(now + epoch_interval_secs(bb)?.max(1))?;
+    set_perfect_performance(bb)?;
+
+    bb.run_as_framework("aptos_governance", "force_end_epoch", vec![], vec![])
+}
+
+/// End `epochs` epochs in a row
+pub fn advance_epochs(bb: &mut AptosBB, epochs: u64) -> Result<()> {
+    for _ in 0..epochs {
+        advance_epoch(bb)?;
+    }
+    Ok(())
+}
+
+/// End epochs until the pool's current lockup cycle has expired
+///
+/// Stake unlocked before this call becomes inactive, and so withdrawable. Returns the
+/// number of epochs that were ended.
+pub fn advance_past_lockup(bb: &mut AptosBB, pool: AccountAddress) -> Result<u64> {
+    let lockup = locked_until(bb, pool)?;
+    let mut epochs = 0;
+    // Pending inactive stake is only released when an epoch ends at or after the lockup
+    loop {
+        advance_epoch(bb)?;
+        epochs += 1;
+        if bb.now_secs()? >= lockup {
+            return Ok(epochs);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+//! Digital Asset (token v2) helpers built on `0x4::aptos_token`
+
+use super::ensure_success;
+use crate::{AptosBB, EntryCall};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::account_address::{create_object_address, AccountAddress};
+use move_core_types::language_storage::TypeTag;
+use serde::Deserialize;
+use std::str::FromStr;
+
+const TOKEN_TYPE: &str = "0x4::token::Token";
+const COLLECTION_TYPE: &str = "0x4::collection::Collection";
+
+/// Royalty configuration of a collection or token
+#[derive(Clone, Debug, Deserialize)]
+pub struct Royalty {
+    pub numerator: u64,
+    pub denominator: u64,
+    pub payee_address: AccountAddress,
+}
+
+/// Settings for a test collection
+#[derive(Clone, Debug)]
+pub struct CollectionConfig {
+    pub name: String,
+    pub description: String,
+    pub uri: String,
+    pub max_supply: u64,
+    pub royalty_numerator: u64,
+    pub royalty_denominator: u64,
+}
+
+impl CollectionConfig {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: String::new(),
+            uri: String::new(),
+            max_supply: 1_000,
+            royalty_numerator: 0,
+            royalty_denominator: 100,
+        }
+    }
+
+    pub fn max_supply(mut self, max_supply: u64) -> Self {
+        self.max_supply = max_supply;
+        self
+    }
+
+    pub fn royalty(mut self, numerator: u64, denominator: u64) -> Self {
+        self.royalty_numerator = numerator;
+        self.royalty_denominator = denominator;
+        self
+    }
+}
+
+/// Address of the named collection created by `creator`
+pub fn collection_address(creator: &AccountAddress, collection: &str) -> AccountAddress {
+    create_object_address(*creator, collection.as_bytes())
+}
+
+/// Address of a token with a deterministic (named) address
+pub fn token_address(creator: &AccountAddress, collection: &str, name: &str) -> AccountAddress {
+    create_object_address(*creator, format!("{}::{}", collection, name).as_bytes())
+}
+
+/// Create a fully mutable, burnable collection and return its address
+pub fn create_collection(bb: &mut AptosBB, creator: &Account, config: &CollectionConfig) -> Result<AccountAddress> {
+    let call = EntryCall::new("0x4::aptos_token::create_collection")
+        .arg(config.description.clone())
+        .arg(config.max_supply)
+        .arg(config.name.clone())
+        .arg(config.uri.clone())
+        .arg(true) // mutable_description
+        .arg(true) // mutable_royalty
+        .arg(true) // mutable_uri
+        .arg(true) // mutable_token_description
+        .arg(true) // mutable_token_name
+        .arg(true) // mutable_token_properties
+        .arg(true) // mutable_token_uri
+        .arg(true) // tokens_burnable_by_creator
+        .arg(true) // tokens_freezable_by_creator
+        .arg(config.royalty_numerator)
+        .arg(config.royalty_denominator);
+    ensure_success(bb.call(creator, call)?, "create_collection")?;
+    Ok(collection_address(creator.address(), &config.name))
+}
+
+/// Mint a token without properties into `collection`, owned by the creator
+///
+/// Tokens minted by `aptos_token::mint` get unique (non-deterministic) addresses, so the
+/// new token is located through the `Token` resource created by the transaction.
+pub fn mint(bb: &mut AptosBB, creator: &Account, collection: &str, name: &str) -> Result<AccountAddress> {
+    let call = EntryCall::new("0x4::aptos_token::mint")
+        .arg(collection)
+        .arg("")
+        .arg(name)
+        .arg("")
+        .arg(Vec::<String>::new())
+        .arg(Vec::<String>::new())
+        .arg(Vec::<Vec<u8>>::new());
+    let payload = call.into_payload()?;
+    let (status, output) = bb.run_transaction_with_output(creator, payload)?;
+    ensure_success(status, "mint")?;
+
+    output
+        .events()
+        .iter()
+        .filter(|event| event.type_tag().to_string() == "0x4::collection::Mint")
+        .find_map(|event| {
+            let json = bb.value_to_json(event.type_tag(), event.event_data()).ok()?;
+            AccountAddress::from_str(json.get("token")?.as_str()?).ok()
+        })
+        .ok_or_else(|| anyhow::anyhow!("Mint event for token {} not found", name))
+}
+
+/// Transfer any object (token, collection, ...) to another address
+pub fn transfer(bb: &mut AptosBB, owner: &Account, object: AccountAddress, to: AccountAddress) -> Result<()> {
+    let call = EntryCall::new("0x1::object::transfer_call")
+        .arg(object)
+        .arg(to);
+    ensure_success(bb.call(owner, call)?, "object::transfer_call")
+}
+
+/// Current owner of an object
+pub fn owner(bb: &mut AptosBB, object: AccountAddress) -> Result<AccountAddress> {
+    bb.view_as(
+        "0x1::object::owner",
+        vec![TypeTag::from_str(TOKEN_TYPE)?],
+        vec![bcs::to_bytes(&object)?],
+    )
+}
+
+/// Royalty of a token (falling back to its collection's royalty on chain)
+pub fn royalty(bb: &mut AptosBB, token: AccountAddress) -> Result<Option<Royalty>> {
+    bb.view_as(
+        "0x4::token::royalty",
+        vec![TypeTag::from_str(TOKEN_TYPE)?],
+        vec![bcs::to_bytes(&token)?],
+    )
+}
+
+/// Number of tokens minted in a collection, if the collection tracks supply
+pub fn supply(bb: &mut AptosBB, collection: AccountAddress) -> Result<Option<u64>> {
+    bb.view_as(
+        "0x4::collection::count",
+        vec![TypeTag::from_str(COLLECTION_TYPE)?],
+        vec![bcs::to_bytes(&collection)?],
+    )
+}
@@ -0,0 +1,270 @@
+//! Typed readers for `0x1::vesting` contracts, with time-warp driven vesting
+
+use super::{ensure_success, staking};
+use crate::{AptosBB, EntryCall};
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::StructTag;
+use serde_json::Value;
+use std::{collections::BTreeMap, str::FromStr};
+
+const VESTING_CONTRACT: &str = "0x1::vesting::VestingContract";
+
+fn json_u64(value: &Value) -> Result<u64> {
+    match value {
+        Value::String(text) => Ok(text.parse()?),
+        Value::Number(n) => n.as_u64().ok_or_else(|| anyhow!("{} is not a u64", n)),
+        _ => Err(anyhow!("Expected an integer, got {}", value)),
+    }
+}
+
+fn json_address(value: &Value) -> Result<AccountAddress> {
+    let text = value.as_str().ok_or_else(|| anyhow!("Expected an address, got {}", value))?;
+    Ok(AccountAddress::from_str(text)?)
+}
+
+/// Entries of a `SimpleMap`, which decodes as `{"data": [{"key": .., "value": ..}]}`
+fn simple_map(value: &Value) -> impl Iterator<Item = (&Value, &Value)> {
+    value["data"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|entry| (&entry["key"], &entry["value"]))
+}
+
+/// `fixed_point32::multiply_u64`
+fn multiply_fixed_point32(value: u64, fraction: u64) -> u64 {
+    ((value as u128 * fraction as u128) >> 32) as u64
+}
+
+/// Vesting schedule of a contract; fractions are raw `FixedPoint32` values (`1 << 32` is 100%)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VestingSchedule {
+    pub fractions: Vec<u64>,
+    pub start_timestamp_secs: u64,
+    pub period_duration: u64,
+    pub last_vested_period: u64,
+}
+
+impl VestingSchedule {
+    /// Fraction vesting in `period` (1-based); periods past the schedule repeat its last entry
+    pub fn fraction(&self, period: u64) -> u64 {
+        let index = (period.saturating_sub(1) as usize).min(self.fractions.len().saturating_sub(1));
+        self.fractions.get(index).copied().unwrap_or_default()
+    }
+
+    /// Last period completed at `unix_secs`, 0 before the schedule starts
+    pub fn completed_periods(&self, unix_secs: u64) -> u64 {
+        if unix_secs < self.start_timestamp_secs || self.period_duration == 0 {
+            return 0;
+        }
+        (unix_secs - self.start_timestamp_secs) / self.period_duration
+    }
+
+    /// Time at which `period` (1-based) completes
+    pub fn period_end_secs(&self, period: u64) -> u64 {
+        self.start_timestamp_secs + period * self.period_duration
+    }
+}
+
+/// A `0x1::vesting::VestingContract`
+#[derive(Clone, Debug)]
+pub struct VestingContract {
+    pub address: AccountAddress,
+    pub admin: AccountAddress,
+    /// 1 while active, 2 once terminated
+    pub state: u64,
+    pub schedule: VestingSchedule,
+    /// Coins in the grant pool when vesting started
+    pub total_grant: u64,
+    /// Part of the grant that has not vested yet
+    pub remaining_grant: u64,
+    /// Grant pool shares per shareholder
+    pub shares: BTreeMap<AccountAddress, u64>,
+    pub total_shares: u64,
+    /// Explicit beneficiaries; shareholders without one are paid directly
+    pub beneficiaries: BTreeMap<AccountAddress, AccountAddress>,
+    pub withdrawal_address: AccountAddress,
+    /// Stake pool the grant is staked in
+    pub stake_pool: AccountAddress,
+    pub operator: AccountAddress,
+    pub voter: AccountAddress,
+    /// Decoded resource, including fields not mapped above
+    pub json: Value,
+}
+
+impl VestingContract {
+    /// Address a shareholder's vested coins are paid to
+    pub fn beneficiary(&self, shareholder: &AccountAddress) -> AccountAddress {
+        self.beneficiaries.get(shareholder).copied().unwrap_or(*shareholder)
+    }
+
+    /// Amount that `vesting::vest` would unlock if called at `unix_secs`
+    ///
+    /// Mirrors the on-chain loop: every completed period after `last_vested_period` vests its
+    /// fraction of the total grant, capped by what remains of the grant.
+    pub fn vestable_at(&self, unix_secs: u64) -> u64 {
+        let mut remaining = self.remaining_grant;
+        let last_completed = self.schedule.completed_periods(unix_secs);
+        let mut period = self.schedule.last_vested_period + 1;
+        while period <= last_completed && remaining > 0 {
+            let amount = multiply_fixed_point32(self.total_grant, self.schedule.fraction(period)).min(remaining);
+            remaining -= amount;
+            period += 1;
+        }
+        self.remaining_grant - remaining
+    }
+
+    /// Share of `vestable_at(unix_secs)` attributed to `shareholder`
+    pub fn claimable_at(&self, shareholder: &AccountAddress, unix_secs: u64) -> u64 {
+        let shares = self.shares.get(shareholder).copied().unwrap_or_default();
+        if self.total_shares == 0 {
+            return 0;
+        }
+        (self.vestable_at(unix_secs) as u128 * shares as u128 / self.total_shares as u128) as u64
+    }
+
+    /// Period after which the whole remaining grant has vested, if the schedule ever gets there
+    pub fn fully_vested_period(&self) -> Option<u64> {
+        let mut remaining = self.remaining_grant;
+        let mut period = self.schedule.last_vested_period;
+        while remaining > 0 {
+            period += 1;
+            let amount = multiply_fixed_point32(self.total_grant, self.schedule.fraction(period));
+            if amount == 0 {
+                return None;
+            }
+            remaining = remaining.saturating_sub(amount);
+        }
+        Some(period)
+    }
+}
+
+/// Read the vesting contract stored at `contract`
+pub fn vesting_contract(bb: &AptosBB, contract: AccountAddress) -> Result<VestingContract> {
+    let json = bb
+        .read_resource_json(&contract, &StructTag::from_str(VESTING_CONTRACT)?)?
+        .ok_or_else(|| anyhow!("No vesting contract at {}", contract))?;
+
+    let schedule = &json["vesting_schedule"];
+    let fractions = schedule["schedule"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Vesting contract {} has no schedule", contract))?
+        .iter()
+        .map(|fraction| json_u64(&fraction["value"]))
+        .collect::<Result<Vec<_>>>()?;
+
+    let pool = &json["grant_pool"];
+    let shares = simple_map(&pool["shares"])
+        .map(|(key, value)| Ok((json_address(key)?, json_u64(value)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    let beneficiaries = simple_map(&json["beneficiaries"])
+        .map(|(key, value)| Ok((json_address(key)?, json_address(value)?)))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+
+    Ok(VestingContract {
+        address: contract,
+        admin: json_address(&json["admin"])?,
+        state: json_u64(&json["state"])?,
+        schedule: VestingSchedule {
+            fractions,
+            start_timestamp_secs: json_u64(&schedule["start_timestamp_secs"])?,
+            period_duration: json_u64(&schedule["period_duration"])?,
+            last_vested_period: json_u64(&schedule["last_vested_period"])?,
+        },
+        total_grant: json_u64(&pool["total_coins"])?,
+        remaining_grant: json_u64(&json["remaining_grant"])?,
+        shares,
+        total_shares: json_u64(&pool["total_shares"])?,
+        beneficiaries,
+        withdrawal_address: json_address(&json["withdrawal_address"])?,
+        stake_pool: json_address(&json["staking"]["pool_address"])?,
+        operator: json_address(&json["staking"]["operator"])?,
+        voter: json_address(&json["staking"]["voter"])?,
+        json,
+    })
+}
+
+/// Vesting contracts administered by `admin`, from its `AdminStore`
+pub fn vesting_contracts_of(bb: &AptosBB, admin: AccountAddress) -> Result<Vec<AccountAddress>> {
+    let Some(store) = bb.read_resource_json(&admin, &StructTag::from_str("0x1::vesting::AdminStore")?)? else {
+        return Ok(vec![]);
+    };
+    store["vesting_contracts"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(json_address)
+        .collect()
+}
+
+/// Unix time (seconds) until which vested coins stay locked in the contract's stake pool
+pub fn vested_locked_until(bb: &mut AptosBB, contract: &VestingContract) -> Result<u64> {
+    staking::locked_until(bb, contract.stake_pool)
+}
+
+/// Move the clock to the end of `period` (1-based) of the contract's schedule
+pub fn warp_to_period(bb: &mut AptosBB, contract: &VestingContract, period: u64) -> Result<()> {
+    bb.set_time(contract.schedule.period_end_secs(period))
+}
+
+/// Call `vesting::vest` on the contract, unlocking every completed period
+pub fn vest(bb: &mut AptosBB, caller: &Account, contract: AccountAddress) -> Result<()> {
+    let call = EntryCall::new("0x1::vesting::vest").arg(contract);
+    ensure_success(bb.call(caller, call)?, "vesting::vest")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUARTER: u64 = 1 << 30;
+
+    fn contract(fractions: Vec<u64>, last_vested_period: u64, remaining_grant: u64) -> VestingContract {
+        VestingContract {
+            address: AccountAddress::ONE,
+            admin: AccountAddress::ONE,
+            state: 1,
+            schedule: VestingSchedule { fractions, start_timestamp_secs: 1_000, period_duration: 100, last_vested_period },
+            total_grant: 1_000,
+            remaining_grant,
+            shares: BTreeMap::from([(AccountAddress::ONE, 3), (AccountAddress::TWO, 1)]),
+            total_shares: 4,
+            beneficiaries: BTreeMap::from([(AccountAddress::TWO, AccountAddress::THREE)]),
+            withdrawal_address: AccountAddress::ONE,
+            stake_pool: AccountAddress::ONE,
+            operator: AccountAddress::ONE,
+            voter: AccountAddress::ONE,
+            json: Value::Null,
+        }
+    }
+
+    #[test]
+    fn schedule_periods_and_fractions() {
+        let schedule = contract(vec![QUARTER, QUARTER / 2], 0, 1_000).schedule;
+        assert_eq!(schedule.completed_periods(999), 0);
+        assert_eq!(schedule.completed_periods(1_250), 2);
+        assert_eq!(schedule.period_end_secs(3), 1_300);
+        assert_eq!(schedule.fraction(1), QUARTER);
+        assert_eq!(schedule.fraction(7), QUARTER / 2);
+    }
+
+    #[test]
+    fn vestable_follows_the_on_chain_loop() {
+        let contract = contract(vec![QUARTER, QUARTER / 2], 1, 750);
+        assert_eq!(contract.vestable_at(1_150), 0);
+        assert_eq!(contract.vestable_at(1_300), 250);
+        assert_eq!(contract.vestable_at(10_000), 750);
+        assert_eq!(contract.claimable_at(&AccountAddress::ONE, 1_300), 187);
+        assert_eq!(contract.claimable_at(&AccountAddress::FOUR, 1_300), 0);
+        assert_eq!(contract.beneficiary(&AccountAddress::TWO), AccountAddress::THREE);
+        assert_eq!(contract.beneficiary(&AccountAddress::ONE), AccountAddress::ONE);
+    }
+
+    #[test]
+    fn fully_vested_period_is_none_for_a_stalled_schedule() {
+        assert_eq!(contract(vec![QUARTER, QUARTER / 2], 1, 750).fully_vested_period(), Some(7));
+        assert_eq!(contract(vec![QUARTER, 0], 1, 750).fully_vested_period(), None);
+    }
+}
@@ -0,0 +1,77 @@
+//! Whale fixtures: the largest real holders of an asset, impersonated on the fork
+
+use super::funding::{apt, Asset};
+use crate::{blocking, indexer::indexer_address, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{account_address::AccountAddress, account_config::ObjectGroupResource};
+use move_core_types::{language_storage::StructTag, move_resource::MoveStructType};
+use std::str::FromStr;
+
+/// APT (in octas) an impersonated whale is topped up to when it cannot pay for gas
+const WHALE_GAS_BALANCE: u64 = 100_000_000_000;
+
+/// Indexer holders fetched per requested holder, since balances may have moved since the fork
+const HOLDER_OVERFETCH: usize = 3;
+
+/// How the indexer identifies an asset in `asset_type` columns
+fn indexer_asset_type(asset: &Asset) -> String {
+    match asset {
+        Asset::FungibleAsset(metadata) => indexer_address(metadata),
+        Asset::Coin(coin_type) => coin_type.to_string(),
+    }
+}
+
+/// A holder of an asset and its balance on the fork
+#[derive(Clone, Debug)]
+pub struct Holder {
+    pub address: AccountAddress,
+    pub balance: u64,
+}
+
+impl AptosBB {
+    /// The `n` largest holders of an asset, with balances as of the fork version
+    ///
+    /// Candidates come from the indexer's current balances and are re-ranked by their balance
+    /// on the fork, so holders that only became large after the fork version drop out.
+    pub fn find_top_holders(&mut self, asset: &Asset, n: usize) -> Result<Vec<Holder>> {
+        let indexer = self.indexer()?;
+        let candidates = blocking::block_on(indexer.top_holders(&indexer_asset_type(asset), (n * HOLDER_OVERFETCH) as u32))?;
+
+        let mut holders = vec![];
+        for candidate in candidates {
+            self.fetch_account(candidate.owner_address)?;
+            let balance = self.asset_balance(&candidate.owner_address, asset)?;
+            if balance > 0 {
+                holders.push(Holder { address: candidate.owner_address, balance });
+            }
+        }
+        holders.sort_by(|a, b| b.balance.cmp(&a.balance));
+        holders.truncate(n);
+        Ok(holders)
+    }
+
+    /// Impersonate the largest holder of an asset that is a user account rather than an object
+    ///
+    /// Protocol pools and vaults are usually objects and are skipped. The whale is topped up
+    /// with APT for gas if it has little, leaving its balance of `asset` untouched.
+    pub fn impersonate_whale(&mut self, asset: &Asset) -> Result<(Account, Holder)> {
+        let object_core = StructTag::from_str("0x1::object::ObjectCore")?;
+        let holders = self.find_top_holders(asset, 10)?;
+        let whale = holders
+            .into_iter()
+            .find(|holder| {
+                self.read_resource_group(&holder.address, &ObjectGroupResource::struct_tag())
+                    .map(|members| !members.contains_key(&object_core))
+                    .unwrap_or(false)
+            })
+            .ok_or_else(|| anyhow!("No user account among the top holders of {:?}", asset))?;
+
+        let account = self.impersonate(whale.address)?;
+        if self.read_aptos_balance(&whale.address) < WHALE_GAS_BALANCE {
+            self.fund(&whale.address, &apt(), WHALE_GAS_BALANCE)?;
+        }
+        println!("🐋 Impersonating {} holding {} of {:?}", whale.address, whale.balance, asset);
+        Ok((account, whale))
+    }
+}
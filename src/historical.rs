@@ -0,0 +1,60 @@
+use crate::{blocking, AptosBB};
+use anyhow::{bail, Result};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::ObjectGroupResource,
+    state_store::{state_key::StateKey, state_value::StateValue},
+};
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    move_resource::MoveStructType,
+};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+impl AptosBB {
+    /// Raw state value as of a historical ledger version, read from the network
+    ///
+    /// Bypasses the fork entirely: local modifications are not visible and the fork's
+    /// pinned version is left untouched. Reads share the fork's cache and rate limit.
+    pub fn read_state_value_at_version(&self, state_key: &StateKey, version: u64) -> Result<Option<StateValue>> {
+        let remote = self.remote.at_version(version);
+        blocking::block_on(remote.get_state_value(state_key))
+    }
+
+    /// A resource in the API's JSON representation as of a historical ledger version
+    ///
+    /// Looks inside the object group if needed, like `read_resource_json`. Values are
+    /// decoded with the struct layouts of the fork, which upgrade compatibility keeps valid
+    /// for earlier versions of the struct.
+    pub fn read_resource_at_version(&self, addr: &AccountAddress, struct_tag: &StructTag, version: u64) -> Result<Option<Value>> {
+        let type_tag = TypeTag::Struct(Box::new(struct_tag.clone()));
+        if let Some(value) = self.read_state_value_at_version(&StateKey::resource(addr, struct_tag)?, version)? {
+            return Ok(Some(self.value_to_json(&type_tag, value.bytes())?));
+        }
+        let group_key = StateKey::resource_group(addr, &ObjectGroupResource::struct_tag());
+        let members: BTreeMap<StructTag, Vec<u8>> = match self.read_state_value_at_version(&group_key, version)? {
+            Some(value) => bcs::from_bytes(value.bytes())?,
+            None => return Ok(None),
+        };
+        members
+            .get(struct_tag)
+            .map(|bytes| self.value_to_json(&type_tag, bytes))
+            .transpose()
+    }
+
+    /// A resource right before and right after the transaction at `version` was applied
+    pub fn read_resource_around_version(
+        &self,
+        addr: &AccountAddress,
+        struct_tag: &StructTag,
+        version: u64,
+    ) -> Result<(Option<Value>, Option<Value>)> {
+        if version == 0 {
+            bail!("Version 0 is genesis; there is no state before it");
+        }
+        let before = self.read_resource_at_version(addr, struct_tag, version - 1)?;
+        let after = self.read_resource_at_version(addr, struct_tag, version)?;
+        Ok((before, after))
+    }
+}
@@ -0,0 +1,226 @@
+//! Persistent database of executed scenario runs, so long hunts stay searchable
+
+use crate::{builder::network_name, findings::Finding, AptosBB};
+use anyhow::{anyhow, Result};
+use rusqlite::{params, types::Type, Connection, OpenFlags, Row};
+use serde::Serialize;
+use serde_json::Value;
+use std::{fmt, path::Path, time::Duration};
+
+/// Database `log_experiments` and `aptosbb history` use unless told otherwise
+pub const DEFAULT_HISTORY_DB: &str = ".aptosbb-history.db";
+
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS experiments (
+    id INTEGER PRIMARY KEY,
+    started_at TEXT NOT NULL DEFAULT (datetime('now')),
+    scenario TEXT NOT NULL,
+    network TEXT NOT NULL,
+    fork_version INTEGER NOT NULL,
+    inputs TEXT NOT NULL,
+    success INTEGER NOT NULL,
+    error TEXT,
+    findings TEXT NOT NULL,
+    duration_ms INTEGER NOT NULL
+)";
+
+const COLUMNS: &str = "id, started_at, scenario, network, fork_version, inputs, success, error, findings, duration_ms";
+
+/// One recorded scenario run
+#[derive(Clone, Debug, Serialize)]
+pub struct Experiment {
+    pub id: i64,
+    /// UTC, `YYYY-MM-DD HH:MM:SS`
+    pub started_at: String,
+    pub scenario: String,
+    pub network: String,
+    pub fork_version: u64,
+    /// What the scenario reported from `Scenario::inputs`
+    pub inputs: Value,
+    pub success: bool,
+    pub error: Option<String>,
+    /// Findings reported with `report_finding` while the scenario ran
+    pub findings: Vec<Finding>,
+    pub duration_ms: u64,
+}
+
+impl Experiment {
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let inputs: String = row.get(5)?;
+        let findings: String = row.get(8)?;
+        Ok(Self {
+            id: row.get(0)?,
+            started_at: row.get(1)?,
+            scenario: row.get(2)?,
+            network: row.get(3)?,
+            fork_version: row.get::<_, i64>(4)? as u64,
+            inputs: serde_json::from_str(&inputs)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(5, Type::Text, Box::new(e)))?,
+            success: row.get(6)?,
+            error: row.get(7)?,
+            findings: serde_json::from_str(&findings)
+                .map_err(|e| rusqlite::Error::FromSqlConversionFailure(8, Type::Text, Box::new(e)))?,
+            duration_ms: row.get::<_, i64>(9)? as u64,
+        })
+    }
+}
+
+impl fmt::Display for Experiment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{:<5} {} {} {:<32} {}@{} {:>7}ms {} findings",
+            self.id,
+            self.started_at,
+            if self.success { "✅" } else { "❌" },
+            self.scenario,
+            self.network,
+            self.fork_version,
+            self.duration_ms,
+            self.findings.len()
+        )?;
+        if let Some(error) = &self.error {
+            write!(f, "\n        {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+/// Filters for `ExperimentDb::query`; the default lists the 20 most recent runs
+#[derive(Clone, Debug)]
+pub struct ExperimentQuery {
+    /// Substring of the scenario name
+    pub scenario: Option<String>,
+    pub network: Option<String>,
+    pub failed_only: bool,
+    pub with_findings_only: bool,
+    pub limit: usize,
+}
+
+impl Default for ExperimentQuery {
+    fn default() -> Self {
+        Self { scenario: None, network: None, failed_only: false, with_findings_only: false, limit: 20 }
+    }
+}
+
+/// SQLite database of experiments
+pub struct ExperimentDb {
+    conn: Connection,
+}
+
+impl ExperimentDb {
+    /// Open the database at `path`, creating it if needed
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path).map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+        conn.execute(SCHEMA, [])?;
+        Ok(Self { conn })
+    }
+
+    /// Open an existing database, failing instead of creating one at a mistyped path
+    pub fn open_existing(path: &Path) -> Result<Self> {
+        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+        let conn = Connection::open_with_flags(path, flags)
+            .map_err(|e| anyhow!("Failed to open experiment history {}: {}", path.display(), e))?;
+        conn.execute(SCHEMA, [])?;
+        Ok(Self { conn })
+    }
+
+    /// Store a run, returning its id
+    #[allow(clippy::too_many_arguments)]
+    pub fn record(
+        &self,
+        scenario: &str,
+        network: &str,
+        fork_version: u64,
+        inputs: &Value,
+        error: Option<&str>,
+        findings: &[Finding],
+        duration: Duration,
+    ) -> Result<i64> {
+        self.conn.execute(
+            "INSERT INTO experiments (scenario, network, fork_version, inputs, success, error, findings, duration_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                scenario,
+                network,
+                fork_version as i64,
+                inputs.to_string(),
+                error.is_none(),
+                error,
+                serde_json::to_string(findings)?,
+                duration.as_millis() as i64,
+            ],
+        )?;
+        Ok(self.conn.last_insert_rowid())
+    }
+
+    /// Matching runs, most recent first
+    pub fn query(&self, query: &ExperimentQuery) -> Result<Vec<Experiment>> {
+        let sql = format!(
+            "SELECT {} FROM experiments
+             WHERE (?1 IS NULL OR scenario LIKE '%' || ?1 || '%')
+               AND (?2 IS NULL OR network = ?2)
+               AND (?3 = 0 OR success = 0)
+               AND (?4 = 0 OR findings != '[]')
+             ORDER BY id DESC LIMIT ?5",
+            COLUMNS
+        );
+        let mut statement = self.conn.prepare(&sql)?;
+        let rows = statement.query_map(
+            params![query.scenario, query.network, query.failed_only, query.with_findings_only, query.limit as i64],
+            Experiment::from_row,
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+    }
+
+    pub fn get(&self, id: i64) -> Result<Option<Experiment>> {
+        let mut statement = self.conn.prepare(&format!("SELECT {} FROM experiments WHERE id = ?1", COLUMNS))?;
+        let mut rows = statement.query_map(params![id], Experiment::from_row)?;
+        Ok(rows.next().transpose()?)
+    }
+}
+
+/// The open database and the findings of the scenarios currently running
+pub(crate) struct ExperimentLog {
+    db: ExperimentDb,
+    findings: Vec<Finding>,
+    /// Number of findings before each running scenario started, innermost last; a nested
+    /// scenario's findings also count for the scenarios around it
+    running: Vec<usize>,
+}
+
+impl AptosBB {
+    /// Record every scenario run with `run_scenario` in the database at `path`
+    pub fn log_experiments(&mut self, path: &Path) -> Result<()> {
+        self.history = Some(ExperimentLog { db: ExperimentDb::open(path)?, findings: vec![], running: vec![] });
+        Ok(())
+    }
+
+    pub(crate) fn begin_experiment(&mut self) {
+        if let Some(log) = self.history.as_mut() {
+            log.running.push(log.findings.len());
+        }
+    }
+
+    pub(crate) fn note_experiment_finding(&mut self, finding: &Finding) {
+        if let Some(log) = self.history.as_mut().filter(|log| !log.running.is_empty()) {
+            log.findings.push(finding.clone());
+        }
+    }
+
+    pub(crate) fn record_experiment(&mut self, scenario: &str, inputs: &Value, result: &Result<()>, duration: Duration) {
+        let network = network_name(&self.network);
+        let Some(log) = self.history.as_mut() else {
+            return;
+        };
+        let error = result.as_ref().err().map(|e| format!("{:#}", e));
+        let started = log.running.pop().unwrap_or_default();
+        let findings = log.findings[started.min(log.findings.len())..].to_vec();
+        if log.running.is_empty() {
+            log.findings.clear();
+        }
+        match log.db.record(scenario, &network, self.version, inputs, error.as_deref(), &findings, duration) {
+            Ok(id) => println!("🗄️  Recorded {} as experiment #{}", scenario, id),
+            Err(e) => eprintln!("Warning: failed to record experiment {}: {:#}", scenario, e),
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crate::{
+    rate_limit::{RateLimit, RateLimiter},
+    AptosBB,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_rest_client::AptosBaseUrl;
+use aptos_types::account_address::AccountAddress;
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+use serde_json::{json, Value};
+use url::Url;
+
+/// Hasura serializes `numeric` columns as numbers or strings depending on their size
+fn de_u128<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u128, D::Error> {
+    match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.to_string().parse().map_err(serde::de::Error::custom),
+        Value::String(s) => s.parse().map_err(serde::de::Error::custom),
+        other => Err(serde::de::Error::custom(format!("expected an integer, got {}", other))),
+    }
+}
+
+fn de_opt_u128<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Option<u128>, D::Error> {
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => de_u128(value).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Full-length `0x`-prefixed form the indexer stores addresses in
+pub(crate) fn indexer_address(addr: &AccountAddress) -> String {
+    format!("0x{}", addr.to_hex())
+}
+
+/// An event as stored by the indexer
+#[derive(Clone, Debug, Deserialize)]
+pub struct IndexerEvent {
+    pub transaction_version: u64,
+    pub event_index: u64,
+    pub account_address: AccountAddress,
+    pub creation_number: u64,
+    pub sequence_number: u64,
+    #[serde(rename = "type")]
+    pub type_tag: String,
+    /// Event type without generic parameters, e.g. `0x1::coin::WithdrawEvent`
+    pub indexed_type: String,
+    pub data: Value,
+}
+
+/// A coin or fungible asset movement (deposit, withdraw, gas fee, ...)
+#[derive(Clone, Debug, Deserialize)]
+pub struct CoinActivity {
+    pub transaction_version: u64,
+    pub event_index: i64,
+    pub owner_address: Option<AccountAddress>,
+    /// Coin type (`0x1::aptos_coin::AptosCoin`) or fungible asset metadata address
+    pub asset_type: Option<String>,
+    #[serde(rename = "type")]
+    pub activity_type: String,
+    #[serde(default, deserialize_with = "de_opt_u128")]
+    pub amount: Option<u128>,
+    pub is_gas_fee: bool,
+    pub is_transaction_success: bool,
+    pub entry_function_id_str: Option<String>,
+}
+
+/// A digital asset (token v2) transfer, mint or burn
+#[derive(Clone, Debug, Deserialize)]
+pub struct TokenActivity {
+    pub transaction_version: u64,
+    pub event_index: i64,
+    pub from_address: Option<AccountAddress>,
+    pub to_address: Option<AccountAddress>,
+    pub token_data_id: String,
+    #[serde(deserialize_with = "de_u128")]
+    pub token_amount: u128,
+    #[serde(rename = "type")]
+    pub activity_type: String,
+    pub entry_function_id_str: Option<String>,
+}
+
+/// Current balance of an asset in one owner's primary store (or coin store)
+#[derive(Clone, Debug, Deserialize)]
+pub struct AssetBalance {
+    pub owner_address: AccountAddress,
+    pub asset_type: String,
+    #[serde(deserialize_with = "de_u128")]
+    pub amount: u128,
+}
+
+#[derive(Deserialize)]
+struct GraphQlResponse<T> {
+    data: Option<T>,
+    #[serde(default)]
+    errors: Vec<GraphQlError>,
+}
+
+#[derive(Deserialize)]
+struct GraphQlError {
+    message: String,
+}
+
+/// Client for the Aptos Indexer GraphQL API
+///
+/// Gives scenarios access to historical activity the REST API cannot query by content,
+/// e.g. the largest recent withdrawals from a pool to pick realistic victims and amounts.
+pub struct IndexerClient {
+    http: reqwest::Client,
+    url: Url,
+    api_key: Option<String>,
+    limiter: RateLimiter,
+}
+
+impl IndexerClient {
+    /// Fails if `rate_limit` is invalid (see `RateLimit::validate`)
+    pub fn new(url: Url, api_key: Option<&str>, rate_limit: RateLimit) -> Result<Self> {
+        Ok(Self {
+            http: reqwest::Client::new(),
+            url,
+            api_key: api_key.map(str::to_string),
+            limiter: RateLimiter::new(rate_limit)?,
+        })
+    }
+
+    /// Public indexer endpoint of a network
+    pub fn endpoint(network: &AptosBaseUrl) -> Result<Url> {
+        let url = match network {
+            AptosBaseUrl::Mainnet => "https://api.mainnet.aptoslabs.com/v1/graphql",
+            AptosBaseUrl::Testnet => "https://api.testnet.aptoslabs.com/v1/graphql",
+            AptosBaseUrl::Devnet => "https://api.devnet.aptoslabs.com/v1/graphql",
+            _ => bail!("No known indexer endpoint for {}; use IndexerClient::new with its URL", network.to_url()),
+        };
+        Ok(Url::parse(url)?)
+    }
+
+    /// Run a raw GraphQL query and deserialize its `data` field
+    pub async fn query<T: DeserializeOwned>(&self, query: &str, variables: Value) -> Result<T> {
+        self.limiter.acquire().await?;
+        let mut request = self.http.post(self.url.clone()).json(&json!({
+            "query": query,
+            "variables": variables,
+        }));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response: GraphQlResponse<T> = request
+            .send()
+            .await
+            .with_context(|| format!("Indexer request to {} failed", self.url))?
+            .error_for_status()?
+            .json()
+            .await?;
+        if !response.errors.is_empty() {
+            let messages: Vec<_> = response.errors.into_iter().map(|e| e.message).collect();
+            bail!("Indexer query failed: {}", messages.join("; "));
+        }
+        response.data.ok_or_else(|| anyhow!("Indexer returned no data"))
+    }
+
+    /// Most recent events of a type (without generic parameters), newest first
+    pub async fn events(&self, indexed_type: &str, limit: u32) -> Result<Vec<IndexerEvent>> {
+        #[derive(Deserialize)]
+        struct Data {
+            events: Vec<IndexerEvent>,
+        }
+
+        let data: Data = self
+            .query(
+                "query Events($type: String!, $limit: Int!) {
+                    events(where: {indexed_type: {_eq: $type}}, order_by: {transaction_version: desc}, limit: $limit) {
+                        transaction_version event_index account_address creation_number sequence_number type indexed_type data
+                    }
+                }",
+                json!({ "type": indexed_type, "limit": limit }),
+            )
+            .await?;
+        Ok(data.events)
+    }
+
+    /// Most recent coin and fungible asset activities, newest first
+    ///
+    /// Both filters are optional; `asset_type` is a coin type or fungible asset metadata address.
+    pub async fn coin_activities(
+        &self,
+        owner: Option<AccountAddress>,
+        asset_type: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<CoinActivity>> {
+        #[derive(Deserialize)]
+        struct Data {
+            fungible_asset_activities: Vec<CoinActivity>,
+        }
+
+        let mut filter = serde_json::Map::new();
+        if let Some(owner) = owner {
+            filter.insert("owner_address".into(), json!({ "_eq": indexer_address(&owner) }));
+        }
+        if let Some(asset_type) = asset_type {
+            filter.insert("asset_type".into(), json!({ "_eq": asset_type }));
+        }
+
+        let data: Data = self
+            .query(
+                "query CoinActivities($where: fungible_asset_activities_bool_exp!, $limit: Int!) {
+                    fungible_asset_activities(where: $where, order_by: {transaction_version: desc}, limit: $limit) {
+                        transaction_version event_index owner_address asset_type type amount
+                        is_gas_fee is_transaction_success entry_function_id_str
+                    }
+                }",
+                json!({ "where": filter, "limit": limit }),
+            )
+            .await?;
+        Ok(data.fungible_asset_activities)
+    }
+
+    /// Most recent token activities sending to or from an account, newest first
+    pub async fn token_activities(&self, account: AccountAddress, limit: u32) -> Result<Vec<TokenActivity>> {
+        #[derive(Deserialize)]
+        struct Data {
+            token_activities_v2: Vec<TokenActivity>,
+        }
+
+        let account = indexer_address(&account);
+        let data: Data = self
+            .query(
+                "query TokenActivities($account: String!, $limit: Int!) {
+                    token_activities_v2(
+                        where: {_or: [{from_address: {_eq: $account}}, {to_address: {_eq: $account}}]},
+                        order_by: {transaction_version: desc},
+                        limit: $limit
+                    ) {
+                        transaction_version event_index from_address to_address token_data_id token_amount type entry_function_id_str
+                    }
+                }",
+                json!({ "account": account, "limit": limit }),
+            )
+            .await?;
+        Ok(data.token_activities_v2)
+    }
+
+    /// Largest current holders of a coin type or fungible asset, by primary store balance
+    ///
+    /// Balances are the indexer's latest, not as of the fork version.
+    pub async fn top_holders(&self, asset_type: &str, limit: u32) -> Result<Vec<AssetBalance>> {
+        #[derive(Deserialize)]
+        struct Data {
+            current_fungible_asset_balances: Vec<AssetBalance>,
+        }
+
+        let data: Data = self
+            .query(
+                "query TopHolders($asset_type: String!, $limit: Int!) {
+                    current_fungible_asset_balances(
+                        where: {asset_type: {_eq: $asset_type}, is_primary: {_eq: true}},
+                        order_by: {amount: desc},
+                        limit: $limit
+                    ) {
+                        owner_address asset_type amount
+                    }
+                }",
+                json!({ "asset_type": asset_type, "limit": limit }),
+            )
+            .await?;
+        Ok(data.current_fungible_asset_balances)
+    }
+
+    /// Versions of the most recent transactions that touched an account, newest first
+    pub async fn account_transactions(&self, account: AccountAddress, limit: u32) -> Result<Vec<u64>> {
+        #[derive(Deserialize)]
+        struct Row {
+            transaction_version: u64,
+        }
+        #[derive(Deserialize)]
+        struct Data {
+            account_transactions: Vec<Row>,
+        }
+
+        let data: Data = self
+            .query(
+                "query AccountTransactions($account: String!, $limit: Int!) {
+                    account_transactions(where: {account_address: {_eq: $account}}, order_by: {transaction_version: desc}, limit: $limit) {
+                        transaction_version
+                    }
+                }",
+                json!({ "account": indexer_address(&account), "limit": limit }),
+            )
+            .await?;
+        Ok(data.account_transactions.into_iter().map(|row| row.transaction_version).collect())
+    }
+}
+
+impl AptosBB {
+    /// Indexer client for the forked network, using the fork's API key and rate limit
+    pub fn indexer(&self) -> Result<IndexerClient> {
+        IndexerClient::new(
+            IndexerClient::endpoint(&self.network)?,
+            self.api_key.as_deref(),
+            self.remote.rate_limit(),
+        )
+    }
+}
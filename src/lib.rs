@@ -1,11 +1,13 @@
-use anyhow::Result;
+use error::Result;
 use aptos_language_e2e_tests::{
-    account::Account,
+    account::{Account, TransactionBuilder},
     executor::FakeExecutor,
 };
 use aptos_types::{
     account_address::AccountAddress,
-    transaction::{TransactionPayload, TransactionStatus, EntryFunction},
+    transaction::{TransactionPayload, TransactionStatus, EntryFunction, SignedTransaction, Transaction},
+    transaction::signature_verified_transaction::into_signature_verified_block,
+    block_executor::config::BlockExecutorConfigFromOnchain,
     account_config::AccountResource,
 };
 use move_core_types::{
@@ -13,69 +15,152 @@ use move_core_types::{
     language_storage::{ModuleId, TypeTag},
     move_resource::MoveStructType,
 };
-use aptos_framework::{BuildOptions, BuiltPackage};
+use aptos_framework::BuiltPackage;
 use aptos_cached_packages::aptos_stdlib;
-use aptos_rest_client::{Client, AptosBaseUrl};
-use std::{path::Path, collections::HashMap};
+use aptos_rest_client::AptosBaseUrl;
+use std::{path::{Path, PathBuf}, collections::HashMap};
 
+pub mod access_probe;
+pub mod adapters;
+pub mod aggregator;
+pub mod analysis;
+pub mod annotate;
+pub mod bench;
+pub mod bisect;
+#[cfg(feature = "broadcast")]
+pub mod broadcast;
+pub mod blocking;
+pub mod boundaries;
+pub mod block;
+pub mod builder;
+pub mod calls;
+pub mod cheats;
+pub mod cache;
+pub mod codegen;
+pub mod conflicts;
+pub mod control;
+pub mod diff;
+pub mod entry;
+pub mod error;
+pub mod events;
+pub mod execution_limits;
+pub mod export;
+pub mod features;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fetch_log;
+pub mod findings;
+pub mod gas;
+pub mod griefing;
+pub mod helpers;
+pub mod historical;
+pub mod history;
+pub mod indexer;
+pub mod limits;
+pub mod localnet;
+pub mod move_test;
+pub mod network_diff;
+pub mod observer;
+pub mod package;
+pub mod payload;
 pub mod pentest;
+pub mod module_cache;
+pub mod monitor;
+pub mod ordering;
+pub mod prefetch;
+pub mod pricing;
+pub mod profile;
+pub mod prover;
+pub mod rate_limit;
+pub mod remote;
+pub mod report;
+pub mod replay;
+pub mod replay_protection;
+pub mod sandbox;
+pub mod scaffold;
+pub mod scenario;
+pub mod snapshot;
+pub mod sponsor;
+pub mod staleness;
+pub mod state_keys;
+pub mod status;
+pub mod storage;
+pub mod summary;
+pub mod stubgen;
+pub mod sweep;
+pub mod testing;
+pub mod validate;
+pub mod view;
+pub mod view_cache;
+pub mod vm_version;
+pub mod watch;
+pub mod workload;
+
+pub use builder::{AptosBBBuilder, ForkMode, GasDefaults};
+pub use entry::{EntryCall, MoveArg};
+pub use error::AptosBBError;
+pub use rate_limit::RateLimit;
+pub use scenario::{ForkConfig, Scenario};
+pub use vm_version::VmVersion;
+pub use aptosbb_macros::test;
 
 /// Main interface for the AptosBB pentesting environment
 pub struct AptosBB {
     executor: FakeExecutor,
     sequence_numbers: HashMap<AccountAddress, u64>,
     chain_id: aptos_types::chain_id::ChainId,
+    remote: remote::RemoteState,
+    module_cache: module_cache::ModuleCache,
+    view_cache: view_cache::ViewCache,
+    vm: vm_version::VmSelection,
+    network: AptosBaseUrl,
+    api_key: Option<String>,
+    version: u64,
+    cache_dir: Option<PathBuf>,
+    gas_defaults: GasDefaults,
+    events: events::EventBus,
+    exporter: Option<export::ResultExporter>,
+    report: Option<report::SessionRecorder>,
+    storage: Option<storage::StorageReport>,
+    limits: Option<limits::ActiveLimits>,
+    block_gas_limit: execution_limits::BlockGasLimit,
+    staleness: Option<staleness::StalenessWatch>,
+    history: Option<history::ExperimentLog>,
+    /// Balance changes printed in the summary after every transaction, `None` when off
+    txn_summaries: Option<usize>,
+    /// Every finding reported on this fork, see `AptosBB::report_finding`
+    findings: Vec<findings::Finding>,
 }
 
 impl AptosBB {
+    /// Start configuring a new AptosBB environment
+    pub fn builder() -> AptosBBBuilder {
+        AptosBBBuilder::new()
+    }
+    
     /// Create AptosBB with remote mainnet state at the latest version
     pub async fn from_mainnet_latest() -> Result<Self> {
-        let base_url = AptosBaseUrl::Mainnet;
-        let client = Client::new(base_url.to_url().clone());
-        let ledger_info = client.get_ledger_information().await?
-            .into_inner();
-        let latest_version = ledger_info.version;
-        
-        println!("Connecting to mainnet at latest version: {}", latest_version);
-        println!("Chain ID: {}", ledger_info.chain_id);
-        
-        let mut executor = FakeExecutor::from_remote_state(base_url, latest_version);
-        
-        let timestamp_secs = ledger_info.timestamp_usecs / 1_000_000;
-        executor.set_block_time(timestamp_secs);
-        println!("Set executor block time to: {}", timestamp_secs);
-        
-        Ok(Self {
-            executor,
-            sequence_numbers: HashMap::new(),
-            chain_id: aptos_types::chain_id::ChainId::new(ledger_info.chain_id),
-        })
+        Self::builder().network(AptosBaseUrl::Mainnet).build().await
     }
     
     /// Create AptosBB with remote mainnet state at the latest version (with API key)
     pub async fn from_mainnet_latest_with_api_key(api_key: &str) -> Result<Self> {
-        let base_url = AptosBaseUrl::Mainnet;
-        
-        let client = Client::new(base_url.to_url().clone());
-        
-        let ledger_info = client.get_ledger_information().await?
-            .into_inner();
-        let latest_version = ledger_info.version;
-        
-        println!("Connecting to mainnet at latest version: {} (with API key)", latest_version);
-        println!("Chain ID: {}", ledger_info.chain_id);
-        
-        let mut executor = FakeExecutor::from_remote_state_with_api_key(base_url, latest_version, api_key);
-        
-        let timestamp_secs = ledger_info.timestamp_usecs / 1_000_000;
-        executor.set_block_time(timestamp_secs);
-        println!("Set executor block time to: {}", timestamp_secs);
-        
-        Ok(Self {
-            executor,
-            sequence_numbers: HashMap::new(),
-            chain_id: aptos_types::chain_id::ChainId::new(ledger_info.chain_id),
-        })
+        Self::builder().network(AptosBaseUrl::Mainnet).api_key(api_key).build().await
+    }
+    
+    /// Ledger version the environment was forked at
+    pub fn fork_version(&self) -> u64 {
+        self.version
+    }
+    
+    /// Network the environment was forked from
+    pub fn network(&self) -> &AptosBaseUrl {
+        &self.network
+    }
+    
+    /// Directory where fetched remote state is persisted, if configured
+    pub fn cache_dir(&self) -> Option<&Path> {
+        self.cache_dir.as_deref()
     }
     
     /// Create a new account with balance
@@ -83,6 +168,7 @@ impl AptosBB {
         let account = Account::new();
         let executor_account = self.executor.new_account_at(*account.address());
         self.sequence_numbers.insert(*executor_account.address(), 0);
+        self.view_cache.invalidate();
         if let Some(account_resource) = self.read_account_resource_at_address(executor_account.address()) {
             println!("Account created at address: {}", executor_account.address());
             println!("   Sequence number: {}", account_resource.sequence_number());
@@ -92,8 +178,12 @@ impl AptosBB {
     }
     
     /// Create an account at a specific address
+    ///
+    /// Replaces whatever account state the address had, resetting its sequence number to 0.
+    /// To sign for an existing on-chain account, use `impersonate` instead.
     pub fn new_account_at(&mut self, addr: AccountAddress) -> Account {
         let account = self.executor.new_account_at(addr);
+        self.view_cache.invalidate();
         
         if let Some(_account_resource) = self.read_account_resource_at_address(&addr) {
             self.sequence_numbers.insert(addr, 0);
@@ -105,41 +195,48 @@ impl AptosBB {
     }
     
     /// Publish a Move package
-    pub fn publish_package(&mut self, account: &Account, path: &Path) -> TransactionStatus {
-        let build_options = BuildOptions {
-            with_srcs: true,
-            with_abis: true,
-            with_source_maps: true,
-            with_error_map: true,
-            ..BuildOptions::default()
-        };
-        
-        let package = match BuiltPackage::build(path.to_path_buf(), build_options) {
-            Ok(pkg) => pkg,
-            Err(e) => {
-                eprintln!("Failed to build package: {}", e);
-                use aptos_types::transaction::ExecutionStatus;
-                return TransactionStatus::Keep(ExecutionStatus::MiscellaneousError(Some(
-                    aptos_types::vm_status::StatusCode::ABORTED.into()
-                )));
-            }
-        };
+    ///
+    /// The named addresses the package's modules live under are remapped to `account`,
+    /// so `Move.toml` does not need to be edited for every new publisher.
+    pub fn publish_package(&mut self, account: &Account, path: &Path) -> Result<TransactionStatus> {
+        self.publish_package_with_options(account, path, package::PublishOptions::default())
+    }
+    
+    /// Publish a Move package with custom named addresses and compiler settings
+    ///
+    /// Packages too large for a single transaction are published in chunks (see `publish_chunked`).
+    pub fn publish_package_with_options(
+        &mut self,
+        account: &Account,
+        path: &Path,
+        options: package::PublishOptions,
+    ) -> Result<TransactionStatus> {
+        let package = package::prepare_package(path, *account.address(), &options)
+            .and_then(|dir| self.build_with_prefetch(dir.path(), options.build_options))
+            .map_err(AptosBBError::BuildError)?;
         
-        let payload = self.generate_module_payload(&package);
+        let payload = self.generate_module_payload(&package)?;
+        let size = bcs::to_bytes(&payload)
+            .map_err(|e| AptosBBError::encoding("publish payload", e))?
+            .len();
+        if options.chunked || size > package::MAX_PUBLISH_PAYLOAD_SIZE {
+            return self
+                .publish_chunked(account, &package)
+                .map_err(|e| AptosBBError::ExecutionError(format!("chunked publish: {:#}", e)));
+        }
         self.run_transaction(account, payload)
     }
     
     /// Generate a TransactionPayload for publishing modules
-    fn generate_module_payload(&self, package: &BuiltPackage) -> TransactionPayload {
+    fn generate_module_payload(&self, package: &BuiltPackage) -> Result<TransactionPayload> {
         let code = package.extract_code();
         let metadata = package
             .extract_metadata()
-            .expect("extracting package metadata must succeed");
+            .map_err(|e| AptosBBError::encoding("package metadata", e))?;
+        let metadata = bcs::to_bytes(&metadata)
+            .map_err(|e| AptosBBError::encoding("package metadata", e))?;
         
-        aptos_stdlib::code_publish_package_txn(
-            bcs::to_bytes(&metadata).expect("PackageMetadata has BCS"),
-            code,
-        )
+        Ok(aptos_stdlib::code_publish_package_txn(metadata, code))
     }
     
     /// Run an entry function
@@ -151,52 +248,194 @@ impl AptosBB {
         function: &str,
         ty_args: Vec<TypeTag>,
         args: Vec<Vec<u8>>,
-    ) -> TransactionStatus {
-        
-        let module_id = ModuleId::new(
-            module,
-            Identifier::new(module_name).unwrap()
-        );
+    ) -> Result<TransactionStatus> {
+        self.run_entry_function_multi(&[account], module, module_name, function, ty_args, args)
+    }
+    
+    /// Run an entry function taking several `&signer` parameters
+    ///
+    /// `signers` are in the order of the function's signer parameters; the first one sends
+    /// the transaction and the rest co-sign it as a multi-agent transaction.
+    pub fn run_entry_function_multi(
+        &mut self,
+        signers: &[&Account],
+        module: AccountAddress,
+        module_name: &str,
+        function: &str,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Vec<u8>>,
+    ) -> Result<TransactionStatus> {
+        let Some((sender, secondary_signers)) = signers.split_first() else {
+            return Err(AptosBBError::ExecutionError("at least one signer is required".to_string()));
+        };
+        let module_name = Identifier::new(module_name)
+            .map_err(|e| AptosBBError::encoding("module name", e))?;
+        let function = Identifier::new(function)
+            .map_err(|e| AptosBBError::encoding("function name", e))?;
         
         let payload = TransactionPayload::EntryFunction(EntryFunction::new(
-            module_id,
-            Identifier::new(function).unwrap(),
+            ModuleId::new(module, module_name),
+            function,
             ty_args,
             args,
         ));
         
-        self.run_transaction(account, payload)
+        let (status, _) = self.run_multi_agent_transaction_with_output(sender, secondary_signers, payload)?;
+        Ok(status)
     }
     
-    /// Run transaction with custom payload and return full output
-    pub fn run_transaction_with_output(
-        &mut self,
-        account: &Account,
-        payload: TransactionPayload,
-    ) -> (TransactionStatus, aptos_types::transaction::TransactionOutput) {
-        let sequence_number = *self.sequence_numbers.get(account.address()).unwrap_or(&0);
-        self.sequence_numbers.insert(*account.address(), sequence_number + 1);
-        
-        // Use a longer TTL to avoid expiration
-        let ttl = std::time::SystemTime::now()
+    /// Sign a transaction from `account` with the next sequence number and the gas defaults
+    pub(crate) fn sign_transaction(&mut self, account: &Account, payload: TransactionPayload) -> SignedTransaction {
+        self.sign_multi_agent_transaction(account, &[], payload)
+    }
+    
+    /// Sign a transaction that will only be simulated, leaving the sender's sequence number unused
+    pub(crate) fn sign_dry_run_transaction(&mut self, account: &Account, payload: TransactionPayload) -> SignedTransaction {
+        let sequence_number = self.sequence_numbers.get(account.address()).copied();
+        let txn = self.sign_transaction(account, payload);
+        match sequence_number {
+            Some(sequence_number) => self.sequence_numbers.insert(*account.address(), sequence_number),
+            None => self.sequence_numbers.remove(account.address()),
+        };
+        txn
+    }
+    
+    /// Consume `sender`'s next sequence number on the fork
+    pub(crate) fn next_sequence_number(&mut self, sender: AccountAddress) -> u64 {
+        let sequence_number = *self.sequence_numbers.get(&sender).unwrap_or(&0);
+        self.sequence_numbers.insert(sender, sequence_number + 1);
+        sequence_number
+    }
+    
+    /// Give back the sequence number of a transaction that left no trace on chain
+    ///
+    /// Only the most recently consumed number is released, so replays of older transactions
+    /// and orderless transactions (signed with `u64::MAX`) leave the counter alone.
+    pub(crate) fn release_sequence_number(&mut self, sender: AccountAddress, sequence_number: u64) {
+        let consumed_last = sequence_number
+            .checked_add(1)
+            .is_some_and(|next| self.sequence_numbers.get(&sender) == Some(&next));
+        if consumed_last {
+            self.sequence_numbers.insert(sender, sequence_number);
+        }
+    }
+    
+    /// Expiration timestamp of a transaction signed now, `expiration_secs` of the gas defaults ahead
+    pub(crate) fn expiration_timestamp(&self) -> u64 {
+        std::time::SystemTime::now()
             .duration_since(std::time::UNIX_EPOCH)
             .unwrap()
-            .as_secs() + 300; // 5 minutes from now
-            
-        let txn = account
+            .as_secs() + self.gas_defaults.expiration_secs
+    }
+    
+    /// Transaction from `account` with its next sequence number, the gas defaults and the
+    /// fork's chain ID, ready to sign
+    pub(crate) fn transaction_builder(&mut self, account: &Account, payload: TransactionPayload) -> TransactionBuilder {
+        let sequence_number = self.next_sequence_number(*account.address());
+        account
             .transaction()
             .payload(payload)
             .sequence_number(sequence_number)
-            .max_gas_amount(2_000_000)
-            .gas_unit_price(100)
-            .ttl(ttl)
+            .max_gas_amount(self.gas_defaults.max_gas_amount)
+            .gas_unit_price(self.gas_defaults.gas_unit_price)
+            .ttl(self.expiration_timestamp())
             .chain_id(self.chain_id)
-            .sign();
+    }
+    
+    /// Sign a transaction from `account`, co-signed by `secondary_signers` when there are any
+    ///
+    /// Only the sender's sequence number is consumed by a multi-agent transaction.
+    pub(crate) fn sign_multi_agent_transaction(
+        &mut self,
+        account: &Account,
+        secondary_signers: &[&Account],
+        payload: TransactionPayload,
+    ) -> SignedTransaction {
+        let builder = self.transaction_builder(account, payload);
+        if secondary_signers.is_empty() {
+            builder.sign()
+        } else {
+            builder
+                .secondary_signers(secondary_signers.iter().map(|signer| (*signer).clone()).collect())
+                .sign_multi_agent()
+        }
+    }
+    
+    /// Run transaction with custom payload and return full output
+    pub fn run_transaction_with_output(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+    ) -> Result<(TransactionStatus, aptos_types::transaction::TransactionOutput)> {
+        self.run_multi_agent_transaction_with_output(account, &[], payload)
+    }
+    
+    /// Run a transaction signed by `account` and `secondary_signers`, returning full output
+    ///
+    /// The sender is the first `&signer` of the entry function or script, followed by the
+    /// secondary signers in order.
+    pub fn run_multi_agent_transaction_with_output(
+        &mut self,
+        account: &Account,
+        secondary_signers: &[&Account],
+        payload: TransactionPayload,
+    ) -> Result<(TransactionStatus, aptos_types::transaction::TransactionOutput)> {
+        let txn = self.sign_multi_agent_transaction(account, secondary_signers, payload);
+        self.run_signed_transaction_with_output(txn)
+    }
+    
+    /// Execute an already signed transaction, notifying every `observer::TxnObserver`
+    ///
+    /// Fails only when the selected VM could not execute the transaction at all (e.g. the
+    /// mainnet release executor failed); the sender's sequence number is then left unused.
+    pub(crate) fn run_signed_transaction_with_output(
+        &mut self,
+        txn: SignedTransaction,
+    ) -> Result<(TransactionStatus, aptos_types::transaction::TransactionOutput)> {
+        let (sender, sequence_number) = (txn.sender(), txn.sequence_number());
+        if let Some(exceeded) = self.check_limits() {
+            // Refused before execution, so the sequence number stays unused
+            self.release_sequence_number(sender, sequence_number);
+            eprintln!("⏱️  Transaction refused: scenario {}", exceeded);
+            let status = TransactionStatus::Discard(aptos_types::vm_status::StatusCode::UNKNOWN_STATUS);
+            let output = aptos_types::transaction::TransactionOutput::new(
+                Default::default(),
+                vec![],
+                0,
+                status.clone(),
+                Default::default(),
+            );
+            return Ok((status, output));
+        }
         
-        let output = self.executor.execute_and_apply(txn);
+        let signed = txn.clone();
+        let executed = self.begin_observers(&txn).and_then(|(recorders, record_reads)| {
+            let executed = if record_reads {
+                self.execute_recording_reads(txn).map(|(output, reads)| (output, Some(reads)))
+            } else {
+                self.execute_on_selected_vm(txn).map(|output| (output, None))
+            };
+            executed.map(|(output, reads)| (recorders, output, reads))
+        });
+        let (recorders, output, reads) = match executed {
+            Ok(executed) => executed,
+            Err(e) => {
+                self.release_sequence_number(sender, sequence_number);
+                return Err(AptosBBError::ExecutionError(format!("{:#}", e)));
+            }
+        };
         let status = output.status().to_owned();
+        if !matches!(status, TransactionStatus::Keep(_)) {
+            // Discarded transactions don't bump the on-chain sequence number, so the sender's
+            // next transaction reuses it
+            self.release_sequence_number(sender, sequence_number);
+        }
+        self.module_cache.invalidate(output.write_set());
+        self.view_cache.invalidate();
+        let executed = observer::Executed { txn: &signed, output: &output, reads: reads.as_ref() };
+        self.finish_observers(recorders, &executed);
         
-        (status, output)
+        Ok((status, output))
     }
     
     /// Run transaction with custom payload
@@ -204,9 +443,71 @@ impl AptosBB {
         &mut self,
         account: &Account,
         payload: TransactionPayload,
-    ) -> TransactionStatus {
-        let (status, _) = self.run_transaction_with_output(account, payload);
-        status
+    ) -> Result<TransactionStatus> {
+        let (status, _) = self.run_transaction_with_output(account, payload)?;
+        Ok(status)
+    }
+    
+    /// Async variant of `run_entry_function` that keeps the tokio runtime responsive
+    ///
+    /// State the transaction reads is fetched with awaited requests (see
+    /// `run_transaction_with_output_async`).
+    pub async fn run_entry_function_async(
+        &mut self,
+        account: &Account,
+        module: AccountAddress,
+        module_name: &str,
+        function: &str,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Vec<u8>>,
+    ) -> Result<TransactionStatus> {
+        let module_name = Identifier::new(module_name)
+            .map_err(|e| AptosBBError::encoding("module name", e))?;
+        let function = Identifier::new(function)
+            .map_err(|e| AptosBBError::encoding("function name", e))?;
+        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(module, module_name),
+            function,
+            ty_args,
+            args,
+        ));
+        self.run_transaction_async(account, payload).await
+    }
+    
+    /// Async variant of `run_transaction_with_output` that keeps the tokio runtime responsive
+    ///
+    /// The transaction is first simulated against local state only, awaiting the fetch of
+    /// every key it reads that the fork does not hold yet, so executing it never blocks the
+    /// runtime on a network request, whatever its flavor.
+    pub async fn run_transaction_with_output_async(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+    ) -> Result<(TransactionStatus, aptos_types::transaction::TransactionOutput)> {
+        let txn = self.sign_dry_run_transaction(account, payload.clone());
+        let block = into_signature_verified_block(vec![Transaction::UserTransaction(txn)]);
+        self.fetch_reads(|bb, view| {
+            // Only the keys it read matter; failing on the missing ones is expected
+            let _ = bb.executor.execute_transaction_block_impl_with_state_view(
+                &block,
+                BlockExecutorConfigFromOnchain::new_no_block_limit(),
+                false,
+                view,
+            );
+        })
+        .await
+        .map_err(AptosBBError::RemoteStateError)?;
+        blocking::run_blocking(|| self.run_transaction_with_output(account, payload))
+    }
+    
+    /// Async variant of `run_transaction` that keeps the tokio runtime responsive
+    pub async fn run_transaction_async(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+    ) -> Result<TransactionStatus> {
+        let (status, _) = self.run_transaction_with_output_async(account, payload).await?;
+        Ok(status)
     }
     
     /// Read a resource from an address
@@ -233,9 +534,10 @@ impl AptosBB {
         }
         
         if let Ok(path) = AccessPath::resource_access_path(*addr, struct_tag) {
-            let key_bytes = bcs::to_bytes(&path).unwrap();
-            if let Ok(key) = StateKey::decode(&key_bytes) {
-                return self.executor.read_state_value(&key).is_some();
+            if let Ok(key_bytes) = bcs::to_bytes(&path) {
+                if let Ok(key) = StateKey::decode(&key_bytes) {
+                    return self.executor.read_state_value(&key).is_some();
+                }
             }
         }
         
@@ -255,14 +557,45 @@ impl AptosBB {
         
         let function_id = format!("{}::{}::{}", module, module_name, function);
         let member_id: MemberId = function_id.parse()
-            .map_err(|e| anyhow::anyhow!("Failed to parse function ID: {}", e))?;
+            .map_err(|e| AptosBBError::encoding("function ID", e))?;
         
         let output = self.executor.execute_view_function(member_id, ty_args, args);
         
-        match output.values {
-            Ok(results) => Ok(results),
-            Err(e) => Err(anyhow::anyhow!("View function execution failed: {:?}", e)),
-        }
+        output.values
+            .map_err(|e| AptosBBError::ExecutionError(format!("view function {} failed: {:?}", function_id, e)))
+    }
+    
+    /// Async variant of `execute_view_function` that keeps the tokio runtime responsive
+    ///
+    /// State the function reads is fetched with awaited requests first, like
+    /// `run_transaction_with_output_async` does.
+    pub async fn execute_view_function_async(
+        &mut self,
+        module: AccountAddress,
+        module_name: &str,
+        function: &str,
+        ty_args: Vec<TypeTag>,
+        args: Vec<Vec<u8>>,
+    ) -> Result<Vec<Vec<u8>>> {
+        let module_id = ModuleId::new(
+            module,
+            Identifier::new(module_name).map_err(|e| AptosBBError::encoding("module name", e))?,
+        );
+        let function_name = Identifier::new(function)
+            .map_err(|e| AptosBBError::encoding("function name", e))?;
+        self.fetch_reads(|_, view| {
+            aptos_vm::AptosVM::execute_view_function(
+                view,
+                module_id.clone(),
+                function_name.clone(),
+                ty_args.clone(),
+                args.clone(),
+                u64::MAX,
+            );
+        })
+        .await
+        .map_err(AptosBBError::RemoteStateError)?;
+        blocking::run_blocking(|| self.execute_view_function(module, module_name, function, ty_args, args))
     }
     
     /// Reads the resource `Value` for an account under the given address from
@@ -329,6 +662,72 @@ impl AptosBB {
         self.read_aptos_balance(account.address()) > 0
     }
     
+    /// Fetch a state value from the network at the fork version and load it into the executor
+    ///
+    /// Local state is authoritative: a key the fork already holds (and may have changed) is
+    /// returned as it is, and one deleted on the fork as absent, without a request. Goes through the persistent state cache when one
+    /// is configured, so later runs pinned to the same version can serve the key without any
+    /// network request.
+    pub fn fetch_state_value(&mut self, state_key: &aptos_types::state_store::state_key::StateKey) -> Result<Option<aptos_types::state_store::state_value::StateValue>> {
+        if let Some(value) = self.executor.data_store().inner().get(state_key) {
+            return Ok(Some(value.clone()));
+        }
+        if self.remote.is_deleted(state_key) {
+            return Ok(None);
+        }
+        let value = blocking::block_on(self.remote.get_state_value(state_key))
+            .map_err(AptosBBError::RemoteStateError)?;
+        if let Some(value) = &value {
+            self.load_missing([(state_key.clone(), value.clone())]);
+        }
+        Ok(value)
+    }
+    
+    /// Fetch many state values in batched requests and load them into the executor
+    ///
+    /// Keys the fork already holds are skipped. Returns the number of keys loaded.
+    pub fn fetch_state_values(&mut self, state_keys: &[aptos_types::state_store::state_key::StateKey]) -> Result<usize> {
+        let missing: Vec<_> = state_keys
+            .iter()
+            .filter(|state_key| !self.executor.data_store().inner().contains_key(*state_key))
+            .cloned()
+            .collect();
+        let values = blocking::block_on(self.remote.get_state_values(&missing))
+            .map_err(AptosBBError::RemoteStateError)?;
+        Ok(self.load_missing(values.into_iter().filter_map(|(state_key, value)| Some((state_key, value?)))))
+    }
+    
+    /// Load all resources and modules of an account into the executor
+    ///
+    /// Keys the fork already holds are left as they are. Returns the number of keys loaded.
+    pub fn fetch_account(&mut self, addr: AccountAddress) -> Result<usize> {
+        let (resources, modules) = blocking::block_on(async {
+            futures::try_join!(
+                self.remote.get_account_resources(addr),
+                self.remote.get_account_modules(addr),
+            )
+        })
+        .map_err(AptosBBError::RemoteStateError)?;
+        Ok(self.load_missing(resources.into_iter().chain(modules)))
+    }
+    
+    /// Load values read from the network into the executor, leaving every key the fork
+    /// already holds or has deleted untouched: local state, changed by transactions and
+    /// cheatcodes, is authoritative. Returns the number of keys loaded.
+    pub(crate) fn load_missing(
+        &mut self,
+        values: impl IntoIterator<Item = (aptos_types::state_store::state_key::StateKey, aptos_types::state_store::state_value::StateValue)>,
+    ) -> usize {
+        let mut loaded = 0;
+        for (state_key, value) in values {
+            if !self.executor.data_store().inner().contains_key(&state_key) && !self.remote.is_deleted(&state_key) {
+                self.executor.data_store_mut().set(state_key, value);
+                loaded += 1;
+            }
+        }
+        loaded
+    }
+    
     /// Read raw state value at a state key
     pub fn read_state_value(&self, state_key: &aptos_types::state_store::state_key::StateKey) -> Option<aptos_types::state_store::state_value::StateValue> {
         self.executor.read_state_value(state_key)
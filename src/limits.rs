@@ -0,0 +1,237 @@
+use crate::{
+    execution_limits::TxnLimit,
+    findings::{Category, Finding, Likelihood},
+    observer::TxnObserver,
+    scenario::Scenario,
+    storage::StorageUsage,
+    AptosBB,
+};
+use aptos_types::transaction::{SignedTransaction, TransactionOutput};
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// Bounds each scenario of a sweep must stay within
+///
+/// Every remote request counts against `remote_requests`: those AptosBB issues itself and
+/// the executor's lazy reads of untouched state alike, since both go through the rate
+/// limiter. The limiter also refuses requests past `wall_time`, so a scenario stuck reading
+/// remote state stops there; otherwise wall time and written bytes are checked before each
+/// transaction. A single transaction is bounded by the VM's gas meter, which
+/// `max_execution_gas` can tighten.
+#[derive(Clone, Copy, Debug)]
+pub struct ScenarioLimits {
+    pub wall_time: Option<Duration>,
+    pub remote_requests: Option<u64>,
+    /// Bytes of state values written by the scenario's transactions, from their write sets
+    pub written_bytes: Option<u64>,
+    /// Cap on every transaction's execution gas (`txn.max_execution_gas`, in internal gas
+    /// units), so the gas meter aborts runaway loops within a transaction
+    pub max_execution_gas: Option<u64>,
+}
+
+impl Default for ScenarioLimits {
+    fn default() -> Self {
+        Self {
+            wall_time: Some(Duration::from_secs(300)),
+            remote_requests: Some(5_000),
+            written_bytes: Some(512 * 1024 * 1024),
+            max_execution_gas: None,
+        }
+    }
+}
+
+/// The limit a scenario ran into
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LimitExceeded {
+    WallTime(Duration),
+    RemoteRequests(u64),
+    WrittenBytes(u64),
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WallTime(limit) => write!(f, "exceeded wall time limit of {:?}", limit),
+            Self::RemoteRequests(limit) => write!(f, "exceeded remote request limit of {}", limit),
+            Self::WrittenBytes(limit) => write!(f, "wrote more than {} bytes of state", limit),
+        }
+    }
+}
+
+/// How a scenario of a sweep ended
+#[derive(Clone, Debug)]
+pub enum ScenarioResult {
+    Passed,
+    Failed(String),
+    Panicked(String),
+    LimitExceeded(LimitExceeded),
+}
+
+/// Result and resource usage of one scenario of a sweep
+#[derive(Clone, Debug)]
+pub struct ScenarioOutcome {
+    pub name: String,
+    pub result: ScenarioResult,
+    pub elapsed: Duration,
+    pub remote_requests: u64,
+    pub written_bytes: u64,
+}
+
+impl ScenarioOutcome {
+    /// Finding for an exploit scenario that succeeded; a working PoC makes it certain
+    pub fn finding(&self, category: Category, funds_at_risk_usd: f64) -> Option<Finding> {
+        if !matches!(self.result, ScenarioResult::Passed) {
+            return None;
+        }
+        let finding = Finding::scored(&self.name, category, funds_at_risk_usd, Likelihood::Certain)
+            .evidence(format!("Scenario succeeded on the fork in {:.1?}", self.elapsed))
+            .reproduction(&self.name);
+        Some(finding)
+    }
+}
+
+/// Limits of the scenario currently running, with its usage so far
+pub(crate) struct ActiveLimits {
+    limits: ScenarioLimits,
+    started: Instant,
+    requests_at_start: u64,
+    written_bytes: u64,
+    /// The limit that made the pipeline refuse a transaction
+    exceeded: Option<LimitExceeded>,
+}
+
+pub(crate) fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+impl AptosBB {
+    /// Total size of the values held in the fork's local state
+    ///
+    /// Walks the whole local state, so it is meant for occasional measurements rather than
+    /// per-transaction checks.
+    pub(crate) fn local_state_bytes(&self) -> usize {
+        self.executor.data_store().inner().values().map(|value| value.bytes().len()).sum()
+    }
+
+    fn exceeded_limit(&self, active: &ActiveLimits) -> Option<LimitExceeded> {
+        let limits = &active.limits;
+        if let Some(limit) = limits.wall_time.filter(|limit| active.started.elapsed() > *limit) {
+            return Some(LimitExceeded::WallTime(limit));
+        }
+        let requests = self.remote.limiter().requests() - active.requests_at_start;
+        if let Some(limit) = limits.remote_requests.filter(|limit| requests >= *limit) {
+            return Some(LimitExceeded::RemoteRequests(limit));
+        }
+        limits.written_bytes.filter(|limit| active.written_bytes > *limit).map(LimitExceeded::WrittenBytes)
+    }
+
+    /// The limit the running scenario is past, if any; called before every transaction,
+    /// which the pipeline then refuses
+    pub(crate) fn check_limits(&mut self) -> Option<LimitExceeded> {
+        let exceeded = self.limits.as_ref().and_then(|active| self.exceeded_limit(active))?;
+        if let Some(active) = self.limits.as_mut() {
+            active.exceeded.get_or_insert(exceeded.clone());
+        }
+        Some(exceeded)
+    }
+
+    /// Count a transaction's writes against the running scenario's limits
+    pub(crate) fn record_limits(&mut self, output: &TransactionOutput) {
+        if let Some(active) = self.limits.as_mut() {
+            active.written_bytes += StorageUsage::of(output).bytes_written;
+        }
+    }
+
+    /// Run a scenario in its own snapshot within `limits`, never failing or panicking
+    ///
+    /// The fork is reverted afterwards, whatever the scenario did, so a sweep's scenarios
+    /// cannot affect each other. A scenario past a limit sees its remote reads refused and its
+    /// transactions discarded, and is reported as `LimitExceeded` however it handled that.
+    pub fn run_scenario_isolated(&mut self, scenario: &mut dyn Scenario, limits: ScenarioLimits) -> ScenarioOutcome {
+        let checkpoint = self.checkpoint();
+        let started = Instant::now();
+        let requests_at_start = self.remote.limiter().requests();
+        self.remote
+            .limiter()
+            .set_budget(limits.remote_requests.map(|limit| requests_at_start + limit));
+        self.remote.limiter().set_deadline(limits.wall_time.map(|limit| started + limit));
+        self.limits = Some(ActiveLimits { limits, started, requests_at_start, written_bytes: 0, exceeded: None });
+
+        let result = match limits.max_execution_gas.map(|gas| self.cap_execution_gas(gas)) {
+            Some(Err(e)) => Ok(Err(e.context("Failed to cap execution gas"))),
+            _ => std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| self.run_scenario(scenario))),
+        };
+        let elapsed = started.elapsed();
+        let active = self.limits.take().expect("limits are set for the scenario");
+        let remote_requests = self.remote.limiter().requests() - requests_at_start;
+
+        let exceeded = active.exceeded.clone();
+        let result = match result {
+            Ok(Ok(())) => ScenarioResult::Passed,
+            // Refused requests and transactions surface as the scenario's error
+            Ok(Err(e)) => match exceeded.or_else(|| self.exceeded_limit(&active)) {
+                Some(exceeded) => ScenarioResult::LimitExceeded(exceeded),
+                None => ScenarioResult::Failed(format!("{:#}", e)),
+            },
+            Err(panic) => match exceeded {
+                Some(exceeded) => ScenarioResult::LimitExceeded(exceeded),
+                None => ScenarioResult::Panicked(panic_message(panic.as_ref())),
+            },
+        };
+        self.remote.limiter().set_budget(None);
+        self.remote.limiter().set_deadline(None);
+        self.revert(&checkpoint);
+
+        ScenarioOutcome {
+            name: scenario.name().to_string(),
+            result,
+            elapsed,
+            remote_requests,
+            written_bytes: active.written_bytes,
+        }
+    }
+
+    /// Lower `txn.max_execution_gas` to `gas` unless it is already lower; the scenario's
+    /// revert restores the gas schedule
+    fn cap_execution_gas(&mut self, gas: u64) -> anyhow::Result<()> {
+        if self.txn_limit(TxnLimit::MaxExecutionGas)? > gas {
+            self.set_txn_limit(TxnLimit::MaxExecutionGas, gas)?;
+        }
+        Ok(())
+    }
+
+    /// Run every scenario isolated from the others, reporting which failed or exceeded limits
+    pub fn run_scenarios(&mut self, scenarios: &mut [Box<dyn Scenario>], limits: ScenarioLimits) -> Vec<ScenarioOutcome> {
+        let mut outcomes = Vec::with_capacity(scenarios.len());
+        for scenario in scenarios.iter_mut() {
+            let outcome = self.run_scenario_isolated(scenario.as_mut(), limits);
+            let verdict = match &outcome.result {
+                ScenarioResult::Passed => "✅ passed".to_string(),
+                ScenarioResult::Failed(e) => format!("❌ failed: {}", e),
+                ScenarioResult::Panicked(message) => format!("💥 panicked: {}", message),
+                ScenarioResult::LimitExceeded(exceeded) => format!("⏱️  {}", exceeded),
+            };
+            println!(
+                "{} {} ({:.1?}, {} remote requests, {} bytes written)",
+                outcome.name, verdict, outcome.elapsed, outcome.remote_requests, outcome.written_bytes
+            );
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+}
+
+/// Counts each transaction's writes against the running scenario's limits
+pub(crate) struct LimitObserver;
+
+impl TxnObserver for LimitObserver {
+    fn applied(&self, bb: &mut AptosBB, _txn: &SignedTransaction, output: &TransactionOutput) {
+        bb.record_limits(output);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{blocking, AptosBB};
+use anyhow::{anyhow, Result};
+use futures::future::try_join_all;
+use aptos_crypto::{bls12381, x25519, PrivateKey, Uniform};
+use aptos_types::{
+    account_address::AccountAddress,
+    account_config::NewEpochEvent,
+    chain_id::ChainId,
+    contract_event::ContractEvent,
+    network_address::NetworkAddress,
+    on_chain_config::new_epoch_event_key,
+    transaction::{ChangeSet, Transaction, WriteSetPayload},
+    write_set::{WriteOp, WriteSetMut},
+};
+use move_core_types::{
+    language_storage::{StructTag, TypeTag},
+    move_resource::MoveStructType,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Validator network port of the exported single-node network
+const VALIDATOR_PORT: u16 = 6180;
+
+/// Keys of the single validator the exported network runs with, in the node's identity format
+#[derive(Serialize)]
+struct ValidatorIdentity {
+    account_address: AccountAddress,
+    consensus_private_key: bls12381::PrivateKey,
+    network_private_key: x25519::PrivateKey,
+}
+
+/// Files written by `export_localnet`
+#[derive(Clone, Debug)]
+pub struct LocalnetExport {
+    /// BCS genesis transaction holding the fork's state as a direct write set
+    pub genesis: PathBuf,
+    /// Validator identity (consensus and network keys) for the node config
+    pub identity: PathBuf,
+    pub validator: AccountAddress,
+    pub key_count: usize,
+}
+
+fn struct_tag(tag: &str) -> Result<StructTag> {
+    StructTag::from_str(tag).map_err(|e| anyhow!("Invalid struct tag {}: {}", tag, e))
+}
+
+impl AptosBB {
+    /// Fetch the accounts' resources and modules, keeping every key the fork already holds
+    /// so local modifications are exported as they are
+    fn fetch_missing(&mut self, addrs: &[AccountAddress]) -> Result<usize> {
+        let remote = &self.remote;
+        let fetched = blocking::block_on(try_join_all(addrs.iter().map(|addr| async move {
+            let (resources, modules) = futures::try_join!(
+                remote.get_account_resources(*addr),
+                remote.get_account_modules(*addr),
+            )?;
+            Ok::<_, anyhow::Error>(resources.into_iter().chain(modules).collect::<Vec<_>>())
+        })))?;
+
+        Ok(self.load_missing(fetched.into_iter().flatten()))
+    }
+
+    /// Fetch the state the local validator runs on: the first active validator's account,
+    /// `StakePool` and `ValidatorConfig`, and the pool's operator and voter accounts
+    fn fetch_validator_accounts(&mut self) -> Result<()> {
+        let set = self
+            .read_resource_json(&AccountAddress::ONE, &struct_tag("0x1::stake::ValidatorSet")?)?
+            .ok_or_else(|| anyhow!("ValidatorSet not found"))?;
+        let addr = set["active_validators"][0]["addr"]
+            .as_str()
+            .ok_or_else(|| anyhow!("ValidatorSet has no active validators"))?;
+        let addr = AccountAddress::from_hex_literal(addr)?;
+        self.fetch_missing(&[addr])?;
+
+        let pool = self
+            .read_resource_json(&addr, &struct_tag("0x1::stake::StakePool")?)?
+            .ok_or_else(|| anyhow!("StakePool of validator {} not found", addr))?;
+        if self.read_resource_json(&addr, &struct_tag("0x1::stake::ValidatorConfig")?)?.is_none() {
+            return Err(anyhow!("ValidatorConfig of validator {} not found", addr));
+        }
+        let roles = ["operator_address", "delegated_voter"]
+            .iter()
+            .filter_map(|role| pool[role].as_str())
+            .map(AccountAddress::from_hex_literal)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        self.fetch_missing(&roles)?;
+        Ok(())
+    }
+
+    /// Replace the validator set by one validator, reusing the first active validator's
+    /// address and stake but with freshly generated keys listening on localhost
+    fn install_local_validator(&mut self, consensus_key: &bls12381::PrivateKey, network_key: &x25519::PrivateKey) -> Result<AccountAddress> {
+        let set_tag = struct_tag("0x1::stake::ValidatorSet")?;
+        let mut set = self
+            .read_resource_json(&AccountAddress::ONE, &set_tag)?
+            .ok_or_else(|| anyhow!("ValidatorSet not found"))?;
+        let mut validator = set["active_validators"]
+            .get(0)
+            .cloned()
+            .ok_or_else(|| anyhow!("ValidatorSet has no active validators"))?;
+        let addr = AccountAddress::from_hex_literal(
+            validator["addr"].as_str().ok_or_else(|| anyhow!("Malformed ValidatorSet"))?,
+        )?;
+
+        let network_address = NetworkAddress::from_str(&format!("/ip4/127.0.0.1/tcp/{}", VALIDATOR_PORT))?
+            .append_prod_protos(network_key.public_key(), 0);
+        let config = json!({
+            "consensus_pubkey": format!("0x{}", hex::encode(consensus_key.public_key().to_bytes())),
+            "network_addresses": format!("0x{}", hex::encode(bcs::to_bytes(&vec![network_address])?)),
+            "fullnode_addresses": "0x00",
+            "validator_index": "0",
+        });
+        validator["config"] = config.clone();
+        set["total_voting_power"] = validator["voting_power"].clone();
+        set["total_joining_power"] = json!("0");
+        set["active_validators"] = json!([validator]);
+        set["pending_active"] = json!([]);
+        set["pending_inactive"] = json!([]);
+        self.write_resource_json(&AccountAddress::ONE, &set_tag, &set)?;
+        self.write_resource_json(&addr, &struct_tag("0x1::stake::ValidatorConfig")?, &config)?;
+        Ok(addr)
+    }
+
+    /// Materialize the fork's state (with every local modification) for a local network
+    ///
+    /// Writes `genesis.blob`, a genesis transaction whose direct write set is the fork's
+    /// local state, and `validator-identity.yaml`. The validator set is replaced by a
+    /// single validator with fresh keys on `127.0.0.1:6180` and the chain ID is changed to
+    /// `chain_id`, so transactions signed for the forked network cannot be replayed.
+    ///
+    /// Only state the fork holds locally is exported: the framework accounts and the kept
+    /// validator's account, `StakePool`, `ValidatorConfig`, operator and voter are fetched
+    /// first (without overwriting local modifications), but anything else the local network
+    /// should see must have been touched (or prefetched) on the fork. Bootstrap a DB with
+    /// `aptos-db-bootstrapper <db> --genesis-txn-file genesis.blob --commit` and point a
+    /// validator config at the printed waypoint and the identity file.
+    pub fn export_localnet(&mut self, dir: &Path, chain_id: ChainId) -> Result<LocalnetExport> {
+        self.fetch_missing(&[AccountAddress::ONE, AccountAddress::THREE, AccountAddress::FOUR])?;
+        self.fetch_validator_accounts()?;
+
+        let mut rng = rand::rngs::OsRng;
+        let consensus_key = bls12381::PrivateKey::generate(&mut rng);
+        let network_key = x25519::PrivateKey::generate(&mut rng);
+
+        // Changes are made on a snapshot so the fork itself keeps the original network
+        let (validator, state, epoch) = self.with_snapshot(|bb| -> Result<_> {
+            let validator = bb.install_local_validator(&consensus_key, &network_key)?;
+            bb.write_resource_json(&AccountAddress::ONE, &struct_tag("0x1::chain_id::ChainId")?, &json!({ "id": chain_id.id() }))?;
+            let epoch = bb
+                .read_resource_json(&AccountAddress::ONE, &struct_tag("0x1::reconfiguration::Configuration")?)?
+                .and_then(|config| config["epoch"].as_str().and_then(|epoch| epoch.parse::<u64>().ok()))
+                .ok_or_else(|| anyhow!("Reconfiguration state not found"))?;
+            Ok((validator, bb.local_state(), epoch))
+        })?;
+
+        let key_count = state.len();
+        let write_set = WriteSetMut::new(
+            state
+                .into_iter()
+                .map(|(key, value)| (key, WriteOp::legacy_creation(value.bytes().clone()))),
+        )
+        .freeze()?;
+        let new_epoch = ContractEvent::new_v1(
+            new_epoch_event_key(),
+            0,
+            TypeTag::Struct(Box::new(NewEpochEvent::struct_tag())),
+            bcs::to_bytes(&NewEpochEvent::new(epoch))?,
+        )?;
+        let genesis = Transaction::GenesisTransaction(WriteSetPayload::Direct(ChangeSet::new(write_set, vec![new_epoch])));
+
+        std::fs::create_dir_all(dir)?;
+        let genesis_path = dir.join("genesis.blob");
+        std::fs::write(&genesis_path, bcs::to_bytes(&genesis)?)?;
+        let identity_path = dir.join("validator-identity.yaml");
+        let identity = ValidatorIdentity {
+            account_address: validator,
+            consensus_private_key: consensus_key,
+            network_private_key: network_key,
+        };
+        std::fs::write(&identity_path, serde_yaml::to_string(&identity)?)?;
+
+        let manifest: Value = json!({
+            "forked_network": self.network.to_url().to_string(),
+            "fork_version": self.version,
+            "chain_id": chain_id.id(),
+            "validator": validator.to_hex_literal(),
+            "key_count": key_count,
+        });
+        std::fs::write(dir.join("export.json"), serde_json::to_string_pretty(&manifest)?)?;
+
+        println!(
+            "Exported {} state keys to {} (validator {}, chain ID {})",
+            key_count,
+            dir.display(),
+            validator,
+            chain_id
+        );
+        Ok(LocalnetExport { genesis: genesis_path, identity: identity_path, validator, key_count })
+    }
+}
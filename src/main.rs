@@ -1,13 +1,31 @@
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use futures::StreamExt;
 
-use aptosbb::AptosBB;
-use aptosbb::pentest::run_pentest;
+use aptosbb::{
+    blocking::run_blocking, builder::{AptosBBBuilder, ForkMode}, entry::parse_module_id, pricing::PriceTable, snapshot::Snapshot, AptosBB, EntryCall,
+};
+use aptos_types::chain_id::ChainId;
+use aptosbb::control::ControlPlane;
+use aptosbb::history::{ExperimentDb, ExperimentQuery};
+use aptosbb::monitor::Monitor;
+use aptosbb::pentest::{run_pentest, run_pentest_on};
+use aptos_rest_client::AptosBaseUrl;
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[clap(name = "aptosbb")]
 #[clap(about = "Aptos Bug Bounty pentesting tool", long_about = None)]
 struct Cli {
+    /// Guarantee nothing is ever broadcast to a real network, even by explicit broadcast helpers
+    #[clap(long, global = true)]
+    no_broadcast: bool,
+    /// Do not print a decoded summary after each executed transaction
+    #[clap(long, global = true)]
+    no_summary: bool,
+    /// Balance changes listed in each transaction summary
+    #[clap(long, global = true, default_value_t = 5)]
+    summary_top: usize,
     #[clap(subcommand)]
     command: Commands,
 }
@@ -16,23 +34,208 @@ struct Cli {
 enum Commands {
     Default, // Use default mainnet connection (rate limited)
     Api,     // Use API key (https://geomi.dev/) from APTOSBB_KEY environment variable for higher rate limits
+    /// Tail mainnet for package publishes/upgrades and scan each one on a fork
+    Monitor {
+        /// Ledger version to start from (defaults to the latest version)
+        #[clap(long)]
+        from_version: Option<u64>,
+        /// Seconds between polls once caught up
+        #[clap(long, default_value_t = 5)]
+        poll_secs: u64,
+    },
+    /// Run the pentest and print decoded events emitted on the fork
+    Events {
+        /// Print events as each transaction executes instead of after the run
+        #[clap(long)]
+        follow: bool,
+        /// Only show events whose type starts with this prefix (e.g. 0x1::coin)
+        #[clap(long)]
+        filter: Option<String>,
+    },
+    /// Run the pentest and render a report of every transaction it executed
+    Report {
+        #[clap(subcommand)]
+        format: ReportFormat,
+    },
+    /// Re-execute the mainnet block containing a version and compare outputs with the chain
+    ValidateBlock {
+        version: u64,
+    },
+    /// Create an exploit project: Move package with target stubs, Rust scenario and pinned fork config
+    Init {
+        /// Project name, also the directory it is created in
+        name: String,
+        /// Module under attack, e.g. 0xabc::vault
+        #[clap(long)]
+        target: String,
+        /// Ledger version to pin the fork to (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+    },
+    /// Generate a Move exploit package with typed wrappers for every public function of a module
+    GenExploit {
+        /// Module to wrap, e.g. 0xabc::pool
+        target: String,
+        /// Directory to write the package into
+        #[clap(long, default_value = "exploit")]
+        out: PathBuf,
+        /// Ledger version to read the module at (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+    },
+    /// Rebuild, republish and re-run an exploit package on every save
+    Watch {
+        /// Move package to watch
+        #[clap(long)]
+        package: PathBuf,
+        /// Entry function of the package to run as the attacker, e.g. vault_drain::run
+        #[clap(long)]
+        scenario: String,
+        /// Ledger version to fork at (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+    },
+    /// Export the fork's state as a genesis a local single-node network can boot from
+    ExportLocalnet {
+        /// Directory to write genesis.blob and the validator identity into
+        dir: PathBuf,
+        /// Ledger version to fork at (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+        /// Snapshot of a modified fork (see save_snapshot) to export instead of pristine state
+        #[clap(long)]
+        snapshot: Option<PathBuf>,
+    },
+    /// Run the pentest and log every state key it pulled into the fork
+    Record {
+        /// File to write the fetch log to
+        log: PathBuf,
+        /// Ledger version to pin the fork to (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+        /// Persistent state cache, which makes reruns from the log network-free
+        #[clap(long)]
+        cache: Option<PathBuf>,
+    },
+    /// Rerun the pentest at a fetch log's version, pre-seeded with exactly the keys it used
+    ReplayFromLog {
+        log: PathBuf,
+        /// Persistent state cache the recording run used
+        #[clap(long)]
+        cache: Option<PathBuf>,
+    },
+    /// Decode hex-encoded BCS bytes of a Move type into JSON, using mainnet layouts
+    ///
+    /// Only the JSON is written to stdout, so the output can be piped into `jq`.
+    Decode {
+        /// Move type of the value, e.g. 0x1::coin::CoinStore<0x1::aptos_coin::AptosCoin>
+        type_tag: String,
+        /// BCS bytes as hex, with or without 0x prefix
+        bytes: String,
+        /// Ledger version to resolve layouts at (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+    },
+    /// Serve a fork over HTTP with a JSON-RPC control namespace (snapshot, revert, balances, time)
+    Serve {
+        /// Address to listen on
+        #[clap(long, default_value = "127.0.0.1:8545")]
+        listen: std::net::SocketAddr,
+        /// Ledger version to fork at (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+    },
+    /// Query the experiment history recorded by `log_experiments`
+    History {
+        /// Experiment database
+        #[clap(long, default_value = aptosbb::history::DEFAULT_HISTORY_DB)]
+        db: PathBuf,
+        /// Only runs of scenarios whose name contains this
+        #[clap(long)]
+        scenario: Option<String>,
+        /// Only runs on this network (mainnet, testnet, devnet or a URL)
+        #[clap(long)]
+        network: Option<String>,
+        /// Only failed runs
+        #[clap(long)]
+        failed: bool,
+        /// Only runs that reported findings
+        #[clap(long)]
+        findings: bool,
+        /// Most recent runs to list
+        #[clap(long, default_value_t = 20)]
+        limit: usize,
+        /// Print one experiment in full, as JSON
+        #[clap(long)]
+        show: Option<i64>,
+    },
+    /// Generate Rust types for a module's structs, for use with `read_resource::<T>`
+    Codegen {
+        /// Module whose structs to generate, e.g. 0xabc::pool
+        target: String,
+        /// File to write the types to (defaults to <module>.rs)
+        #[clap(long)]
+        out: Option<PathBuf>,
+        /// Ledger version to read the module at (defaults to the latest version)
+        #[clap(long)]
+        version: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReportFormat {
+    /// Interactive HTML report with call traces, events, state diffs and profit summary
+    Html {
+        /// Directory to write index.html and session.json into
+        out_dir: PathBuf,
+        /// TOML price table for USD values (defaults to Pyth prices at the fork version)
+        #[clap(long)]
+        prices: Option<PathBuf>,
+    },
+}
+
+/// `APTOSBB_KEY`, when it is set to a non-empty key
+fn api_key_from_env() -> Option<String> {
+    let api_key = std::env::var("APTOSBB_KEY").ok().filter(|key| !key.is_empty())?;
+    eprintln!("✅ Using API key from APTOSBB_KEY environment variable");
+    Some(api_key)
+}
+
+/// Fork for a subcommand: at `version` (or the latest), authenticated with `APTOSBB_KEY` when
+/// it is set, printing a summary after each transaction when `summaries` is set
+async fn build_from_cli(mut builder: AptosBBBuilder, version: Option<u64>, summaries: Option<usize>) -> Result<AptosBB> {
+    if let Some(version) = version {
+        builder = builder.version(version);
+    }
+    if let Some(api_key) = api_key_from_env() {
+        builder = builder.api_key(api_key);
+    }
+    let mut aptosbb = builder.build().await?;
+    aptosbb.set_txn_summaries(summaries);
+    Ok(aptosbb)
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    if cli.no_broadcast {
+        aptosbb::sandbox::enable_no_broadcast();
+        eprintln!("🔒 No-broadcast mode: network access is read-only");
+    }
+    let summaries = (!cli.no_summary).then_some(cli.summary_top);
     
     match cli.command {
         Commands::Default => {
             println!("🚀 Starting AptosBB in default mode (rate limited)...");
             println!("⚠️  Using anonymous connection - may hit rate limits");
             
-            let aptosbb = AptosBB::from_mainnet_latest().await?;
+            let mut aptosbb = AptosBB::from_mainnet_latest().await?;
+            aptosbb.set_txn_summaries(summaries);
             println!("✅ Connected to mainnet successfully!");
             
             // Run pentest with remote state
             println!("\n🧪 Running pentest with remote mainnet state...\n");
-            run_pentest(aptosbb)?;
+            run_blocking(|| run_pentest(aptosbb))?;
             
             println!("\n✅ Complete!");
         }
@@ -50,15 +253,219 @@ async fn main() -> Result<()> {
             
             println!("✅ Using API key from APTOSBB_KEY environment variable");
             
-            let aptosbb = AptosBB::from_mainnet_latest_with_api_key(&api_key).await?;
+            let mut aptosbb = AptosBB::from_mainnet_latest_with_api_key(&api_key).await?;
+            aptosbb.set_txn_summaries(summaries);
             println!("✅ Connected to mainnet successfully!");
             
             // Run pentest with remote state
             println!("\n🧪 Running pentest with remote mainnet state...\n");
-            run_pentest(aptosbb)?;
+            run_blocking(|| run_pentest(aptosbb))?;
+            
+            println!("\n✅ Complete!");
+        }
+        
+        Commands::Monitor { from_version, poll_secs } => {
+            println!("🚀 Starting AptosBB in monitor mode...");
+            
+            let mut monitor = Monitor::new(AptosBaseUrl::Mainnet)
+                .poll_interval(std::time::Duration::from_secs(poll_secs));
+            if let Some(api_key) = api_key_from_env() {
+                monitor = monitor.api_key(api_key);
+            }
+            
+            monitor.run(from_version).await?;
+        }
+        
+        Commands::Events { follow, filter } => {
+            println!("🚀 Starting AptosBB in event tail mode...");
+            
+            let mut aptosbb = build_from_cli(AptosBB::builder(), None, summaries).await?;
+            println!("✅ Connected to mainnet successfully!");
+            
+            let mut events = aptosbb.event_stream(filter.as_deref());
+            
+            println!("\n🧪 Running pentest with remote mainnet state...\n");
+            if follow {
+                // The stream ends once the pentest drops the environment
+                let printer = tokio::spawn(async move {
+                    while let Some(event) = events.next().await {
+                        println!("📣 {}", event);
+                    }
+                });
+                run_blocking(|| run_pentest(aptosbb))?;
+                printer.await?;
+            } else {
+                run_blocking(|| run_pentest(aptosbb))?;
+                println!("\n📣 Emitted events:");
+                while let Ok(Some(event)) = events.try_next() {
+                    println!("  {}", event);
+                }
+            }
             
             println!("\n✅ Complete!");
         }
+        
+        Commands::Report { format: ReportFormat::Html { out_dir, prices } } => {
+            println!("🚀 Starting AptosBB in report mode...");
+            
+            let mut aptosbb = build_from_cli(AptosBB::builder(), None, summaries).await?;
+            println!("✅ Connected to mainnet successfully!");
+            
+            // Priced at session start, before the pentest can move any feed
+            let prices = match prices {
+                Some(path) => Some(PriceTable::load(&path)?),
+                None => match run_blocking(|| aptosbb.pyth_prices(&[])) {
+                    Ok(prices) => Some(prices),
+                    Err(e) => {
+                        println!("⚠️  No USD prices: {}", e);
+                        None
+                    }
+                },
+            };
+            
+            let session = aptosbb.record_session(&[]);
+            if let Some(prices) = prices {
+                session.lock().unwrap().set_prices(prices);
+            }
+            
+            println!("\n🧪 Running pentest with remote mainnet state...\n");
+            run_blocking(|| run_pentest(aptosbb))?;
+            
+            let index = session.lock().unwrap().write_html(&out_dir)?;
+            println!("\n📄 Report written to {}", index.display());
+        }
+        
+        Commands::Record { log, version, cache } => {
+            println!("🚀 Starting AptosBB in record mode...");
+            
+            let mut builder = AptosBB::builder();
+            if let Some(cache) = cache {
+                builder = builder.cache(cache);
+            }
+            let mut aptosbb = build_from_cli(builder, version, summaries).await?;
+            
+            println!("\n🧪 Running pentest with remote mainnet state...\n");
+            run_blocking(|| run_pentest_on(&mut aptosbb))?;
+            aptosbb.write_fetch_log(&log)?;
+        }
+        
+        Commands::ReplayFromLog { log, cache } => {
+            println!("🚀 Starting AptosBB in replay mode...");
+            
+            let mut builder = AptosBB::builder().fork_mode(ForkMode::Replay { log });
+            if let Some(cache) = cache {
+                builder = builder.cache(cache);
+            }
+            let aptosbb = build_from_cli(builder, None, summaries).await?;
+            
+            println!("\n🧪 Running pentest from the fetch log...\n");
+            run_blocking(|| run_pentest(aptosbb))?;
+        }
+        
+        Commands::ValidateBlock { version } => {
+            println!("🚀 Starting AptosBB in block validation mode...");
+            
+            let aptosbb = build_from_cli(AptosBB::builder(), Some(version), None).await?;
+            
+            let validation = run_blocking(|| aptosbb.validate_block(version))?;
+            validation.ensure_valid()?;
+            println!("\n✅ Block {} matches mainnet", validation.block_height);
+        }
+        
+        Commands::Init { name, target, version } => {
+            let target = parse_module_id(&target)?;
+            
+            let mut aptosbb = build_from_cli(AptosBB::builder(), version, None).await?;
+            
+            run_blocking(|| aptosbb.init_project(&PathBuf::from(&name), &name, &target))?;
+            println!("\n✅ Run it with: cd {} && cargo run", name);
+        }
+        
+        Commands::GenExploit { target, out, version } => {
+            let target = parse_module_id(&target)?;
+            
+            let mut aptosbb = build_from_cli(AptosBB::builder(), version, None).await?;
+            
+            let name = format!("{}_exploit", target.name());
+            run_blocking(|| aptosbb.generate_exploit_package(&target, &out, &name))?;
+        }
+        
+        Commands::Watch { package, scenario, version } => {
+            println!("🚀 Starting AptosBB in watch mode...");
+            
+            let mut aptosbb = build_from_cli(AptosBB::builder(), version, summaries).await?;
+            
+            run_blocking(|| {
+                aptosbb.watch_package(&package, std::time::Duration::from_millis(500), |bb, attacker| {
+                    let function = format!("{}::{}", attacker.address().to_hex_literal(), scenario);
+                    let status = bb.call(attacker, EntryCall::new(&function))?;
+                    println!("Scenario status: {:?}", status);
+                    anyhow::ensure!(status.status().is_ok_and(|s| s.is_success()), "{} failed", function);
+                    Ok(())
+                })
+            })?;
+        }
+        
+        Commands::ExportLocalnet { dir, version, snapshot } => {
+            println!("🚀 Starting AptosBB in localnet export mode...");
+            
+            // A snapshot only loads into a fork at its own version
+            let version = match &snapshot {
+                Some(path) => Some(Snapshot::read_metadata(path)?.version),
+                None => version,
+            };
+            let mut aptosbb = build_from_cli(AptosBB::builder(), version, None).await?;
+            if let Some(snapshot) = &snapshot {
+                aptosbb.load_snapshot(snapshot)?;
+            }
+            
+            let export = run_blocking(|| aptosbb.export_localnet(&dir, ChainId::test()))?;
+            println!("\n✅ Bootstrap with: aptos-db-bootstrapper <db> --genesis-txn-file {} --commit", export.genesis.display());
+        }
+        
+        Commands::Decode { type_tag, bytes, version } => {
+            let bytes = hex::decode(bytes.trim_start_matches("0x"))?;
+            
+            let aptosbb = build_from_cli(AptosBB::builder(), version, None).await?;
+            
+            let json = run_blocking(|| aptosbb.bcs_to_json(&type_tag, &bytes))?;
+            println!("{}", serde_json::to_string_pretty(&json)?);
+        }
+        
+        Commands::Serve { listen, version } => {
+            println!("🚀 Starting AptosBB in server mode...");
+            
+            let aptosbb = build_from_cli(AptosBB::builder(), version, summaries).await?;
+            
+            ControlPlane::new(aptosbb).serve(listen).await?;
+        }
+        
+        Commands::Codegen { target, out, version } => {
+            let target = parse_module_id(&target)?;
+            let out = out.unwrap_or_else(|| PathBuf::from(format!("{}.rs", target.name())));
+            
+            let mut aptosbb = build_from_cli(AptosBB::builder(), version, None).await?;
+            
+            let source = run_blocking(|| aptosbb.generate_rust_types(&target))?;
+            std::fs::write(&out, source)?;
+            println!("\n✅ Wrote {}", out.display());
+        }
+        
+        Commands::History { db, scenario, network, failed, findings, limit, show } => {
+            let db = ExperimentDb::open_existing(&db)?;
+            if let Some(id) = show {
+                let experiment = db.get(id)?.ok_or_else(|| anyhow::anyhow!("No experiment #{}", id))?;
+                println!("{}", serde_json::to_string_pretty(&experiment)?);
+                return Ok(());
+            }
+            
+            let query = ExperimentQuery { scenario, network, failed_only: failed, with_findings_only: findings, limit };
+            let experiments = db.query(&query)?;
+            for experiment in &experiments {
+                println!("{}", experiment);
+            }
+            println!("\n{} experiments", experiments.len());
+        }
     }
     
     Ok(())
@@ -0,0 +1,107 @@
+use crate::AptosBB;
+use anyhow::{anyhow, Result};
+use aptos_types::{
+    access_path::Path,
+    state_store::state_key::{inner::StateKeyInner, StateKey},
+    write_set::WriteSet,
+};
+use move_binary_format::CompiledModule;
+use move_core_types::language_storage::ModuleId;
+use std::{collections::HashMap, sync::Arc};
+
+/// Deserialized and verified modules kept across transactions and snapshots
+///
+/// Serves AptosBB's own module reads only (`get_module`, call decoding, analyses). The VM
+/// loads code through its own loader, which takes module bytes from the state view and
+/// cannot be handed modules verified here, so transactions still pay loading and
+/// verification themselves: caching for the VM is not provided. Entries are dropped whenever
+/// a transaction executed through AptosBB writes to the module, so upgrades on the fork are
+/// always observed.
+#[derive(Default)]
+pub struct ModuleCache {
+    modules: HashMap<ModuleId, Arc<CompiledModule>>,
+}
+
+/// Module id addressed by a state key, if it is a code key
+pub fn module_id_of(state_key: &StateKey) -> Option<ModuleId> {
+    match state_key.inner() {
+        StateKeyInner::AccessPath(access_path) => match access_path.get_path() {
+            Path::Code(module_id) => Some(module_id),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+impl ModuleCache {
+    pub fn get(&self, module_id: &ModuleId) -> Option<Arc<CompiledModule>> {
+        self.modules.get(module_id).cloned()
+    }
+
+    pub fn insert(&mut self, module: CompiledModule) -> Arc<CompiledModule> {
+        let module = Arc::new(module);
+        self.modules.insert(module.self_id(), module.clone());
+        module
+    }
+
+    /// Drop cached modules that the write set republished
+    pub fn invalidate(&mut self, write_set: &WriteSet) {
+        for (state_key, _) in write_set.iter() {
+            if let Some(module_id) = module_id_of(state_key) {
+                self.modules.remove(&module_id);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, module_id: &ModuleId) {
+        self.modules.remove(module_id);
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.is_empty()
+    }
+}
+
+impl AptosBB {
+    /// Get a module from the fork, deserializing and verifying it at most once
+    pub fn get_module(&mut self, module_id: &ModuleId) -> Result<Arc<CompiledModule>> {
+        if let Some(module) = self.module_cache.get(module_id) {
+            return Ok(module);
+        }
+
+        let state_value = self.read_state_value(&StateKey::module_id(module_id))
+            .ok_or_else(|| anyhow!("Module {} not found", module_id))?;
+        let module = CompiledModule::deserialize(state_value.bytes())
+            .map_err(|e| anyhow!("Failed to deserialize {}: {:?}", module_id, e))?;
+        move_bytecode_verifier::verify_module(&module)
+            .map_err(|e| anyhow!("Module {} failed verification: {:?}", module_id, e))?;
+
+        Ok(self.module_cache.insert(module))
+    }
+
+    /// Fetch the code of a set of modules into the fork up front, in one batch, and load
+    /// them into the module cache
+    ///
+    /// Modules already on the fork, including ones republished locally, are left as they are.
+    /// Transactions read code from the fork's local state, so after warming, fuzzing loops and
+    /// snapshot/revert cycles over the same targets never wait on the network for it, and
+    /// AptosBB's own reads of the modules are served verified. The VM still deserializes and
+    /// verifies the modules itself (see `ModuleCache`).
+    pub fn warm_modules(&mut self, module_ids: &[ModuleId]) -> Result<()> {
+        let missing: Vec<_> = module_ids
+            .iter()
+            .map(StateKey::module_id)
+            .filter(|state_key| !self.executor.data_store().inner().contains_key(state_key))
+            .collect();
+        self.fetch_state_values(&missing)?;
+        for module_id in module_ids {
+            self.get_module(module_id)?;
+        }
+        println!("Warmed {} modules", module_ids.len());
+        Ok(())
+    }
+}
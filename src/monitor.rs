@@ -0,0 +1,167 @@
+use crate::{findings::Finding, remote::rest_client, AptosBB};
+use anyhow::Result;
+use aptos_api_types::AptosErrorCode;
+use aptos_framework::natives::code::PackageRegistry;
+use aptos_rest_client::{error::RestError, AptosBaseUrl};
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::ModuleId;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Transactions requested per poll
+const PAGE_SIZE: u16 = 100;
+
+/// Longest wait between retries after failed polls
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// The cursor is past the ledger's end, i.e. there are no new transactions yet
+fn is_past_ledger_end(err: &RestError) -> bool {
+    matches!(err, RestError::Api(e) if e.status_code.as_u16() == 404 || e.error.error_code == AptosErrorCode::VersionNotFound)
+}
+
+/// `0x1::code::PublishPackage` event payload
+#[derive(Clone, Debug, Deserialize)]
+pub struct PublishEvent {
+    pub code_address: AccountAddress,
+    pub is_upgrade: bool,
+}
+
+/// Quick-scan scenario run against every newly published package
+pub trait MonitorHook {
+    fn name(&self) -> &str;
+
+    /// Inspect a fork taken right after the publish and report findings
+    fn on_publish(&mut self, bb: &mut AptosBB, version: u64, event: &PublishEvent) -> Result<Vec<Finding>>;
+}
+
+/// Runs the static pattern checks on every module at the published address
+pub struct StaticChecks;
+
+impl MonitorHook for StaticChecks {
+    fn name(&self) -> &str {
+        "static-checks"
+    }
+
+    fn on_publish(&mut self, bb: &mut AptosBB, _version: u64, event: &PublishEvent) -> Result<Vec<Finding>> {
+        bb.fetch_account(event.code_address)?;
+        let Some(registry) = bb.read_resource::<PackageRegistry>(&event.code_address) else {
+            return Ok(vec![]);
+        };
+
+        let mut findings = vec![];
+        for module in registry.packages.iter().flat_map(|package| &package.modules) {
+            let module_id = ModuleId::new(event.code_address, module.name.parse()?);
+            findings.extend(bb.analyze_module(&module_id)?);
+        }
+        Ok(findings)
+    }
+}
+
+/// Tails a network for package publishes and upgrades, forking at each one
+pub struct Monitor {
+    network: AptosBaseUrl,
+    api_key: Option<String>,
+    poll_interval: Duration,
+    hooks: Vec<Box<dyn MonitorHook>>,
+}
+
+impl Monitor {
+    pub fn new(network: AptosBaseUrl) -> Self {
+        Self {
+            network,
+            api_key: None,
+            poll_interval: Duration::from_secs(5),
+            hooks: vec![Box::new(StaticChecks)],
+        }
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Register an additional quick-scan scenario
+    pub fn register(mut self, hook: impl MonitorHook + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    async fn scan(&mut self, version: u64, event: &PublishEvent) -> Result<()> {
+        let kind = if event.is_upgrade { "Upgrade" } else { "Publish" };
+        println!("📦 {} at {} (version {})", kind, event.code_address, version);
+
+        let mut builder = AptosBB::builder().network(self.network.clone()).version(version);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.api_key(api_key);
+        }
+        let mut bb = builder.build().await?;
+
+        for hook in &mut self.hooks {
+            match hook.on_publish(&mut bb, version, event) {
+                Ok(findings) => {
+                    for finding in findings {
+                        println!("🚨 [{}] {}", hook.name(), finding);
+                    }
+                }
+                Err(e) => eprintln!("⚠️  {} failed on {}: {}", hook.name(), event.code_address, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Follow the chain from `start_version` (or the latest version), indefinitely
+    ///
+    /// At the ledger's tip the monitor waits a poll interval before asking again; other
+    /// failed polls are retried with exponential backoff.
+    pub async fn run(mut self, start_version: Option<u64>) -> Result<()> {
+        let client = rest_client(&self.network, self.api_key.as_deref())?;
+        let mut next = match start_version {
+            Some(version) => version,
+            None => client.get_ledger_information().await?.into_inner().version,
+        };
+        println!("👀 Monitoring package publishes from version {}", next);
+
+        let mut backoff = self.poll_interval;
+        loop {
+            let transactions = match client.get_transactions_bcs(Some(next), Some(PAGE_SIZE)).await {
+                Ok(response) => response.into_inner(),
+                Err(e) if is_past_ledger_end(&e) => vec![],
+                Err(e) => {
+                    eprintln!("⚠️  Polling from version {} failed, retrying in {:?}: {}", next, backoff, e);
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                    continue;
+                }
+            };
+            backoff = self.poll_interval;
+            if transactions.is_empty() {
+                tokio::time::sleep(self.poll_interval).await;
+                continue;
+            }
+
+            for txn in &transactions {
+                for event in &txn.events {
+                    if event.type_tag().to_string() != "0x1::code::PublishPackage" {
+                        continue;
+                    }
+                    let publish: PublishEvent = match bcs::from_bytes(event.event_data()) {
+                        Ok(publish) => publish,
+                        Err(e) => {
+                            eprintln!("⚠️  Undecodable publish event at version {}: {}", txn.version, e);
+                            continue;
+                        }
+                    };
+                    if let Err(e) = self.scan(txn.version, &publish).await {
+                        eprintln!("⚠️  Failed to scan {} at version {}: {}", publish.code_address, txn.version, e);
+                    }
+                }
+            }
+            next = transactions.last().map(|txn| txn.version + 1).unwrap_or(next);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::AptosBB;
+use anyhow::{anyhow, Result};
+use aptos_types::{
+    access_path::Path as AccessPathKind,
+    state_store::state_key::inner::StateKeyInner,
+};
+use move_cli::base::test::{run_move_unit_tests, UnitTestResult};
+use move_core_types::{
+    effects::{ChangeSet, Op},
+    language_storage::StructTag,
+};
+use move_package::BuildConfig;
+use move_unit_test::UnitTestingConfig;
+use std::{collections::BTreeMap, path::Path};
+
+impl AptosBB {
+    /// Genesis change set mirroring the fork's local modules and resources
+    ///
+    /// The unit test runner stores every resource on its own, so resource group members are
+    /// unpacked into plain resources, as `aptos move test` sees them. Table items have no
+    /// representation in a Move `ChangeSet`. Prefetch (or run eagerly against) the accounts a
+    /// test touches, since only locally held state is included.
+    fn move_test_genesis(&self) -> Result<ChangeSet> {
+        let mut change_set = ChangeSet::new();
+        for (state_key, value) in self.local_state() {
+            let StateKeyInner::AccessPath(access_path) = state_key.inner() else {
+                continue;
+            };
+            let bytes = value.bytes().clone();
+            match access_path.get_path() {
+                AccessPathKind::Code(module_id) => {
+                    change_set.add_module_op(module_id, Op::New(bytes))?;
+                }
+                AccessPathKind::Resource(struct_tag) => {
+                    change_set.add_resource_op(access_path.address, struct_tag, Op::New(bytes))?;
+                }
+                AccessPathKind::ResourceGroup(group) => {
+                    let members: BTreeMap<StructTag, Vec<u8>> = bcs::from_bytes(&bytes)
+                        .map_err(|e| anyhow!("Malformed resource group {} at {}: {}", group, access_path.address, e))?;
+                    for (struct_tag, member) in members {
+                        change_set.add_resource_op(access_path.address, struct_tag, Op::New(member.into()))?;
+                    }
+                }
+            }
+        }
+        Ok(change_set)
+    }
+
+    /// Run a package's Move unit tests with the forked state as genesis storage
+    ///
+    /// Natives behave as under the fork's on-chain feature flags. Returns whether every test
+    /// passed; per-test output is written to stdout.
+    pub fn run_move_tests(&self, path: &Path, filter: Option<&str>) -> Result<bool> {
+        aptos_vm::natives::configure_for_unit_test();
+        let natives = aptos_vm::natives::aptos_natives(
+            aptos_gas_schedule::LATEST_GAS_FEATURE_VERSION,
+            aptos_gas_schedule::NativeGasParameters::zeros(),
+            aptos_gas_schedule::MiscGasParameters::zeros(),
+            aptos_types::on_chain_config::TimedFeaturesBuilder::enable_all().build(),
+            self.features()?,
+        );
+
+        let build_config = BuildConfig {
+            test_mode: true,
+            dev_mode: true,
+            ..BuildConfig::default()
+        };
+        let unit_test_config = UnitTestingConfig {
+            filter: filter.map(str::to_string),
+            report_stacktrace_on_abort: true,
+            ..UnitTestingConfig::default()
+        };
+
+        let result = run_move_unit_tests(
+            path,
+            build_config,
+            unit_test_config,
+            natives,
+            self.move_test_genesis()?,
+            None,
+            None,
+            false,
+            &mut std::io::stdout(),
+        )
+        .map_err(|e| anyhow!("Failed to run Move unit tests: {}", e))?;
+
+        Ok(matches!(result, UnitTestResult::Success))
+    }
+}
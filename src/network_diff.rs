@@ -0,0 +1,151 @@
+//! Differential execution of one call on forks of two networks, e.g. mainnet and testnet
+
+use crate::{builder::network_name, export::status_name, AptosBB};
+use anyhow::Result;
+use aptos_types::{
+    account_address::AccountAddress,
+    state_store::state_key::inner::StateKeyInner,
+    transaction::{TransactionOutput, TransactionPayload, TransactionStatus},
+};
+use std::{collections::BTreeSet, fmt};
+
+/// Result of the call on one network's fork
+#[derive(Clone, Debug)]
+pub struct NetworkOutcome {
+    /// Network name and fork version, e.g. `mainnet@2000000000`
+    pub fork: String,
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    /// Event types in emission order
+    pub event_types: Vec<String>,
+    /// Types of the resources written, wherever they live, plus `module`/`table item` entries;
+    /// addresses are left out since balances and object addresses legitimately differ
+    pub written_types: BTreeSet<String>,
+}
+
+impl NetworkOutcome {
+    fn from_output(bb: &AptosBB, output: &TransactionOutput) -> Self {
+        let written_types = output
+            .write_set()
+            .iter()
+            .map(|(key, _)| match key.inner() {
+                StateKeyInner::AccessPath(path) => match path.get_struct_tag() {
+                    Some(tag) => tag.to_canonical_string(),
+                    None if path.is_code() => "module".to_string(),
+                    None => "resource group".to_string(),
+                },
+                StateKeyInner::TableItem { .. } => "table item".to_string(),
+                StateKeyInner::Raw(_) => "raw".to_string(),
+            })
+            .collect();
+        Self {
+            fork: format!("{}@{}", network_name(&bb.network), bb.version),
+            status: output.status().clone(),
+            gas_used: output.gas_used(),
+            event_types: output.events().iter().map(|event| event.type_tag().to_canonical_string()).collect(),
+            written_types,
+        }
+    }
+
+    pub fn succeeded(&self) -> bool {
+        self.status.status().is_ok_and(|status| status.is_success())
+    }
+}
+
+/// The same call executed on two networks' forks
+#[derive(Clone, Debug)]
+pub struct NetworkDiff {
+    pub mainnet: NetworkOutcome,
+    pub testnet: NetworkOutcome,
+}
+
+impl NetworkDiff {
+    pub fn status_differs(&self) -> bool {
+        self.mainnet.status != self.testnet.status
+    }
+
+    pub fn gas_differs(&self) -> bool {
+        self.mainnet.gas_used != self.testnet.gas_used
+    }
+
+    pub fn events_differ(&self) -> bool {
+        self.mainnet.event_types != self.testnet.event_types
+    }
+
+    pub fn writes_differ(&self) -> bool {
+        self.mainnet.written_types != self.testnet.written_types
+    }
+
+    /// The call goes through on mainnet but not on testnet, so testing on testnet hides it
+    pub fn is_mainnet_only(&self) -> bool {
+        self.mainnet.succeeded() && !self.testnet.succeeded()
+    }
+
+    /// Whether both networks behave the same for this call
+    pub fn is_equivalent(&self) -> bool {
+        !(self.status_differs() || self.gas_differs() || self.events_differ() || self.writes_differ())
+    }
+}
+
+impl fmt::Display for NetworkDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (main, test) = (&self.mainnet, &self.testnet);
+        if self.is_equivalent() {
+            return write!(f, "identical on {} and {} ({}, {} gas)", main.fork, test.fork, status_name(&main.status), main.gas_used);
+        }
+        write!(f, "Behavior differs between {} and {}:", main.fork, test.fork)?;
+        if self.status_differs() {
+            let mark = if self.is_mainnet_only() { " 🚨 mainnet-only" } else { "" };
+            write!(f, "\n  status: {} vs {}{}", status_name(&main.status), status_name(&test.status), mark)?;
+        }
+        if self.gas_differs() {
+            write!(f, "\n  gas: {} vs {}", main.gas_used, test.gas_used)?;
+        }
+        if self.events_differ() {
+            write!(f, "\n  events: {:?} vs {:?}", main.event_types, test.event_types)?;
+        }
+        if self.writes_differ() {
+            for only in main.written_types.difference(&test.written_types) {
+                write!(f, "\n  only {} writes {}", main.fork, only)?;
+            }
+            for only in test.written_types.difference(&main.written_types) {
+                write!(f, "\n  only {} writes {}", test.fork, only)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AptosBB {
+    /// Simulate `payload` from an impersonated `sender` without applying it
+    ///
+    /// The impersonation runs in a snapshot, so the sender's account is left untouched.
+    fn simulate_as(&mut self, sender: AccountAddress, payload: TransactionPayload) -> Result<NetworkOutcome> {
+        self.with_snapshot(|bb| {
+            let account = bb.impersonate(sender)?;
+            let txn = bb.sign_dry_run_transaction(&account, payload);
+            let output = bb.executor.execute_transaction(txn);
+            Ok(NetworkOutcome::from_output(bb, &output))
+        })
+    }
+}
+
+/// Execute the same call from `sender` on a mainnet and a testnet fork and diff the behavior
+///
+/// Meant for protocols deployed at the same address on both networks: differing statuses,
+/// events or written resource types point at configuration drift, and calls that only succeed
+/// on mainnet at exposures a testnet deployment does not show. `sender` is impersonated on
+/// both, and neither fork keeps the transaction or the impersonation.
+pub fn run_on_both(
+    mainnet_bb: &mut AptosBB,
+    testnet_bb: &mut AptosBB,
+    sender: AccountAddress,
+    payload: TransactionPayload,
+) -> Result<NetworkDiff> {
+    let diff = NetworkDiff {
+        mainnet: mainnet_bb.simulate_as(sender, payload.clone())?,
+        testnet: testnet_bb.simulate_as(sender, payload)?,
+    };
+    println!("{}", diff);
+    Ok(diff)
+}
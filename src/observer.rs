@@ -0,0 +1,85 @@
+//! Subsystems that watch every transaction the fork executes: event subscribers, scenario
+//! limits, result export, session reports, storage tracking, staleness checks and summaries
+
+use crate::{
+    events::EventObserver, export::ExportObserver, limits::LimitObserver, report::ReportObserver,
+    staleness::StalenessObserver, storage::StorageObserver, summary::SummaryObserver, AptosBB,
+};
+use anyhow::Result;
+use aptos_types::{
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{SignedTransaction, TransactionOutput},
+};
+use std::collections::BTreeMap;
+
+/// A transaction the fork executed on its own, handed to recorders once it is applied
+pub(crate) struct Executed<'a> {
+    pub(crate) txn: &'a SignedTransaction,
+    pub(crate) output: &'a TransactionOutput,
+    /// Every existing value the transaction read, when an observer asked for them
+    pub(crate) reads: Option<&'a BTreeMap<StateKey, StateValue>>,
+}
+
+/// Finishes an observation started before execution, e.g. diffs balances captured then
+pub(crate) type Recorder = Box<dyn FnOnce(&mut AptosBB, &Executed<'_>)>;
+
+/// A subsystem notified of every transaction the fork executes
+pub(crate) trait TxnObserver {
+    /// Capture what the observer needs before `txn` executes on its own; `None` when it is
+    /// off or needs nothing from before. An error refuses the transaction.
+    fn begin(&self, _bb: &AptosBB, _txn: &SignedTransaction) -> Result<Option<Recorder>> {
+        Ok(None)
+    }
+
+    /// Whether the transaction must run recording the values it reads, for `Executed::reads`
+    fn needs_reads(&self, _bb: &AptosBB) -> bool {
+        false
+    }
+
+    /// Observe an applied transaction, including one executed as part of a block
+    fn applied(&self, _bb: &mut AptosBB, _txn: &SignedTransaction, _output: &TransactionOutput) {}
+}
+
+/// Box a recorder, inferring the closure's signature
+pub(crate) fn recorder(record: impl FnOnce(&mut AptosBB, &Executed<'_>) + 'static) -> Recorder {
+    Box::new(record)
+}
+
+/// Every observer, in notification order
+const OBSERVERS: &[&dyn TxnObserver] = &[
+    &EventObserver,
+    &LimitObserver,
+    &ExportObserver,
+    &ReportObserver,
+    &StorageObserver,
+    &StalenessObserver,
+    &SummaryObserver,
+];
+
+impl AptosBB {
+    /// Start observing a transaction about to execute on its own: the recorders to finish
+    /// once it is applied, and whether it must run recording its reads
+    pub(crate) fn begin_observers(&self, txn: &SignedTransaction) -> Result<(Vec<Recorder>, bool)> {
+        let mut recorders = vec![];
+        for observer in OBSERVERS {
+            recorders.extend(observer.begin(self, txn)?);
+        }
+        let record_reads = OBSERVERS.iter().any(|observer| observer.needs_reads(self));
+        Ok((recorders, record_reads))
+    }
+
+    /// Notify every observer of an applied transaction, then finish its recorders
+    pub(crate) fn finish_observers(&mut self, recorders: Vec<Recorder>, executed: &Executed<'_>) {
+        self.notify_applied(executed.txn, executed.output);
+        for recorder in recorders {
+            recorder(self, executed);
+        }
+    }
+
+    /// Notify every observer of an applied transaction that had no recorders, e.g. one of a block
+    pub(crate) fn notify_applied(&mut self, txn: &SignedTransaction, output: &TransactionOutput) {
+        for observer in OBSERVERS {
+            observer.applied(self, txn, output);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::AptosBB;
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{TransactionPayload, TransactionStatus},
+};
+use std::collections::{BTreeSet, HashMap};
+
+/// End state of executing the transactions in one particular order
+#[derive(Clone, Debug)]
+pub struct OrderingOutcome {
+    /// Indices into the original transaction list, in execution order
+    pub order: Vec<usize>,
+    /// Statuses in the original list's order, so outcomes are comparable across orderings
+    pub statuses: Vec<TransactionStatus>,
+    state: HashMap<StateKey, StateValue>,
+}
+
+/// Results of running a transaction set under several orderings
+#[derive(Clone, Debug)]
+pub struct OrderingReport {
+    pub outcomes: Vec<OrderingOutcome>,
+}
+
+impl OrderingReport {
+    /// Whether every ordering produced the same statuses and end state
+    pub fn is_order_independent(&self) -> bool {
+        self.outcomes
+            .windows(2)
+            .all(|pair| pair[0].statuses == pair[1].statuses && pair[0].state == pair[1].state)
+    }
+
+    /// State keys whose end value differs between the first ordering and any other
+    ///
+    /// Gas fee and sequence number bookkeeping is included, so expect the transactions'
+    /// senders to show up when they pay different gas under different orders.
+    pub fn differing_keys(&self) -> BTreeSet<StateKey> {
+        let Some(first) = self.outcomes.first() else {
+            return BTreeSet::new();
+        };
+        let mut keys = BTreeSet::new();
+        for outcome in &self.outcomes[1..] {
+            for key in first.state.keys().chain(outcome.state.keys()) {
+                if first.state.get(key) != outcome.state.get(key) {
+                    keys.insert(key.clone());
+                }
+            }
+        }
+        keys
+    }
+
+    /// Orderings whose statuses differ from the original order's
+    pub fn status_divergences(&self) -> Vec<&OrderingOutcome> {
+        let Some(first) = self.outcomes.first() else {
+            return vec![];
+        };
+        self.outcomes[1..].iter().filter(|outcome| outcome.statuses != first.statuses).collect()
+    }
+}
+
+/// Advance to the next lexicographic permutation, returning false after the last one
+fn next_permutation(order: &mut [usize]) -> bool {
+    let Some(pivot) = (1..order.len()).rev().find(|&i| order[i - 1] < order[i]) else {
+        return false;
+    };
+    let swap = (pivot..order.len()).rev().find(|&i| order[i] > order[pivot - 1]).expect("pivot has a successor");
+    order.swap(pivot - 1, swap);
+    order[pivot..].reverse();
+    true
+}
+
+/// Up to `max` orderings: the original order, its reverse, then lexicographic permutations
+fn orderings(len: usize, max: usize) -> Vec<Vec<usize>> {
+    let original: Vec<usize> = (0..len).collect();
+    let reversed: Vec<usize> = (0..len).rev().collect();
+    let mut result = vec![original.clone()];
+    if reversed != original {
+        result.push(reversed.clone());
+    }
+
+    let mut order = original;
+    while result.len() < max && next_permutation(&mut order) {
+        if order != reversed {
+            result.push(order.clone());
+        }
+    }
+    result.truncate(max);
+    result
+}
+
+impl AptosBB {
+    /// Execute the same transactions under several orderings and compare the outcomes
+    ///
+    /// Each ordering starts from the current fork state, which is restored afterwards. All
+    /// `n!` permutations are tried when they fit in `max_orderings`; otherwise the original
+    /// order, its reverse and the first lexicographic permutations are used.
+    pub fn permute_block(&mut self, txns: &[(Account, TransactionPayload)], max_orderings: usize) -> Result<OrderingReport> {
+        let mut outcomes = vec![];
+
+        for order in orderings(txns.len(), max_orderings.max(1)) {
+            let outcome = self.with_snapshot(|bb| -> Result<OrderingOutcome> {
+                let mut statuses = vec![None; txns.len()];
+                for &idx in &order {
+                    let (account, payload) = &txns[idx];
+                    statuses[idx] = Some(bb.run_transaction(account, payload.clone())?);
+                }
+                Ok(OrderingOutcome {
+                    order,
+                    statuses: statuses.into_iter().map(|status| status.expect("every index is executed")).collect(),
+                    state: bb.local_state().into_iter().collect(),
+                })
+            })?;
+            outcomes.push(outcome);
+        }
+
+        let report = OrderingReport { outcomes };
+        if report.is_order_independent() {
+            println!("✅ {} orderings produced identical outcomes", report.outcomes.len());
+        } else {
+            println!(
+                "⚠️  Order-dependent outcome: {} orderings with differing statuses, {} differing state keys",
+                report.status_divergences().len(),
+                report.differing_keys().len()
+            );
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,499 @@
+use crate::AptosBB;
+use aptos_cached_packages::aptos_stdlib;
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_framework::{
+    chunked_publish::{chunk_package_and_create_payloads, PublishType, CHUNK_SIZE_IN_BYTES},
+    natives::code::{PackageMetadata, PackageRegistry, UpgradePolicy},
+    unzip_metadata_str, BuildOptions, BuiltPackage,
+};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{EntryFunction, ExecutionStatus, TransactionPayload, TransactionStatus},
+};
+use move_core_types::{identifier::Identifier, language_storage::ModuleId};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+};
+use tempfile::TempDir;
+
+/// Framework dependency used by generated packages, matching `module/Move.toml`
+const FRAMEWORK_DEPENDENCY: &str = r#"[dependencies.AptosFramework]
+git = "https://github.com/aptos-labs/aptos-framework.git"
+rev = "mainnet"
+subdir = "aptos-framework"
+"#;
+
+/// Publish payloads above this size are split into chunks, like the CLI does
+pub const MAX_PUBLISH_PAYLOAD_SIZE: usize = 60_000;
+
+/// Address of the `large_packages` staging module on mainnet, testnet and devnet
+pub const LARGE_PACKAGES_MODULE_ADDRESS: &str =
+    "0x0e1ca3011bdd07246d4d16d909dbb2d6953a86c4735d5acf5865d962c630cce7";
+
+/// Build and publish settings for `publish_package_with_options`
+#[derive(Clone)]
+pub struct PublishOptions {
+    /// Named address assignments, overriding whatever `Move.toml` declares
+    pub named_addresses: BTreeMap<String, AccountAddress>,
+    /// Remap the named addresses the package's modules live under to the publishing account
+    pub remap_self_address: bool,
+    /// Compiler settings (bytecode/compiler/language version, experiments, ...)
+    pub build_options: BuildOptions,
+    /// Always stage the package through `large_packages`, even if it fits in one transaction
+    pub chunked: bool,
+}
+
+impl Default for PublishOptions {
+    fn default() -> Self {
+        Self {
+            named_addresses: BTreeMap::new(),
+            remap_self_address: true,
+            build_options: BuildOptions {
+                with_srcs: true,
+                with_abis: true,
+                with_source_maps: true,
+                with_error_map: true,
+                ..BuildOptions::default()
+            },
+            chunked: false,
+        }
+    }
+}
+
+impl PublishOptions {
+    pub fn named_address(mut self, name: impl Into<String>, addr: AccountAddress) -> Self {
+        self.named_addresses.insert(name.into(), addr);
+        self
+    }
+
+    pub fn bytecode_version(mut self, version: u32) -> Self {
+        self.build_options.bytecode_version = Some(version);
+        self
+    }
+
+    pub fn remap_self_address(mut self, remap: bool) -> Self {
+        self.remap_self_address = remap;
+        self
+    }
+
+    pub fn chunked(mut self, chunked: bool) -> Self {
+        self.chunked = chunked;
+        self
+    }
+}
+
+/// Named addresses the package's modules are declared under (`module <name>::<module>`)
+pub fn package_self_address_names(package_path: &Path) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+    let Ok(entries) = std::fs::read_dir(package_path.join("sources")) else {
+        return names;
+    };
+
+    for entry in entries.flatten() {
+        let Ok(source) = std::fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        for line in source.lines() {
+            let Some(rest) = line.trim_start().strip_prefix("module ") else {
+                continue;
+            };
+            if let Some((name, _)) = rest.split_once("::") {
+                let name = name.trim();
+                // Literal addresses (`module 0x1::foo`) cannot be remapped
+                if !name.starts_with("0x") {
+                    names.insert(name.to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+fn copy_package_dir(src: &Path, dst: &Path) -> Result<()> {
+    std::fs::create_dir_all(dst)?;
+    for entry in std::fs::read_dir(src)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name == "build" || name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            copy_package_dir(&entry.path(), &dst.join(&name))?;
+        } else {
+            std::fs::copy(entry.path(), dst.join(&name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Copy a package into a temporary directory with its named addresses rewritten
+///
+/// Local dependency paths are made absolute so they still resolve from the copy.
+pub fn prepare_package(package_path: &Path, publisher: AccountAddress, options: &PublishOptions) -> Result<TempDir> {
+    let dir = tempfile::Builder::new().prefix("aptosbb-").tempdir()?;
+    copy_package_dir(package_path, dir.path())?;
+
+    let manifest_path = package_path.join("Move.toml");
+    let mut manifest: toml::Table = std::fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?
+        .parse()?;
+
+    let mut assignments = BTreeMap::new();
+    if options.remap_self_address {
+        for name in package_self_address_names(package_path) {
+            assignments.insert(name, publisher);
+        }
+    }
+    assignments.extend(options.named_addresses.clone());
+
+    let addresses = manifest
+        .entry("addresses")
+        .or_insert_with(|| toml::Value::Table(Default::default()));
+    if let Some(addresses) = addresses.as_table_mut() {
+        for (name, addr) in assignments {
+            addresses.insert(name, toml::Value::String(addr.to_hex_literal()));
+        }
+    }
+
+    let base = package_path.canonicalize()?;
+    for section in ["dependencies", "dev-dependencies"] {
+        let Some(deps) = manifest.get_mut(section).and_then(|deps| deps.as_table_mut()) else {
+            continue;
+        };
+        for dep in deps.values_mut() {
+            if let Some(local) = dep.get_mut("local") {
+                if let Some(relative) = local.as_str() {
+                    *local = toml::Value::String(base.join(relative).display().to_string());
+                }
+            }
+        }
+    }
+
+    std::fs::write(dir.path().join("Move.toml"), toml::to_string(&manifest)?)?;
+    Ok(dir)
+}
+
+/// Render a `Move.toml` for a generated package
+pub fn render_manifest(name: &str, named_addresses: &[(&str, AccountAddress)]) -> String {
+    let mut manifest = format!("[package]\nname = \"{}\"\nversion = \"1.0.0\"\nauthors = []\n\n[addresses]\n", name);
+    for (named, addr) in named_addresses {
+        manifest.push_str(&format!("{} = \"{}\"\n", named, addr.to_hex_literal()));
+    }
+    manifest.push('\n');
+    manifest.push_str(FRAMEWORK_DEPENDENCY);
+    manifest
+}
+
+/// Write a Move package (manifest plus `sources/`) into `dir`
+pub fn write_package(
+    dir: &Path,
+    name: &str,
+    sources: &[(&str, &str)],
+    named_addresses: &[(&str, AccountAddress)],
+) -> Result<()> {
+    std::fs::create_dir_all(dir.join("sources"))?;
+    std::fs::write(dir.join("Move.toml"), render_manifest(name, named_addresses))?;
+    for (file_name, source) in sources {
+        let file_name = if file_name.ends_with(".move") {
+            file_name.to_string()
+        } else {
+            format!("{}.move", file_name)
+        };
+        std::fs::write(dir.join("sources").join(file_name), source)?;
+    }
+    Ok(())
+}
+
+impl AptosBB {
+    /// Publish a package given as inline Move sources
+    ///
+    /// `sources` are `(file name, source)` pairs. The package is materialized in a
+    /// temporary directory, built and published from `account`, which lets a PoC embed
+    /// its Move code directly in the Rust file.
+    ///
+    /// ```ignore
+    /// let status = bb.publish_source(&attacker, "exploit", &[("exploit", r#"
+    ///     module exploit::exploit { public entry fun run(_s: &signer) {} }
+    /// "#)], &[("exploit", *attacker.address())])?;
+    /// ```
+    pub fn publish_source(
+        &mut self,
+        account: &Account,
+        name: &str,
+        sources: &[(&str, &str)],
+        named_addresses: &[(&str, AccountAddress)],
+    ) -> Result<TransactionStatus> {
+        let dir = tempfile::Builder::new().prefix("aptosbb-").tempdir()?;
+        write_package(dir.path(), name, sources, named_addresses)?;
+        Ok(self.publish_package(account, dir.path())?)
+    }
+}
+
+impl AptosBB {
+    /// Publish a built package in chunks through the `large_packages` staging module
+    ///
+    /// Mirrors the CLI's `--chunked-publish` flow: every transaction but the last stages
+    /// part of the metadata and bytecode under `account`, and the last one publishes the
+    /// assembled package. Returns the status of the first failing transaction, or of the
+    /// publishing one. If a transaction fails after chunks were staged, the staging area is
+    /// cleaned up (`large_packages::cleanup_staging_area`) so a retry starts from scratch.
+    pub fn publish_chunked(&mut self, account: &Account, package: &BuiltPackage) -> Result<TransactionStatus> {
+        let metadata = bcs::to_bytes(&package.extract_metadata()?)?;
+        let large_packages = AccountAddress::from_hex_literal(LARGE_PACKAGES_MODULE_ADDRESS)?;
+        if self.get_module(&ModuleId::new(large_packages, Identifier::new("large_packages")?)).is_err() {
+            bail!("large_packages module not found at {}, chunked publishing is unavailable", large_packages);
+        }
+
+        let payloads: Vec<TransactionPayload> = chunk_package_and_create_payloads(
+            metadata,
+            package.extract_code(),
+            PublishType::AccountDeploy,
+            None,
+            large_packages,
+            CHUNK_SIZE_IN_BYTES,
+        );
+        let total = payloads.len();
+        for (i, payload) in payloads.into_iter().enumerate() {
+            let status = self.run_transaction(account, payload)?;
+            let failed = status != TransactionStatus::Keep(ExecutionStatus::Success);
+            if failed || i + 1 == total {
+                println!("Chunked publish: {}/{} transactions executed", i + 1, total);
+                if failed && i > 0 {
+                    self.cleanup_staging_area(account, large_packages)?;
+                }
+                return Ok(status);
+            }
+        }
+        bail!("Package produced no publish transactions")
+    }
+
+    /// Drop the chunks `account` staged in `large_packages`
+    fn cleanup_staging_area(&mut self, account: &Account, large_packages: AccountAddress) -> Result<()> {
+        let payload = TransactionPayload::EntryFunction(EntryFunction::new(
+            ModuleId::new(large_packages, Identifier::new("large_packages")?),
+            Identifier::new("cleanup_staging_area")?,
+            vec![],
+            vec![],
+        ));
+        let status = self.run_transaction(account, payload)?;
+        if status != TransactionStatus::Keep(ExecutionStatus::Success) {
+            bail!("Failed to clean up the chunked publish staging area of {}: {:?}", account.address(), status);
+        }
+        Ok(())
+    }
+
+    /// Upgrade the package already published at `account` through `code::publish_package_txn`
+    ///
+    /// The new version is published with `policy` as its upgrade policy and goes through the
+    /// same checks as on chain: the framework rejects weakening the policy or upgrading an
+    /// immutable package, and the VM enforces module compatibility under `compat`. A failed
+    /// status therefore means the upgrade would not be accepted. To upgrade someone else's
+    /// package, sign as its address with `impersonate`, which keeps the account's state.
+    pub fn upgrade_package(&mut self, account: &Account, path: &Path, policy: UpgradePolicy) -> Result<TransactionStatus> {
+        let options = PublishOptions::default();
+        let dir = prepare_package(path, *account.address(), &options)?;
+        let package = self.build_with_prefetch(dir.path(), options.build_options)?;
+
+        let mut metadata = package.extract_metadata()?;
+        let previous = self
+            .read_package_metadata(*account.address(), &metadata.name)
+            .context("upgrade_package expects the package to be published already")?;
+        println!(
+            "Upgrading {}::{} (upgrade #{}, policy {} -> {})",
+            account.address(),
+            metadata.name,
+            previous.upgrade_number + 1,
+            previous.upgrade_policy,
+            policy
+        );
+        metadata.upgrade_policy = policy;
+
+        let payload = aptos_stdlib::code_publish_package_txn(bcs::to_bytes(&metadata)?, package.extract_code());
+        Ok(self.run_transaction(account, payload)?)
+    }
+}
+
+/// A package reconstructed from on-chain metadata
+pub struct FetchedPackage {
+    pub path: PathBuf,
+    pub metadata: PackageMetadata,
+    /// Present when a rebuild was requested
+    pub built: Option<BuiltPackage>,
+}
+
+/// Accounts whose packages are resolved from the manifest's own (git) dependencies
+pub(crate) fn is_framework_address(addr: &AccountAddress) -> bool {
+    *addr == AccountAddress::ONE || *addr == AccountAddress::THREE || *addr == AccountAddress::FOUR
+}
+
+impl AptosBB {
+    /// Read a package's metadata from the `PackageRegistry` at `addr`
+    pub fn read_package_metadata(&self, addr: AccountAddress, name: &str) -> Result<PackageMetadata> {
+        let registry: PackageRegistry = self.read_resource(&addr)
+            .ok_or_else(|| anyhow!("No packages published at {}", addr))?;
+        registry.packages
+            .into_iter()
+            .find(|package| package.name == name)
+            .ok_or_else(|| anyhow!("Package {} not found at {}", name, addr))
+    }
+
+    /// Download a published package's sources into `out_dir` as a buildable package
+    ///
+    /// Requires the package to have been published with sources (`with_srcs`).
+    /// Dependencies on other non-framework on-chain packages are fetched recursively
+    /// into `out_dir/deps/` and wired up as local dependencies; unassigned (`_`) named
+    /// addresses the modules live under are set to `addr`.
+    pub fn fetch_package_source(
+        &mut self,
+        addr: AccountAddress,
+        name: &str,
+        out_dir: &Path,
+        rebuild: bool,
+    ) -> Result<FetchedPackage> {
+        let metadata = self.read_package_metadata(addr, name)?;
+        std::fs::create_dir_all(out_dir.join("sources"))?;
+
+        for module in &metadata.modules {
+            if module.source.is_empty() {
+                bail!("Package {}::{} was published without sources", addr, name);
+            }
+            let source = unzip_metadata_str(&module.source)?;
+            std::fs::write(out_dir.join("sources").join(format!("{}.move", module.name)), source)?;
+        }
+
+        let mut manifest: toml::Table = unzip_metadata_str(&metadata.manifest)?.parse()?;
+
+        let self_names = package_self_address_names(out_dir);
+        if let Some(addresses) = manifest.get_mut("addresses").and_then(|a| a.as_table_mut()) {
+            for (named, value) in addresses.iter_mut() {
+                if value.as_str() == Some("_") && self_names.contains(named) {
+                    *value = toml::Value::String(addr.to_hex_literal());
+                }
+            }
+        }
+
+        let deps = manifest
+            .entry("dependencies")
+            .or_insert_with(|| toml::Value::Table(Default::default()));
+        if let Some(deps) = deps.as_table_mut() {
+            for dep in &metadata.deps {
+                if is_framework_address(&dep.account) || dep.account == addr && dep.package_name == name {
+                    continue;
+                }
+                let dep_dir = out_dir.join("deps").join(&dep.package_name);
+                if !dep_dir.exists() {
+                    self.fetch_package_source(dep.account, &dep.package_name, &dep_dir, false)?;
+                }
+                let mut local = toml::Table::new();
+                local.insert("local".to_string(), toml::Value::String(format!("deps/{}", dep.package_name)));
+                deps.insert(dep.package_name.clone(), toml::Value::Table(local));
+            }
+        }
+
+        std::fs::write(out_dir.join("Move.toml"), toml::to_string(&manifest)?)?;
+        println!("Fetched {}::{} ({} modules) into {}", addr, name, metadata.modules.len(), out_dir.display());
+
+        let built = if rebuild {
+            Some(BuiltPackage::build(out_dir.to_path_buf(), PublishOptions::default().build_options)?)
+        } else {
+            None
+        };
+
+        Ok(FetchedPackage {
+            path: out_dir.to_path_buf(),
+            metadata,
+            built,
+        })
+    }
+}
+
+/// What an address has published, for reconnaissance of a target
+#[derive(Clone, Debug)]
+pub struct PackageInfo {
+    pub name: String,
+    pub upgrade_policy: UpgradePolicy,
+    /// Number of upgrades since the first publish
+    pub upgrade_number: u64,
+    pub source_digest: String,
+    pub modules: Vec<String>,
+    /// Whether every module was published with its sources, so `fetch_package_source` works
+    pub has_source: bool,
+    /// Declared dependencies as (address, package name)
+    pub deps: Vec<(AccountAddress, String)>,
+}
+
+impl PackageInfo {
+    /// Dependencies outside the framework, i.e. other protocols this package builds on
+    pub fn third_party_deps(&self) -> Vec<&(AccountAddress, String)> {
+        self.deps.iter().filter(|(addr, _)| !is_framework_address(addr)).collect()
+    }
+}
+
+/// Every package published at an address
+#[derive(Clone, Debug)]
+pub struct PackageInventory {
+    pub address: AccountAddress,
+    pub packages: Vec<PackageInfo>,
+}
+
+impl PackageInventory {
+    pub fn module_count(&self) -> usize {
+        self.packages.iter().map(|package| package.modules.len()).sum()
+    }
+
+    pub fn package(&self, name: &str) -> Option<&PackageInfo> {
+        self.packages.iter().find(|package| package.name == name)
+    }
+}
+
+impl std::fmt::Display for PackageInventory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {} packages, {} modules", self.address, self.packages.len(), self.module_count())?;
+        for package in &self.packages {
+            write!(
+                f,
+                "\n  📦 {} (upgrade #{}, {}, {})",
+                package.name,
+                package.upgrade_number,
+                package.upgrade_policy,
+                if package.has_source { "source available" } else { "no source" }
+            )?;
+            write!(f, "\n     modules: {}", package.modules.join(", "))?;
+            if !package.deps.is_empty() {
+                let deps: Vec<String> = package.deps.iter().map(|(addr, name)| format!("{}::{}", addr.to_hex_literal(), name)).collect();
+                write!(f, "\n     deps: {}", deps.join(", "))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AptosBB {
+    /// Packages at `addr` with their modules, upgrade history, source availability and
+    /// dependencies, read from its `PackageRegistry` in one go
+    ///
+    /// An address without packages yields an empty inventory.
+    pub fn package_inventory(&mut self, addr: AccountAddress) -> Result<PackageInventory> {
+        self.fetch_account(addr)?;
+        let registry: Option<PackageRegistry> = self.read_resource(&addr);
+        let packages = registry
+            .map(|registry| registry.packages)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|package| PackageInfo {
+                has_source: !package.modules.is_empty() && package.modules.iter().all(|module| !module.source.is_empty()),
+                modules: package.modules.iter().map(|module| module.name.clone()).collect(),
+                deps: package.deps.iter().map(|dep| (dep.account, dep.package_name.clone())).collect(),
+                name: package.name,
+                upgrade_policy: package.upgrade_policy,
+                upgrade_number: package.upgrade_number,
+                source_digest: package.source_digest,
+            })
+            .collect();
+        let inventory = PackageInventory { address: addr, packages };
+        println!("{}", inventory);
+        Ok(inventory)
+    }
+}
@@ -0,0 +1,132 @@
+use crate::AptosBB;
+use anyhow::{bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{
+        MultisigTransactionPayload, SignedTransaction, TransactionExecutable, TransactionExtraConfig, TransactionOutput, TransactionPayload,
+        TransactionPayloadInner, TransactionStatus,
+    },
+};
+
+/// Orderless transactions may not expire later than this after the current block time
+pub(crate) const MAX_ORDERLESS_EXPIRATION_SECS: u64 = 60;
+
+/// How a transaction is protected against replay
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReplayProtection {
+    /// The sender's sequence number, as for legacy payloads
+    SequenceNumber,
+    /// An orderless transaction identified by a nonce instead of a sequence number
+    Nonce(u64),
+}
+
+impl ReplayProtection {
+    fn nonce(self) -> Option<u64> {
+        match self {
+            Self::SequenceNumber => None,
+            Self::Nonce(nonce) => Some(nonce),
+        }
+    }
+}
+
+/// Rewrite a legacy entry function, script or multisig payload into the `Payload` (v2) format
+///
+/// A multisig payload keeps its multisig address in the extra config.
+pub fn to_v2_payload(payload: TransactionPayload, replay_protection: ReplayProtection) -> Result<TransactionPayload> {
+    let (executable, multisig_address) = match payload {
+        TransactionPayload::EntryFunction(entry) => (TransactionExecutable::EntryFunction(entry), None),
+        TransactionPayload::Script(script) => (TransactionExecutable::Script(script), None),
+        TransactionPayload::Multisig(multisig) => {
+            let executable = match multisig.transaction_payload {
+                Some(MultisigTransactionPayload::EntryFunction(entry)) => TransactionExecutable::EntryFunction(entry),
+                None => TransactionExecutable::Empty,
+            };
+            (executable, Some(multisig.multisig_address))
+        }
+        TransactionPayload::Payload(TransactionPayloadInner::V1 {
+            executable,
+            extra_config: TransactionExtraConfig::V1 { multisig_address, .. },
+        }) => (executable, multisig_address),
+        other => bail!("Cannot convert {:?} to the v2 payload format", other),
+    };
+    Ok(TransactionPayload::Payload(TransactionPayloadInner::V1 {
+        executable,
+        extra_config: TransactionExtraConfig::V1 {
+            multisig_address,
+            replay_protection_nonce: replay_protection.nonce(),
+        },
+    }))
+}
+
+/// Multisig payload in the v2 format, executing `executable` on behalf of `multisig_address`
+pub fn multisig_v2_payload(
+    multisig_address: AccountAddress,
+    executable: TransactionExecutable,
+    replay_protection: ReplayProtection,
+) -> TransactionPayload {
+    TransactionPayload::Payload(TransactionPayloadInner::V1 {
+        executable,
+        extra_config: TransactionExtraConfig::V1 {
+            multisig_address: Some(multisig_address),
+            replay_protection_nonce: replay_protection.nonce(),
+        },
+    })
+}
+
+impl AptosBB {
+    /// Execute a payload in the v2 (`TransactionExecutable` + extra config) format
+    ///
+    /// Legacy payloads are converted with `to_v2_payload`. With `ReplayProtection::Nonce`
+    /// the transaction is orderless: it does not consume the sender's sequence number and
+    /// expires shortly after the fork's current time, as the prologue requires. The network
+    /// must have the `TRANSACTION_PAYLOAD_V2` (and, for nonces, `ORDERLESS_TRANSACTIONS`)
+    /// feature flags enabled; flip them with `set_feature` to probe these paths early.
+    pub fn run_v2_transaction_with_output(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+        replay_protection: ReplayProtection,
+    ) -> Result<(TransactionStatus, TransactionOutput)> {
+        let txn = self.sign_v2_transaction(account, payload, replay_protection)?;
+        Ok(self.run_signed_transaction_with_output(txn)?)
+    }
+
+    /// Sign a payload in the v2 format; orderless transactions expire shortly after the fork's
+    /// current time
+    pub(crate) fn sign_v2_transaction(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+        replay_protection: ReplayProtection,
+    ) -> Result<SignedTransaction> {
+        let payload = to_v2_payload(payload, replay_protection)?;
+        Ok(match replay_protection {
+            ReplayProtection::SequenceNumber => self.sign_transaction(account, payload),
+            ReplayProtection::Nonce(_) => {
+                let expiration = self.now_secs()? + MAX_ORDERLESS_EXPIRATION_SECS / 2;
+                account
+                    .transaction()
+                    .payload(payload)
+                    // Ignored for orderless transactions; the SDKs fill in the maximum
+                    .sequence_number(u64::MAX)
+                    .max_gas_amount(self.gas_defaults.max_gas_amount)
+                    .gas_unit_price(self.gas_defaults.gas_unit_price)
+                    .ttl(expiration)
+                    .chain_id(self.chain_id)
+                    .sign()
+            }
+        })
+    }
+
+    /// Execute a payload in the v2 format, returning only its status
+    pub fn run_v2_transaction(
+        &mut self,
+        account: &Account,
+        payload: TransactionPayload,
+        replay_protection: ReplayProtection,
+    ) -> Result<TransactionStatus> {
+        let (status, _) = self.run_v2_transaction_with_output(account, payload, replay_protection)?;
+        Ok(status)
+    }
+}
@@ -13,8 +13,13 @@ use std::str::FromStr;
 use std::path::Path;
 
 /// Main pentesting function - called by AptosBB with live mainnet state
-/// Edit this function to write your custom tests against live contracts
 pub fn run_pentest(mut bb: AptosBB) -> Result<()> {
+    run_pentest_on(&mut bb)
+}
+
+/// Pentest body, run against a fork the caller keeps (e.g. to log its fetches afterwards)
+/// Edit this function to write your custom tests against live contracts
+pub fn run_pentest_on(bb: &mut AptosBB) -> Result<()> {
     
     println!("🎯 AptosBB Pentesting Framework");
     println!("Connected to live mainnet state for testing\n");
@@ -41,30 +46,10 @@ pub fn run_pentest(mut bb: AptosBB) -> Result<()> {
     println!();
     
     // Example step2: Deploy custom local module for testing
-    // Update the Move.toml with the attacker's address
-    let move_toml_path = Path::new("./module/Move.toml");
-    let move_toml_content = std::fs::read_to_string(move_toml_path)
-        .expect("Failed to read Move.toml");
-    
-    // Replace the hello_world address with the attacker's address
-    let updated_toml = move_toml_content
-        .lines()
-        .map(|line| {
-            if line.starts_with("hello_world = ") {
-                format!("hello_world = \"{}\"", attacker.address())
-            } else {
-                line.to_string()
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n");
-    
-    std::fs::write(move_toml_path, updated_toml)
-        .expect("Failed to write updated Move.toml");
-    println!("Updated Move.toml with attacker address: {}", attacker.address());
-    
+    bb.begin_scenario("Deploy and call hello_world");
+    // The `hello_world` named address is remapped to the attacker's address at publish time
     let hello_world_path = Path::new("./module");
-    let status = bb.publish_package(&attacker, hello_world_path);
+    let status = bb.publish_package(&attacker, hello_world_path)?;
     println!("Deploy status: {:?}\n", status);
     
     // Call initialize function
@@ -77,7 +62,7 @@ pub fn run_pentest(mut bb: AptosBB) -> Result<()> {
         "initialize",
         vec![],
         vec![],
-    );
+    )?;
     println!("Initialize status: {:?}\n", init_status);
     
     // Call say_hello function
@@ -90,7 +75,7 @@ pub fn run_pentest(mut bb: AptosBB) -> Result<()> {
             "say_hello",
             vec![],
             vec![],
-        );
+        )?;
         println!("  Status: {:?}", hello_status);
     }
     println!();
@@ -145,11 +130,12 @@ pub fn run_pentest(mut bb: AptosBB) -> Result<()> {
     //     "set_paused",
     //     vec![], // no type args
     //     pause_args,
-    // );
+    // )?;
     // println!("set_paused(true) status: {:?}", pause_status); // it should fail because we don't have permission to call
     // println!();
     
     // Example step5: Call swap_exact_in from Thala's stable_pool_scripts
+    bb.begin_scenario("Thala stable pool swap");
     let thala_addr = AccountAddress::from_hex_literal("0x48271d39d0b05bd6efca2278f22277d6fcc375504f9839fd73f74ace240861af").unwrap();
     
     // StablePool<AptosCoin, ShrimpCoin, Null, Null>
@@ -183,7 +169,7 @@ pub fn run_pentest(mut bb: AptosBB) -> Result<()> {
         swap_args,
     );
     
-    let (swap_status, swap_output) = bb.run_transaction_with_output(&attacker, aptos_types::transaction::TransactionPayload::EntryFunction(entry_fn));
+    let (swap_status, swap_output) = bb.run_transaction_with_output(&attacker, aptos_types::transaction::TransactionPayload::EntryFunction(entry_fn))?;
     println!("swap_exact_in status: {:?}", swap_status);
     
     // Display transaction details
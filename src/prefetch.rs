@@ -0,0 +1,113 @@
+use crate::{blocking, AptosBB};
+use anyhow::Result;
+use aptos_framework::{BuildOptions, BuiltPackage};
+use aptos_types::account_address::AccountAddress;
+use futures::future::try_join_all;
+use std::{collections::BTreeSet, path::Path};
+
+/// Hex addresses declared in a package's `Move.toml` `[addresses]` section
+pub fn move_toml_addresses(package_path: &Path) -> Vec<AccountAddress> {
+    let Ok(content) = std::fs::read_to_string(package_path.join("Move.toml")) else {
+        return vec![];
+    };
+    let Ok(manifest) = content.parse::<toml::Table>() else {
+        return vec![];
+    };
+
+    manifest
+        .get("addresses")
+        .and_then(|addresses| addresses.as_table())
+        .map(|addresses| {
+            addresses
+                .values()
+                .filter_map(|value| value.as_str())
+                .filter_map(|value| AccountAddress::from_hex_literal(value).ok())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Addresses of every module the built package calls into, excluding its own modules
+pub fn package_dependency_addresses(package: &BuiltPackage) -> BTreeSet<AccountAddress> {
+    let own: BTreeSet<_> = package.all_modules().map(|module| *module.self_id().address()).collect();
+
+    package
+        .all_modules()
+        .flat_map(|module| module.immediate_dependencies())
+        .map(|module_id| *module_id.address())
+        .filter(|addr| !own.contains(addr))
+        .collect()
+}
+
+impl AptosBB {
+    /// Load the modules and resources of the given accounts concurrently
+    ///
+    /// Keys the fork already holds, e.g. written by cheatcodes, are left as they are.
+    pub async fn prefetch_accounts(&mut self, addrs: &[AccountAddress]) -> Result<usize> {
+        let remote = &self.remote;
+        let fetched = try_join_all(addrs.iter().map(|addr| async move {
+            let (resources, modules) = futures::try_join!(
+                remote.get_account_resources(*addr),
+                remote.get_account_modules(*addr),
+            )?;
+            Ok::<_, anyhow::Error>(resources.into_iter().chain(modules).collect::<Vec<_>>())
+        }))
+        .await?;
+        Ok(self.load_missing(fetched.into_iter().flatten()))
+    }
+
+    /// Build a package while prefetching the on-chain accounts it depends on
+    ///
+    /// Addresses named in `Move.toml` are fetched concurrently with the build; any further
+    /// dependency addresses found in the compiled bytecode are fetched once it finishes.
+    /// Prefetch failures are only reported, since the executor still loads state lazily.
+    pub(crate) fn build_with_prefetch(&mut self, path: &Path, build_options: BuildOptions) -> Result<BuiltPackage> {
+        let declared = move_toml_addresses(path);
+        let package_path = path.to_path_buf();
+
+        blocking::block_on(async {
+            let build = tokio::task::spawn_blocking(move || BuiltPackage::build(package_path, build_options));
+            if let Err(e) = self.prefetch_accounts(&declared).await {
+                eprintln!("Warning: prefetching declared addresses failed: {}", e);
+            }
+            let package = build.await??;
+
+            let remaining: Vec<_> = package_dependency_addresses(&package)
+                .into_iter()
+                .filter(|addr| !declared.contains(addr))
+                .collect();
+            if let Err(e) = self.prefetch_accounts(&remaining).await {
+                eprintln!("Warning: prefetching package dependencies failed: {}", e);
+            }
+
+            Ok(package)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::transaction::{ExecutionStatus, TransactionStatus};
+
+    #[test]
+    #[ignore = "forks mainnet"]
+    fn cheatcode_writes_survive_a_publish() {
+        let mut bb = blocking::block_on(AptosBB::builder().build()).unwrap();
+        let warped = bb.now_secs().unwrap() + 86_400;
+        bb.set_time(warped).unwrap();
+
+        // Naming 0x1 in Move.toml makes the publish prefetch the account holding the clock
+        let attacker = bb.new_account();
+        let status = bb
+            .publish_source(
+                &attacker,
+                "probe",
+                &[("probe", "module probe::probe { public fun noop() {} }")],
+                &[("probe", *attacker.address()), ("clock", AccountAddress::ONE)],
+            )
+            .unwrap();
+        assert_eq!(status, TransactionStatus::Keep(ExecutionStatus::Success));
+        assert_eq!(bb.now_secs().unwrap(), warped);
+    }
+}
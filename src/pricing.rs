@@ -0,0 +1,100 @@
+use crate::{helpers::oracle::Pyth, snapshot::Checkpoint, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_types::account_address::AccountAddress;
+use move_core_types::language_storage::TypeTag;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path, str::FromStr};
+
+/// Asset key of APT in price tables
+pub const APT: &str = "0x1::aptos_coin::AptosCoin";
+
+/// Pyth APT/USD price feed identifier
+pub const PYTH_APT_USD_FEED: &str = "0x03ae4db29ed4ae33d323568895aa00337e658e348b37509f5372ae51f0af00d5";
+
+/// USD price of one whole unit of an asset
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssetPrice {
+    pub usd: f64,
+    /// Decimals of the asset's on-chain amounts, e.g. 8 for APT
+    pub decimals: u8,
+}
+
+/// Normalized key of an asset: a coin type, or the hex address of a fungible asset's metadata
+fn asset_key(asset: &str) -> String {
+    if let Ok(addr) = AccountAddress::from_hex_literal(asset) {
+        return addr.to_hex_literal();
+    }
+    TypeTag::from_str(asset).map(|tag| tag.to_string()).unwrap_or_else(|_| asset.to_string())
+}
+
+/// USD prices used to express balances and profit in the unit bounty severity is judged in
+///
+/// Built either offline, from a TOML table of `"<asset>" = { usd = 8.5, decimals = 8 }`
+/// entries, or from on-chain oracle feeds at the fork version with `AptosBB::pyth_prices`.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PriceTable {
+    #[serde(flatten)]
+    prices: BTreeMap<String, AssetPrice>,
+}
+
+impl PriceTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let table: Self = toml::from_str(&content).map_err(|e| anyhow!("Invalid price table {}: {}", path.display(), e))?;
+        Ok(table.prices.into_iter().fold(Self::new(), |table, (asset, price)| {
+            table.with_price(&asset, price.usd, price.decimals)
+        }))
+    }
+
+    /// Set the USD price of one whole unit of `asset`
+    pub fn with_price(mut self, asset: &str, usd: f64, decimals: u8) -> Self {
+        self.prices.insert(asset_key(asset), AssetPrice { usd, decimals });
+        self
+    }
+
+    pub fn price(&self, asset: &str) -> Option<AssetPrice> {
+        self.prices.get(&asset_key(asset)).copied()
+    }
+
+    /// USD value of a raw on-chain amount (or balance delta) of `asset`
+    pub fn usd_value(&self, asset: &str, amount: i128) -> Option<f64> {
+        let price = self.price(asset)?;
+        Some(amount as f64 / 10f64.powi(price.decimals as i32) * price.usd)
+    }
+
+    /// USD value of an amount of APT in octas
+    pub fn apt_usd(&self, octas: i128) -> Option<f64> {
+        self.usd_value(APT, octas)
+    }
+}
+
+impl AptosBB {
+    /// Price table read from Pyth feeds at the fork version
+    ///
+    /// `feeds` lists `(asset, feed identifier, decimals)`; APT is always included. Prices
+    /// reflect the fork's current state, `set_price` overrides and manipulated feeds
+    /// included, so read them before a scenario runs or use `pyth_prices_at`. Fails on
+    /// networks without a known Pyth deployment (see `Pyth::for_network`).
+    pub fn pyth_prices(&mut self, feeds: &[(&str, &str, u8)]) -> Result<PriceTable> {
+        let pyth = Pyth::for_network(&self.network)?;
+        let mut table = PriceTable::new();
+        for (asset, feed, decimals) in [(APT, PYTH_APT_USD_FEED, 8)].iter().chain(feeds) {
+            let feed = hex::decode(feed.trim_start_matches("0x"))?;
+            table = table.with_price(asset, pyth.price(self, &feed)?, *decimals);
+        }
+        Ok(table)
+    }
+
+    /// `pyth_prices` as of a checkpoint, e.g. one taken at session start, leaving the fork as
+    /// it is
+    pub fn pyth_prices_at(&mut self, checkpoint: &Checkpoint, feeds: &[(&str, &str, u8)]) -> Result<PriceTable> {
+        self.with_snapshot(|bb| {
+            bb.revert(checkpoint);
+            bb.pyth_prices(feeds)
+        })
+    }
+}
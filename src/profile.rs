@@ -0,0 +1,124 @@
+use crate::AptosBB;
+use anyhow::{anyhow, Context, Result};
+use aptos_crypto::{
+    ed25519::{Ed25519PrivateKey, Ed25519PublicKey},
+    ValidCryptoMaterialStringExt,
+};
+use aptos_language_e2e_tests::account::Account;
+use aptos_rest_client::AptosBaseUrl;
+use aptos_types::account_address::AccountAddress;
+use serde::Deserialize;
+use std::{collections::BTreeMap, path::PathBuf};
+use url::Url;
+
+/// A single profile from the Aptos CLI `config.yaml`
+#[derive(Clone, Debug, Deserialize)]
+pub struct CliProfile {
+    pub network: Option<String>,
+    pub private_key: Option<String>,
+    pub public_key: Option<String>,
+    pub account: Option<String>,
+    pub rest_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CliConfig {
+    #[serde(default)]
+    profiles: BTreeMap<String, CliProfile>,
+}
+
+/// Locate the Aptos CLI config, preferring the workspace `.aptos/` over the global one
+fn cli_config_path() -> Result<PathBuf> {
+    let workspace = PathBuf::from(".aptos/config.yaml");
+    if workspace.exists() {
+        return Ok(workspace);
+    }
+
+    let home = std::env::var("HOME").context("HOME is not set")?;
+    let global = PathBuf::from(home).join(".aptos/config.yaml");
+    if global.exists() {
+        Ok(global)
+    } else {
+        Err(anyhow!("No Aptos CLI config found in .aptos/ or ~/.aptos/"))
+    }
+}
+
+/// Load a named profile from the Aptos CLI config
+pub fn load_cli_profile(name: &str) -> Result<CliProfile> {
+    let path = cli_config_path()?;
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut config: CliConfig = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+    config.profiles.remove(name)
+        .ok_or_else(|| anyhow!("Profile '{}' not found in {}", name, path.display()))
+}
+
+/// Strip the AIP-80 prefix (`ed25519-priv-`) newer CLI versions write in front of keys
+fn strip_key_prefix(key: &str) -> &str {
+    key.strip_prefix("ed25519-priv-")
+        .or_else(|| key.strip_prefix("ed25519-pub-"))
+        .unwrap_or(key)
+}
+
+impl CliProfile {
+    /// Network the profile points at, falling back to its REST URL for custom networks
+    pub fn base_url(&self) -> Result<AptosBaseUrl> {
+        match self.network.as_deref().map(str::to_lowercase).as_deref() {
+            Some("mainnet") => Ok(AptosBaseUrl::Mainnet),
+            Some("testnet") => Ok(AptosBaseUrl::Testnet),
+            Some("devnet") => Ok(AptosBaseUrl::Devnet),
+            _ => {
+                let rest_url = self.rest_url.as_deref()
+                    .ok_or_else(|| anyhow!("Profile has neither a known network nor a rest_url"))?;
+                Ok(AptosBaseUrl::Custom(Url::parse(rest_url)?))
+            }
+        }
+    }
+
+    /// Build an `Account` from the profile's address and key pair
+    pub fn to_account(&self) -> Result<Account> {
+        let private_key = self.private_key.as_deref()
+            .ok_or_else(|| anyhow!("Profile has no private key"))?;
+        let private_key = Ed25519PrivateKey::from_encoded_string(strip_key_prefix(private_key))
+            .map_err(|e| anyhow!("Invalid private key: {}", e))?;
+        let public_key = Ed25519PublicKey::from(&private_key);
+
+        let account = self.account.as_deref()
+            .ok_or_else(|| anyhow!("Profile has no account address"))?;
+        let address = AccountAddress::from_hex_literal(&format!("0x{}", account.trim_start_matches("0x")))?;
+
+        Ok(Account::new_from_addr(address, private_key, public_key))
+    }
+}
+
+/// Import accounts from Aptos CLI profiles
+pub trait CliProfileAccount: Sized {
+    fn from_cli_profile(name: &str) -> Result<Self>;
+}
+
+impl CliProfileAccount for Account {
+    fn from_cli_profile(name: &str) -> Result<Self> {
+        load_cli_profile(name)?.to_account()
+    }
+}
+
+impl AptosBB {
+    /// Fork the network configured in the named Aptos CLI profile
+    pub async fn from_profile_network(profile: &str) -> Result<Self> {
+        let profile = load_cli_profile(profile)?;
+        Ok(Self::builder().network(profile.base_url()?).build().await?)
+    }
+
+    /// Register an existing on-chain account (e.g. from a CLI profile) with the fork
+    ///
+    /// Sequence numbers are picked up from the forked state so the account's next
+    /// transaction is accepted.
+    pub fn import_account(&mut self, account: &Account) -> Result<()> {
+        let account_resource = self.read_account_resource_at_address(account.address())
+            .ok_or_else(|| anyhow!("Account {} does not exist on the fork", account.address()))?;
+        self.sequence_numbers.insert(*account.address(), account_resource.sequence_number());
+        Ok(())
+    }
+}
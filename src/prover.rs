@@ -0,0 +1,146 @@
+use crate::{
+    findings::{Category, Finding, Severity},
+    AptosBB,
+};
+use anyhow::Result;
+use aptos_types::account_address::AccountAddress;
+use aptos_framework::{extended_checks, prover::ProverOptions};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+/// Package to run the Move Prover on
+#[derive(Clone, Debug)]
+pub enum ProveTarget {
+    /// A package on the local filesystem
+    Local(PathBuf),
+    /// A package published on chain, fetched from its on-chain sources
+    OnChain { address: AccountAddress, package: String },
+}
+
+/// How the prover's error ends when specs were checked and some do not hold
+const VERIFICATION_FAILED: &str = "exiting with verification errors";
+
+/// Result of a prover run
+#[derive(Clone, Debug)]
+pub struct ProverReport {
+    /// Package that was proven: its directory, or `addr::package` for an on-chain package
+    pub target: String,
+    pub passed: bool,
+    /// Prover diagnostics, one entry per reported error (violated spec, timeout, ...)
+    pub violations: Vec<String>,
+}
+
+impl ProverReport {
+    /// One finding per verification error, none when every spec holds
+    ///
+    /// Violated specs are confirmed against the spec, not the chain, so they rate low until
+    /// shown exploitable.
+    pub fn findings(&self) -> Vec<Finding> {
+        self.violations
+            .iter()
+            .map(|violation| {
+                Finding::new("Move Prover spec violated", Severity::Low, Category::Logic)
+                    .affected(&self.target)
+                    .evidence(violation.as_str())
+            })
+            .collect()
+    }
+}
+
+/// Split the prover's combined error output into individual diagnostics
+fn split_diagnostics(output: &str) -> Vec<String> {
+    output
+        .split("\nerror")
+        .map(str::trim)
+        .filter(|chunk| !chunk.is_empty())
+        .map(|chunk| {
+            if chunk.starts_with("error") {
+                chunk.to_string()
+            } else {
+                format!("error{}", chunk)
+            }
+        })
+        .collect()
+}
+
+/// Run the Move Prover on a package directory
+///
+/// Requires the Boogie and Z3 toolchain the Aptos CLI uses (`BOOGIE_EXE`, `Z3_EXE`). Violated
+/// specs are reported in the `ProverReport`; failing to run the prover at all is an error.
+pub fn prove_path(package_path: &Path) -> Result<ProverReport> {
+    let outcome = ProverOptions::default().prove(
+        false,
+        package_path,
+        BTreeMap::new(),
+        None,
+        None,
+        None,
+        false,
+        &extended_checks::get_all_attribute_names(),
+        &[],
+    );
+
+    let violations = match outcome {
+        Ok(()) => vec![],
+        Err(e) => {
+            let output = format!("{:#}", e);
+            // Anything but failed verification (missing Boogie/Z3, compile errors, timeouts of
+            // the tool itself) says nothing about the specs
+            if !output.contains(VERIFICATION_FAILED) {
+                return Err(e.context(format!("Move Prover failed to run on {}", package_path.display())));
+            }
+            split_diagnostics(&output)
+        }
+    };
+    Ok(ProverReport {
+        target: package_path.display().to_string(),
+        passed: violations.is_empty(),
+        violations,
+    })
+}
+
+impl AptosBB {
+    /// Run the Move Prover on a target package and report each violated spec as a finding
+    pub fn prove_package(&mut self, target: ProveTarget) -> Result<ProverReport> {
+        let report = match target {
+            ProveTarget::Local(path) => prove_path(&path)?,
+            ProveTarget::OnChain { address, package } => {
+                let dir = tempfile::Builder::new().prefix("aptosbb-prove-").tempdir()?;
+                self.fetch_package_source(address, &package, dir.path(), false)?;
+                ProverReport {
+                    target: format!("{}::{}", address, package),
+                    ..prove_path(dir.path())?
+                }
+            }
+        };
+
+        if report.passed {
+            println!("Prover: all specs hold for {}", report.target);
+        } else {
+            println!("Prover: {} violation(s) in {}", report.violations.len(), report.target);
+        }
+        for finding in report.findings() {
+            self.report_finding(finding);
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn findings_only_for_verification_errors() {
+        let passed = ProverReport { target: "0x1::pool".to_string(), passed: true, violations: vec![] };
+        assert!(passed.findings().is_empty());
+
+        let output = "error: post-condition does not hold\n  a\nerror: abort not covered\n  b\nexiting with verification errors";
+        let failed = ProverReport { target: "0x1::pool".to_string(), passed: false, violations: split_diagnostics(output) };
+        let findings = failed.findings();
+        assert_eq!(findings.len(), 2);
+        assert!(findings.iter().all(|finding| finding.affected == ["0x1::pool"]));
+    }
+}
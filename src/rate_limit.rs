@@ -0,0 +1,189 @@
+use anyhow::{bail, Result};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex as SyncMutex,
+    },
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+/// Client-side request budget for remote state reads
+#[derive(Clone, Copy, Debug)]
+pub struct RateLimit {
+    /// Sustained requests per second
+    pub requests_per_second: f64,
+    /// Requests that may be issued back-to-back before throttling kicks in
+    pub burst: u32,
+}
+
+impl RateLimit {
+    /// Conservative budget for the anonymous public endpoint
+    pub fn anonymous() -> Self {
+        Self { requests_per_second: 2.0, burst: 5 }
+    }
+
+    /// Budget for connections authenticated with an API key
+    pub fn with_api_key() -> Self {
+        Self { requests_per_second: 20.0, burst: 40 }
+    }
+
+    /// A budget of `requests_per_second` sustained requests with bursts of up to `burst`
+    pub fn new(requests_per_second: f64, burst: u32) -> Result<Self> {
+        let limit = Self { requests_per_second, burst };
+        limit.validate()?;
+        Ok(limit)
+    }
+
+    /// Refuse budgets the limiter could never honor: a rate that is not a positive finite
+    /// number, or a burst too small to ever let a request through
+    pub fn validate(&self) -> Result<()> {
+        if !self.requests_per_second.is_finite() || self.requests_per_second <= 0.0 {
+            bail!("Rate limit must allow a positive, finite number of requests per second, got {}", self.requests_per_second);
+        }
+        if self.burst == 0 {
+            bail!("Rate limit burst must be at least 1");
+        }
+        Ok(())
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// Token-bucket limiter shared by every request of a `RemoteState`: the executor's lazy
+/// reads, batched fetches and direct REST calls through `RemoteState::client`
+///
+/// Also counts the requests it lets through, which may be capped with a budget and a deadline.
+pub struct RateLimiter {
+    limit: RateLimit,
+    bucket: Mutex<Bucket>,
+    requests: AtomicU64,
+    /// Total request count after which requests are refused, `u64::MAX` when unbounded
+    budget: AtomicU64,
+    /// Time after which requests are refused
+    deadline: SyncMutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    /// Fails if `limit` is invalid (see `RateLimit::validate`)
+    pub fn new(limit: RateLimit) -> Result<Self> {
+        limit.validate()?;
+        Ok(Self {
+            limit,
+            bucket: Mutex::new(Bucket {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+            }),
+            requests: AtomicU64::new(0),
+            budget: AtomicU64::new(u64::MAX),
+            deadline: SyncMutex::new(None),
+        })
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Requests issued so far
+    pub fn requests(&self) -> u64 {
+        self.requests.load(Ordering::SeqCst)
+    }
+
+    /// Refuse requests once `budget` requests have been issued in total, or never with `None`
+    pub fn set_budget(&self, budget: Option<u64>) {
+        self.budget.store(budget.unwrap_or(u64::MAX), Ordering::SeqCst);
+    }
+
+    /// Refuse requests after `deadline`, or never with `None`
+    pub fn set_deadline(&self, deadline: Option<Instant>) {
+        *self.deadline.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = deadline;
+    }
+
+    /// Wait until a request may be issued, failing once the request budget is exhausted or
+    /// the deadline has passed
+    pub async fn acquire(&self) -> Result<()> {
+        let deadline = *self.deadline.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            bail!("Remote request deadline passed");
+        }
+        let budget = self.budget.load(Ordering::SeqCst);
+        if self.requests.fetch_add(1, Ordering::SeqCst) >= budget {
+            self.requests.fetch_sub(1, Ordering::SeqCst);
+            bail!("Remote request budget of {} requests exhausted", budget);
+        }
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                let now = Instant::now();
+                let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+                bucket.tokens = (bucket.tokens + elapsed * self.limit.requests_per_second)
+                    .min(self.limit.burst as f64);
+                bucket.refilled_at = now;
+
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return Ok(());
+                }
+                (1.0 - bucket.tokens) / self.limit.requests_per_second
+            };
+            tokio::time::sleep(Duration::from_secs_f64(wait)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimit { requests_per_second: 1_000.0, burst: 10 }).unwrap()
+    }
+
+    #[tokio::test]
+    async fn budget_refuses_requests_past_it() {
+        let limiter = limiter();
+        limiter.set_budget(Some(3));
+        for _ in 0..3 {
+            limiter.acquire().await.unwrap();
+        }
+        assert!(limiter.acquire().await.is_err());
+        assert_eq!(limiter.requests(), 3);
+        limiter.set_budget(None);
+        limiter.acquire().await.unwrap();
+        assert_eq!(limiter.requests(), 4);
+    }
+
+    #[tokio::test]
+    async fn deadline_refuses_requests_after_it() {
+        let limiter = limiter();
+        limiter.set_deadline(Some(Instant::now()));
+        assert!(limiter.acquire().await.is_err());
+        assert_eq!(limiter.requests(), 0);
+        limiter.set_deadline(Some(Instant::now() + Duration::from_secs(60)));
+        limiter.acquire().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn throttles_past_the_burst() {
+        let limiter = RateLimiter::new(RateLimit { requests_per_second: 20.0, burst: 2 }).unwrap();
+        let started = Instant::now();
+        for _ in 0..4 {
+            limiter.acquire().await.unwrap();
+        }
+        assert!(started.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[test]
+    fn refuses_limits_it_could_never_honor() {
+        for requests_per_second in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            assert!(RateLimit::new(requests_per_second, 5).is_err());
+            assert!(RateLimiter::new(RateLimit { requests_per_second, burst: 5 }).is_err());
+        }
+        assert!(RateLimit::new(2.0, 0).is_err());
+        assert!(RateLimiter::new(RateLimit { requests_per_second: 2.0, burst: 0 }).is_err());
+        assert!(RateLimit::new(0.5, 1).is_ok());
+    }
+}
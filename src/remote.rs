@@ -0,0 +1,363 @@
+use crate::{
+    blocking,
+    cache::StateCache,
+    rate_limit::{RateLimit, RateLimiter},
+    sandbox::ReadOnlyClient,
+    state_keys::declared_group,
+};
+use anyhow::Result;
+use aptos_rest_client::{error::RestError, AptosBaseUrl, Client};
+use aptos_types::{
+    access_path::Path,
+    account_address::AccountAddress,
+    chain_id::ChainId,
+    state_store::{
+        state_key::{inner::StateKeyInner, StateKey},
+        errors::StateViewError,
+        state_storage_usage::StateStorageUsage,
+        state_value::StateValue,
+        StateViewResult, TStateView,
+    },
+};
+use futures::{stream, StreamExt, TryStreamExt};
+use move_binary_format::CompiledModule;
+use move_core_types::language_storage::{ModuleId, StructTag};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+/// Maximum number of single-key requests kept in flight by `get_state_values`
+const MAX_CONCURRENT_FETCHES: usize = 16;
+
+/// Every read of on-chain state, by AptosBB itself and by the executor's lazy reads
+///
+/// Reads are pinned to the fork version, served from memory once fetched, go through the
+/// persistent `StateCache` when one is configured and count against the rate limiter.
+/// Clones share all of that; the executor holds one as its state view.
+#[derive(Clone)]
+pub struct RemoteState {
+    client: ReadOnlyClient,
+    chain_id: ChainId,
+    version: u64,
+    cache: Option<Arc<StateCache>>,
+    limiter: Arc<RateLimiter>,
+    /// Values read so far, `None` for keys absent on chain
+    fetched: Arc<Mutex<HashMap<StateKey, Option<StateValue>>>>,
+    /// Keys deleted on the fork, which the executor's lazy reads must not bring back
+    deleted: Arc<Mutex<HashSet<StateKey>>>,
+}
+
+/// Create a read-only REST client for a network, authenticated when an API key is given
+pub fn rest_client(network: &AptosBaseUrl, api_key: Option<&str>) -> Result<ReadOnlyClient> {
+    let mut builder = Client::builder(network.clone());
+    if let Some(api_key) = api_key {
+        builder = builder.api_key(api_key)?;
+    }
+    Ok(ReadOnlyClient::new(builder.build()))
+}
+
+fn is_not_found(err: &RestError) -> bool {
+    matches!(err, RestError::Api(e) if e.status_code.as_u16() == 404)
+}
+
+impl RemoteState {
+    pub fn new(client: ReadOnlyClient, chain_id: ChainId, version: u64, cache: Option<StateCache>, limiter: RateLimiter) -> Self {
+        Self {
+            client,
+            chain_id,
+            version,
+            cache: cache.map(Arc::new),
+            limiter: Arc::new(limiter),
+            fetched: Default::default(),
+            deleted: Default::default(),
+        }
+    }
+
+    /// A view of the same network pinned to another version, sharing cache and rate limit
+    pub fn at_version(&self, version: u64) -> Self {
+        Self {
+            client: self.client.clone(),
+            chain_id: self.chain_id,
+            version,
+            cache: self.cache.clone(),
+            limiter: self.limiter.clone(),
+            fetched: Default::default(),
+            deleted: Default::default(),
+        }
+    }
+
+    /// The REST client, once the rate limiter lets another request through
+    pub async fn client(&self) -> Result<&ReadOnlyClient> {
+        self.limiter.acquire().await?;
+        Ok(&self.client)
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn cache(&self) -> Option<&StateCache> {
+        self.cache.as_deref()
+    }
+
+    pub fn rate_limit(&self) -> RateLimit {
+        self.limiter.limit()
+    }
+
+    pub fn limiter(&self) -> &RateLimiter {
+        &self.limiter
+    }
+
+    /// Keys read so far at this version, whether or not they exist on chain
+    pub fn fetched_keys(&self) -> Vec<StateKey> {
+        self.fetched.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Make the executor's lazy reads see `state_key` as absent, whatever the chain holds
+    pub(crate) fn delete(&self, state_key: &StateKey) {
+        self.deleted.lock().unwrap().insert(state_key.clone());
+    }
+
+    /// Keys deleted on the fork so far
+    pub(crate) fn deleted_keys(&self) -> HashSet<StateKey> {
+        self.deleted.lock().unwrap().clone()
+    }
+
+    /// Replace the set of deleted keys, e.g. when reverting to a checkpoint
+    pub(crate) fn set_deleted_keys(&self, keys: HashSet<StateKey>) {
+        *self.deleted.lock().unwrap() = keys;
+    }
+
+    pub(crate) fn is_deleted(&self, state_key: &StateKey) -> bool {
+        self.deleted.lock().unwrap().contains(state_key)
+    }
+
+    fn fetched_value(&self, state_key: &StateKey) -> Option<Option<StateValue>> {
+        self.fetched.lock().unwrap().get(state_key).cloned()
+    }
+
+    fn record_fetched(&self, state_key: &StateKey, value: Option<&StateValue>) {
+        self.fetched.lock().unwrap().insert(state_key.clone(), value.cloned());
+    }
+
+    /// A state value known without a request: deleted on the fork, read before or in the
+    /// persistent cache. `None` when only the network can tell.
+    pub(crate) fn get_state_value_without_request(&self, state_key: &StateKey) -> Result<Option<Option<StateValue>>> {
+        if self.is_deleted(state_key) {
+            return Ok(Some(None));
+        }
+        if let Some(value) = self.fetched_value(state_key) {
+            return Ok(Some(value));
+        }
+        if let Some(cache) = &self.cache {
+            if let Some(value) = cache.get(self.chain_id, self.version, state_key)? {
+                self.record_fetched(state_key, value.as_ref());
+                return Ok(Some(value));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetch a single state value at the fork version
+    pub async fn get_state_value(&self, state_key: &StateKey) -> Result<Option<StateValue>> {
+        if let Some(value) = self.fetched_value(state_key) {
+            return Ok(value);
+        }
+        if let Some(cache) = &self.cache {
+            if let Some(value) = cache.get(self.chain_id, self.version, state_key)? {
+                self.record_fetched(state_key, value.as_ref());
+                return Ok(value);
+            }
+        }
+
+        self.limiter.acquire().await?;
+        let value = match self.client.get_raw_state_value(state_key, self.version).await {
+            Ok(response) => Some(bcs::from_bytes::<StateValue>(&response.into_inner())?),
+            Err(err) if is_not_found(&err) => None,
+            Err(err) => return Err(err.into()),
+        };
+
+        if let Some(cache) = &self.cache {
+            cache.put(self.chain_id, self.version, state_key, value.as_ref())?;
+        }
+        self.record_fetched(state_key, value.as_ref());
+
+        Ok(value)
+    }
+}
+
+/// The executor's lazy reads, so they share the cache, rate limit and fetch record, and
+/// keys deleted on the fork stay deleted
+impl TStateView for RemoteState {
+    type Key = StateKey;
+
+    fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+        if self.is_deleted(state_key) {
+            return Ok(None);
+        }
+        blocking::block_on(RemoteState::get_state_value(self, state_key))
+            .map_err(|e| StateViewError::Other(format!("{:#}", e)))
+    }
+
+    fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+        Ok(StateStorageUsage::new_untracked())
+    }
+}
+
+impl RemoteState {
+    /// Resource group a struct is declared a member of, from its module's metadata
+    async fn resource_group_of(&self, struct_tag: &StructTag) -> Result<Option<StructTag>> {
+        let module_key = StateKey::module(&struct_tag.address, &struct_tag.module);
+        let Some(module) = self.get_state_value(&module_key).await? else {
+            return Ok(None);
+        };
+        Ok(declared_group(&CompiledModule::deserialize(module.bytes())?, struct_tag))
+    }
+
+    /// Keys of every resource stored under an account, listed with a single request
+    ///
+    /// The API lists resource-group members one by one, but on chain they only exist inside
+    /// their group's slot, so members are listed under their group's
+    /// `StateKey::resource_group` key instead.
+    async fn list_account_resources(&self, addr: AccountAddress) -> Result<HashSet<StateKey>> {
+        self.limiter.acquire().await?;
+        let resources = match self.client.get_account_resources_at_version_bcs(addr, self.version).await {
+            Ok(response) => response.into_inner(),
+            Err(err) if is_not_found(&err) => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut keys = HashSet::new();
+        for struct_tag in resources.into_keys() {
+            let state_key = match self.resource_group_of(&struct_tag).await? {
+                Some(group) => StateKey::resource_group(&addr, &group),
+                None => StateKey::resource(&addr, &struct_tag)?,
+            };
+            keys.insert(state_key);
+        }
+        Ok(keys)
+    }
+
+    /// Keys of every module published under an account, listed with a single request
+    async fn list_account_modules(&self, addr: AccountAddress) -> Result<HashSet<StateKey>> {
+        self.limiter.acquire().await?;
+        let modules = match self.client.get_account_modules_bcs_at_version(addr, self.version).await {
+            Ok(response) => response.into_inner(),
+            Err(err) if is_not_found(&err) => BTreeMap::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(modules
+            .into_keys()
+            .map(|module_id| StateKey::module_id(&ModuleId::from(module_id)))
+            .collect())
+    }
+
+    /// Fetch every resource (and resource group) stored under an account
+    ///
+    /// The account is listed with one request, but the listing carries no storage metadata,
+    /// so each value is then read on its own like any other key (and cached as such).
+    pub async fn get_account_resources(&self, addr: AccountAddress) -> Result<Vec<(StateKey, StateValue)>> {
+        let keys: Vec<StateKey> = self.list_account_resources(addr).await?.into_iter().collect();
+        self.get_existing_values(keys).await
+    }
+
+    /// Fetch every module published under an account, listed like `get_account_resources`
+    pub async fn get_account_modules(&self, addr: AccountAddress) -> Result<Vec<(StateKey, StateValue)>> {
+        let keys: Vec<StateKey> = self.list_account_modules(addr).await?.into_iter().collect();
+        self.get_existing_values(keys).await
+    }
+
+    /// Fetch keys individually with bounded concurrency, keeping the ones that exist
+    async fn get_existing_values(&self, keys: Vec<StateKey>) -> Result<Vec<(StateKey, StateValue)>> {
+        let values: Vec<(StateKey, Option<StateValue>)> = self.get_each(keys).await?;
+        Ok(values
+            .into_iter()
+            .filter_map(|(key, value)| Some((key, value?)))
+            .collect())
+    }
+
+    /// Fetch keys individually with bounded concurrency
+    async fn get_each(&self, keys: Vec<StateKey>) -> Result<Vec<(StateKey, Option<StateValue>)>> {
+        stream::iter(keys)
+            .map(|key| async move {
+                let value = self.get_state_value(&key).await?;
+                Ok::<_, anyhow::Error>((key, value))
+            })
+            .buffer_unordered(MAX_CONCURRENT_FETCHES)
+            .try_collect()
+            .await
+    }
+
+    /// Record that a key does not exist on chain, in memory and in the persistent cache
+    fn record_absent(&self, state_key: &StateKey) -> Result<()> {
+        if let Some(cache) = &self.cache {
+            cache.put(self.chain_id, self.version, state_key, None)?;
+        }
+        self.record_fetched(state_key, None);
+        Ok(())
+    }
+
+    /// Fetch many keys at once
+    ///
+    /// Plain resource and module keys are grouped by account and checked against one
+    /// resources/modules listing per account, so keys absent on chain cost no request of
+    /// their own. Every existing key (and every resource group, table item or raw key) is
+    /// then fetched individually with bounded concurrency, keeping its storage metadata.
+    pub async fn get_state_values(&self, keys: &[StateKey]) -> Result<HashMap<StateKey, Option<StateValue>>> {
+        let mut results = HashMap::with_capacity(keys.len());
+        let mut resource_accounts = BTreeMap::<AccountAddress, Vec<StateKey>>::new();
+        let mut module_accounts = BTreeMap::<AccountAddress, Vec<StateKey>>::new();
+        let mut singles = Vec::new();
+
+        for key in keys {
+            if let Some(value) = self.fetched_value(key) {
+                results.insert(key.clone(), value);
+                continue;
+            }
+            if let Some(cache) = &self.cache {
+                if let Some(value) = cache.get(self.chain_id, self.version, key)? {
+                    self.record_fetched(key, value.as_ref());
+                    results.insert(key.clone(), value);
+                    continue;
+                }
+            }
+            match key.inner() {
+                StateKeyInner::AccessPath(access_path) => match access_path.get_path() {
+                    Path::Resource(_) => resource_accounts.entry(access_path.address).or_default().push(key.clone()),
+                    Path::Code(_) => module_accounts.entry(access_path.address).or_default().push(key.clone()),
+                    Path::ResourceGroup(_) => singles.push(key.clone()),
+                },
+                _ => singles.push(key.clone()),
+            }
+        }
+
+        for (addr, wanted) in resource_accounts {
+            let listed = self.list_account_resources(addr).await?;
+            for key in wanted {
+                if listed.contains(&key) {
+                    singles.push(key);
+                } else {
+                    self.record_absent(&key)?;
+                    results.insert(key, None);
+                }
+            }
+        }
+
+        for (addr, wanted) in module_accounts {
+            let listed = self.list_account_modules(addr).await?;
+            for key in wanted {
+                if listed.contains(&key) {
+                    singles.push(key);
+                } else {
+                    self.record_absent(&key)?;
+                    results.insert(key, None);
+                }
+            }
+        }
+
+        results.extend(self.get_each(singles).await?);
+
+        Ok(results)
+    }
+}
@@ -0,0 +1,272 @@
+use crate::{blocking, calls::DecodedCall, export::function_name, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_crypto::HashValue;
+use aptos_language_e2e_tests::account::Account;
+use aptos_api_types::TransactionData;
+use aptos_types::{
+    account_address::AccountAddress,
+    contract_event::ContractEvent,
+    transaction::{
+        ExecutionStatus, SignedTransaction, Transaction, TransactionOutput, TransactionPayload, TransactionStatus,
+    },
+};
+
+/// A transaction the attacker injects around the victim's
+pub struct AttackerTxn {
+    pub account: Account,
+    pub payload: TransactionPayload,
+}
+
+impl AttackerTxn {
+    pub fn new(account: Account, payload: TransactionPayload) -> Self {
+        Self { account, payload }
+    }
+}
+
+/// Observable result of executing one transaction
+#[derive(Clone, Debug)]
+pub struct TxnOutcome {
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    pub events: Vec<ContractEvent>,
+}
+
+impl From<&TransactionOutput> for TxnOutcome {
+    fn from(output: &TransactionOutput) -> Self {
+        Self {
+            status: output.status().clone(),
+            gas_used: output.gas_used(),
+            events: output.events().to_vec(),
+        }
+    }
+}
+
+/// Victim outcome with and without the attacker's transactions around it
+#[derive(Clone, Debug)]
+pub struct FrontrunReport {
+    pub victim_version: u64,
+    /// The victim's entry function call, decoded through its ABI
+    pub victim_call: Option<DecodedCall>,
+    /// The victim executed alone, as it did on chain
+    pub baseline: TxnOutcome,
+    /// The victim executed after the attacker's front-running transactions
+    pub attacked: TxnOutcome,
+    pub front_run: Vec<TxnOutcome>,
+    pub back_run: Vec<TxnOutcome>,
+    /// Change in APT balance of every attacker account over the whole sequence
+    pub attacker_apt_delta: Vec<(AccountAddress, i128)>,
+}
+
+impl FrontrunReport {
+    /// Whether the attack changed the victim's status or emitted events
+    pub fn victim_affected(&self) -> bool {
+        self.baseline.status != self.attacked.status || self.baseline.events != self.attacked.events
+    }
+}
+
+impl AptosBB {
+    /// Fork the same network at another version, keeping API key, cache, gas and VM settings
+    pub fn fork_at(&self, version: u64) -> crate::error::Result<AptosBB> {
+        let mut builder = AptosBB::builder()
+            .network(self.network.clone())
+            .version(version)
+            .gas_defaults(self.gas_defaults)
+            .vm_version(self.vm.version);
+        if let Some(api_key) = &self.api_key {
+            builder = builder.api_key(api_key);
+        }
+        if let Some(dir) = &self.cache_dir {
+            builder = builder.cache(dir);
+        }
+        blocking::block_on(builder.build())
+    }
+
+    /// Look up a committed user transaction and the version it was executed at
+    pub fn fetch_user_transaction(&self, txn_hash: &str) -> Result<(u64, SignedTransaction)> {
+        let hash = HashValue::from_hex(txn_hash.trim_start_matches("0x"))?;
+        let data = blocking::block_on(async { anyhow::Ok(self.remote.client().await?.get_transaction_by_hash_bcs(hash).await?) })?
+            .into_inner();
+        let TransactionData::OnChain(txn) = data else {
+            return Err(anyhow!("Transaction {} is still pending", txn_hash));
+        };
+        match txn.transaction {
+            Transaction::UserTransaction(signed) => Ok((txn.version, signed)),
+            _ => Err(anyhow!("Transaction {} is not a user transaction", txn_hash)),
+        }
+    }
+
+    /// Execute an already signed transaction (e.g. a historical one) on the fork
+    pub fn execute_signed(&mut self, txn: SignedTransaction) -> TransactionOutput {
+        let output = self.executor.execute_and_apply(txn.clone());
+        self.module_cache.invalidate(output.write_set());
+        self.view_cache.invalidate();
+        self.notify_applied(&txn, &output);
+        output
+    }
+
+    /// Replay a historical transaction with attacker transactions inserted before (and after) it
+    ///
+    /// Forks just before the victim's version twice: once to execute the victim alone, once to
+    /// execute `front_run`, the victim and `back_run` in sequence as if in the same block.
+    /// Attacker accounts that do not exist on chain are created on the fork.
+    pub fn replay_with_frontrun(
+        &self,
+        victim_txn_hash: &str,
+        front_run: Vec<AttackerTxn>,
+        back_run: Vec<AttackerTxn>,
+    ) -> Result<FrontrunReport> {
+        let (victim_version, victim) = self.fetch_user_transaction(victim_txn_hash)?;
+        let fork_version = victim_version
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("Cannot fork before genesis"))?;
+        println!("Replaying {} (version {}) from version {}", victim_txn_hash, victim_version, fork_version);
+
+        let mut baseline_bb = self.fork_at(fork_version)?;
+        let victim_call = baseline_bb.decode_payload(victim.payload());
+        if let Some(call) = &victim_call {
+            println!("  {}", call);
+        }
+        let baseline = TxnOutcome::from(&baseline_bb.execute_signed(victim.clone()));
+
+        let mut bb = self.fork_at(fork_version)?;
+        let mut attackers: Vec<AccountAddress> = front_run
+            .iter()
+            .chain(&back_run)
+            .map(|txn| *txn.account.address())
+            .collect();
+        attackers.sort();
+        attackers.dedup();
+        for addr in &attackers {
+            match bb.read_account_resource_at_address(addr) {
+                Some(resource) => {
+                    bb.sequence_numbers.insert(*addr, resource.sequence_number());
+                }
+                None => {
+                    bb.new_account_at(*addr);
+                }
+            }
+        }
+        let balances_before: Vec<u64> = attackers.iter().map(|addr| bb.read_aptos_balance(addr)).collect();
+
+        let front_run = front_run
+            .into_iter()
+            .map(|txn| Ok(TxnOutcome::from(&bb.run_transaction_with_output(&txn.account, txn.payload)?.1)))
+            .collect::<Result<_>>()?;
+        let attacked = TxnOutcome::from(&bb.execute_signed(victim));
+        let back_run = back_run
+            .into_iter()
+            .map(|txn| Ok(TxnOutcome::from(&bb.run_transaction_with_output(&txn.account, txn.payload)?.1)))
+            .collect::<Result<_>>()?;
+
+        let attacker_apt_delta = attackers
+            .iter()
+            .zip(balances_before)
+            .map(|(addr, before)| (*addr, bb.read_aptos_balance(addr) as i128 - before as i128))
+            .collect();
+
+        Ok(FrontrunReport {
+            victim_version,
+            victim_call,
+            baseline,
+            attacked,
+            front_run,
+            back_run,
+            attacker_apt_delta,
+        })
+    }
+}
+
+/// A historical transaction of the account and its outcome when re-executed on the fork
+#[derive(Clone, Debug)]
+pub struct ReplayedTxn {
+    pub version: u64,
+    pub txn: SignedTransaction,
+    /// The entry function call, decoded through its ABI as of just before it executed
+    pub call: Option<DecodedCall>,
+    pub onchain_status: ExecutionStatus,
+    pub onchain_gas_used: u64,
+    pub replayed: TxnOutcome,
+}
+
+impl ReplayedTxn {
+    /// Whether the re-execution ended differently from the committed transaction
+    pub fn diverged(&self) -> bool {
+        self.replayed.status != TransactionStatus::Keep(self.onchain_status.clone())
+    }
+}
+
+/// An account's recent transactions re-executed in order on a fork pinned just before them
+pub struct AccountHistory {
+    /// Fork the history was replayed on, left in the post-replay state for further scenarios
+    pub bb: AptosBB,
+    pub fork_version: u64,
+    pub txns: Vec<ReplayedTxn>,
+}
+
+impl AccountHistory {
+    pub fn divergences(&self) -> Vec<&ReplayedTxn> {
+        self.txns.iter().filter(|txn| txn.diverged()).collect()
+    }
+}
+
+impl AptosBB {
+    /// Re-execute the last `last_n` transactions sent by `addr` up to the fork version
+    ///
+    /// A new fork is created just before the first of them and the transactions are applied in
+    /// their original order, without the unrelated transactions that landed in between. Results
+    /// can therefore drift from chain; `AccountHistory::divergences` lists where they did.
+    pub fn replay_account_history(&self, addr: AccountAddress, last_n: u16) -> Result<AccountHistory> {
+        let sequence_number = self
+            .read_account_resource_at_address(&addr)
+            .ok_or_else(|| anyhow!("Account {} does not exist at version {}", addr, self.version))?
+            .sequence_number();
+        let start = sequence_number.saturating_sub(last_n as u64);
+        let history = blocking::block_on(async {
+            anyhow::Ok(self.remote.client().await?.get_account_transactions_bcs(addr, Some(start), Some(last_n)).await?)
+        })?
+        .into_inner();
+
+        let history: Vec<_> = history
+            .into_iter()
+            .filter(|txn| txn.version <= self.version)
+            .filter_map(|txn| match txn.transaction {
+                Transaction::UserTransaction(signed) => Some((txn.version, signed, txn.info)),
+                _ => None,
+            })
+            .collect();
+        let Some((first_version, _, _)) = history.first() else {
+            return Err(anyhow!("No transactions sent by {} up to version {}", addr, self.version));
+        };
+        let fork_version = first_version
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("Cannot fork before genesis"))?;
+        println!("Replaying {} transactions of {} from version {}", history.len(), addr, fork_version);
+
+        let mut bb = self.fork_at(fork_version)?;
+        let mut txns = Vec::with_capacity(history.len());
+        for (version, signed, info) in history {
+            let call = bb.decode_payload(signed.payload());
+            let replayed = TxnOutcome::from(&bb.execute_signed(signed.clone()));
+            let mark = if replayed.status == TransactionStatus::Keep(info.status().clone()) { "✓" } else { "✗" };
+            match &call {
+                Some(call) => println!("  {} v{} {}", mark, version, call),
+                None => println!("  {} v{} {}", mark, version, function_name(signed.payload())),
+            }
+            txns.push(ReplayedTxn {
+                version,
+                txn: signed,
+                call,
+                onchain_status: info.status().clone(),
+                onchain_gas_used: info.gas_used(),
+                replayed,
+            });
+        }
+        if let Some(resource) = bb.read_account_resource_at_address(&addr) {
+            bb.sequence_numbers.insert(addr, resource.sequence_number());
+        }
+
+        let history = AccountHistory { bb, fork_version, txns };
+        println!("Replayed {} transactions, {} diverged from chain", history.txns.len(), history.divergences().len());
+        Ok(history)
+    }
+}
@@ -0,0 +1,189 @@
+//! Double-spend harness: try to get one signed transaction executed twice
+
+use crate::{
+    findings::{Category, Finding, Severity},
+    payload::{ReplayProtection, MAX_ORDERLESS_EXPIRATION_SECS},
+    AptosBB,
+};
+use anyhow::{bail, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    on_chain_config::FeatureFlag,
+    transaction::{SignedTransaction, TransactionPayload, TransactionStatus},
+};
+use std::fmt;
+
+/// Replay protection scheme of a transaction
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TxnFormat {
+    /// Protected by the sender's sequence number
+    Legacy,
+    /// Orderless, protected by a nonce until it expires
+    Orderless,
+}
+
+/// One attempt to execute an already executed transaction again
+#[derive(Clone, Debug)]
+pub struct ReplayAttempt {
+    pub format: TxnFormat,
+    pub scenario: &'static str,
+    pub status: TransactionStatus,
+    /// Whether the chain must refuse the replay; attempts that restore the state from before
+    /// the first execution are expected to go through, and show what protection hinges on
+    pub must_reject: bool,
+}
+
+impl ReplayAttempt {
+    pub fn accepted(&self) -> bool {
+        matches!(self.status, TransactionStatus::Keep(_))
+    }
+
+    /// A replay that executed although replay protection should have stopped it
+    pub fn is_violation(&self) -> bool {
+        self.must_reject && self.accepted()
+    }
+}
+
+impl fmt::Display for ReplayAttempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mark = match (self.must_reject, self.accepted()) {
+            (true, true) => "🚨",
+            (true, false) | (false, true) => "✅",
+            (false, false) => "❔",
+        };
+        write!(f, "{} {:?} {}: {:?}", mark, self.format, self.scenario, self.status)
+    }
+}
+
+/// Outcome of every replay attempt of `check_replay_protection`
+#[derive(Clone, Debug)]
+pub struct ReplayReport {
+    pub attempts: Vec<ReplayAttempt>,
+}
+
+impl ReplayReport {
+    pub fn violations(&self) -> Vec<&ReplayAttempt> {
+        self.attempts.iter().filter(|attempt| attempt.is_violation()).collect()
+    }
+}
+
+impl fmt::Display for ReplayReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for attempt in &self.attempts {
+            writeln!(f, "{}", attempt)?;
+        }
+        write!(f, "{} replay attempts, {} violations", self.attempts.len(), self.violations().len())
+    }
+}
+
+/// Any executed replay is a double spend
+impl From<&ReplayReport> for Finding {
+    fn from(report: &ReplayReport) -> Self {
+        let violations = report.violations();
+        let severity = if violations.is_empty() { Severity::Informational } else { Severity::High };
+        violations.iter().fold(
+            Finding::new("Signed transaction executed twice", severity, Category::Logic),
+            |finding, attempt| finding.evidence(attempt.to_string()),
+        )
+    }
+}
+
+impl AptosBB {
+    /// Try to execute `payload` from `account` twice, in both transaction formats
+    ///
+    /// Each format's transaction is signed and executed once, then the identical signed
+    /// transaction is replayed right away, across snapshot reverts, on a branch from before it
+    /// ran, and after sequence number or clock manipulation. Replays the chain must refuse but
+    /// that execute are reported as violations. Orderless transactions need feature flags the
+    /// harness turns on itself; the fork is left as it was.
+    pub fn check_replay_protection(&mut self, account: &Account, payload: TransactionPayload) -> Result<ReplayReport> {
+        let report = self.with_snapshot(|bb| -> Result<ReplayReport> {
+            let mut attempts = bb.legacy_replays(account, &payload)?;
+            bb.set_feature(FeatureFlag::TRANSACTION_PAYLOAD_V2, true)?;
+            bb.set_feature(FeatureFlag::ORDERLESS_TRANSACTIONS, true)?;
+            attempts.extend(bb.orderless_replays(account, &payload)?);
+            Ok(ReplayReport { attempts })
+        })?;
+        println!("{}", report);
+        Ok(report)
+    }
+
+    /// Execute a signed transaction, failing if even the first execution is not kept
+    fn execute_original(&mut self, txn: SignedTransaction, format: TxnFormat) -> Result<()> {
+        let (status, _) = self.run_signed_transaction_with_output(txn)?;
+        if !matches!(status, TransactionStatus::Keep(_)) {
+            bail!("The original {:?} transaction was not executed, nothing to replay: {:?}", format, status);
+        }
+        Ok(())
+    }
+
+    fn replay(
+        &mut self,
+        txn: &SignedTransaction,
+        format: TxnFormat,
+        scenario: &'static str,
+        must_reject: bool,
+    ) -> Result<ReplayAttempt> {
+        let (status, _) = self.run_signed_transaction_with_output(txn.clone())?;
+        Ok(ReplayAttempt { format, scenario, status, must_reject })
+    }
+
+    fn legacy_replays(&mut self, account: &Account, payload: &TransactionPayload) -> Result<Vec<ReplayAttempt>> {
+        let format = TxnFormat::Legacy;
+        let before = self.checkpoint();
+        let txn = self.sign_transaction(account, payload.clone());
+        self.execute_original(txn.clone(), format)?;
+        let after = self.checkpoint();
+
+        let mut attempts = vec![self.replay(&txn, format, "immediate replay", true)?];
+
+        self.revert(&before);
+        self.revert(&after);
+        attempts.push(self.replay(&txn, format, "replay after reverting to a later snapshot", true)?);
+
+        self.revert(&after);
+        self.run_transaction(account, payload.clone())?;
+        attempts.push(self.replay(&txn, format, "replay after a later transaction", true)?);
+
+        self.revert(&before);
+        attempts.push(self.replay(&txn, format, "replay on a branch from before execution", false)?);
+
+        self.revert(&after);
+        self.set_sequence_number(*account.address(), txn.sequence_number())?;
+        attempts.push(self.replay(&txn, format, "replay after rewinding the sequence number", false)?);
+        Ok(attempts)
+    }
+
+    fn orderless_replays(&mut self, account: &Account, payload: &TransactionPayload) -> Result<Vec<ReplayAttempt>> {
+        let format = TxnFormat::Orderless;
+        let protection = ReplayProtection::Nonce(rand::random());
+        let before = self.checkpoint();
+        let txn = self.sign_v2_transaction(account, payload.clone(), protection)?;
+        self.execute_original(txn.clone(), format)?;
+        let after = self.checkpoint();
+
+        let mut attempts = vec![self.replay(&txn, format, "immediate replay", true)?];
+
+        self.revert(&before);
+        self.revert(&after);
+        attempts.push(self.replay(&txn, format, "replay after reverting to a later snapshot", true)?);
+
+        self.revert(&after);
+        // A later expiration makes it a different transaction with the same nonce
+        self.set_time(self.now_secs()? + 1)?;
+        let resigned = self.sign_v2_transaction(account, payload.clone(), protection)?;
+        attempts.push(self.replay(&resigned, format, "new transaction reusing the nonce", true)?);
+
+        self.revert(&after);
+        self.run_transaction(account, payload.clone())?;
+        attempts.push(self.replay(&txn, format, "replay after a sequence-numbered transaction", true)?);
+
+        self.revert(&after);
+        self.set_time(self.now_secs()? + MAX_ORDERLESS_EXPIRATION_SECS + 1)?;
+        attempts.push(self.replay(&txn, format, "replay after expiration", true)?);
+
+        self.revert(&before);
+        attempts.push(self.replay(&txn, format, "replay on a branch from before execution", false)?);
+        Ok(attempts)
+    }
+}
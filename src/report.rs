@@ -0,0 +1,520 @@
+use crate::{
+    calls::DecodedCall,
+    export::{function_name, status_name},
+    findings::{Finding, Severity},
+    observer::{recorder, Recorder, TxnObserver},
+    pricing::{PriceTable, APT},
+    AptosBB,
+};
+use anyhow::{Context, Result};
+use aptos_gas_profiling::{CallFrame, ExecutionGasEvent, FrameName};
+use aptos_types::{
+    access_path::Path as AccessPathKind,
+    account_address::AccountAddress,
+    state_store::state_key::{inner::StateKeyInner, StateKey},
+    transaction::{ExecutionStatus, SignedTransaction, TransactionOutput, TransactionStatus},
+};
+use move_core_types::language_storage::TypeTag;
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A Move call and the calls it made, from the gas profiler's call graph
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceFrame {
+    pub name: String,
+    pub children: Vec<TraceFrame>,
+}
+
+impl TraceFrame {
+    fn from_call_frame(frame: &CallFrame) -> Self {
+        let name = match &frame.name {
+            FrameName::Script => "script".to_string(),
+            FrameName::Function { module_id, name, ty_args } => format_call(&module_id.to_string(), name.as_str(), ty_args),
+        };
+        let children = frame
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                ExecutionGasEvent::Call(frame) => Some(Self::from_call_frame(frame)),
+                ExecutionGasEvent::CallNative { module_id, fn_name, ty_args, .. } => Some(Self {
+                    name: format!("{} [native]", format_call(&module_id.to_string(), fn_name.as_str(), ty_args)),
+                    children: vec![],
+                }),
+                _ => None,
+            })
+            .collect();
+        Self { name, children }
+    }
+}
+
+fn format_call(module: &str, function: &str, ty_args: &[TypeTag]) -> String {
+    if ty_args.is_empty() {
+        format!("{}::{}", module, function)
+    } else {
+        let ty_args: Vec<String> = ty_args.iter().map(|ty| ty.to_string()).collect();
+        format!("{}::{}<{}>", module, function, ty_args.join(", "))
+    }
+}
+
+/// A state key written by a transaction, with decoded values where the layout is known
+#[derive(Clone, Debug, Serialize)]
+pub struct StateChange {
+    pub key: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReportEvent {
+    pub type_tag: String,
+    pub data: Value,
+}
+
+/// Everything recorded about one executed transaction
+#[derive(Clone, Debug, Serialize)]
+pub struct ReportTxn {
+    pub function: String,
+    /// The entry function call with arguments decoded through its ABI, when decodable
+    pub call: Option<DecodedCall>,
+    pub sender: String,
+    pub status: String,
+    pub success: bool,
+    pub gas_used: u64,
+    pub trace: Option<TraceFrame>,
+    pub events: Vec<ReportEvent>,
+    pub changes: Vec<StateChange>,
+    /// APT balance change of the sender and every watched address
+    pub balance_deltas: BTreeMap<String, i128>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Scenario {
+    pub name: String,
+    pub txns: Vec<ReportTxn>,
+}
+
+impl Scenario {
+    /// Net APT balance change per address over the scenario
+    pub fn profit(&self) -> BTreeMap<String, i128> {
+        let mut profit = BTreeMap::new();
+        for (addr, delta) in self.txns.iter().flat_map(|txn| &txn.balance_deltas) {
+            *profit.entry(addr.clone()).or_insert(0) += delta;
+        }
+        profit
+    }
+}
+
+/// Transactions executed on a fork while a session was being recorded, grouped by scenario
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct Session {
+    pub network: String,
+    pub fork_version: u64,
+    pub scenarios: Vec<Scenario>,
+    /// Prices used to show profit in USD, see `Session::set_prices`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prices: Option<PriceTable>,
+    /// Findings reported by scenarios and analyses, see `AptosBB::report_finding`
+    pub findings: Vec<Finding>,
+}
+
+/// Shared view of a session that keeps filling up while the fork executes transactions
+pub type SessionHandle = Arc<Mutex<Session>>;
+
+pub(crate) struct SessionRecorder {
+    session: SessionHandle,
+    watch: Vec<AccountAddress>,
+}
+
+/// State captured before a recorded transaction is applied
+pub(crate) struct PendingReport {
+    function: String,
+    call: Option<DecodedCall>,
+    sender: AccountAddress,
+    trace: Option<TraceFrame>,
+    before: Vec<(StateKey, Option<Value>)>,
+    balances_before: Vec<(AccountAddress, u64)>,
+}
+
+impl AptosBB {
+    /// Record every subsequently executed transaction for a session report
+    ///
+    /// Each transaction is additionally run once under the gas profiler to obtain its call
+    /// trace, so recording roughly doubles execution time. APT balance changes are tracked for
+    /// senders and for the addresses in `watch`.
+    pub fn record_session(&mut self, watch: &[AccountAddress]) -> SessionHandle {
+        let session = Arc::new(Mutex::new(Session {
+            network: self.network.to_url().to_string(),
+            fork_version: self.version,
+            scenarios: vec![],
+            prices: None,
+            findings: vec![],
+        }));
+        self.report = Some(SessionRecorder { session: session.clone(), watch: watch.to_vec() });
+        session
+    }
+
+    /// Start a named scenario; following transactions are grouped under it in the report
+    pub fn begin_scenario(&mut self, name: &str) {
+        if let Some(recorder) = &self.report {
+            recorder.session.lock().unwrap().scenarios.push(Scenario { name: name.to_string(), txns: vec![] });
+        }
+    }
+
+    /// Print a finding and keep it, also adding it to the recorded session, if any
+    pub fn report_finding(&mut self, finding: Finding) {
+        println!("🔎 {}", finding);
+        self.note_experiment_finding(&finding);
+        if let Some(recorder) = &self.report {
+            recorder.session.lock().unwrap().findings.push(finding.clone());
+        }
+        self.findings.push(finding);
+    }
+
+    /// Every finding reported on this fork so far, whether or not a session is recorded
+    pub fn findings(&self) -> &[Finding] {
+        &self.findings
+    }
+
+    /// Decode a state value for display, falling back to hex
+    fn state_value_json(&self, state_key: &StateKey, bytes: &[u8]) -> Value {
+        let decoded = match state_key.inner() {
+            StateKeyInner::AccessPath(access_path) => match access_path.get_path() {
+                AccessPathKind::Resource(struct_tag) => self.value_to_json(&TypeTag::Struct(Box::new(struct_tag)), bytes).ok(),
+                _ => None,
+            },
+            _ => None,
+        };
+        decoded.unwrap_or_else(|| Value::String(format!("0x{}", hex::encode(bytes))))
+    }
+
+    pub(crate) fn begin_report(&self, txn: &SignedTransaction) -> Option<PendingReport> {
+        let recorder = self.report.as_ref()?;
+
+        let (trace, keys): (_, Vec<StateKey>) = match self.executor.execute_transaction_with_gas_profiler(txn.clone()) {
+            Ok((output, log)) => (
+                Some(TraceFrame::from_call_frame(&log.exec_io.call_graph)),
+                output.write_set().iter().map(|(key, _)| key.clone()).collect(),
+            ),
+            Err(e) => {
+                eprintln!("Warning: failed to trace transaction for the session report: {}", e);
+                (None, vec![])
+            }
+        };
+        let before = keys
+            .into_iter()
+            .map(|key| {
+                let value = self.read_state_value(&key).map(|value| self.state_value_json(&key, value.bytes()));
+                (key, value)
+            })
+            .collect();
+
+        let mut watched = recorder.watch.clone();
+        if !watched.contains(&txn.sender()) {
+            watched.push(txn.sender());
+        }
+        Some(PendingReport {
+            function: function_name(txn.payload()),
+            call: self.decode_payload(txn.payload()),
+            sender: txn.sender(),
+            trace,
+            before,
+            balances_before: watched.into_iter().map(|addr| (addr, self.read_aptos_balance(&addr))).collect(),
+        })
+    }
+
+    pub(crate) fn record_report(&self, pending: PendingReport, output: &TransactionOutput) {
+        let Some(recorder) = &self.report else {
+            return;
+        };
+
+        let mut before: HashMap<StateKey, Option<Value>> = pending.before.into_iter().collect();
+        let changes = output
+            .write_set()
+            .iter()
+            .map(|(key, op)| StateChange {
+                key: format!("{:?}", key),
+                before: before.remove(key).flatten(),
+                after: op.bytes().map(|bytes| self.state_value_json(key, bytes)),
+            })
+            .collect();
+        let events = output
+            .events()
+            .iter()
+            .map(|event| ReportEvent {
+                type_tag: event.type_tag().to_string(),
+                data: self
+                    .value_to_json(event.type_tag(), event.event_data())
+                    .unwrap_or_else(|_| Value::String(format!("0x{}", hex::encode(event.event_data())))),
+            })
+            .collect();
+        let balance_deltas = pending
+            .balances_before
+            .iter()
+            .map(|(addr, before)| (addr.to_hex_literal(), self.read_aptos_balance(addr) as i128 - *before as i128))
+            .filter(|(_, delta)| *delta != 0)
+            .collect();
+
+        let txn = ReportTxn {
+            function: pending.function,
+            call: pending.call,
+            sender: pending.sender.to_hex_literal(),
+            status: status_name(output.status()),
+            success: matches!(output.status(), TransactionStatus::Keep(ExecutionStatus::Success)),
+            gas_used: output.gas_used(),
+            trace: pending.trace,
+            events,
+            changes,
+            balance_deltas,
+        };
+
+        let mut session = recorder.session.lock().unwrap();
+        if session.scenarios.is_empty() {
+            session.scenarios.push(Scenario { name: "default".to_string(), txns: vec![] });
+        }
+        session.scenarios.last_mut().expect("a scenario was just ensured").txns.push(txn);
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn render_trace(out: &mut String, frame: &TraceFrame) {
+    if frame.children.is_empty() {
+        let _ = write!(out, "<li><code>{}</code></li>", escape(&frame.name));
+        return;
+    }
+    let _ = write!(out, "<li><details open><summary><code>{}</code></summary><ul>", escape(&frame.name));
+    for child in &frame.children {
+        render_trace(out, child);
+    }
+    out.push_str("</ul></details></li>");
+}
+
+fn render_profit(out: &mut String, profit: &BTreeMap<String, i128>, prices: Option<&PriceTable>) {
+    if profit.is_empty() {
+        out.push_str("<p class=\"muted\">No APT balance changes</p>");
+        return;
+    }
+    let priced = prices.and_then(|prices| prices.price(APT)).is_some();
+    out.push_str("<table><tr><th>Address</th><th>APT delta (octas)</th><th>APT</th>");
+    if priced {
+        out.push_str("<th>USD</th>");
+    }
+    out.push_str("</tr>");
+    for (addr, delta) in profit {
+        let class = if *delta > 0 { "gain" } else { "loss" };
+        let _ = write!(
+            out,
+            "<tr><td><code>{}</code></td><td class=\"{}\">{:+}</td><td class=\"{}\">{:+.8}</td>",
+            addr, class, delta, class, *delta as f64 / 1e8
+        );
+        if let Some(usd) = prices.and_then(|prices| prices.apt_usd(*delta)) {
+            let _ = write!(out, "<td class=\"{}\">{:+.2}</td>", class, usd);
+        }
+        out.push_str("</tr>");
+    }
+    out.push_str("</table>");
+}
+
+/// Findings table, most severe first
+fn render_findings(out: &mut String, findings: &[Finding]) {
+    let mut findings: Vec<&Finding> = findings.iter().collect();
+    findings.sort_by(|a, b| b.severity.cmp(&a.severity));
+    out.push_str("<table><tr><th>Severity</th><th>Finding</th><th>Category</th><th>Affected</th><th>Evidence</th><th>Reproduction</th></tr>");
+    for finding in findings {
+        let class = if finding.severity >= Severity::High { "fail" } else { "muted" };
+        let _ = write!(
+            out,
+            "<tr><td class=\"{}\">{}</td><td>{}</td><td>{}</td><td><code>{}</code></td><td>{}</td><td><code>{}</code></td></tr>",
+            class,
+            finding.severity,
+            escape(&finding.title),
+            finding.category,
+            escape(&finding.affected.join(", ")),
+            escape(&finding.evidence.join("; ")),
+            escape(finding.reproduction.as_deref().unwrap_or("")),
+        );
+    }
+    out.push_str("</table>");
+}
+
+fn pretty(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => escape(&serde_json::to_string_pretty(value).unwrap_or_default()),
+        None => "&mdash;".to_string(),
+    }
+}
+
+const STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2em;color:#222}\
+code,pre{font-family:ui-monospace,monospace;font-size:12px}\
+pre{background:#f6f8fa;padding:.5em;overflow-x:auto;margin:0}\
+table{border-collapse:collapse;margin:.5em 0}td,th{border:1px solid #ddd;padding:4px 8px;vertical-align:top;text-align:left}\
+.ok{color:#1a7f37}.fail{color:#cf222e}.gain{color:#1a7f37}.loss{color:#cf222e}.muted{color:#888}\
+details.txn{border:1px solid #ddd;border-radius:4px;margin:.5em 0;padding:.5em}\
+ul{list-style:none;padding-left:1.2em}";
+
+const SCRIPT: &str = "document.getElementById('failed-only').addEventListener('change',function(e){\
+document.querySelectorAll('details.txn.success').forEach(function(el){el.style.display=e.target.checked?'none':''})})";
+
+impl Session {
+    /// Net APT balance change per address over the whole session
+    pub fn profit(&self) -> BTreeMap<String, i128> {
+        let mut profit = BTreeMap::new();
+        for (addr, delta) in self.scenarios.iter().flat_map(|scenario| scenario.profit()) {
+            *profit.entry(addr).or_insert(0) += delta;
+        }
+        profit
+    }
+
+    /// Price the session's APT balance changes in USD, in `profit_usd` and the HTML report
+    pub fn set_prices(&mut self, prices: PriceTable) {
+        self.prices = Some(prices);
+    }
+
+    /// Net USD value of each address's APT balance change, if prices are set
+    pub fn profit_usd(&self) -> Option<BTreeMap<String, f64>> {
+        let prices = self.prices.as_ref()?;
+        self.profit()
+            .into_iter()
+            .map(|(addr, delta)| Some((addr, prices.apt_usd(delta)?)))
+            .collect()
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::new();
+        let txn_count: usize = self.scenarios.iter().map(|scenario| scenario.txns.len()).sum();
+        let _ = write!(
+            out,
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>AptosBB session report</title><style>{}</style></head><body>",
+            STYLE
+        );
+        let _ = write!(
+            out,
+            "<h1>AptosBB session report</h1><p>Forked {} at version {} &middot; {} scenario(s), {} transaction(s)</p>",
+            escape(&self.network),
+            self.fork_version,
+            self.scenarios.len(),
+            txn_count
+        );
+
+        if !self.findings.is_empty() {
+            let _ = write!(out, "<h2>Findings ({})</h2>", self.findings.len());
+            render_findings(&mut out, &self.findings);
+        }
+
+        out.push_str("<h2>Profit summary</h2>");
+        render_profit(&mut out, &self.profit(), self.prices.as_ref());
+
+        out.push_str("<h2>Scenarios</h2><ol>");
+        for (i, scenario) in self.scenarios.iter().enumerate() {
+            let failed = scenario.txns.iter().filter(|txn| !txn.success).count();
+            let _ = write!(
+                out,
+                "<li><a href=\"#scenario-{}\">{}</a> &mdash; {} transaction(s), <span class=\"{}\">{} failed</span></li>",
+                i,
+                escape(&scenario.name),
+                scenario.txns.len(),
+                if failed > 0 { "fail" } else { "ok" },
+                failed
+            );
+        }
+        out.push_str("</ol><label><input type=\"checkbox\" id=\"failed-only\"> Show failed transactions only</label>");
+
+        for (i, scenario) in self.scenarios.iter().enumerate() {
+            let _ = write!(out, "<h2 id=\"scenario-{}\">{}</h2>", i, escape(&scenario.name));
+            render_profit(&mut out, &scenario.profit(), self.prices.as_ref());
+
+            for (j, txn) in scenario.txns.iter().enumerate() {
+                let (class, mark) = if txn.success { ("success", "ok") } else { ("failure", "fail") };
+                let _ = write!(
+                    out,
+                    "<details class=\"txn {}\"><summary>#{} <code>{}</code> from <code>{}</code> &middot; <span class=\"{}\">{}</span> &middot; {} gas</summary>",
+                    class,
+                    j,
+                    escape(&txn.call.as_ref().map_or_else(|| txn.function.clone(), |call| call.to_string())),
+                    escape(&txn.sender),
+                    mark,
+                    escape(&txn.status),
+                    txn.gas_used
+                );
+
+                out.push_str("<h4>Call trace</h4>");
+                match &txn.trace {
+                    Some(trace) => {
+                        out.push_str("<ul>");
+                        render_trace(&mut out, trace);
+                        out.push_str("</ul>");
+                    }
+                    None => out.push_str("<p class=\"muted\">Not available</p>"),
+                }
+
+                let _ = write!(out, "<h4>Events ({})</h4>", txn.events.len());
+                if !txn.events.is_empty() {
+                    out.push_str("<table><tr><th>Type</th><th>Data</th></tr>");
+                    for event in &txn.events {
+                        let _ = write!(
+                            out,
+                            "<tr><td><code>{}</code></td><td><pre>{}</pre></td></tr>",
+                            escape(&event.type_tag),
+                            pretty(&Some(event.data.clone()))
+                        );
+                    }
+                    out.push_str("</table>");
+                }
+
+                let _ = write!(out, "<h4>State changes ({})</h4>", txn.changes.len());
+                if !txn.changes.is_empty() {
+                    out.push_str("<table><tr><th>Key</th><th>Before</th><th>After</th></tr>");
+                    for change in &txn.changes {
+                        let _ = write!(
+                            out,
+                            "<tr><td><code>{}</code></td><td><pre>{}</pre></td><td><pre>{}</pre></td></tr>",
+                            escape(&change.key),
+                            pretty(&change.before),
+                            pretty(&change.after)
+                        );
+                    }
+                    out.push_str("</table>");
+                }
+
+                out.push_str("<h4>Balance changes</h4>");
+                render_profit(&mut out, &txn.balance_deltas, self.prices.as_ref());
+                out.push_str("</details>");
+            }
+        }
+
+        let _ = write!(out, "<script>{}</script></body></html>", SCRIPT);
+        out
+    }
+
+    /// Write `index.html` and the raw `session.json` into `out_dir`, returning the HTML path
+    pub fn write_html(&self, out_dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(out_dir)
+            .with_context(|| format!("Failed to create {}", out_dir.display()))?;
+        std::fs::write(out_dir.join("session.json"), serde_json::to_vec_pretty(self)?)?;
+        let index = out_dir.join("index.html");
+        std::fs::write(&index, self.to_html())?;
+        Ok(index)
+    }
+}
+
+/// Adds each transaction to the recording session report, with its trace and state diff
+pub(crate) struct ReportObserver;
+
+impl TxnObserver for ReportObserver {
+    fn begin(&self, bb: &AptosBB, txn: &SignedTransaction) -> Result<Option<Recorder>> {
+        Ok(bb
+            .begin_report(txn)
+            .map(|pending| recorder(move |bb, executed| bb.record_report(pending, executed.output))))
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::{bail, Result};
+use aptos_api_types::{BcsBlock, Block, MoveModuleId, TransactionData, TransactionOnChainData};
+use aptos_crypto::HashValue;
+use aptos_rest_client::{error::RestError, Client, Response, State};
+use aptos_types::{account_address::AccountAddress, state_store::state_key::StateKey};
+use bytes::Bytes;
+use move_core_types::language_storage::StructTag;
+use std::{
+    collections::BTreeMap,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+type ReadResult<T> = std::result::Result<Response<T>, RestError>;
+
+/// REST client restricted to reads
+///
+/// This is the only client AptosBB holds: it wraps `aptos_rest_client::Client` without
+/// exposing it, so nothing built on AptosBB can submit, simulate against or otherwise
+/// write to a real endpoint. Fork state, blocks and transactions are all it can fetch.
+/// The guarantee holds unless built with `--features broadcast`, which adds the testnet
+/// broadcast helpers.
+#[derive(Clone)]
+pub struct ReadOnlyClient {
+    inner: Client,
+}
+
+impl ReadOnlyClient {
+    pub(crate) fn new(inner: Client) -> Self {
+        Self { inner }
+    }
+
+    pub async fn get_ledger_information(&self) -> ReadResult<State> {
+        self.inner.get_ledger_information().await
+    }
+
+    pub async fn get_block_by_version(&self, version: u64, with_transactions: bool) -> ReadResult<Block> {
+        self.inner.get_block_by_version(version, with_transactions).await
+    }
+
+    pub async fn get_block_by_version_bcs(&self, version: u64, with_transactions: bool) -> ReadResult<BcsBlock> {
+        self.inner.get_block_by_version_bcs(version, with_transactions).await
+    }
+
+    pub async fn get_transactions_bcs(&self, start: Option<u64>, limit: Option<u16>) -> ReadResult<Vec<TransactionOnChainData>> {
+        self.inner.get_transactions_bcs(start, limit).await
+    }
+
+    pub async fn get_transaction_by_version_bcs(&self, version: u64) -> ReadResult<TransactionData> {
+        self.inner.get_transaction_by_version_bcs(version).await
+    }
+
+    pub async fn get_transaction_by_hash_bcs(&self, hash: HashValue) -> ReadResult<TransactionData> {
+        self.inner.get_transaction_by_hash_bcs(hash).await
+    }
+
+    pub async fn get_account_transactions_bcs(
+        &self,
+        address: AccountAddress,
+        start: Option<u64>,
+        limit: Option<u16>,
+    ) -> ReadResult<Vec<TransactionOnChainData>> {
+        self.inner.get_account_transactions_bcs(address, start, limit).await
+    }
+
+    pub async fn get_raw_state_value(&self, state_key: &StateKey, version: u64) -> ReadResult<Vec<u8>> {
+        self.inner.get_raw_state_value(state_key, version).await
+    }
+
+    pub async fn get_account_resources_at_version_bcs(
+        &self,
+        address: AccountAddress,
+        version: u64,
+    ) -> ReadResult<BTreeMap<StructTag, Vec<u8>>> {
+        self.inner.get_account_resources_at_version_bcs(address, version).await
+    }
+
+    pub async fn get_account_modules_bcs_at_version(
+        &self,
+        address: AccountAddress,
+        version: u64,
+    ) -> ReadResult<BTreeMap<MoveModuleId, Bytes>> {
+        self.inner.get_account_modules_bcs_at_version(address, version).await
+    }
+}
+
+/// Set once no-broadcast mode is on; it can never be turned off again in the same process
+static NO_BROADCAST: AtomicBool = AtomicBool::new(false);
+
+/// Forbid every network write for the rest of the process
+///
+/// AptosBB itself only ever reads (see `ReadOnlyClient`); in a build with `--features
+/// broadcast` this additionally makes the explicit broadcast helpers refuse to run, so a PoC
+/// started with `--no-broadcast` (or `APTOSBB_NO_BROADCAST=1`) cannot fire on any real
+/// network, whatever its code does.
+pub fn enable_no_broadcast() {
+    NO_BROADCAST.store(true, Ordering::SeqCst);
+}
+
+/// Whether no-broadcast mode is on, via `enable_no_broadcast` or `APTOSBB_NO_BROADCAST`
+pub fn no_broadcast() -> bool {
+    if std::env::var("APTOSBB_NO_BROADCAST").is_ok_and(|value| !value.is_empty() && value != "0") {
+        enable_no_broadcast();
+    }
+    NO_BROADCAST.load(Ordering::SeqCst)
+}
+
+/// Fail if no-broadcast mode is on; called before anything is sent to a real network
+pub fn ensure_broadcast_allowed() -> Result<()> {
+    if no_broadcast() {
+        bail!("Refusing to broadcast: no-broadcast mode is enabled for this process");
+    }
+    Ok(())
+}
@@ -0,0 +1,173 @@
+use crate::{
+    package::write_package,
+    scenario::{ForkConfig, FORK_CONFIG_FILE},
+    stubgen::{render_function_signature, render_wrapper},
+    AptosBB,
+};
+use anyhow::{bail, Result};
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::{access::ModuleAccess, file_format::Visibility};
+use move_core_types::language_storage::ModuleId;
+use std::path::Path;
+
+/// Crate manifest of a generated project; the patches mirror the ones aptos-core needs
+///
+/// `{aptosbb}` is the git source of this build of AptosBB, see `aptosbb_source`.
+const PROJECT_MANIFEST: &str = r#"[package]
+name = "{name}"
+version = "0.1.0"
+edition = "2021"
+
+[dependencies]
+anyhow = "1.0"
+aptosbb = { git = "https://github.com/otter-sec/aptosbb", {aptosbb} }
+tokio = { version = "1.21", features = ["full"] }
+
+[patch.crates-io]
+x25519-dalek = { git = "https://github.com/aptos-labs/x25519-dalek", branch = "zeroize_v1" }
+merlin = { git = "https://github.com/aptos-labs/merlin" }
+serde-reflection = { git = "https://github.com/aptos-labs/serde-reflection", rev = "73b6bbf748334b71ff6d7d09d06a29e3062ca075" }
+"#;
+
+const SCENARIO_TEMPLATE: &str = r#"use anyhow::Result;
+use aptosbb::{
+    blocking::run_blocking,
+    history::DEFAULT_HISTORY_DB,
+    scenario::{ForkConfig, Scenario, FORK_CONFIG_FILE},
+    AptosBB,
+};
+use std::path::Path;
+
+/// Exploit against {target}
+struct Exploit;
+
+impl Scenario for Exploit {
+    fn name(&self) -> &str {
+        "{name}"
+    }
+
+    fn setup(&mut self, _bb: &mut AptosBB) -> Result<()> {
+        // Fund accounts, seed pools, move the clock, ...
+        Ok(())
+    }
+
+    fn run(&mut self, bb: &mut AptosBB) -> Result<()> {
+        let attacker = bb.new_account();
+        // The `exploit` named address is remapped to the attacker at publish time
+        bb.publish_package(&attacker, Path::new("move"))?;
+        let status = bb.run_entry_function(&attacker, *attacker.address(), "{module}", "run", vec![], vec![])?;
+        anyhow::ensure!(status.status().is_ok_and(|s| s.is_success()), "Exploit failed: {:?}", status);
+        Ok(())
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let config = ForkConfig::load(Path::new(FORK_CONFIG_FILE))?;
+    let mut bb = config.builder()?.build().await?;
+    bb.log_experiments(Path::new(DEFAULT_HISTORY_DB))?;
+    run_blocking(|| bb.run_scenario(&mut Exploit))
+}
+"#;
+
+/// Cargo config of a generated project; the runtime needs `tokio_unstable`, like AptosBB itself
+const PROJECT_CARGO_CONFIG: &str = r#"[build]
+rustflags = ["--cfg", "tokio_unstable"]
+"#;
+
+/// Git revision (or, when built outside a checkout, release tag) generated projects depend
+/// on, so they keep building against the AptosBB they were generated with
+fn aptosbb_source() -> String {
+    match option_env!("APTOSBB_GIT_REV") {
+        Some(rev) => format!("rev = \"{}\"", rev),
+        None => format!("tag = \"v{}\"", env!("CARGO_PKG_VERSION")),
+    }
+}
+
+/// Move identifier derived from a project name, e.g. `vault-drain` -> `vault_drain`
+fn move_identifier(name: &str) -> String {
+    let ident: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    if ident.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", ident)
+    } else {
+        ident
+    }
+}
+
+impl AptosBB {
+    /// Move source of an exploit module with a typed `call_<function>` wrapper for every
+    /// public function of `target`, plus an empty `run` entry point for the attack
+    ///
+    /// Entry functions that are not public cannot be called from Move; they are listed as
+    /// comments to be called from the Rust scenario instead.
+    pub fn exploit_module_source(&mut self, target: &ModuleId, name: &str) -> Result<String> {
+        let module = self.get_module(target)?;
+        let mut out = format!(
+            "module exploit::{} {{\n    /// Entry point called by the Rust scenario\n    public entry fun run(_attacker: &signer) {{\n    }}\n",
+            name
+        );
+
+        out.push_str(&format!("\n    // Wrappers around {}\n", target.short_str_lossless()));
+        for def in module.function_defs() {
+            if def.visibility == Visibility::Public {
+                out.push_str(&format!("\n    {}\n", render_wrapper(&module, def)));
+            } else if def.is_entry {
+                out.push_str(&format!(
+                    "\n    // Not public, call from the scenario with run_entry_function:\n    // {}\n",
+                    render_function_signature(&module, def)
+                ));
+            }
+        }
+        out.push_str("}\n");
+        Ok(out)
+    }
+
+    /// Write a compiling exploit package for `target` into `dir`
+    ///
+    /// The package holds `exploit_module_source` under the `exploit` named address (remapped
+    /// to the publisher by `publish_package`) and depends on interface stubs of the target's
+    /// address, generated under `dir/deps`.
+    pub fn generate_exploit_package(&mut self, target: &ModuleId, dir: &Path, name: &str) -> Result<()> {
+        let source = self.exploit_module_source(target, name)?;
+        write_package(dir, name, &[(name, &source)], &[("exploit", AccountAddress::ZERO)])?;
+        self.link_onchain_dependencies(dir, &[*target.address()])?;
+        println!("Generated exploit package for {} in {}", target.short_str_lossless(), dir.display());
+        Ok(())
+    }
+
+    /// Create a ready-to-run exploit workspace against `target` in `dir`
+    ///
+    /// The workspace holds a Move exploit package (`move/`) depending on interface stubs of
+    /// the target's address, a Rust scenario (`src/main.rs`) implementing `Scenario`, and an
+    /// `aptosbb.toml` pinning the fork to this environment's network and version. The crate
+    /// depends on the AptosBB revision that generated it, and `.cargo/config.toml` sets the
+    /// `tokio_unstable` cfg so a plain `cargo run` works.
+    pub fn init_project(&mut self, dir: &Path, name: &str, target: &ModuleId) -> Result<()> {
+        if dir.exists() && std::fs::read_dir(dir)?.next().is_some() {
+            bail!("{} already exists and is not empty", dir.display());
+        }
+        let module = move_identifier(name);
+
+        self.generate_exploit_package(target, &dir.join("move"), &module)?;
+
+        ForkConfig::pinned(self, &[*target.address()]).save(&dir.join(FORK_CONFIG_FILE))?;
+
+        std::fs::create_dir_all(dir.join("src"))?;
+        let manifest = PROJECT_MANIFEST.replace("{name}", name).replace("{aptosbb}", &aptosbb_source());
+        std::fs::write(dir.join("Cargo.toml"), manifest)?;
+        std::fs::create_dir_all(dir.join(".cargo"))?;
+        std::fs::write(dir.join(".cargo").join("config.toml"), PROJECT_CARGO_CONFIG)?;
+        let scenario = SCENARIO_TEMPLATE
+            .replace("{name}", name)
+            .replace("{module}", &module)
+            .replace("{target}", &target.short_str_lossless());
+        std::fs::write(dir.join("src").join("main.rs"), scenario)?;
+        std::fs::write(dir.join(".gitignore"), "/target\n/.aptosbb-cache\n/move/build\n")?;
+
+        println!("Created exploit project {} in {}", name, dir.display());
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    builder::{network_name, parse_network},
+    AptosBB, AptosBBBuilder, ForkMode,
+};
+use anyhow::{anyhow, Result};
+use aptos_types::account_address::AccountAddress;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// File name of the fork configuration in a project created by `aptosbb init`
+pub const FORK_CONFIG_FILE: &str = "aptosbb.toml";
+
+/// Fork an exploit project runs against, stored as TOML so runs are reproducible
+///
+/// ```toml
+/// network = "mainnet"
+/// version = 2000000000
+/// targets = ["0xabc"]
+/// cache = ".aptosbb-cache"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ForkConfig {
+    /// `mainnet`, `testnet`, `devnet` or a REST URL
+    pub network: String,
+    /// Ledger version to fork at; unpinned configs follow the latest version
+    pub version: Option<u64>,
+    /// Accounts downloaded eagerly (with the framework) when the fork is created
+    #[serde(default)]
+    pub targets: Vec<String>,
+    /// Directory where fetched remote state is persisted
+    pub cache: Option<PathBuf>,
+}
+
+impl ForkConfig {
+    /// Config reproducing `bb`'s fork, pinned to its version
+    pub fn pinned(bb: &AptosBB, targets: &[AccountAddress]) -> Self {
+        Self {
+            network: network_name(bb.network()),
+            version: Some(bb.fork_version()),
+            targets: targets.iter().map(|addr| addr.to_hex_literal()).collect(),
+            cache: Some(PathBuf::from(".aptosbb-cache")),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| anyhow!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content).map_err(|e| anyhow!("Invalid fork config {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Builder configured for this fork; the API key is taken from `APTOSBB_KEY` if set
+    pub fn builder(&self) -> Result<AptosBBBuilder> {
+        let targets = self
+            .targets
+            .iter()
+            .map(|target| {
+                AccountAddress::from_hex_literal(target).map_err(|e| anyhow!("Invalid target address {}: {}", target, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut builder = AptosBB::builder().network(parse_network(&self.network)?);
+        if let Some(version) = self.version {
+            builder = builder.version(version);
+        }
+        if let Some(dir) = &self.cache {
+            builder = builder.cache(dir);
+        }
+        if let Ok(api_key) = std::env::var("APTOSBB_KEY") {
+            if !api_key.is_empty() {
+                builder = builder.api_key(api_key);
+            }
+        }
+        if !targets.is_empty() {
+            builder = builder.fork_mode(ForkMode::eager_with_framework(&targets));
+        }
+        Ok(builder)
+    }
+}
+
+/// An exploit scenario: state setup followed by the attack itself
+///
+/// Scenarios are what `aptosbb init` scaffolds; run them with `AptosBB::run_scenario`.
+pub trait Scenario {
+    /// Name the scenario's transactions are grouped under in reports
+    fn name(&self) -> &str;
+
+    /// Prepare accounts, balances and deployments the attack needs
+    fn setup(&mut self, _bb: &mut AptosBB) -> Result<()> {
+        Ok(())
+    }
+
+    /// Execute the attack, failing if it does not achieve its goal
+    fn run(&mut self, bb: &mut AptosBB) -> Result<()>;
+
+    /// Parameters of this run, stored with it in the experiment history
+    fn inputs(&self) -> Value {
+        Value::Null
+    }
+}
+
+impl AptosBB {
+    /// Run a scenario's setup and attack, grouped under its name in the session report and
+    /// recorded in the experiment history if `log_experiments` is on
+    pub fn run_scenario(&mut self, scenario: &mut (impl Scenario + ?Sized)) -> Result<()> {
+        let started = Instant::now();
+        self.begin_scenario(scenario.name());
+        self.begin_experiment();
+        let result = scenario.setup(self).and_then(|_| scenario.run(self));
+        self.record_experiment(scenario.name(), &scenario.inputs(), &result, started.elapsed());
+        result
+    }
+}
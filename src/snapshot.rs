@@ -0,0 +1,444 @@
+use crate::{blocking, module_cache::module_id_of, AptosBB};
+use anyhow::{anyhow, bail, Context, Result};
+use aptos_crypto::{hash::CryptoHash, HashValue};
+use aptos_types::{
+    account_address::AccountAddress,
+    state_store::{state_key::{inner::StateKeyInner, StateKey}, state_value::StateValue},
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::{BTreeSet, HashMap, HashSet}, io::{Read, Write}, ops::RangeInclusive, path::Path};
+
+const MAGIC: &[u8; 8] = b"APTOSBB\0";
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+const ZSTD_LEVEL: i32 = 19;
+
+/// Header stored uncompressed at the start of every snapshot file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotMetadata {
+    pub format_version: u32,
+    pub tool_version: String,
+    pub chain_id: u8,
+    pub version: u64,
+    pub created_at_secs: u64,
+    pub key_count: u64,
+    /// Accounts with at least one key in the snapshot
+    pub accounts: Vec<AccountAddress>,
+    /// SHA3-256 of the uncompressed state body
+    pub body_sha3: String,
+}
+
+/// State of a fork at a given version, as stored on disk
+///
+/// File layout: `MAGIC | u32 header length (LE) | JSON header | zstd(BCS state entries)`.
+/// The header is readable without decompressing the body, and the body is checked
+/// against `body_sha3` before any state is used.
+pub struct Snapshot {
+    pub metadata: SnapshotMetadata,
+    pub entries: Vec<(StateKey, StateValue)>,
+}
+
+fn key_address(state_key: &StateKey) -> Option<AccountAddress> {
+    match state_key.inner() {
+        StateKeyInner::AccessPath(access_path) => Some(access_path.address),
+        _ => None,
+    }
+}
+
+impl Snapshot {
+    pub fn new(chain_id: u8, version: u64, mut entries: Vec<(StateKey, StateValue)>) -> Result<Self> {
+        entries.sort_by_key(|(key, _)| key.hash());
+        let body = bcs::to_bytes(&entries)?;
+        let accounts: BTreeSet<_> = entries.iter().filter_map(|(key, _)| key_address(key)).collect();
+
+        let metadata = SnapshotMetadata {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            chain_id,
+            version,
+            created_at_secs: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)?
+                .as_secs(),
+            key_count: entries.len() as u64,
+            accounts: accounts.into_iter().collect(),
+            body_sha3: HashValue::sha3_256_of(&body).to_hex(),
+        };
+        Ok(Self { metadata, entries })
+    }
+
+    /// Write the snapshot to a file
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let header = serde_json::to_vec(&self.metadata)?;
+        let body = bcs::to_bytes(&self.entries)?;
+
+        let mut file = std::fs::File::create(path)
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+        file.write_all(MAGIC)?;
+        file.write_all(&(header.len() as u32).to_le_bytes())?;
+        file.write_all(&header)?;
+        file.write_all(&zstd::encode_all(body.as_slice(), ZSTD_LEVEL)?)?;
+        Ok(())
+    }
+
+    fn read_header(reader: &mut impl Read) -> Result<SnapshotMetadata> {
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            bail!("Not an AptosBB snapshot");
+        }
+
+        let mut len = [0u8; 4];
+        reader.read_exact(&mut len)?;
+        let mut header = vec![0u8; u32::from_le_bytes(len) as usize];
+        reader.read_exact(&mut header)?;
+
+        let metadata: SnapshotMetadata = serde_json::from_slice(&header)?;
+        if metadata.format_version > SNAPSHOT_FORMAT_VERSION {
+            bail!(
+                "Snapshot format version {} is newer than supported version {}",
+                metadata.format_version,
+                SNAPSHOT_FORMAT_VERSION
+            );
+        }
+        Ok(metadata)
+    }
+
+    /// Read only the metadata header of a snapshot file
+    pub fn read_metadata(path: &Path) -> Result<SnapshotMetadata> {
+        let mut file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        Self::read_header(&mut file)
+    }
+
+    /// Read and verify a snapshot file
+    pub fn read(path: &Path) -> Result<Self> {
+        let mut file = std::fs::File::open(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let metadata = Self::read_header(&mut file)?;
+
+        let body = zstd::decode_all(file)?;
+        let checksum = HashValue::sha3_256_of(&body).to_hex();
+        if checksum != metadata.body_sha3 {
+            bail!("Snapshot checksum mismatch: expected {}, got {}", metadata.body_sha3, checksum);
+        }
+
+        let entries: Vec<(StateKey, StateValue)> = bcs::from_bytes(&body)?;
+        if entries.len() as u64 != metadata.key_count {
+            return Err(anyhow!("Snapshot declares {} keys but contains {}", metadata.key_count, entries.len()));
+        }
+        Ok(Self { metadata, entries })
+    }
+}
+
+/// In-memory copy of a fork's local state, restored with `AptosBB::revert`
+#[derive(Clone)]
+pub struct Checkpoint {
+    state: HashMap<StateKey, StateValue>,
+    /// Keys deleted on the fork, which read as absent rather than from the network
+    deleted: HashSet<StateKey>,
+    sequence_numbers: HashMap<AccountAddress, u64>,
+}
+
+/// Outcome of advancing a snapshot to a newer version
+#[derive(Clone, Debug, Default)]
+pub struct SnapshotUpdate {
+    pub from_version: u64,
+    pub to_version: u64,
+    /// Whether the update replayed the write sets in between rather than refetching every key
+    pub incremental: bool,
+    pub changed: usize,
+    pub removed: usize,
+    pub unchanged: usize,
+}
+
+/// Writes turning `current` back into `checkpoint`: the value to set, or `None` to drop a
+/// key created since
+fn restore_ops(
+    current: &HashMap<StateKey, StateValue>,
+    checkpoint: &HashMap<StateKey, StateValue>,
+) -> Vec<(StateKey, Option<StateValue>)> {
+    let mut ops = vec![];
+    for (state_key, value) in current {
+        match checkpoint.get(state_key) {
+            Some(old) if old == value => {}
+            old => ops.push((state_key.clone(), old.cloned())),
+        }
+    }
+    for (state_key, old) in checkpoint {
+        if !current.contains_key(state_key) {
+            ops.push((state_key.clone(), Some(old.clone())));
+        }
+    }
+    ops
+}
+
+impl AptosBB {
+    /// All state held locally by the fork: fetched keys plus local modifications
+    pub(crate) fn local_state(&self) -> Vec<(StateKey, StateValue)> {
+        self.executor
+            .data_store()
+            .inner()
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect()
+    }
+
+    /// Capture the fork's local state in memory
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            state: self.local_state().into_iter().collect(),
+            deleted: self.remote.deleted_keys(),
+            sequence_numbers: self.sequence_numbers.clone(),
+        }
+    }
+
+    /// Restore the fork to a checkpoint
+    ///
+    /// Keys created since the checkpoint are dropped from local state (and re-read from the
+    /// network on next access, unless they were deleted at the checkpoint), changed keys are
+    /// restored to their checkpointed value and keys deleted since the checkpoint are put back.
+    pub fn revert(&mut self, checkpoint: &Checkpoint) {
+        let current: HashMap<StateKey, StateValue> = self.local_state().into_iter().collect();
+        for (state_key, old) in restore_ops(&current, &checkpoint.state) {
+            match old {
+                Some(old) => {
+                    self.executor.data_store_mut().set(state_key.clone(), old);
+                }
+                None => {
+                    self.executor.data_store_mut().remove(&state_key);
+                }
+            }
+            if let Some(module_id) = module_id_of(&state_key) {
+                self.module_cache.remove(&module_id);
+            }
+        }
+        self.remote.set_deleted_keys(checkpoint.deleted.clone());
+        self.sequence_numbers = checkpoint.sequence_numbers.clone();
+        self.view_cache.invalidate();
+    }
+
+    /// Run `f` against the fork and revert every change it made, returning its value
+    ///
+    /// The fork is restored even if `f` panics, after which the panic is resumed.
+    ///
+    /// ```ignore
+    /// let drained = bb.with_snapshot(|bb| {
+    ///     bb.call(&attacker, withdraw_all)?;
+    ///     Ok::<_, anyhow::Error>(bb.read_aptos_balance(attacker.address()))
+    /// })?;
+    /// ```
+    pub fn with_snapshot<R>(&mut self, f: impl FnOnce(&mut AptosBB) -> R) -> R {
+        let checkpoint = self.checkpoint();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| f(self)));
+        self.revert(&checkpoint);
+        result.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+
+    /// Save the fork's local state to a compressed snapshot file
+    pub fn save_snapshot(&self, path: &Path) -> Result<SnapshotMetadata> {
+        let snapshot = Snapshot::new(self.chain_id.id(), self.version, self.local_state())?;
+        snapshot.write(path)?;
+        println!("Saved snapshot with {} keys to {}", snapshot.metadata.key_count, path.display());
+        Ok(snapshot.metadata)
+    }
+
+    /// Load a snapshot file into the fork
+    ///
+    /// The snapshot must come from the same chain and version the fork is pinned to.
+    pub fn load_snapshot(&mut self, path: &Path) -> Result<SnapshotMetadata> {
+        let snapshot = Snapshot::read(path)?;
+        if snapshot.metadata.chain_id != self.chain_id.id() {
+            bail!("Snapshot is for chain {} but the fork is on chain {}", snapshot.metadata.chain_id, self.chain_id);
+        }
+        if snapshot.metadata.version != self.version {
+            bail!("Snapshot is at version {} but the fork is pinned to {}", snapshot.metadata.version, self.version);
+        }
+
+        for (state_key, value) in snapshot.entries {
+            self.executor.data_store_mut().set(state_key, value);
+        }
+        self.view_cache.invalidate();
+        println!("Loaded snapshot with {} keys from {}", snapshot.metadata.key_count, path.display());
+        Ok(snapshot.metadata)
+    }
+}
+
+/// Transactions requested per page when replaying write sets, the API's largest page
+const TRANSACTION_PAGE_SIZE: u16 = 100;
+
+/// Bring snapshot entries up to date, counting what changed in `update`
+///
+/// `latest` gives a key's new value (`Some(None)` once deleted), or `None` when it is known
+/// not to have changed.
+fn advance_entries(
+    entries: Vec<(StateKey, StateValue)>,
+    mut latest: impl FnMut(&StateKey) -> Option<Option<StateValue>>,
+    update: &mut SnapshotUpdate,
+) -> Vec<(StateKey, StateValue)> {
+    let mut advanced = Vec::with_capacity(entries.len());
+    for (key, old_value) in entries {
+        match latest(&key) {
+            None => {
+                update.unchanged += 1;
+                advanced.push((key, old_value));
+            }
+            Some(Some(new_value)) if new_value.bytes() == old_value.bytes() => {
+                update.unchanged += 1;
+                advanced.push((key, old_value));
+            }
+            Some(Some(new_value)) => {
+                update.changed += 1;
+                advanced.push((key, new_value));
+            }
+            Some(None) => update.removed += 1,
+        }
+    }
+    advanced
+}
+
+impl AptosBB {
+    /// Final value of every key written by the committed transactions in `versions`, `None`
+    /// for keys they deleted
+    fn fetch_writes(&self, versions: RangeInclusive<u64>) -> Result<HashMap<StateKey, Option<StateValue>>> {
+        let mut writes = HashMap::new();
+        let mut start = *versions.start();
+        while start <= *versions.end() {
+            let limit = (versions.end() - start + 1).min(TRANSACTION_PAGE_SIZE as u64) as u16;
+            let page = blocking::block_on(async { anyhow::Ok(self.remote.client().await?.get_transactions_bcs(Some(start), Some(limit)).await?) })?
+                .into_inner();
+            if page.is_empty() {
+                bail!("No transactions available from version {}", start);
+            }
+            for txn in &page {
+                for (key, op) in txn.changes.iter() {
+                    let value = op.bytes().map(|bytes| StateValue::new_with_metadata(bytes.clone(), op.metadata().clone()));
+                    writes.insert(key.clone(), value);
+                }
+            }
+            start += page.len() as u64;
+        }
+        Ok(writes)
+    }
+
+    /// Advance a snapshot file to the fork's version
+    ///
+    /// The write sets of the transactions committed in between are replayed onto the
+    /// snapshot's keys, a page of transactions per request. When that would take more
+    /// requests than reading every key again, or the transactions are not available (e.g.
+    /// pruned), every key is refetched at the fork version instead. Keys deleted on chain are
+    /// dropped. The file is replaced atomically once the new snapshot is written.
+    pub fn update_snapshot(&self, path: &Path) -> Result<SnapshotUpdate> {
+        let snapshot = Snapshot::read(path)?;
+        if snapshot.metadata.chain_id != self.chain_id.id() {
+            bail!("Snapshot is for chain {} but the fork is on chain {}", snapshot.metadata.chain_id, self.chain_id);
+        }
+        if snapshot.metadata.version > self.version {
+            bail!("Snapshot is at version {}, ahead of the fork version {}", snapshot.metadata.version, self.version);
+        }
+
+        let mut update = SnapshotUpdate {
+            from_version: snapshot.metadata.version,
+            to_version: self.version,
+            ..Default::default()
+        };
+        let pages = (self.version - snapshot.metadata.version).div_ceil(TRANSACTION_PAGE_SIZE as u64);
+        let writes = if pages <= snapshot.entries.len() as u64 {
+            match self.fetch_writes(snapshot.metadata.version + 1..=self.version) {
+                Ok(writes) => Some(writes),
+                Err(e) => {
+                    eprintln!("Warning: cannot replay transactions since version {} ({:#}), refetching every key", snapshot.metadata.version, e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let entries = match writes {
+            Some(mut writes) => {
+                update.incremental = true;
+                advance_entries(snapshot.entries, |key| writes.remove(key), &mut update)
+            }
+            None => {
+                let keys: Vec<StateKey> = snapshot.entries.iter().map(|(key, _)| key.clone()).collect();
+                let mut latest = blocking::block_on(self.remote.get_state_values(&keys))?;
+                advance_entries(snapshot.entries, |key| Some(latest.remove(key).flatten()), &mut update)
+            }
+        };
+
+        let updated = Snapshot::new(self.chain_id.id(), self.version, entries)?;
+        let tmp_path = path.with_extension("tmp");
+        updated.write(&tmp_path)?;
+        std::fs::rename(&tmp_path, path)?;
+
+        println!(
+            "Updated snapshot {} -> {} ({}): {} changed, {} removed, {} unchanged",
+            update.from_version,
+            update.to_version,
+            if update.incremental { "replayed write sets" } else { "refetched" },
+            update.changed,
+            update.removed,
+            update.unchanged
+        );
+        Ok(update)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(byte: u8) -> StateValue {
+        StateValue::new_legacy(vec![byte].into())
+    }
+
+    #[test]
+    fn restore_ops_undo_changes_creations_and_deletions() {
+        let (unchanged, changed, created, deleted) =
+            (StateKey::raw(b"unchanged"), StateKey::raw(b"changed"), StateKey::raw(b"created"), StateKey::raw(b"deleted"));
+        let checkpoint = HashMap::from([(unchanged.clone(), value(0)), (changed.clone(), value(1)), (deleted.clone(), value(3))]);
+        let current = HashMap::from([(unchanged, value(0)), (changed.clone(), value(2)), (created.clone(), value(4))]);
+
+        let mut ops = restore_ops(&current, &checkpoint);
+        ops.sort_by(|a, b| a.0.hash().cmp(&b.0.hash()));
+        let mut expected = vec![(changed, Some(value(1))), (created, None), (deleted, Some(value(3)))];
+        expected.sort_by(|a, b| a.0.hash().cmp(&b.0.hash()));
+        assert_eq!(ops, expected);
+    }
+
+    #[test]
+    fn advance_entries_applies_changes_and_deletions() {
+        let (same, rewritten, changed, deleted) =
+            (StateKey::raw(b"same"), StateKey::raw(b"rewritten"), StateKey::raw(b"changed"), StateKey::raw(b"deleted"));
+        let entries = vec![(same.clone(), value(0)), (rewritten.clone(), value(1)), (changed.clone(), value(2)), (deleted.clone(), value(3))];
+        let mut writes = HashMap::from([(rewritten.clone(), Some(value(1))), (changed.clone(), Some(value(5))), (deleted, None)]);
+
+        let mut update = SnapshotUpdate::default();
+        let advanced = advance_entries(entries, |key| writes.remove(key), &mut update);
+        assert_eq!(advanced, vec![(same, value(0)), (rewritten, value(1)), (changed, value(5))]);
+        assert_eq!((update.changed, update.removed, update.unchanged), (1, 1, 2));
+    }
+
+    #[test]
+    fn snapshot_file_round_trips_and_detects_corruption() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fork.snap");
+        let entries = vec![(StateKey::raw(b"a"), value(1)), (StateKey::raw(b"b"), value(2))];
+        Snapshot::new(1, 42, entries.clone()).unwrap().write(&path).unwrap();
+
+        let metadata = Snapshot::read_metadata(&path).unwrap();
+        assert_eq!((metadata.chain_id, metadata.version, metadata.key_count), (1, 42, 2));
+        let read = Snapshot::read(&path).unwrap();
+        assert_eq!(read.entries.len(), 2);
+        assert!(entries.iter().all(|entry| read.entries.contains(entry)));
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        let header_len = u32::from_le_bytes(bytes[8..12].try_into().unwrap()) as usize;
+        let body = zstd::encode_all(bcs::to_bytes(&entries[..1]).unwrap().as_slice(), ZSTD_LEVEL).unwrap();
+        bytes.truncate(12 + header_len);
+        bytes.extend(body);
+        std::fs::write(&path, bytes).unwrap();
+        assert!(Snapshot::read(&path).is_err());
+        std::fs::write(&path, b"not a snapshot").unwrap();
+        assert!(Snapshot::read_metadata(&path).is_err());
+    }
+}
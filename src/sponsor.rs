@@ -0,0 +1,165 @@
+use crate::{entry::parse_function_id, error::Result, export::function_name, AptosBB};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{ExecutionStatus, SignedTransaction, TransactionOutput, TransactionPayload, TransactionStatus},
+};
+use std::collections::BTreeMap;
+
+/// One transaction a gas station paid for
+#[derive(Clone, Debug)]
+pub struct SponsoredTxn {
+    pub sender: AccountAddress,
+    pub function: String,
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    /// APT (in octas) the sponsor lost, storage fees included
+    pub sponsor_drain: u64,
+}
+
+/// A fee-payer service paying gas for whatever users submit
+///
+/// Every transaction is signed with the station's account as fee payer. Optionally only
+/// some entry functions are sponsored and gas is capped per transaction, as real services
+/// do; the ledger then shows how much each (possibly failing) transaction cost the sponsor.
+pub struct GasStation {
+    sponsor: Account,
+    allowed_functions: Option<Vec<String>>,
+    max_gas_amount: Option<u64>,
+    ledger: Vec<SponsoredTxn>,
+}
+
+impl GasStation {
+    pub fn new(sponsor: Account) -> Self {
+        Self { sponsor, allowed_functions: None, max_gas_amount: None, ledger: vec![] }
+    }
+
+    /// Only sponsor calls to these entry functions (`0xabc::module::function`)
+    pub fn allow_functions(mut self, functions: &[&str]) -> Self {
+        self.allowed_functions = Some(functions.iter().map(|function| function.to_string()).collect());
+        self
+    }
+
+    /// Cap the gas a user may have sponsored per transaction
+    pub fn max_gas_amount(mut self, max_gas_amount: u64) -> Self {
+        self.max_gas_amount = Some(max_gas_amount);
+        self
+    }
+
+    pub fn sponsor(&self) -> &Account {
+        &self.sponsor
+    }
+
+    fn sponsors(&self, payload: &TransactionPayload) -> bool {
+        let Some(allowed) = &self.allowed_functions else {
+            return true;
+        };
+        let TransactionPayload::EntryFunction(entry) = payload else {
+            return false;
+        };
+        allowed.iter().any(|function| {
+            parse_function_id(function)
+                .is_ok_and(|(module, name)| &module == entry.module() && name.as_ident_str() == entry.function())
+        })
+    }
+
+    /// Submit `payload` from `user` with the station paying gas
+    ///
+    /// Returns `None` when the station's allow-list refuses to sponsor the payload.
+    pub fn submit(&mut self, bb: &mut AptosBB, user: &Account, payload: TransactionPayload) -> Result<Option<&SponsoredTxn>> {
+        if !self.sponsors(&payload) {
+            return Ok(None);
+        }
+        let function = function_name(&payload);
+
+        let before = bb.read_aptos_balance(self.sponsor.address());
+        let (status, output) = bb.run_sponsored_transaction(user, &self.sponsor, payload, self.max_gas_amount)?;
+        let after = bb.read_aptos_balance(self.sponsor.address());
+        self.ledger.push(SponsoredTxn {
+            sender: *user.address(),
+            function,
+            status,
+            gas_used: output.gas_used(),
+            sponsor_drain: before.saturating_sub(after),
+        });
+        Ok(self.ledger.last())
+    }
+
+    pub fn ledger(&self) -> &[SponsoredTxn] {
+        &self.ledger
+    }
+
+    /// Total APT (in octas) the sponsor lost across every sponsored transaction
+    pub fn total_drain(&self) -> u64 {
+        self.ledger.iter().map(|txn| txn.sponsor_drain).sum()
+    }
+
+    /// Average drain per sponsored transaction
+    pub fn drain_per_txn(&self) -> f64 {
+        self.total_drain() as f64 / self.ledger.len().max(1) as f64
+    }
+
+    /// Drain caused by transactions that did not succeed, i.e. paid for nothing
+    pub fn failed_drain(&self) -> u64 {
+        self.ledger
+            .iter()
+            .filter(|txn| txn.status != TransactionStatus::Keep(ExecutionStatus::Success))
+            .map(|txn| txn.sponsor_drain)
+            .sum()
+    }
+
+    /// Total drain per user
+    pub fn drain_by_sender(&self) -> BTreeMap<AccountAddress, u64> {
+        let mut drain = BTreeMap::new();
+        for txn in &self.ledger {
+            *drain.entry(txn.sender).or_default() += txn.sponsor_drain;
+        }
+        drain
+    }
+
+    /// Print the ledger and its totals
+    pub fn print_summary(&self) {
+        for txn in &self.ledger {
+            println!(
+                "  {} {} -> {:?}: {} gas, {} octas drained",
+                txn.sender, txn.function, txn.status, txn.gas_used, txn.sponsor_drain
+            );
+        }
+        println!(
+            "⛽ {} sponsored transactions drained {} octas ({:.0} per transaction, {} on failures)",
+            self.ledger.len(),
+            self.total_drain(),
+            self.drain_per_txn(),
+            self.failed_drain()
+        );
+    }
+}
+
+impl AptosBB {
+    /// Sign a transaction from `account` with `fee_payer` paying its gas
+    pub(crate) fn sign_fee_payer_transaction(
+        &mut self,
+        account: &Account,
+        fee_payer: &Account,
+        payload: TransactionPayload,
+        max_gas_amount: Option<u64>,
+    ) -> SignedTransaction {
+        let builder = self.transaction_builder(account, payload);
+        builder
+            .max_gas_amount(max_gas_amount.unwrap_or(self.gas_defaults.max_gas_amount))
+            .fee_payer(fee_payer.clone())
+            .sign_fee_payer()
+    }
+
+    /// Run a transaction from `account` with `fee_payer` sponsoring its gas
+    pub fn run_sponsored_transaction(
+        &mut self,
+        account: &Account,
+        fee_payer: &Account,
+        payload: TransactionPayload,
+        max_gas_amount: Option<u64>,
+    ) -> Result<(TransactionStatus, TransactionOutput)> {
+        let txn = self.sign_fee_payer_transaction(account, fee_payer, payload, max_gas_amount);
+        self.run_signed_transaction_with_output(txn)
+    }
+}
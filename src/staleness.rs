@@ -0,0 +1,375 @@
+//! Stale oracle detection: flag transactions that read price or timestamp data older than a
+//! freshness threshold, e.g. after warping time past the last oracle update
+
+use crate::{
+    export::function_name,
+    findings::{Category, Finding, Severity},
+    observer::{recorder, Recorder, TxnObserver},
+    vm_version::VmVersion,
+    AptosBB,
+};
+use anyhow::{bail, Context, Result};
+use aptos_types::{
+    access_path::Path,
+    account_address::AccountAddress,
+    state_store::{
+        state_key::{inner::StateKeyInner, StateKey},
+        state_storage_usage::StateStorageUsage,
+        state_value::StateValue,
+        StateViewResult, TStateView,
+    },
+    transaction::{SignedTransaction, TransactionOutput},
+};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, sync::Mutex};
+
+/// Field names holding the time a value was last updated
+const TIMESTAMP_FIELDS: &[&str] = &[
+    "timestamp",
+    "last_update",
+    "last_updated",
+    "updated_at",
+    "update_time",
+    "publish_time",
+    "attestation_time",
+    "arrival_time",
+    "last_refresh",
+];
+
+/// Type or field name fragments marking a resource as price data
+const PRICE_HINTS: &[&str] = &["price", "oracle", "feed", "twap"];
+
+/// Timestamps before this (2001) are durations or counters rather than points in time
+const MIN_TIMESTAMP_SECS: u64 = 1_000_000_000;
+
+/// Freshness thresholds for `watch_staleness`
+#[derive(Clone, Debug)]
+pub struct StalenessConfig {
+    /// Oldest data, in seconds, a transaction may consume
+    pub max_age_secs: u64,
+    /// Thresholds for resources whose type contains the pattern, first match wins
+    pub overrides: Vec<(String, u64)>,
+    /// Type patterns checked even though they do not look like price data
+    pub watch: Vec<String>,
+    /// Tables holding feeds, with the struct their values decode as
+    pub tables: Vec<(AccountAddress, StructTag)>,
+}
+
+impl Default for StalenessConfig {
+    fn default() -> Self {
+        Self::new(60)
+    }
+}
+
+impl StalenessConfig {
+    pub fn new(max_age_secs: u64) -> Self {
+        Self { max_age_secs, overrides: vec![], watch: vec![], tables: vec![] }
+    }
+
+    /// Use a different threshold for resources whose type contains `type_pattern`
+    pub fn max_age_for(mut self, type_pattern: &str, max_age_secs: u64) -> Self {
+        self.overrides.push((type_pattern.to_string(), max_age_secs));
+        self
+    }
+
+    /// Also check resources whose type contains `type_pattern`
+    pub fn watch(mut self, type_pattern: &str) -> Self {
+        self.watch.push(type_pattern.to_string());
+        self
+    }
+
+    /// Also check items of the table `handle`, whose values are `value_type`
+    ///
+    /// Oracles such as Pyth keep their feeds in tables, which carry no type information;
+    /// `Pyth::staleness_table` returns the pair for Pyth.
+    pub fn watch_table(mut self, handle: AccountAddress, value_type: StructTag) -> Self {
+        self.tables.push((handle, value_type));
+        self
+    }
+
+    fn max_age(&self, type_name: &str) -> u64 {
+        self.overrides
+            .iter()
+            .find(|(pattern, _)| type_name.contains(pattern.as_str()))
+            .map_or(self.max_age_secs, |(_, max_age)| *max_age)
+    }
+}
+
+/// A read of data older than its threshold
+#[derive(Clone, Debug)]
+pub struct StaleRead {
+    /// `address::module::function` of the transaction, or its payload kind
+    pub function: String,
+    /// Account holding the resource, or the handle of the table holding the item
+    pub address: AccountAddress,
+    /// Type of the resource or table item
+    pub resource: StructTag,
+    /// Path of the timestamp field inside the resource, e.g. `price.timestamp`
+    pub field: String,
+    pub timestamp_secs: u64,
+    pub age_secs: u64,
+    pub max_age_secs: u64,
+    /// Whether the transaction succeeded, i.e. acted on the stale data
+    pub consumed: bool,
+}
+
+impl fmt::Display for StaleRead {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} read {}::{} with {} {}s old (max {}s)",
+            if self.consumed { "🚨" } else { "⚠️ " },
+            self.function,
+            self.address.to_hex_literal(),
+            self.resource,
+            self.field,
+            self.age_secs,
+            self.max_age_secs
+        )
+    }
+}
+
+/// Stale reads that went through are oracle findings; reverted ones show the protocol checks
+impl From<&StaleRead> for Finding {
+    fn from(read: &StaleRead) -> Self {
+        let severity = if read.consumed { Severity::Medium } else { Severity::Informational };
+        Finding::new("Stale price data consumed", severity, Category::Oracle)
+            .affected(&read.function)
+            .evidence(read.to_string())
+    }
+}
+
+/// Stale reads recorded while watching, and the thresholds applied
+#[derive(Clone, Debug)]
+pub(crate) struct StalenessWatch {
+    config: StalenessConfig,
+    reads: Vec<StaleRead>,
+}
+
+/// Staleness check of a transaction about to execute
+pub(crate) struct PendingStalenessCheck {
+    function: String,
+    /// Fork clock before execution, in seconds
+    now: u64,
+}
+
+/// State view recording every existing value a transaction reads
+///
+/// Reads of the transaction's own writes are served by the VM, so the recorded values are
+/// the state before the transaction.
+pub(crate) struct RecordingView<'a, S> {
+    inner: &'a S,
+    reads: Mutex<BTreeMap<StateKey, StateValue>>,
+}
+
+impl<'a, S> RecordingView<'a, S> {
+    pub(crate) fn new(inner: &'a S) -> Self {
+        Self { inner, reads: Mutex::new(BTreeMap::new()) }
+    }
+
+    pub(crate) fn into_reads(self) -> BTreeMap<StateKey, StateValue> {
+        self.reads.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl<S: TStateView<Key = StateKey>> TStateView for RecordingView<'_, S> {
+    type Key = StateKey;
+
+    fn get_state_value(&self, state_key: &StateKey) -> StateViewResult<Option<StateValue>> {
+        let value = self.inner.get_state_value(state_key)?;
+        if let Some(value) = &value {
+            self.reads
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .insert(state_key.clone(), value.clone());
+        }
+        Ok(value)
+    }
+
+    fn get_usage(&self) -> StateViewResult<StateStorageUsage> {
+        self.inner.get_usage()
+    }
+}
+
+/// Resources and watched table items among the values a transaction read, with their bytes
+fn read_values(reads: &BTreeMap<StateKey, StateValue>, config: &StalenessConfig) -> Result<Vec<(AccountAddress, StructTag, Vec<u8>)>> {
+    let mut values = vec![];
+    for (state_key, value) in reads {
+        match state_key.inner() {
+            StateKeyInner::AccessPath(access_path) => match access_path.get_path() {
+                Path::Resource(struct_tag) => values.push((access_path.address, struct_tag, value.bytes().to_vec())),
+                Path::ResourceGroup(_) => {
+                    let members: BTreeMap<StructTag, Vec<u8>> = bcs::from_bytes(value.bytes())?;
+                    values.extend(members.into_iter().map(|(struct_tag, bytes)| (access_path.address, struct_tag, bytes)));
+                }
+                Path::Code(_) => {}
+            },
+            StateKeyInner::TableItem { handle, .. } => {
+                if let Some((_, value_type)) = config.tables.iter().find(|(table, _)| *table == handle.0) {
+                    values.push((handle.0, value_type.clone(), value.bytes().to_vec()));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(values)
+}
+
+/// Seconds since the epoch of a timestamp in seconds, milliseconds or microseconds
+fn timestamp_secs(value: &Value) -> Option<u64> {
+    let raw = match value {
+        Value::String(s) => s.parse().ok()?,
+        value => value.as_u64()?,
+    };
+    let secs = match raw {
+        raw if raw >= 100_000_000_000_000 => raw / 1_000_000,
+        raw if raw >= 100_000_000_000 => raw / 1_000,
+        raw => raw,
+    };
+    (secs >= MIN_TIMESTAMP_SECS).then_some(secs)
+}
+
+/// Timestamp fields anywhere in a decoded resource, with their paths
+fn timestamp_fields(value: &Value, path: &str, found: &mut Vec<(String, u64)>) {
+    match value {
+        Value::Object(fields) => {
+            for (name, field) in fields {
+                let field_path = if path.is_empty() { name.clone() } else { format!("{}.{}", path, name) };
+                match timestamp_secs(field) {
+                    Some(secs) if TIMESTAMP_FIELDS.contains(&name.as_str()) => found.push((field_path, secs)),
+                    _ => timestamp_fields(field, &field_path, found),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter().enumerate() {
+                timestamp_fields(item, &format!("{}[{}]", path, i), found);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn looks_like_price(type_name: &str, json: &Value) -> bool {
+    let type_name = type_name.to_lowercase();
+    PRICE_HINTS.iter().any(|hint| type_name.contains(hint))
+        || json.as_object().is_some_and(|fields| fields.keys().any(|name| name.contains("price")))
+}
+
+impl AptosBB {
+    /// Check every subsequently executed transaction for reads of stale price data
+    ///
+    /// Values the transaction reads during its execution are checked: resources (including
+    /// resource group members) that look like price data (by type or field names, or
+    /// matching `config.watch`), and items of the tables registered with
+    /// `StalenessConfig::watch_table`. Their update timestamps are compared with the fork's
+    /// clock before the transaction. Needs the main-branch VM; while watching, a transaction
+    /// fails if the fork's clock cannot be read. Restarts watching if it was already running.
+    pub fn watch_staleness(&mut self, config: StalenessConfig) {
+        self.staleness = Some(StalenessWatch { config, reads: vec![] });
+    }
+
+    /// Stale reads recorded so far, empty when watching is off
+    pub fn stale_reads(&self) -> Vec<StaleRead> {
+        self.staleness.as_ref().map(|watch| watch.reads.clone()).unwrap_or_default()
+    }
+
+    /// Stop watching and return every stale read recorded
+    pub fn finish_staleness_watch(&mut self) -> Vec<StaleRead> {
+        self.staleness.take().map(|watch| watch.reads).unwrap_or_default()
+    }
+
+    /// Check to run on a transaction about to execute, `None` when watching is off
+    pub(crate) fn begin_staleness_check(&self, txn: &SignedTransaction) -> Result<Option<PendingStalenessCheck>> {
+        if self.staleness.is_none() {
+            return Ok(None);
+        }
+        if self.vm.version != VmVersion::Main {
+            bail!("Staleness watching needs the main-branch VM, transactions run on the {}", self.vm.version);
+        }
+        let now = self.now_secs().context("Staleness watching needs the fork's clock")?;
+        Ok(Some(PendingStalenessCheck { function: function_name(txn.payload()), now }))
+    }
+
+    /// Record stale values among those the transaction read
+    pub(crate) fn record_staleness(
+        &mut self,
+        pending: PendingStalenessCheck,
+        reads: &BTreeMap<StateKey, StateValue>,
+        output: &TransactionOutput,
+    ) {
+        let Some(config) = self.staleness.as_ref().map(|watch| watch.config.clone()) else {
+            return;
+        };
+        let values = match read_values(reads, &config) {
+            Ok(values) => values,
+            Err(e) => {
+                eprintln!("Warning: failed to decode the values {} read: {:#}", pending.function, e);
+                return;
+            }
+        };
+        let consumed = output.status().status().is_ok_and(|status| status.is_success());
+
+        let mut stale = vec![];
+        for (address, resource, bytes) in values {
+            let type_name = resource.to_canonical_string();
+            let json = match self.value_to_json(&TypeTag::Struct(Box::new(resource.clone())), &bytes) {
+                Ok(json) => json,
+                Err(e) => {
+                    eprintln!("Warning: failed to decode {} read by {}: {:#}", type_name, pending.function, e);
+                    continue;
+                }
+            };
+            let watched = config.watch.iter().any(|pattern| type_name.contains(pattern.as_str()))
+                || config.tables.iter().any(|(table, _)| *table == address);
+            if !watched && !looks_like_price(&type_name, &json) {
+                continue;
+            }
+            let max_age_secs = config.max_age(&type_name);
+            let mut fields = vec![];
+            timestamp_fields(&json, "", &mut fields);
+            for (field, timestamp_secs) in fields {
+                let age_secs = pending.now.saturating_sub(timestamp_secs);
+                if age_secs > max_age_secs {
+                    stale.push(StaleRead {
+                        function: pending.function.clone(),
+                        address,
+                        resource: resource.clone(),
+                        field,
+                        timestamp_secs,
+                        age_secs,
+                        max_age_secs,
+                        consumed,
+                    });
+                }
+            }
+        }
+
+        if let Some(watch) = self.staleness.as_mut() {
+            for read in stale {
+                println!("{}", read);
+                watch.reads.push(read);
+            }
+        }
+    }
+}
+
+/// Checks the values each transaction read for staleness while watching is on
+pub(crate) struct StalenessObserver;
+
+impl TxnObserver for StalenessObserver {
+    fn begin(&self, bb: &AptosBB, txn: &SignedTransaction) -> Result<Option<Recorder>> {
+        Ok(bb.begin_staleness_check(txn)?.map(|pending| {
+            recorder(move |bb, executed| {
+                if let Some(reads) = executed.reads {
+                    bb.record_staleness(pending, reads, executed.output);
+                }
+            })
+        }))
+    }
+
+    fn needs_reads(&self, bb: &AptosBB) -> bool {
+        bb.staleness.is_some()
+    }
+}
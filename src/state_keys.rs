@@ -0,0 +1,109 @@
+use crate::{annotate::IntoTypeTag, AptosBB};
+use anyhow::{bail, Result};
+use aptos_types::{
+    account_address::{create_object_address, create_resource_address, AccountAddress},
+    state_store::{state_key::StateKey, table::TableHandle},
+    vm::module_metadata::get_metadata_from_compiled_code,
+};
+use move_binary_format::CompiledModule;
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde_json::Value;
+
+/// Where a resource's bytes live in storage
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResourceSlot {
+    /// A standalone resource, stored under its own state key
+    Resource(StateKey),
+    /// A member of a resource group: the group's state key holds a BCS
+    /// `BTreeMap<StructTag, Vec<u8>>` of every member, this one under `member`
+    GroupMember {
+        state_key: StateKey,
+        group: StructTag,
+        member: StructTag,
+    },
+}
+
+impl ResourceSlot {
+    /// State key to read or overwrite with the raw state cheatcodes
+    pub fn state_key(&self) -> &StateKey {
+        match self {
+            Self::Resource(state_key) => state_key,
+            Self::GroupMember { state_key, .. } => state_key,
+        }
+    }
+}
+
+/// Address of a named object, as created by `object::create_named_object(creator, seed)`
+pub fn object_address(creator: &AccountAddress, seed: &[u8]) -> AccountAddress {
+    create_object_address(*creator, seed)
+}
+
+/// Address of a resource account, as created by `account::create_resource_account(source, seed)`
+pub fn resource_account_address(source: &AccountAddress, seed: &[u8]) -> AccountAddress {
+    create_resource_address(*source, seed)
+}
+
+fn struct_type(ty: impl IntoTypeTag) -> Result<StructTag> {
+    match ty.into_type_tag()? {
+        TypeTag::Struct(tag) => Ok(*tag),
+        other => bail!("{} is not a struct type", other),
+    }
+}
+
+/// Group a struct of `module` is declared a member of with `#[resource_group_member]`
+pub(crate) fn declared_group(module: &CompiledModule, tag: &StructTag) -> Option<StructTag> {
+    get_metadata_from_compiled_code(module).and_then(|metadata| {
+        metadata
+            .struct_attributes
+            .get(tag.name.as_str())
+            .and_then(|attributes| attributes.iter().find_map(|attribute| attribute.get_resource_group_member()))
+    })
+}
+
+impl AptosBB {
+    /// Resource group `resource` is declared a member of, from its module's metadata
+    pub fn resource_group_of(&mut self, resource: impl IntoTypeTag) -> Result<Option<StructTag>> {
+        let tag = struct_type(resource)?;
+        let module = self.get_module(&tag.module_id())?;
+        Ok(declared_group(module.as_ref(), &tag))
+    }
+
+    /// `resource_group_of` for read-only callers, reading the module without caching it
+    pub(crate) fn read_resource_group_of(&self, tag: &StructTag) -> Result<Option<StructTag>> {
+        let Some(module) = self.read_state_value(&StateKey::module(&tag.address, &tag.module)) else {
+            return Ok(None);
+        };
+        Ok(declared_group(&CompiledModule::deserialize(module.bytes())?, tag))
+    }
+
+    /// Storage location of a resource, e.g. `resource_slot(addr, "0xabc::pool::Pool<0x1::aptos_coin::AptosCoin>")`
+    ///
+    /// Resource-group members (such as everything stored at an object) resolve to the
+    /// group's state key, following the `#[resource_group_member]` attribute of the
+    /// declaring module.
+    pub fn resource_slot(&mut self, addr: &AccountAddress, resource: impl IntoTypeTag) -> Result<ResourceSlot> {
+        let member = struct_type(resource)?;
+        match self.resource_group_of(&member)? {
+            Some(group) => Ok(ResourceSlot::GroupMember {
+                state_key: StateKey::resource_group(addr, &group),
+                group,
+                member,
+            }),
+            None => Ok(ResourceSlot::Resource(StateKey::resource(addr, &member)?)),
+        }
+    }
+
+    /// State key holding a resource (or the resource group it is a member of)
+    pub fn state_key_for(&mut self, addr: &AccountAddress, resource: impl IntoTypeTag) -> Result<StateKey> {
+        Ok(self.resource_slot(addr, resource)?.state_key().clone())
+    }
+
+    /// State key of a table item, with the key given in the API's JSON representation
+    ///
+    /// Keys are encoded with `json_to_bcs`, so `key_type` can be anything from `address`
+    /// to a protocol's own struct, e.g. `table_item_key(handle, "0x1::string::String", &json!("USDC"))`.
+    pub fn table_item_key(&mut self, handle: AccountAddress, key_type: impl IntoTypeTag, key: &Value) -> Result<StateKey> {
+        let key_bytes = self.json_to_bcs(key_type, key)?;
+        Ok(StateKey::table_item(&TableHandle(handle), &key_bytes))
+    }
+}
@@ -0,0 +1,177 @@
+use crate::AptosBB;
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    transaction::{ExecutionStatus, TransactionPayload, TransactionStatus},
+    vm_status::{AbortLocation, StatusCode, VMStatus},
+};
+use move_binary_format::access::ModuleAccess;
+use std::fmt;
+
+/// Human-readable explanation of the status codes failed PoCs most often run into
+pub fn explain_status_code(code: StatusCode) -> Option<&'static str> {
+    let explanation = match code {
+        StatusCode::OUT_OF_GAS => {
+            "Ran out of gas: raise `max_gas_amount` in the gas defaults, or the call loops over unbounded state"
+        }
+        StatusCode::EXECUTION_LIMIT_REACHED => "Hit the per-transaction execution gas limit, independent of max_gas_amount",
+        StatusCode::IO_LIMIT_REACHED => "Hit the per-transaction IO gas limit: too many or too large state reads/writes",
+        StatusCode::STORAGE_LIMIT_REACHED => "Hit the per-transaction storage fee limit: too much new state was created",
+        StatusCode::DEPENDENCY_LIMIT_REACHED => {
+            "Loaded more modules (or bytes of modules) than a transaction may depend on; the call graph is too wide"
+        }
+        StatusCode::MEMORY_LIMIT_EXCEEDED => "Exceeded the VM memory quota, usually by building very large vectors or values",
+        StatusCode::CALL_STACK_OVERFLOW => "Call stack overflow: recursion deeper than the VM allows",
+        StatusCode::VM_MAX_VALUE_DEPTH_REACHED => "A value is nested deeper than the VM allows",
+        StatusCode::ARITHMETIC_ERROR => "Arithmetic error: overflow, underflow, division by zero or an invalid shift",
+        StatusCode::VECTOR_OPERATION_ERROR => "Vector operation failed: index out of bounds or pop from an empty vector",
+        StatusCode::RESOURCE_DOES_NOT_EXIST => "borrow_global/move_from on a resource that does not exist at that address",
+        StatusCode::RESOURCE_ALREADY_EXISTS => "move_to of a resource that already exists at that address",
+        StatusCode::MISSING_DEPENDENCY | StatusCode::LINKER_ERROR => {
+            "A module or function the code depends on is not published on the fork"
+        }
+        StatusCode::FUNCTION_RESOLUTION_FAILURE => "The called function does not exist or is not an entry function",
+        StatusCode::NUMBER_OF_TYPE_ARGUMENTS_MISMATCH => "Wrong number of type arguments for the called function",
+        StatusCode::TYPE_MISMATCH => "An argument or type argument does not match the function signature",
+        StatusCode::FAILED_TO_DESERIALIZE_ARGUMENT => "An argument's BCS bytes do not decode as the parameter type",
+        StatusCode::BACKWARD_INCOMPATIBLE_MODULE_UPDATE => "Module upgrade rejected as incompatible with the published version",
+        StatusCode::SEQUENCE_NUMBER_TOO_OLD | StatusCode::SEQUENCE_NUMBER_TOO_NEW => {
+            "Sequence number mismatch: the account was used outside AptosBB's bookkeeping; sign for existing addresses with impersonate, or call set_sequence_number"
+        }
+        StatusCode::INSUFFICIENT_BALANCE_FOR_TRANSACTION_FEE => "The sender cannot pay max_gas_amount * gas_unit_price; fund it",
+        StatusCode::TRANSACTION_EXPIRED => "Transaction expired: the fork's clock is past the expiration, see set_time",
+        StatusCode::MAX_GAS_UNITS_EXCEEDS_MAX_GAS_UNITS_BOUND => "max_gas_amount is above the network's maximum",
+        StatusCode::FEATURE_UNDER_GATING => "The operation requires a feature flag that is disabled on the fork",
+        StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR => {
+            "VM invariant violation; when raised by paranoid mode, bytecode bypassed the verifier's type or reference safety"
+        }
+        _ => return None,
+    };
+    Some(explanation)
+}
+
+/// Everything the VM reports about a failed transaction
+#[derive(Clone, Debug)]
+pub struct FailureDetails {
+    /// Status as recorded on chain
+    pub status: TransactionStatus,
+    /// Full VM status, before it is collapsed into `status`
+    pub vm_status: VMStatus,
+    pub status_code: StatusCode,
+    pub sub_status: Option<u64>,
+    /// Auxiliary message attached by the VM, e.g. the failing check of a paranoid-mode error
+    pub message: Option<String>,
+    /// `module::function` (and code offset) the failure was raised in
+    pub location: Option<String>,
+    /// Abort reason from the module's error map, for `MoveAbort`s
+    pub abort_reason: Option<String>,
+    pub explanation: Option<&'static str>,
+}
+
+impl FailureDetails {
+    /// Whether the failure was raised by the VM's runtime (paranoid) type and reference checks
+    pub fn is_paranoid_failure(&self) -> bool {
+        self.status_code == StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR
+            && self
+                .message
+                .as_deref()
+                .is_some_and(|message| message.to_lowercase().contains("paranoid"))
+    }
+}
+
+impl fmt::Display for FailureDetails {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.status_code)?;
+        if let Some(sub_status) = self.sub_status {
+            write!(f, " (sub status {})", sub_status)?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        if let Some(reason) = &self.abort_reason {
+            write!(f, "\n  abort reason: {}", reason)?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, "\n  message: {}", message)?;
+        }
+        if self.is_paranoid_failure() {
+            write!(f, "\n  raised by paranoid-mode runtime checks")?;
+        }
+        if let Some(explanation) = self.explanation {
+            write!(f, "\n  hint: {}", explanation)?;
+        }
+        Ok(())
+    }
+}
+
+impl AptosBB {
+    /// Name of a function definition, falling back to its index if the module is unavailable
+    fn function_def_name(&mut self, location: &AbortLocation, function: u16) -> String {
+        let AbortLocation::Module(module_id) = location else {
+            return format!("script#{}", function);
+        };
+        self.get_module(module_id)
+            .ok()
+            .and_then(|module| {
+                let def = module.function_defs().get(function as usize)?;
+                Some(module.identifier_at(module.function_handle_at(def.function).name).to_string())
+            })
+            .map(|name| format!("{}::{}", module_id, name))
+            .unwrap_or_else(|| format!("{}::#{}", module_id, function))
+    }
+
+    /// Execute a transaction without applying it and report the full VM status if it fails
+    ///
+    /// Returns `None` when the transaction would succeed. The sender's sequence number is
+    /// not consumed, so the same payload can be run for real afterwards.
+    pub fn explain_failure(&mut self, account: &Account, payload: TransactionPayload) -> Result<Option<FailureDetails>> {
+        let txn = self.sign_dry_run_transaction(account, payload);
+
+        let (vm_status, output) = self
+            .executor
+            .execute_block_and_keep_vm_status(vec![txn])
+            .map_err(|status| anyhow!("Block execution failed: {:?}", status))?
+            .pop()
+            .ok_or_else(|| anyhow!("Executor returned no output"))?;
+        let status = output.status().clone();
+        if status == TransactionStatus::Keep(ExecutionStatus::Success) {
+            return Ok(None);
+        }
+
+        let (sub_status, message) = match &vm_status {
+            VMStatus::Error { sub_status, message, .. } | VMStatus::ExecutionFailure { sub_status, message, .. } => {
+                (*sub_status, message.clone())
+            }
+            _ => (None, None),
+        };
+        let (location, abort_reason) = match &status {
+            TransactionStatus::Keep(ExecutionStatus::MoveAbort { location, code, info }) => {
+                let location = match location {
+                    AbortLocation::Module(module_id) => module_id.to_string(),
+                    AbortLocation::Script => "script".to_string(),
+                };
+                let reason = info
+                    .as_ref()
+                    .map(|info| format!("{} (code {}): {}", info.reason_name, code, info.description));
+                (Some(location), reason)
+            }
+            TransactionStatus::Keep(ExecutionStatus::ExecutionFailure { location, function, code_offset }) => {
+                let function = self.function_def_name(location, *function);
+                (Some(format!("{} @ {}", function, code_offset)), None)
+            }
+            _ => (None, None),
+        };
+
+        let status_code = vm_status.status_code();
+        Ok(Some(FailureDetails {
+            status,
+            status_code,
+            sub_status,
+            message,
+            location,
+            abort_reason,
+            explanation: explain_status_code(status_code),
+            vm_status,
+        }))
+    }
+}
@@ -0,0 +1,174 @@
+use crate::{export::function_name, observer::TxnObserver, AptosBB};
+use aptos_types::{
+    fee_statement::FeeStatement,
+    transaction::{SignedTransaction, TransactionOutput, TransactionPayload},
+};
+use std::{fmt, ops::AddAssign};
+
+/// State growth caused by one transaction (or a sum of them)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageUsage {
+    /// Size of all values written, excluding keys; deletions count as zero
+    pub bytes_written: u64,
+    pub slots_created: u64,
+    pub slots_modified: u64,
+    pub slots_deleted: u64,
+    /// Storage deposit charged for new slots and bytes, from the `FeeStatement` event
+    pub storage_fee_octas: u64,
+    /// Deposit refunded for freed slots
+    pub storage_fee_refund_octas: u64,
+}
+
+impl StorageUsage {
+    pub fn of(output: &TransactionOutput) -> Self {
+        let mut usage = Self::default();
+        for (_, op) in output.write_set().iter() {
+            usage.bytes_written += op.bytes().map_or(0, |bytes| bytes.len() as u64);
+            if op.is_creation() {
+                usage.slots_created += 1;
+            } else if op.is_deletion() {
+                usage.slots_deleted += 1;
+            } else {
+                usage.slots_modified += 1;
+            }
+        }
+
+        let fee_statement = output
+            .events()
+            .iter()
+            .find(|event| event.type_tag().to_string() == "0x1::transaction_fee::FeeStatement")
+            .and_then(|event| bcs::from_bytes::<FeeStatement>(event.event_data()).ok());
+        if let Some(fee_statement) = fee_statement {
+            usage.storage_fee_octas = fee_statement.storage_fee_used();
+            usage.storage_fee_refund_octas = fee_statement.storage_fee_refund();
+        }
+        usage
+    }
+
+    /// Slots created minus slots deleted
+    pub fn net_slots(&self) -> i64 {
+        self.slots_created as i64 - self.slots_deleted as i64
+    }
+
+    /// Deposit locked up by the state, net of refunds
+    pub fn net_storage_fee_octas(&self) -> i128 {
+        self.storage_fee_octas as i128 - self.storage_fee_refund_octas as i128
+    }
+}
+
+impl AddAssign for StorageUsage {
+    fn add_assign(&mut self, other: Self) {
+        self.bytes_written += other.bytes_written;
+        self.slots_created += other.slots_created;
+        self.slots_modified += other.slots_modified;
+        self.slots_deleted += other.slots_deleted;
+        self.storage_fee_octas += other.storage_fee_octas;
+        self.storage_fee_refund_octas += other.storage_fee_refund_octas;
+    }
+}
+
+/// Storage usage of one tracked transaction
+#[derive(Clone, Debug)]
+pub struct TxnStorage {
+    /// `address::module::function` for entry functions, otherwise the payload kind
+    pub function: String,
+    pub usage: StorageUsage,
+}
+
+/// Storage usage of every transaction executed since tracking started
+#[derive(Clone, Debug, Default)]
+pub struct StorageReport {
+    pub txns: Vec<TxnStorage>,
+}
+
+impl StorageReport {
+    pub fn total(&self) -> StorageUsage {
+        let mut total = StorageUsage::default();
+        for txn in &self.txns {
+            total += txn.usage;
+        }
+        total
+    }
+
+    /// Usage summed per called function, in order of first call
+    pub fn by_function(&self) -> Vec<(String, u64, StorageUsage)> {
+        let mut functions: Vec<(String, u64, StorageUsage)> = vec![];
+        for txn in &self.txns {
+            match functions.iter_mut().find(|(function, _, _)| *function == txn.function) {
+                Some((_, calls, usage)) => {
+                    *calls += 1;
+                    *usage += txn.usage;
+                }
+                None => functions.push((txn.function.clone(), 1, txn.usage)),
+            }
+        }
+        functions
+    }
+}
+
+impl fmt::Display for StorageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<60} {:>6} {:>12} {:>8} {:>8} {:>14}",
+            "function", "calls", "bytes", "created", "deleted", "deposit (oct)"
+        )?;
+        for (function, calls, usage) in self.by_function() {
+            writeln!(
+                f,
+                "{:<60} {:>6} {:>12} {:>8} {:>8} {:>+14}",
+                function,
+                calls,
+                usage.bytes_written,
+                usage.slots_created,
+                usage.slots_deleted,
+                usage.net_storage_fee_octas()
+            )?;
+        }
+        let total = self.total();
+        write!(
+            f,
+            "{:<60} {:>6} {:>12} {:>8} {:>8} {:>+14}",
+            "total",
+            self.txns.len(),
+            total.bytes_written,
+            total.slots_created,
+            total.slots_deleted,
+            total.net_storage_fee_octas()
+        )
+    }
+}
+
+impl AptosBB {
+    /// Record the storage usage of every subsequently executed transaction
+    ///
+    /// Restarts tracking if it was already running.
+    pub fn track_storage(&mut self) {
+        self.storage = Some(StorageReport::default());
+    }
+
+    /// Storage usage recorded so far, empty when tracking is off
+    pub fn storage_report(&self) -> StorageReport {
+        self.storage.clone().unwrap_or_default()
+    }
+
+    /// Stop tracking and return everything recorded
+    pub fn finish_storage_tracking(&mut self) -> StorageReport {
+        self.storage.take().unwrap_or_default()
+    }
+
+    pub(crate) fn record_storage(&mut self, payload: &TransactionPayload, output: &TransactionOutput) {
+        if let Some(report) = self.storage.as_mut() {
+            report.txns.push(TxnStorage { function: function_name(payload), usage: StorageUsage::of(output) });
+        }
+    }
+}
+
+/// Records each transaction's storage usage while tracking is on
+pub(crate) struct StorageObserver;
+
+impl TxnObserver for StorageObserver {
+    fn applied(&self, bb: &mut AptosBB, txn: &SignedTransaction, output: &TransactionOutput) {
+        bb.record_storage(txn.payload(), output);
+    }
+}
@@ -0,0 +1,336 @@
+use crate::{package::{is_framework_address, render_manifest}, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_types::account_address::AccountAddress;
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{
+        AbilitySet, FunctionDefinition, SignatureToken, StructFieldInformation, StructHandleIndex,
+        Visibility,
+    },
+    CompiledModule,
+};
+use move_core_types::language_storage::ModuleId;
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+fn render_abilities(abilities: AbilitySet) -> String {
+    let abilities: Vec<String> = abilities.into_iter().map(|a| a.to_string()).collect();
+    if abilities.is_empty() {
+        String::new()
+    } else {
+        format!(" has {}", abilities.join(", "))
+    }
+}
+
+fn render_constraints(abilities: AbilitySet) -> String {
+    let abilities: Vec<String> = abilities.into_iter().map(|a| a.to_string()).collect();
+    if abilities.is_empty() {
+        String::new()
+    } else {
+        format!(": {}", abilities.join(" + "))
+    }
+}
+
+/// Fully qualified name of a struct, so stubs need no `use` declarations
+///
+/// Structs of the module itself are left unqualified unless `qualify_self` is set.
+fn struct_name(module: &CompiledModule, idx: StructHandleIndex, qualify_self: bool) -> String {
+    let handle = module.struct_handle_at(idx);
+    let owner = module.module_id_for_handle(module.module_handle_at(handle.module));
+    let name = module.identifier_at(handle.name);
+    if owner == module.self_id() && !qualify_self {
+        name.to_string()
+    } else {
+        format!("{}::{}::{}", owner.address().to_hex_literal(), owner.name(), name)
+    }
+}
+
+/// Render a signature token as Move source
+pub fn render_type(module: &CompiledModule, token: &SignatureToken) -> String {
+    render_type_with(module, token, false)
+}
+
+/// Render a signature token as Move source for use outside the module it appears in
+pub fn render_qualified_type(module: &CompiledModule, token: &SignatureToken) -> String {
+    render_type_with(module, token, true)
+}
+
+fn render_type_with(module: &CompiledModule, token: &SignatureToken, qualify_self: bool) -> String {
+    let render_type = |token: &SignatureToken| render_type_with(module, token, qualify_self);
+    match token {
+        SignatureToken::Bool => "bool".to_string(),
+        SignatureToken::U8 => "u8".to_string(),
+        SignatureToken::U16 => "u16".to_string(),
+        SignatureToken::U32 => "u32".to_string(),
+        SignatureToken::U64 => "u64".to_string(),
+        SignatureToken::U128 => "u128".to_string(),
+        SignatureToken::U256 => "u256".to_string(),
+        SignatureToken::Address => "address".to_string(),
+        SignatureToken::Signer => "signer".to_string(),
+        SignatureToken::Vector(inner) => format!("vector<{}>", render_type(&**inner)),
+        SignatureToken::Struct(idx) => struct_name(module, *idx, qualify_self),
+        SignatureToken::StructInstantiation(idx, ty_args) => format!(
+            "{}<{}>",
+            struct_name(module, *idx, qualify_self),
+            ty_args.iter().map(render_type).collect::<Vec<_>>().join(", ")
+        ),
+        SignatureToken::Reference(inner) => format!("&{}", render_type(&**inner)),
+        SignatureToken::MutableReference(inner) => format!("&mut {}", render_type(&**inner)),
+        SignatureToken::TypeParameter(idx) => format!("T{}", idx),
+        SignatureToken::Function(args, results, abilities) => {
+            let args: Vec<_> = args.iter().map(render_type).collect();
+            let results: Vec<_> = results.iter().map(render_type).collect();
+            let results = match results.len() {
+                0 => String::new(),
+                1 => results[0].clone(),
+                _ => format!("({})", results.join(", ")),
+            };
+            format!("|{}|{}{}", args.join(", "), results, render_abilities(*abilities))
+        }
+    }
+}
+
+/// Render a function's declaration line, e.g. `public entry fun f<T0: store>(_a0: &signer): u64`
+pub fn render_function_signature(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    let handle = module.function_handle_at(def.function);
+    let type_params: Vec<String> = handle
+        .type_parameters
+        .iter()
+        .enumerate()
+        .map(|(i, constraints)| format!("T{}{}", i, render_constraints(*constraints)))
+        .collect();
+    let generics = if type_params.is_empty() { String::new() } else { format!("<{}>", type_params.join(", ")) };
+    let params: Vec<String> = module
+        .signature_at(handle.parameters)
+        .0
+        .iter()
+        .enumerate()
+        .map(|(i, token)| format!("_a{}: {}", i, render_type(module, token)))
+        .collect();
+    let returns: Vec<String> = module
+        .signature_at(handle.return_)
+        .0
+        .iter()
+        .map(|token| render_type(module, token))
+        .collect();
+    let returns = match returns.len() {
+        0 => String::new(),
+        1 => format!(": {}", returns[0]),
+        _ => format!(": ({})", returns.join(", ")),
+    };
+    let visibility = match def.visibility {
+        Visibility::Public => "public ",
+        Visibility::Friend => "public(friend) ",
+        Visibility::Private => "",
+    };
+    let entry = if def.is_entry { "entry " } else { "" };
+
+    format!(
+        "{}{}fun {}{}({}){}",
+        visibility, entry, module.identifier_at(handle.name), generics, params.join(", "), returns
+    )
+}
+
+/// Render a public function of `module` that forwards to `def`, named `call_<function>`
+///
+/// Used in generated exploit modules so calls into the target start out type-correct.
+pub fn render_wrapper(module: &CompiledModule, def: &FunctionDefinition) -> String {
+    let handle = module.function_handle_at(def.function);
+    let type_params: Vec<String> = handle
+        .type_parameters
+        .iter()
+        .enumerate()
+        .map(|(i, constraints)| format!("T{}{}", i, render_constraints(*constraints)))
+        .collect();
+    let (generics, ty_args) = if type_params.is_empty() {
+        (String::new(), String::new())
+    } else {
+        let names: Vec<String> = (0..type_params.len()).map(|i| format!("T{}", i)).collect();
+        (format!("<{}>", type_params.join(", ")), format!("<{}>", names.join(", ")))
+    };
+    let params = &module.signature_at(handle.parameters).0;
+    let declared: Vec<String> = params
+        .iter()
+        .enumerate()
+        .map(|(i, token)| format!("a{}: {}", i, render_qualified_type(module, token)))
+        .collect();
+    let passed: Vec<String> = (0..params.len()).map(|i| format!("a{}", i)).collect();
+    let returns: Vec<String> = module
+        .signature_at(handle.return_)
+        .0
+        .iter()
+        .map(|token| render_qualified_type(module, token))
+        .collect();
+    let returns = match returns.len() {
+        0 => String::new(),
+        1 => format!(": {}", returns[0]),
+        _ => format!(": ({})", returns.join(", ")),
+    };
+    let self_id = module.self_id();
+    let name = module.identifier_at(handle.name);
+
+    format!(
+        "public fun call_{}{}({}){} {{\n        {}::{}::{}{}({})\n    }}",
+        name,
+        generics,
+        declared.join(", "),
+        returns,
+        self_id.address().to_hex_literal(),
+        self_id.name(),
+        name,
+        ty_args,
+        passed.join(", ")
+    )
+}
+
+/// Addresses (other than its own) whose types or functions a module refers to
+pub fn referenced_addresses(module: &CompiledModule) -> BTreeSet<AccountAddress> {
+    module
+        .immediate_dependencies()
+        .into_iter()
+        .map(|id| *id.address())
+        .filter(|addr| addr != module.self_id().address())
+        .collect()
+}
+
+/// Render an interface-only Move module: struct declarations plus public functions
+/// whose bodies abort. Enough to compile code that calls into the on-chain module.
+pub fn module_stub(module: &CompiledModule) -> String {
+    let self_id = module.self_id();
+    let mut out = format!("module {}::{} {{\n", self_id.address().to_hex_literal(), self_id.name());
+
+    for def in module.struct_defs() {
+        let handle = module.struct_handle_at(def.struct_handle);
+        let type_params: Vec<String> = handle
+            .type_parameters
+            .iter()
+            .enumerate()
+            .map(|(i, param)| {
+                let phantom = if param.is_phantom { "phantom " } else { "" };
+                format!("{}T{}{}", phantom, i, render_constraints(param.constraints))
+            })
+            .collect();
+        let generics = if type_params.is_empty() { String::new() } else { format!("<{}>", type_params.join(", ")) };
+        let name = module.identifier_at(handle.name);
+        let render_fields = |fields: &[move_binary_format::file_format::FieldDefinition]| {
+            fields
+                .iter()
+                .map(|field| format!("{}: {}", module.identifier_at(field.name), render_type(module, &field.signature.0)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        match &def.field_information {
+            StructFieldInformation::Native => continue,
+            StructFieldInformation::Declared(fields) => {
+                out.push_str(&format!(
+                    "    struct {}{}{} {{ {} }}\n",
+                    name, generics, render_abilities(handle.abilities), render_fields(fields)
+                ));
+            }
+            StructFieldInformation::DeclaredVariants(variants) => {
+                let variants: Vec<String> = variants
+                    .iter()
+                    .map(|variant| format!("{} {{ {} }}", module.identifier_at(variant.name), render_fields(&variant.fields)))
+                    .collect();
+                out.push_str(&format!(
+                    "    enum {}{}{} {{ {} }}\n",
+                    name, generics, render_abilities(handle.abilities), variants.join(", ")
+                ));
+            }
+        }
+    }
+
+    for def in module.function_defs() {
+        if def.visibility != Visibility::Public {
+            continue;
+        }
+        out.push_str(&format!("    {} {{ abort 0 }}\n", render_function_signature(module, def)));
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+/// Package name and directory name used for the stubs of an address
+pub fn stub_package_name(addr: &AccountAddress) -> String {
+    format!("Stubs_{}", addr.short_str_lossless())
+}
+
+impl AptosBB {
+    /// Generate an interface-stub package for the modules published at `addr`
+    ///
+    /// The package is written to `out_dir/Stubs_<addr>`. Non-framework addresses the stubs
+    /// refer to are stubbed as well, next to it, and wired in as local dependencies.
+    /// Returns the generated package paths.
+    pub fn generate_dependency_stubs(&mut self, addr: AccountAddress, out_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut generated = Vec::new();
+        let mut pending = vec![addr];
+        let mut seen = BTreeSet::new();
+
+        while let Some(addr) = pending.pop() {
+            if !seen.insert(addr) || is_framework_address(&addr) {
+                continue;
+            }
+            self.fetch_account(addr)?;
+            let registry: aptos_framework::natives::code::PackageRegistry = self.read_resource(&addr)
+                .ok_or_else(|| anyhow!("No packages published at {}", addr))?;
+
+            let package_name = stub_package_name(&addr);
+            let package_dir = out_dir.join(&package_name);
+            std::fs::create_dir_all(package_dir.join("sources"))?;
+
+            let mut referenced = BTreeSet::new();
+            for module_metadata in registry.packages.iter().flat_map(|package| &package.modules) {
+                let module_id = ModuleId::new(addr, module_metadata.name.parse()?);
+                let module = self.get_module(&module_id)?;
+                referenced.extend(referenced_addresses(&module));
+                std::fs::write(
+                    package_dir.join("sources").join(format!("{}.move", module_metadata.name)),
+                    module_stub(&module),
+                )?;
+            }
+
+            let mut manifest = render_manifest(&package_name, &[]);
+            for dep_addr in referenced.into_iter().filter(|a| !is_framework_address(a)) {
+                let dep_name = stub_package_name(&dep_addr);
+                manifest.push_str(&format!("\n[dependencies.{}]\nlocal = \"../{}\"\n", dep_name, dep_name));
+                pending.push(dep_addr);
+            }
+            std::fs::write(package_dir.join("Move.toml"), manifest)?;
+            generated.push(package_dir);
+        }
+
+        println!("Generated {} stub package(s) in {}", generated.len(), out_dir.display());
+        Ok(generated)
+    }
+
+    /// Make on-chain protocols available as dependencies of a local exploit package
+    ///
+    /// Stubs are generated from on-chain bytecode under `<package>/deps/` and added to the
+    /// package's `Move.toml`, so the protocol's source does not need to be vendored.
+    pub fn link_onchain_dependencies(&mut self, package_path: &Path, targets: &[AccountAddress]) -> Result<()> {
+        let deps_dir = package_path.join("deps");
+        for target in targets {
+            self.generate_dependency_stubs(*target, &deps_dir)?;
+        }
+
+        let manifest_path = package_path.join("Move.toml");
+        let mut manifest: toml::Table = std::fs::read_to_string(&manifest_path)?.parse()?;
+        let deps = manifest
+            .entry("dependencies")
+            .or_insert_with(|| toml::Value::Table(Default::default()));
+        if let Some(deps) = deps.as_table_mut() {
+            for target in targets.iter().filter(|a| !is_framework_address(a)) {
+                let name = stub_package_name(target);
+                let mut local = toml::Table::new();
+                local.insert("local".to_string(), toml::Value::String(format!("deps/{}", name)));
+                deps.insert(name, toml::Value::Table(local));
+            }
+        }
+        std::fs::write(&manifest_path, toml::to_string(&manifest)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+//! Concise decoded receipts of executed transactions, like `cast receipt` for the fork
+
+use crate::{export::status_name, observer::TxnObserver, AptosBB};
+use aptos_types::{
+    account_address::AccountAddress,
+    transaction::{SignedTransaction, TransactionOutput},
+};
+use move_core_types::language_storage::StructTag;
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, str::FromStr};
+
+/// Balance changes a summary shows unless told otherwise
+const DEFAULT_TOP_CHANGES: usize = 5;
+
+/// Event data longer than this is cut off in summaries
+const MAX_EVENT_CHARS: usize = 160;
+
+/// Net change of one owner's balance of one asset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BalanceChange {
+    pub owner: AccountAddress,
+    /// Symbol or metadata address of a fungible asset, or the coin type
+    pub asset: String,
+    pub delta: i128,
+}
+
+/// What a transaction did, decoded
+#[derive(Clone, Debug)]
+pub struct TxnSummary {
+    pub status: String,
+    pub success: bool,
+    pub gas_used: u64,
+    /// Largest changes first
+    pub balance_changes: Vec<BalanceChange>,
+    /// Event types with their data as JSON
+    pub events: Vec<(String, Value)>,
+}
+
+impl TxnSummary {
+    /// The summary with only the `top` largest balance changes listed
+    pub fn render(&self, top: usize) -> String {
+        let mut out = format!("{} {} | {} gas", if self.success { "✅" } else { "❌" }, self.status, self.gas_used);
+        if !self.balance_changes.is_empty() {
+            out.push_str("\n  balance changes:");
+            for change in self.balance_changes.iter().take(top) {
+                out.push_str(&format!("\n    {} {:+} {}", change.owner, change.delta, change.asset));
+            }
+            if self.balance_changes.len() > top {
+                out.push_str(&format!("\n    ... {} more", self.balance_changes.len() - top));
+            }
+        }
+        if !self.events.is_empty() {
+            out.push_str("\n  events:");
+            for (type_tag, data) in &self.events {
+                let mut data = data.to_string();
+                if data.chars().count() > MAX_EVENT_CHARS {
+                    data = format!("{}…", data.chars().take(MAX_EVENT_CHARS).collect::<String>());
+                }
+                out.push_str(&format!("\n    {} {}", type_tag, data));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for TxnSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(DEFAULT_TOP_CHANGES))
+    }
+}
+
+fn json_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        value => value.as_u64(),
+    }
+}
+
+fn json_address(value: &Value) -> Option<AccountAddress> {
+    AccountAddress::from_str(value.as_str()?).ok()
+}
+
+/// Nonzero net changes, largest first
+fn balance_changes(deltas: BTreeMap<(AccountAddress, String), i128>) -> Vec<BalanceChange> {
+    let mut changes: Vec<BalanceChange> = deltas
+        .into_iter()
+        .filter(|(_, delta)| *delta != 0)
+        .map(|((owner, asset), delta)| BalanceChange { owner, asset, delta })
+        .collect();
+    changes.sort_by_key(|change| std::cmp::Reverse(change.delta.unsigned_abs()));
+    changes
+}
+
+impl AptosBB {
+    /// Print a summary after every transaction this fork executes, with the `top_changes`
+    /// largest balance changes, or stop with `None`; off by default
+    pub fn set_txn_summaries(&mut self, top_changes: Option<usize>) {
+        self.txn_summaries = top_changes.map(|top| top.max(1));
+    }
+
+    /// Owner and asset label of a fungible store, read after the transaction
+    fn fungible_store_owner(&self, store: AccountAddress) -> (AccountAddress, String) {
+        let read = |tag: &str| StructTag::from_str(tag).ok().and_then(|tag| self.read_resource_json(&store, &tag).ok().flatten());
+        let owner = read("0x1::object::ObjectCore")
+            .and_then(|core| json_address(&core["owner"]))
+            .unwrap_or(store);
+        let Some(metadata) = read("0x1::fungible_asset::FungibleStore").and_then(|store| json_address(&store["metadata"]["inner"])) else {
+            return (owner, "unknown asset".to_string());
+        };
+        let symbol = StructTag::from_str("0x1::fungible_asset::Metadata")
+            .ok()
+            .and_then(|tag| self.read_resource_json(&metadata, &tag).ok().flatten())
+            .and_then(|json| json["symbol"].as_str().map(str::to_string));
+        (owner, symbol.unwrap_or_else(|| metadata.to_hex_literal()))
+    }
+
+    /// Decode an executed transaction's status, gas, balance changes and events
+    ///
+    /// Balance changes come from fungible asset and coin deposit/withdraw events, so gas fees
+    /// (which emit neither) are only reflected in `gas_used`.
+    pub fn summarize_output(&self, output: &TransactionOutput) -> TxnSummary {
+        let mut deltas: BTreeMap<(AccountAddress, String), i128> = BTreeMap::new();
+        let mut events = vec![];
+        for event in output.events() {
+            let type_name = event.type_tag().to_string();
+            if type_name == "0x1::transaction_fee::FeeStatement" {
+                continue;
+            }
+            let data = self.value_to_json(event.type_tag(), event.event_data()).unwrap_or(Value::Null);
+            let sign = match type_name.as_str() {
+                "0x1::fungible_asset::Deposit" | "0x1::coin::CoinDeposit" => 1,
+                "0x1::fungible_asset::Withdraw" | "0x1::coin::CoinWithdraw" => -1,
+                _ => 0,
+            };
+            if let (true, Some(amount)) = (sign != 0, json_u64(&data["amount"])) {
+                let key = match json_address(&data["store"]) {
+                    Some(store) => Some(self.fungible_store_owner(store)),
+                    None => json_address(&data["account"]).zip(data["coin_type"].as_str().map(str::to_string)),
+                };
+                if let Some(key) = key {
+                    *deltas.entry(key).or_default() += sign * amount as i128;
+                }
+            }
+            events.push((type_name, data));
+        }
+
+        let status = output.status();
+        TxnSummary {
+            status: status_name(status),
+            success: status.status().is_ok_and(|status| status.is_success()),
+            gas_used: output.gas_used(),
+            balance_changes: balance_changes(deltas),
+            events,
+        }
+    }
+}
+
+/// Prints a summary of each transaction when `set_txn_summaries` is on
+pub(crate) struct SummaryObserver;
+
+impl TxnObserver for SummaryObserver {
+    fn applied(&self, bb: &mut AptosBB, _txn: &SignedTransaction, output: &TransactionOutput) {
+        if let Some(top) = bb.txn_summaries {
+            println!("{}", bb.summarize_output(output).render(top));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn deltas(entries: &[(u8, &str, i128)]) -> BTreeMap<(AccountAddress, String), i128> {
+        entries
+            .iter()
+            .map(|(owner, asset, delta)| ((AccountAddress::from_hex_literal(&format!("0x{:x}", owner)).unwrap(), asset.to_string()), *delta))
+            .collect()
+    }
+
+    #[test]
+    fn balance_changes_drop_zero_deltas_and_sort_by_magnitude() {
+        let changes = balance_changes(deltas(&[(1, "APT", 5), (2, "USDC", -50), (3, "APT", 0), (4, "USDC", 20)]));
+        let ordered: Vec<i128> = changes.iter().map(|change| change.delta).collect();
+        assert_eq!(ordered, [-50, 20, 5]);
+    }
+
+    #[test]
+    fn render_lists_the_top_changes_and_truncates_event_data() {
+        let summary = TxnSummary {
+            status: "Success".to_string(),
+            success: true,
+            gas_used: 42,
+            balance_changes: balance_changes(deltas(&[(1, "APT", 3), (2, "APT", -2), (3, "APT", 1)])),
+            events: vec![("0x1::m::E".to_string(), json!("x".repeat(2 * MAX_EVENT_CHARS)))],
+        };
+        let rendered = summary.render(2);
+        assert!(rendered.starts_with("✅ Success | 42 gas"));
+        assert!(rendered.contains(&format!("{} +3 APT", AccountAddress::ONE)));
+        assert!(rendered.contains(&format!("{} -2 APT", AccountAddress::TWO)));
+        assert!(rendered.contains("... 1 more"));
+        let event_line = rendered.lines().last().unwrap();
+        assert!(event_line.ends_with('…'));
+        assert!(event_line.chars().count() < MAX_EVENT_CHARS + 40);
+    }
+}
@@ -0,0 +1,431 @@
+use crate::{boundaries, AptosBB};
+use anyhow::{anyhow, Result};
+use move_core_types::language_storage::TypeTag;
+use serde_json::Value;
+use std::{collections::HashMap, fmt, ops::RangeInclusive};
+
+/// Inverse of the golden ratio, the fraction golden-section search keeps each step
+const INV_PHI: f64 = 0.618_033_988_749_895;
+
+/// Numeric parameters to sweep a scenario over; every combination is tried
+#[derive(Clone, Debug, Default)]
+pub struct ParamGrid {
+    axes: Vec<(String, Vec<i128>)>,
+}
+
+impl ParamGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Explicit values of a parameter
+    pub fn values<T: Into<i128>>(mut self, name: &str, values: impl IntoIterator<Item = T>) -> Self {
+        self.axes.push((name.to_string(), values.into_iter().map(Into::into).collect()));
+        self
+    }
+
+    /// Evenly spaced values from `range.start()` up to `range.end()` (inclusive)
+    pub fn range(self, name: &str, range: RangeInclusive<i128>, step: i128) -> Self {
+        let (start, end) = range.into_inner();
+        let values = std::iter::successors(Some(start), |value| Some(value + step.max(1))).take_while(|value| *value <= end);
+        self.values(name, values)
+    }
+
+    /// Geometric values from `start` up to `end`, multiplying by `factor`, for amounts
+    /// spanning several orders of magnitude
+    pub fn log_range(self, name: &str, start: i128, end: i128, factor: i128) -> Self {
+        let values = std::iter::successors(Some(start.max(1)), |value| value.checked_mul(factor.max(2)))
+            .take_while(|value| *value <= end);
+        self.values(name, values)
+    }
+
+    /// The `boundaries` edge cases of an integer type that fit in an `i128`
+    pub fn boundaries(self, name: &str, ty: &TypeTag) -> Self {
+        let values: Vec<i128> = boundaries::values(ty)
+            .iter()
+            .filter_map(|value| match value {
+                Value::String(s) => s.parse().ok(),
+                value => value.as_i64().map(i128::from),
+            })
+            .collect();
+        self.values(name, values)
+    }
+
+    /// Number of points in the grid
+    pub fn len(&self) -> usize {
+        self.axes.iter().map(|(_, values)| values.len()).product()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Every combination of values, the last parameter varying fastest
+    fn points(&self) -> Vec<Params> {
+        let mut points = vec![Params::default()];
+        for (name, values) in &self.axes {
+            points = points
+                .into_iter()
+                .flat_map(|point| {
+                    values.iter().map(move |value| {
+                        let mut point = point.clone();
+                        point.values.push((name.clone(), *value));
+                        point
+                    })
+                })
+                .collect();
+        }
+        points
+    }
+}
+
+/// Parameter values of one grid point
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Params {
+    values: Vec<(String, i128)>,
+}
+
+impl Params {
+    pub fn get(&self, name: &str) -> Result<i128> {
+        self.values
+            .iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| *value)
+            .ok_or_else(|| anyhow!("No parameter named {}", name))
+    }
+
+    pub fn u64(&self, name: &str) -> Result<u64> {
+        let value = self.get(name)?;
+        u64::try_from(value).map_err(|_| anyhow!("Parameter {} = {} does not fit in a u64", name, value))
+    }
+}
+
+impl fmt::Display for Params {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let values: Vec<String> = self.values.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+        write!(f, "{}", values.join(" "))
+    }
+}
+
+/// Result of the scenario at one grid point
+#[derive(Clone, Debug)]
+pub struct SweepRow {
+    pub params: Params,
+    /// The value the scenario measured (e.g. profit), or its error
+    pub result: Result<f64, String>,
+}
+
+/// Results table of a parameter sweep
+#[derive(Clone, Debug)]
+pub struct SweepReport {
+    pub rows: Vec<SweepRow>,
+}
+
+impl SweepReport {
+    fn successful(&self) -> impl Iterator<Item = (&SweepRow, f64)> {
+        self.rows.iter().filter_map(|row| row.result.as_ref().ok().map(|value| (row, *value)))
+    }
+
+    /// Grid point with the highest value, e.g. the maximum extractable value
+    pub fn best(&self) -> Option<&SweepRow> {
+        self.successful().max_by(|a, b| a.1.total_cmp(&b.1)).map(|(row, _)| row)
+    }
+
+    /// Grid point with the lowest value
+    pub fn worst(&self) -> Option<&SweepRow> {
+        self.successful().min_by(|a, b| a.1.total_cmp(&b.1)).map(|(row, _)| row)
+    }
+
+    pub fn failures(&self) -> Vec<&SweepRow> {
+        self.rows.iter().filter(|row| row.result.is_err()).collect()
+    }
+}
+
+impl fmt::Display for SweepReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let best = self.best().map(|row| &row.params);
+        for row in &self.rows {
+            let marker = if Some(&row.params) == best { " ⭐" } else { "" };
+            match &row.result {
+                Ok(value) => writeln!(f, "{} -> {}{}", row.params, value, marker)?,
+                Err(e) => writeln!(f, "{} -> ❌ {}", row.params, e)?,
+            }
+        }
+        write!(f, "{} points, {} failed", self.rows.len(), self.failures().len())
+    }
+}
+
+/// Bounds of the parameters an optimizer searches
+#[derive(Clone, Debug, Default)]
+pub struct SearchSpace {
+    bounds: Vec<(String, i128, i128)>,
+}
+
+impl SearchSpace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn param(mut self, name: &str, range: RangeInclusive<i128>) -> Self {
+        let (low, high) = range.into_inner();
+        self.bounds.push((name.to_string(), low, high));
+        self
+    }
+}
+
+/// Evaluation budget and search breadth of `AptosBB::maximize`
+#[derive(Clone, Copy, Debug)]
+pub struct OptimizeOptions {
+    /// Scenario runs after which the search stops with the best point so far
+    pub max_evaluations: usize,
+    /// Evenly spaced points probed per parameter before narrowing down, so the search is not
+    /// trapped by the first local maximum it meets
+    pub coarse_points: usize,
+    /// Passes over all parameters; a pass that improves nothing ends the search early
+    pub rounds: usize,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self { max_evaluations: 100, coarse_points: 8, rounds: 3 }
+    }
+}
+
+/// Best point found by `AptosBB::maximize`
+#[derive(Clone, Debug)]
+pub struct Optimum {
+    pub params: Params,
+    pub value: f64,
+    /// Every point evaluated, in evaluation order
+    pub evaluations: Vec<SweepRow>,
+}
+
+impl fmt::Display for Optimum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "maximum {} at {} ({} evaluations)", self.value, self.params, self.evaluations.len())
+    }
+}
+
+/// The point `i / steps` of the way from `low` to `high`, without overflowing on ranges as
+/// wide as `i128` itself; `low <= high` and `i <= steps`
+fn interpolate(low: i128, high: i128, i: u128, steps: u128) -> i128 {
+    let span = high.abs_diff(low);
+    let offset = span / steps * i + span % steps * i / steps;
+    low.saturating_add_unsigned(offset)
+}
+
+/// Memoized objective evaluations shared by the coordinate searches
+struct Search<F> {
+    names: Vec<String>,
+    objective: F,
+    cache: HashMap<Vec<i128>, f64>,
+    evaluations: Vec<SweepRow>,
+    max_evaluations: usize,
+}
+
+impl<F: FnMut(&Params) -> Result<f64>> Search<F> {
+    fn exhausted(&self) -> bool {
+        self.evaluations.len() >= self.max_evaluations
+    }
+
+    /// Objective at a point; failed scenarios count as minus infinity
+    fn eval(&mut self, point: &[i128]) -> f64 {
+        if let Some(value) = self.cache.get(point) {
+            return *value;
+        }
+        if self.exhausted() {
+            return f64::NEG_INFINITY;
+        }
+        let params = Params { values: self.names.iter().cloned().zip(point.iter().copied()).collect() };
+        let result = (self.objective)(&params).map_err(|e| format!("{:#}", e));
+        let value = *result.as_ref().unwrap_or(&f64::NEG_INFINITY);
+        self.cache.insert(point.to_vec(), value);
+        self.evaluations.push(SweepRow { params, result });
+        value
+    }
+
+    /// Optimize parameter `axis` of `point` in place: a coarse scan, then golden-section
+    /// search between the neighbors of the best coarse point
+    fn optimize_axis(&mut self, point: &mut [i128], axis: usize, low: i128, high: i128, coarse_points: usize) {
+        let at = |search: &mut Self, value: i128| {
+            let mut probe = point.to_vec();
+            probe[axis] = value;
+            search.eval(&probe)
+        };
+
+        let steps = coarse_points.max(2) as u128 - 1;
+        let coarse: Vec<i128> = (0..=steps).map(|i| interpolate(low, high, i, steps)).collect();
+        let best = coarse
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (i, at(self, *value)))
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map_or(0, |(i, _)| i);
+        let (mut lo, mut hi) = (coarse[best.saturating_sub(1)], coarse[(best + 1).min(coarse.len() - 1)]);
+
+        while hi.abs_diff(lo) > 2 && !self.exhausted() {
+            let span = (hi.abs_diff(lo) as f64 * INV_PHI).round() as u128;
+            let a = hi.saturating_sub_unsigned(span).clamp(lo + 1, hi - 2);
+            let b = lo.saturating_add_unsigned(span).clamp(a + 1, hi - 1);
+            if at(self, a) < at(self, b) {
+                lo = a;
+            } else {
+                hi = b;
+            }
+        }
+        let best = (lo..=hi).max_by(|a, b| at(self, *a).total_cmp(&at(self, *b))).unwrap_or(lo);
+        point[axis] = best;
+    }
+}
+
+/// Coordinate ascent of `AptosBB::maximize` over a plain objective
+fn optimize(
+    space: &SearchSpace,
+    options: OptimizeOptions,
+    objective: impl FnMut(&Params) -> Result<f64>,
+) -> Result<Optimum> {
+    if space.bounds.is_empty() {
+        return Err(anyhow!("Search space has no parameters"));
+    }
+    if options.max_evaluations == 0 {
+        return Err(anyhow!("max_evaluations must be at least 1"));
+    }
+    if let Some((name, low, high)) = space.bounds.iter().find(|(_, low, high)| low > high) {
+        return Err(anyhow!("Empty range {}..={} for parameter {}", low, high, name));
+    }
+    let mut search = Search {
+        names: space.bounds.iter().map(|(name, _, _)| name.clone()).collect(),
+        objective,
+        cache: HashMap::new(),
+        evaluations: vec![],
+        max_evaluations: options.max_evaluations,
+    };
+    let mut point: Vec<i128> = space.bounds.iter().map(|(_, low, high)| interpolate(*low, *high, 1, 2)).collect();
+    let mut round_start = search.eval(&point);
+    for _ in 0..options.rounds.max(1) {
+        for (axis, (_, low, high)) in space.bounds.iter().enumerate() {
+            search.optimize_axis(&mut point, axis, *low, *high, options.coarse_points);
+        }
+        let round_end = search.eval(&point);
+        if round_end <= round_start || search.exhausted() {
+            break;
+        }
+        round_start = round_end;
+    }
+
+    let (best, value) = search
+        .cache
+        .iter()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(point, value)| (point.clone(), *value))
+        .ok_or_else(|| anyhow!("No point was evaluated"))?;
+    if value == f64::NEG_INFINITY {
+        return Err(anyhow!("The scenario failed at every one of {} points", search.evaluations.len()));
+    }
+    Ok(Optimum {
+        params: Params { values: search.names.iter().cloned().zip(best).collect() },
+        value,
+        evaluations: search.evaluations,
+    })
+}
+
+impl AptosBB {
+    /// Search for the parameters maximizing a scenario's objective, e.g. attacker profit
+    ///
+    /// Coordinate ascent: each parameter in turn gets a coarse scan of its range followed by a
+    /// golden-section search around the best probe, the others held at their best values so
+    /// far. Every evaluation runs in its own snapshot. Golden-section assumes the objective is
+    /// unimodal near the optimum; the coarse scan keeps other local maxima from hiding it.
+    /// Failing scenarios count as minus infinity. Fails on an empty search space or range, or
+    /// a zero evaluation budget.
+    pub fn maximize(
+        &mut self,
+        space: &SearchSpace,
+        options: OptimizeOptions,
+        mut scenario: impl FnMut(&Params, &mut AptosBB) -> Result<f64>,
+    ) -> Result<Optimum> {
+        let optimum = optimize(space, options, |params| self.with_snapshot(|bb| scenario(params, bb)))?;
+        println!("🎯 {}", optimum);
+        Ok(optimum)
+    }
+
+    /// Run a scenario at every point of a parameter grid, each from the same fork state
+    ///
+    /// `scenario` measures one value (profit, price impact, gas); every point runs in its own
+    /// snapshot, which is reverted afterwards. A point whose scenario fails is recorded as a
+    /// failure and the sweep continues.
+    pub fn sweep(&mut self, grid: &ParamGrid, mut scenario: impl FnMut(&Params, &mut AptosBB) -> Result<f64>) -> SweepReport {
+        let rows = grid
+            .points()
+            .into_iter()
+            .map(|params| {
+                let result = self.with_snapshot(|bb| scenario(&params, bb)).map_err(|e| format!("{:#}", e));
+                SweepRow { params, result }
+            })
+            .collect();
+        let report = SweepReport { rows };
+        match report.best() {
+            Some(SweepRow { params, result: Ok(value) }) => {
+                println!("Swept {} points, best at {}: {}", report.rows.len(), params, value)
+            }
+            _ => println!("Swept {} points, none succeeded", report.rows.len()),
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grid_varies_the_last_parameter_fastest() {
+        let grid = ParamGrid::new().values("a", [1, 2]).range("b", 0..=20, 10);
+        assert_eq!(grid.len(), 6);
+        let points: Vec<String> = grid.points().iter().map(ToString::to_string).collect();
+        assert_eq!(points, ["a=1 b=0", "a=1 b=10", "a=1 b=20", "a=2 b=0", "a=2 b=10", "a=2 b=20"]);
+    }
+
+    #[test]
+    fn log_range_stops_at_end_and_on_overflow() {
+        let grid = ParamGrid::new().log_range("x", 1, 1_000, 10);
+        let values: Vec<i128> = grid.points().iter().map(|p| p.get("x").unwrap()).collect();
+        assert_eq!(values, [1, 10, 100, 1_000]);
+        assert_eq!(ParamGrid::new().log_range("x", i128::MAX / 2, i128::MAX, 4).len(), 1);
+    }
+
+    #[test]
+    fn interpolate_spans_the_whole_i128_range() {
+        assert_eq!(interpolate(i128::MIN, i128::MAX, 0, 4), i128::MIN);
+        assert_eq!(interpolate(i128::MIN, i128::MAX, 4, 4), i128::MAX);
+        assert_eq!(interpolate(0, 10, 1, 2), 5);
+    }
+
+    #[test]
+    fn optimize_finds_the_peak_of_a_unimodal_objective() {
+        let space = SearchSpace::new().param("x", 0..=10_000);
+        let optimum = optimize(&space, OptimizeOptions::default(), |p| {
+            let x = p.get("x")? as f64;
+            Ok(-(x - 3_719.0).powi(2))
+        })
+        .unwrap();
+        assert_eq!(optimum.params.get("x").unwrap(), 3_719);
+        assert!(optimum.evaluations.len() <= OptimizeOptions::default().max_evaluations);
+    }
+
+    #[test]
+    fn optimize_skips_failing_points_and_respects_the_budget() {
+        let space = SearchSpace::new().param("x", 0..=100);
+        let options = OptimizeOptions { max_evaluations: 5, ..Default::default() };
+        let optimum = optimize(&space, options, |p| {
+            let x = p.get("x")?;
+            anyhow::ensure!(x % 2 == 0, "odd");
+            Ok(x as f64)
+        })
+        .unwrap();
+        assert_eq!(optimum.evaluations.len(), 5);
+        assert_eq!(optimum.params.get("x").unwrap() % 2, 0);
+        assert!(optimize(&space, options, |_| Err(anyhow!("always fails"))).is_err());
+        assert!(optimize(&SearchSpace::new().param("x", 1..=0), options, |_| Ok(0.0)).is_err());
+    }
+}
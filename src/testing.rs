@@ -0,0 +1,77 @@
+use crate::{blocking, builder::parse_network, AptosBB, ForkMode};
+use anyhow::{anyhow, Result};
+use aptos_types::account_address::AccountAddress;
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+/// Fork a `#[aptosbb::test]` runs against, as given in the attribute
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ForkSpec {
+    /// `mainnet`, `testnet`, `devnet` or a REST URL
+    pub network: &'static str,
+    pub version: Option<u64>,
+    /// Accounts downloaded eagerly (with the framework) when the fork is created
+    pub targets: &'static [&'static str],
+    pub cache_dir: Option<&'static str>,
+}
+
+impl ForkSpec {
+    fn build(&self) -> Result<AptosBB> {
+        let network = parse_network(self.network)?;
+        let targets = self
+            .targets
+            .iter()
+            .map(|target| {
+                AccountAddress::from_hex_literal(target).map_err(|e| anyhow!("Invalid target address {}: {}", target, e))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut builder = AptosBB::builder().network(network);
+        if let Some(version) = self.version {
+            builder = builder.version(version);
+        }
+        if let Some(dir) = self.cache_dir {
+            builder = builder.cache(dir);
+        }
+        if let Ok(api_key) = std::env::var("APTOSBB_KEY") {
+            if !api_key.is_empty() {
+                builder = builder.api_key(api_key);
+            }
+        }
+        if !targets.is_empty() {
+            builder = builder.fork_mode(ForkMode::eager_with_framework(&targets));
+        }
+        Ok(blocking::block_on(builder.build())?)
+    }
+}
+
+/// Forks created so far by this test binary, one per distinct spec
+static FORKS: Lazy<Mutex<HashMap<ForkSpec, Arc<Mutex<AptosBB>>>>> = Lazy::new(Default::default);
+
+/// Run `f` on the shared fork for `spec`, reverting everything it changed afterwards
+///
+/// The fork is created the first time a spec is used; tests sharing a spec run one at a
+/// time on it. This is what `#[aptosbb::test]` expands to.
+pub fn with_fork<R>(spec: &ForkSpec, f: impl FnOnce(&mut AptosBB) -> R) -> R {
+    let fork = {
+        let mut forks = FORKS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match forks.get(spec) {
+            Some(fork) => fork.clone(),
+            None => {
+                let bb = spec
+                    .build()
+                    .unwrap_or_else(|e| panic!("Failed to fork {} for test: {:#}", spec.network, e));
+                let fork = Arc::new(Mutex::new(bb));
+                forks.insert(spec.clone(), fork.clone());
+                fork
+            }
+        }
+    };
+
+    // A panicking test leaves the mutex poisoned, but `with_snapshot` has already reverted it
+    let mut bb = fork.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    bb.with_snapshot(f)
+}
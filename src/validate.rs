@@ -0,0 +1,222 @@
+use crate::{blocking, AptosBB};
+use anyhow::{anyhow, bail, Result};
+use aptos_api_types::TransactionOnChainData;
+use aptos_types::{
+    contract_event::ContractEvent,
+    state_store::state_key::StateKey,
+    transaction::{Transaction, TransactionStatus},
+    write_set::{WriteOp, WriteSet},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+
+/// Local re-execution of one transaction of the block compared with what the chain committed
+#[derive(Clone, Debug)]
+pub struct TxnValidation {
+    pub version: u64,
+    /// Transaction kind, e.g. `user_transaction` or `block_metadata`
+    pub kind: String,
+    pub status_matches: bool,
+    pub gas_matches: bool,
+    pub events_match: bool,
+    /// Keys written with different bytes (or only on one side)
+    pub mismatched_keys: Vec<StateKey>,
+    /// Keys written with the same bytes but different state value metadata (slot deposit,
+    /// creation time), which a fork cannot always reproduce; not counted as a mismatch
+    pub metadata_mismatches: Vec<StateKey>,
+    pub local_status: TransactionStatus,
+    pub onchain_status: TransactionStatus,
+    pub local_events: Vec<ContractEvent>,
+    pub onchain_events: Vec<ContractEvent>,
+}
+
+impl TxnValidation {
+    pub fn matches(&self) -> bool {
+        self.status_matches && self.gas_matches && self.events_match && self.mismatched_keys.is_empty()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockValidation {
+    pub block_height: u64,
+    pub first_version: u64,
+    pub last_version: u64,
+    pub txns: Vec<TxnValidation>,
+}
+
+impl BlockValidation {
+    pub fn mismatches(&self) -> Vec<&TxnValidation> {
+        self.txns.iter().filter(|txn| !txn.matches()).collect()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.txns.iter().all(TxnValidation::matches)
+    }
+
+    /// Fail with a description of every mismatching transaction
+    pub fn ensure_valid(&self) -> Result<()> {
+        if !self.is_valid() {
+            bail!("{}", self);
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for BlockValidation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Block {} (versions {}..={}): {} transactions, {} mismatched",
+            self.block_height,
+            self.first_version,
+            self.last_version,
+            self.txns.len(),
+            self.mismatches().len()
+        )?;
+        for txn in self.mismatches() {
+            write!(f, "\n  version {} ({}):", txn.version, txn.kind)?;
+            if !txn.status_matches {
+                write!(f, " status {:?} != {:?}", txn.local_status, txn.onchain_status)?;
+            }
+            if !txn.gas_matches {
+                write!(f, " gas differs")?;
+            }
+            if !txn.events_match {
+                write!(f, " events differ ({} local, {} on chain)", txn.local_events.len(), txn.onchain_events.len())?;
+            }
+            for key in &txn.mismatched_keys {
+                write!(f, "\n    write differs: {:?}", key)?;
+            }
+        }
+        let metadata_mismatches: usize = self.txns.iter().map(|txn| txn.metadata_mismatches.len()).sum();
+        if metadata_mismatches > 0 {
+            write!(f, "\n  {} writes with matching bytes but different metadata", metadata_mismatches)?;
+        }
+        Ok(())
+    }
+}
+
+/// Keys whose written bytes differ between two write sets, and keys whose bytes match but
+/// whose metadata does not
+fn mismatched_keys(local: &WriteSet, onchain: &WriteSet) -> (Vec<StateKey>, Vec<StateKey>) {
+    let local: BTreeMap<&StateKey, &WriteOp> = local.iter().collect();
+    let onchain: BTreeMap<&StateKey, &WriteOp> = onchain.iter().collect();
+    let keys: BTreeSet<&StateKey> = local.keys().chain(onchain.keys()).copied().collect();
+
+    let (mut bytes, mut metadata) = (vec![], vec![]);
+    for key in keys {
+        match (local.get(key), onchain.get(key)) {
+            (Some(local), Some(onchain)) if local.bytes() == onchain.bytes() => {
+                if local.metadata() != onchain.metadata() {
+                    metadata.push(key.clone());
+                }
+            }
+            _ => bytes.push(key.clone()),
+        }
+    }
+    (bytes, metadata)
+}
+
+impl AptosBB {
+    /// Every committed transaction of the block containing `version`, with its outputs
+    fn fetch_block(&self, version: u64) -> Result<(u64, u64, u64, Vec<TransactionOnChainData>)> {
+        let block = blocking::block_on(async { anyhow::Ok(self.remote.client().await?.get_block_by_version_bcs(version, true).await?) })?
+            .into_inner();
+        let mut txns = block.transactions.unwrap_or_default();
+
+        // Large blocks come back truncated to the API page size
+        let expected = (block.last_version - block.first_version + 1) as usize;
+        while txns.len() < expected {
+            let start = block.first_version + txns.len() as u64;
+            let limit = (expected - txns.len()).min(u16::MAX as usize) as u16;
+            let page = blocking::block_on(async { anyhow::Ok(self.remote.client().await?.get_transactions_bcs(Some(start), Some(limit)).await?) })?
+                .into_inner();
+            if page.is_empty() {
+                bail!("Could not fetch transactions of block {} from version {}", block.block_height, start);
+            }
+            txns.extend(page);
+        }
+        Ok((block.block_height, block.first_version, block.last_version, txns))
+    }
+
+    /// Re-execute every transaction of the mainnet block containing `version` and compare
+    ///
+    /// The block is executed as a whole on a fresh fork of the version just before it, and
+    /// each output is compared byte for byte with the committed write set, events, status
+    /// and gas. Any mismatch is either a fork artifact or an execution-layer divergence.
+    /// Writes that only differ in state value metadata are listed separately.
+    pub fn validate_block(&self, version: u64) -> Result<BlockValidation> {
+        let (block_height, first_version, last_version, onchain) = self.fetch_block(version)?;
+        let fork_version = first_version
+            .checked_sub(1)
+            .ok_or_else(|| anyhow!("Cannot fork before genesis"))?;
+        println!(
+            "Validating block {} ({} transactions) from version {}",
+            block_height,
+            onchain.len(),
+            fork_version
+        );
+
+        let bb = self.fork_at(fork_version)?;
+        let block: Vec<Transaction> = onchain.iter().map(|txn| txn.transaction.clone()).collect();
+        let outputs = bb
+            .executor
+            .execute_transaction_block(block)
+            .map_err(|status| anyhow!("Block execution failed: {:?}", status))?;
+        if outputs.len() != onchain.len() {
+            bail!("Executor returned {} outputs for {} transactions", outputs.len(), onchain.len());
+        }
+
+        let txns = onchain
+            .iter()
+            .zip(&outputs)
+            .map(|(committed, output)| {
+                let (mismatched_keys, metadata_mismatches) = mismatched_keys(output.write_set(), &committed.changes);
+                let onchain_status = TransactionStatus::Keep(committed.info.status().clone());
+                TxnValidation {
+                    version: committed.version,
+                    kind: committed.transaction.type_name().to_string(),
+                    status_matches: *output.status() == onchain_status,
+                    gas_matches: output.gas_used() == committed.info.gas_used(),
+                    events_match: output.events() == committed.events.as_slice(),
+                    mismatched_keys,
+                    metadata_mismatches,
+                    local_status: output.status().clone(),
+                    onchain_status,
+                    local_events: output.events().to_vec(),
+                    onchain_events: committed.events.clone(),
+                }
+            })
+            .collect();
+
+        let validation = BlockValidation { block_height, first_version, last_version, txns };
+        println!("{}", validation);
+        Ok(validation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aptos_types::write_set::WriteSetMut;
+
+    fn write_set(ops: &[(&[u8], &[u8])]) -> WriteSet {
+        WriteSetMut::new(ops.iter().map(|(key, value)| (StateKey::raw(key), WriteOp::legacy_creation(value.to_vec().into()))))
+            .freeze()
+            .unwrap()
+    }
+
+    #[test]
+    fn mismatched_keys_flag_different_and_one_sided_writes() {
+        let local = write_set(&[(b"same", b"1"), (b"changed", b"2"), (b"local_only", b"3")]);
+        let onchain = write_set(&[(b"same", b"1"), (b"changed", b"9"), (b"onchain_only", b"4")]);
+        let (bytes, metadata) = mismatched_keys(&local, &onchain);
+        let expected: BTreeSet<StateKey> =
+            [b"changed".as_slice(), b"local_only", b"onchain_only"].into_iter().map(StateKey::raw).collect();
+        assert_eq!(bytes.into_iter().collect::<BTreeSet<_>>(), expected);
+        assert!(metadata.is_empty());
+        assert_eq!(mismatched_keys(&local, &local), (vec![], vec![]));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{entry::parse_function_id, AptosBB};
+use anyhow::{anyhow, bail, Result};
+use move_binary_format::{
+    access::ModuleAccess,
+    file_format::{SignatureToken, StructHandleIndex},
+    CompiledModule,
+};
+use move_core_types::language_storage::{StructTag, TypeTag};
+use serde::de::DeserializeOwned;
+
+/// Resolve a struct handle to its fully qualified struct tag
+pub fn struct_tag_for_handle(module: &CompiledModule, idx: StructHandleIndex, type_args: Vec<TypeTag>) -> StructTag {
+    let handle = module.struct_handle_at(idx);
+    let owner = module.module_id_for_handle(module.module_handle_at(handle.module));
+    StructTag {
+        address: *owner.address(),
+        module: owner.name().to_owned(),
+        name: module.identifier_at(handle.name).to_owned(),
+        type_args,
+    }
+}
+
+/// Convert a signature token into a concrete type tag, substituting type arguments
+pub fn signature_to_type_tag(module: &CompiledModule, token: &SignatureToken, ty_args: &[TypeTag]) -> Result<TypeTag> {
+    Ok(match token {
+        SignatureToken::Bool => TypeTag::Bool,
+        SignatureToken::U8 => TypeTag::U8,
+        SignatureToken::U16 => TypeTag::U16,
+        SignatureToken::U32 => TypeTag::U32,
+        SignatureToken::U64 => TypeTag::U64,
+        SignatureToken::U128 => TypeTag::U128,
+        SignatureToken::U256 => TypeTag::U256,
+        SignatureToken::Address => TypeTag::Address,
+        SignatureToken::Signer => TypeTag::Signer,
+        SignatureToken::Vector(inner) => TypeTag::Vector(Box::new(signature_to_type_tag(module, inner, ty_args)?)),
+        SignatureToken::Struct(idx) => TypeTag::Struct(Box::new(struct_tag_for_handle(module, *idx, vec![]))),
+        SignatureToken::StructInstantiation(idx, args) => {
+            let args = args
+                .iter()
+                .map(|arg| signature_to_type_tag(module, arg, ty_args))
+                .collect::<Result<Vec<_>>>()?;
+            TypeTag::Struct(Box::new(struct_tag_for_handle(module, *idx, args)))
+        }
+        SignatureToken::TypeParameter(idx) => ty_args
+            .get(*idx as usize)
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing type argument T{}", idx))?,
+        SignatureToken::Reference(_) | SignatureToken::MutableReference(_) | SignatureToken::Function(..) => {
+            bail!("Type {:?} has no value representation", token)
+        }
+    })
+}
+
+/// Return values of a view function, with their types resolved from the module ABI
+#[derive(Clone, Debug)]
+pub struct ViewResult {
+    pub types: Vec<TypeTag>,
+    pub values: Vec<Vec<u8>>,
+    pub json: Vec<serde_json::Value>,
+}
+
+impl ViewResult {
+    /// Deserialize the `index`-th return value into a Rust type
+    pub fn value_as<T: DeserializeOwned>(&self, index: usize) -> Result<T> {
+        let bytes = self.values.get(index)
+            .ok_or_else(|| anyhow!("View function returned {} values", self.values.len()))?;
+        Ok(bcs::from_bytes(bytes)?)
+    }
+}
+
+impl AptosBB {
+    /// Return types of a function, resolved from the on-chain (or locally published) module
+    pub fn function_return_types(&mut self, function: &str, ty_args: &[TypeTag]) -> Result<Vec<TypeTag>> {
+        let (module_id, name) = parse_function_id(function)?;
+        let module = self.get_module(&module_id)?;
+        let handle = module
+            .function_handles()
+            .iter()
+            .find(|handle| module.identifier_at(handle.name) == name.as_ident_str() && handle.module == module.self_handle_idx())
+            .ok_or_else(|| anyhow!("Function {} not found", function))?;
+
+        module
+            .signature_at(handle.return_)
+            .0
+            .iter()
+            .map(|token| signature_to_type_tag(&module, token, ty_args))
+            .collect()
+    }
+
+    /// Execute a view function and decode its return values using the function's ABI
+    ///
+    /// `function` is `address::module::function`.
+    ///
+    /// Results are memoized when the view cache is enabled (see `enable_view_cache`).
+    pub fn view(&mut self, function: &str, ty_args: Vec<TypeTag>, args: Vec<Vec<u8>>) -> Result<ViewResult> {
+        if let Some(result) = self.view_cache.get(function, &ty_args, &args) {
+            return Ok(result);
+        }
+        let cache_key = (ty_args.clone(), args.clone());
+        let types = self.function_return_types(function, &ty_args)?;
+        let (module_id, name) = parse_function_id(function)?;
+        let values = self.execute_view_function(
+            *module_id.address(),
+            module_id.name().as_str(),
+            name.as_str(),
+            ty_args,
+            args,
+        )?;
+
+        let json = types
+            .iter()
+            .zip(&values)
+            .map(|(type_tag, bytes)| self.value_to_json(type_tag, bytes))
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = ViewResult { types, values, json };
+        let (ty_args, args) = cache_key;
+        self.view_cache.insert(function, ty_args, args, &result);
+        Ok(result)
+    }
+
+    /// Execute a view function returning a single value and deserialize it into `T`
+    pub fn view_as<T: DeserializeOwned>(&mut self, function: &str, ty_args: Vec<TypeTag>, args: Vec<Vec<u8>>) -> Result<T> {
+        self.view(function, ty_args, args)?.value_as(0)
+    }
+}
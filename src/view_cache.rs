@@ -0,0 +1,98 @@
+use crate::{view::ViewResult, AptosBB};
+use move_core_types::language_storage::TypeTag;
+use std::collections::HashMap;
+
+/// View function call a cached result belongs to
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct ViewKey {
+    function: String,
+    ty_args: Vec<TypeTag>,
+    args: Vec<Vec<u8>>,
+}
+
+/// Memoized view function results for the current fork state
+///
+/// Every state change made through AptosBB (transactions, cheatcodes, reverts, loaded
+/// snapshots) starts a new state generation and drops all entries, so a hit is always
+/// what the view would return if executed now.
+#[derive(Default)]
+pub struct ViewCache {
+    enabled: bool,
+    generation: u64,
+    entries: HashMap<ViewKey, ViewResult>,
+    hits: u64,
+    misses: u64,
+}
+
+/// Hit/miss counters of the view cache
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ViewCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+    /// Number of state changes seen so far
+    pub generation: u64,
+}
+
+impl ViewCache {
+    pub(crate) fn get(&mut self, function: &str, ty_args: &[TypeTag], args: &[Vec<u8>]) -> Option<ViewResult> {
+        if !self.enabled {
+            return None;
+        }
+        let key = ViewKey {
+            function: function.to_string(),
+            ty_args: ty_args.to_vec(),
+            args: args.to_vec(),
+        };
+        let result = self.entries.get(&key).cloned();
+        match result {
+            Some(_) => self.hits += 1,
+            None => self.misses += 1,
+        }
+        result
+    }
+
+    pub(crate) fn insert(&mut self, function: &str, ty_args: Vec<TypeTag>, args: Vec<Vec<u8>>, result: &ViewResult) {
+        if self.enabled {
+            let key = ViewKey { function: function.to_string(), ty_args, args };
+            self.entries.insert(key, result.clone());
+        }
+    }
+
+    /// Start a new state generation, dropping every cached result
+    pub fn invalidate(&mut self) {
+        self.generation += 1;
+        self.entries.clear();
+    }
+
+    pub fn stats(&self) -> ViewCacheStats {
+        ViewCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            entries: self.entries.len(),
+            generation: self.generation,
+        }
+    }
+}
+
+impl AptosBB {
+    /// Memoize `view` results until the next state change
+    ///
+    /// Off by default. Useful when the same views are queried repeatedly against unchanged
+    /// state, e.g. quoting swap amounts while searching over parameters.
+    pub fn enable_view_cache(&mut self, enabled: bool) {
+        self.view_cache.enabled = enabled;
+        if !enabled {
+            self.view_cache.entries.clear();
+        }
+    }
+
+    /// Drop all cached view results, e.g. after changing state behind AptosBB's back
+    pub fn invalidate_view_cache(&mut self) {
+        self.view_cache.invalidate();
+    }
+
+    pub fn view_cache_stats(&self) -> ViewCacheStats {
+        self.view_cache.stats()
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{staleness::RecordingView, AptosBB};
+use anyhow::{anyhow, Context, Result};
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::{
+    block_executor::config::BlockExecutorConfigFromOnchain,
+    contract_event::ContractEvent,
+    state_store::{state_key::StateKey, state_value::StateValue},
+    transaction::{
+        signature_verified_transaction::into_signature_verified_block, SignedTransaction, Transaction,
+        TransactionOutput, TransactionPayload, TransactionStatus,
+    },
+    write_set::WriteSet,
+};
+use std::{collections::BTreeMap, fmt};
+
+/// aptos-vm release transactions are executed with
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VmVersion {
+    /// The aptos-core `main` branch AptosBB is built against
+    #[default]
+    Main,
+    /// The current mainnet release; requires the `vm-mainnet` feature
+    MainnetRelease,
+}
+
+impl fmt::Display for VmVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Main => write!(f, "main"),
+            Self::MainnetRelease => write!(f, "mainnet release"),
+        }
+    }
+}
+
+/// Version-independent result of executing a transaction on one VM
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VmOutcome {
+    pub status: TransactionStatus,
+    pub gas_used: u64,
+    pub write_set: WriteSet,
+    pub events: Vec<ContractEvent>,
+}
+
+impl VmOutcome {
+    fn from_output(output: &TransactionOutput) -> Self {
+        Self {
+            status: output.status().clone(),
+            gas_used: output.gas_used(),
+            write_set: output.write_set().clone(),
+            events: output.events().to_vec(),
+        }
+    }
+}
+
+/// The same transaction executed on the main-branch VM and on the mainnet release
+#[derive(Clone, Debug)]
+pub struct VmDiff {
+    pub main: VmOutcome,
+    pub mainnet: VmOutcome,
+}
+
+impl VmDiff {
+    pub fn status_differs(&self) -> bool {
+        self.main.status != self.mainnet.status
+    }
+
+    pub fn gas_differs(&self) -> bool {
+        self.main.gas_used != self.mainnet.gas_used
+    }
+
+    pub fn write_set_differs(&self) -> bool {
+        self.main.write_set != self.mainnet.write_set
+    }
+
+    pub fn events_differ(&self) -> bool {
+        self.main.events != self.mainnet.events
+    }
+
+    /// Whether the upcoming VM release changes nothing observable for this transaction
+    pub fn is_equivalent(&self) -> bool {
+        !(self.status_differs() || self.gas_differs() || self.write_set_differs() || self.events_differ())
+    }
+}
+
+impl fmt::Display for VmDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_equivalent() {
+            return write!(f, "identical on both VMs ({:?}, {} gas)", self.main.status, self.main.gas_used);
+        }
+        write!(f, "VM behavior differs:")?;
+        if self.status_differs() {
+            write!(f, "\n  status: main {:?}, mainnet {:?}", self.main.status, self.mainnet.status)?;
+        }
+        if self.gas_differs() {
+            write!(f, "\n  gas: main {}, mainnet {}", self.main.gas_used, self.mainnet.gas_used)?;
+        }
+        if self.write_set_differs() {
+            write!(
+                f,
+                "\n  write set: main {} ops, mainnet {} ops",
+                self.main.write_set.iter().count(),
+                self.mainnet.write_set.iter().count()
+            )?;
+        }
+        if self.events_differ() {
+            write!(f, "\n  events: main {}, mainnet {}", self.main.events.len(), self.mainnet.events.len())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "vm-mainnet")]
+mod mainnet {
+    use super::VmOutcome;
+    use crate::AptosBB;
+    use anyhow::Result;
+    use aptos_types::{
+        state_store::{state_key::StateKey, state_value::StateValue},
+        transaction::SignedTransaction,
+        write_set::WriteSet,
+    };
+    use aptos_language_e2e_tests_mainnet::executor::FakeExecutor as MainnetExecutor;
+    use aptos_rest_client_mainnet::AptosBaseUrl as MainnetBaseUrl;
+    use serde::{de::DeserializeOwned, Serialize};
+    use std::collections::HashMap;
+
+    /// Move a value between the two aptos-core versions' types through its BCS encoding
+    fn convert<T: Serialize, U: DeserializeOwned>(value: &T) -> Result<U> {
+        Ok(bcs::from_bytes(&bcs::to_bytes(value)?)?)
+    }
+
+    /// Executor of the mainnet release, mirroring the fork's local state
+    pub(crate) struct MainnetVm {
+        executor: MainnetExecutor,
+        /// Local state the mainnet executor holds, to only copy what changed since
+        synced: HashMap<StateKey, StateValue>,
+    }
+
+    impl MainnetVm {
+        pub(crate) fn fork(network_url: &url::Url, version: u64, api_key: Option<&str>) -> Result<Self> {
+            let network = MainnetBaseUrl::Custom(network_url.clone());
+            let executor = crate::blocking::run_blocking(|| match api_key {
+                Some(api_key) => MainnetExecutor::from_remote_state_with_api_key(network, version, api_key),
+                None => MainnetExecutor::from_remote_state(network, version),
+            });
+            Ok(Self { executor, synced: HashMap::new() })
+        }
+
+        /// Copy the values (with their metadata) the fork changed or removed outside of
+        /// transactions applied on both executors, e.g. through cheatcodes or blocks
+        fn sync(&mut self, bb: &AptosBB) -> Result<()> {
+            let state = bb.executor.data_store().inner();
+            for (key, value) in state {
+                if self.synced.get(key) != Some(value) {
+                    self.executor.data_store_mut().set(convert(key)?, convert(value)?);
+                    self.synced.insert(key.clone(), value.clone());
+                }
+            }
+            let removed: Vec<StateKey> = self.synced.keys().filter(|key| !state.contains_key(*key)).cloned().collect();
+            for key in removed {
+                self.executor.data_store_mut().remove(&convert(&key)?);
+                self.synced.remove(&key);
+            }
+            self.executor.set_block_time(bb.now_secs()?);
+            Ok(())
+        }
+
+        /// Execute a transaction against the fork's current state without applying it
+        pub(crate) fn execute(&mut self, bb: &AptosBB, txn: &SignedTransaction) -> Result<VmOutcome> {
+            self.sync(bb)?;
+            let output = self.executor.execute_transaction(convert(txn)?);
+            Ok(VmOutcome {
+                status: convert(output.status())?,
+                gas_used: output.gas_used(),
+                write_set: convert(output.write_set())?,
+                events: convert(&output.events().to_vec())?,
+            })
+        }
+
+        /// Execute a transaction and apply its write set to the mainnet executor
+        ///
+        /// The caller applies the same write set to the fork, then calls `applied` so the
+        /// next sync skips its keys.
+        pub(crate) fn execute_and_apply(&mut self, bb: &AptosBB, txn: &SignedTransaction) -> Result<VmOutcome> {
+            self.sync(bb)?;
+            let output = self.executor.execute_and_apply(convert(txn)?);
+            Ok(VmOutcome {
+                status: convert(output.status())?,
+                gas_used: output.gas_used(),
+                write_set: convert(output.write_set())?,
+                events: convert(&output.events().to_vec())?,
+            })
+        }
+
+        /// Record the fork's values of the keys of a write set both executors applied
+        pub(crate) fn applied(&mut self, bb: &AptosBB, write_set: &WriteSet) {
+            let state = bb.executor.data_store().inner();
+            for (key, _) in write_set.iter() {
+                match state.get(key) {
+                    Some(value) => self.synced.insert(key.clone(), value.clone()),
+                    None => self.synced.remove(key),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(feature = "vm-mainnet")]
+pub(crate) use mainnet::MainnetVm;
+
+/// Stand-in when AptosBB is built without the mainnet release VM
+#[cfg(not(feature = "vm-mainnet"))]
+pub(crate) struct MainnetVm;
+
+#[cfg(not(feature = "vm-mainnet"))]
+impl MainnetVm {
+    pub(crate) fn fork(_network_url: &url::Url, _version: u64, _api_key: Option<&str>) -> Result<Self> {
+        anyhow::bail!("AptosBB was built without the `vm-mainnet` feature")
+    }
+
+    pub(crate) fn execute(&mut self, _bb: &AptosBB, _txn: &SignedTransaction) -> Result<VmOutcome> {
+        anyhow::bail!("AptosBB was built without the `vm-mainnet` feature")
+    }
+
+    pub(crate) fn execute_and_apply(&mut self, _bb: &AptosBB, _txn: &SignedTransaction) -> Result<VmOutcome> {
+        anyhow::bail!("AptosBB was built without the `vm-mainnet` feature")
+    }
+
+    pub(crate) fn applied(&mut self, _bb: &AptosBB, _write_set: &WriteSet) {}
+}
+
+/// VM transactions run on, plus the mainnet release executor once it has been used
+#[derive(Default)]
+pub(crate) struct VmSelection {
+    pub(crate) version: VmVersion,
+    pub(crate) mainnet: Option<MainnetVm>,
+}
+
+impl AptosBB {
+    /// VM release transactions are executed with
+    pub fn vm_version(&self) -> VmVersion {
+        self.vm.version
+    }
+
+    /// Execute and apply a transaction on the selected VM
+    ///
+    /// Fails when the mainnet release executor cannot be forked or cannot execute the
+    /// transaction; nothing is applied then.
+    pub(crate) fn execute_on_selected_vm(&mut self, txn: SignedTransaction) -> Result<TransactionOutput> {
+        if self.vm.version == VmVersion::Main {
+            return Ok(self.executor.execute_and_apply(txn));
+        }
+        let mut vm = self.take_mainnet_vm()?;
+        let outcome = vm.execute_and_apply(self, &txn);
+        if let Ok(outcome) = &outcome {
+            self.executor.apply_write_set(&outcome.write_set);
+            vm.applied(self, &outcome.write_set);
+        }
+        self.vm.mainnet = Some(vm);
+        let outcome = outcome.context("Mainnet release VM failed to execute the transaction")?;
+        Ok(TransactionOutput::new(
+            outcome.write_set,
+            outcome.events,
+            outcome.gas_used,
+            outcome.status,
+            Default::default(),
+        ))
+    }
+
+    /// Execute and apply a transaction on the main-branch VM, also returning every existing
+    /// value it read, as it was before the transaction
+    pub(crate) fn execute_recording_reads(
+        &mut self,
+        txn: SignedTransaction,
+    ) -> Result<(TransactionOutput, BTreeMap<StateKey, StateValue>)> {
+        let block = into_signature_verified_block(vec![Transaction::UserTransaction(txn)]);
+        let view = RecordingView::new(self.executor.data_store());
+        let mut outputs = self
+            .executor
+            .execute_transaction_block_impl_with_state_view(
+                &block,
+                BlockExecutorConfigFromOnchain::new_no_block_limit(),
+                false,
+                &view,
+            )
+            .map_err(|status| anyhow!("Transaction execution failed: {:?}", status))?;
+        let reads = view.into_reads();
+        let output = outputs.pop().ok_or_else(|| anyhow!("Executor returned no output"))?;
+        if matches!(output.status(), TransactionStatus::Keep(_)) {
+            self.executor.apply_write_set(output.write_set());
+        }
+        Ok((output, reads))
+    }
+
+    /// The mainnet release executor, forked lazily: it is only paid for once it is used
+    fn take_mainnet_vm(&mut self) -> Result<MainnetVm> {
+        match self.vm.mainnet.take() {
+            Some(vm) => Ok(vm),
+            None => MainnetVm::fork(&self.network.to_url(), self.version, self.api_key.as_deref()),
+        }
+    }
+
+    fn execute_on_mainnet_vm(&mut self, txn: &SignedTransaction) -> Result<VmOutcome> {
+        let mut vm = self.take_mainnet_vm()?;
+        let outcome = vm.execute(self, txn);
+        self.vm.mainnet = Some(vm);
+        outcome
+    }
+
+    /// Run a payload on both the main-branch VM and the mainnet release, without applying it
+    ///
+    /// Differences show behavior changes that will hit mainnet with the next release.
+    /// Requires the `vm-mainnet` feature; the sender's sequence number is not consumed.
+    pub fn diff_vm_versions(&mut self, account: &Account, payload: TransactionPayload) -> Result<VmDiff> {
+        let txn = self.sign_dry_run_transaction(account, payload);
+        let main = VmOutcome::from_output(&self.executor.execute_transaction(txn.clone()));
+        let mainnet = self.execute_on_mainnet_vm(&txn)?;
+        let diff = VmDiff { main, mainnet };
+        println!("{}", diff);
+        Ok(diff)
+    }
+}
@@ -0,0 +1,79 @@
+use crate::{helpers::ensure_success, AptosBB};
+use anyhow::{anyhow, Result};
+use aptos_language_e2e_tests::account::Account;
+use std::{
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+/// Latest modification time of any file under `dir`
+fn latest_modification(dir: &Path, latest: &mut SystemTime) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            latest_modification(&path, latest)?;
+        } else {
+            *latest = (*latest).max(entry.metadata()?.modified()?);
+        }
+    }
+    Ok(())
+}
+
+/// Latest modification time of a package's manifest and Move sources
+fn package_modified(package: &Path) -> Result<SystemTime> {
+    let manifest = package.join("Move.toml");
+    let mut latest = std::fs::metadata(&manifest)
+        .map_err(|e| anyhow!("No Move package at {}: {}", package.display(), e))?
+        .modified()?;
+    for dir in ["sources", "scripts"] {
+        let dir = package.join(dir);
+        if dir.is_dir() {
+            latest_modification(&dir, &mut latest)?;
+        }
+    }
+    Ok(latest)
+}
+
+impl AptosBB {
+    /// Rebuild, republish and re-run a scenario every time the package's sources change
+    ///
+    /// Every run starts from the same checkpoint, taken right after the attacker account is
+    /// created: the package is published afresh at the attacker's address (no upgrade
+    /// compatibility checks) and earlier runs leave no trace. Build, publish and scenario
+    /// failures are printed and the loop keeps waiting for the next save; it only returns
+    /// if the package can no longer be read.
+    pub fn watch_package(
+        &mut self,
+        package: &Path,
+        poll_interval: Duration,
+        mut scenario: impl FnMut(&mut AptosBB, &Account) -> Result<()>,
+    ) -> Result<()> {
+        let attacker = self.new_account();
+        let checkpoint = self.checkpoint();
+        println!("Watching {} (attacker {})", package.display(), attacker.address());
+
+        let mut last_run = None;
+        loop {
+            let modified = package_modified(package)?;
+            if last_run == Some(modified) {
+                std::thread::sleep(poll_interval);
+                continue;
+            }
+            last_run = Some(modified);
+
+            self.revert(&checkpoint);
+            println!("\n🔁 Rebuilding {}...", package.display());
+            let outcome = self
+                .publish_package(&attacker, package)
+                .map_err(anyhow::Error::from)
+                .and_then(|status| ensure_success(status, "Publish"))
+                .and_then(|()| scenario(self, &attacker));
+            match outcome {
+                Ok(()) => println!("✅ Scenario succeeded"),
+                Err(e) => println!("❌ {:#}", e),
+            }
+            println!("👀 Waiting for changes...");
+        }
+    }
+}
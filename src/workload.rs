@@ -0,0 +1,174 @@
+use crate::{export::status_name, helpers::funding::apt, AptosBB};
+use anyhow::Result;
+use aptos_language_e2e_tests::account::Account;
+use aptos_types::transaction::TransactionPayload;
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt,
+    time::{Duration, Instant},
+};
+
+/// APT (in octas) each workload account is funded with for gas
+const WORKLOAD_GAS_BALANCE: u64 = 10_000_000_000;
+
+/// Shape of a generated load
+#[derive(Clone, Copy, Debug)]
+pub struct Workload {
+    /// Fresh funded accounts sending the load
+    pub accounts: usize,
+    /// Transactions each account sends
+    pub txns_per_account: usize,
+    /// Transactions offered per block; accounts take turns, so a block holds one transaction
+    /// of each of `block_size` different senders as it would under concurrent submission
+    pub block_size: usize,
+}
+
+impl Default for Workload {
+    fn default() -> Self {
+        Self { accounts: 100, txns_per_account: 10, block_size: 100 }
+    }
+}
+
+/// Aggregates of one block of a workload, executed through the block executor
+#[derive(Clone, Debug, Default)]
+pub struct WorkloadBlock {
+    /// Transactions the block executed
+    pub txns: usize,
+    pub gas_used: u64,
+    pub failures: usize,
+    /// Transactions left out by the block gas limit, offered again in the next block
+    pub retried: usize,
+}
+
+/// Aggregate cost and outcome of a workload
+#[derive(Clone, Debug)]
+pub struct WorkloadReport {
+    pub accounts: usize,
+    pub blocks: Vec<WorkloadBlock>,
+    /// Number of transactions per status, e.g. `Success` or `MoveAbort { .. }`
+    pub statuses: BTreeMap<String, usize>,
+    /// Growth of the fork's local state over the workload, in bytes of stored values
+    pub state_growth: usize,
+    pub elapsed: Duration,
+}
+
+impl WorkloadReport {
+    pub fn txns(&self) -> usize {
+        self.blocks.iter().map(|block| block.txns).sum()
+    }
+
+    pub fn total_gas(&self) -> u64 {
+        self.blocks.iter().map(|block| block.gas_used).sum()
+    }
+
+    pub fn failures(&self) -> usize {
+        self.blocks.iter().map(|block| block.failures).sum()
+    }
+
+    /// Times a transaction was pushed to a later block by the block gas limit
+    pub fn retried(&self) -> usize {
+        self.blocks.iter().map(|block| block.retried).sum()
+    }
+
+    /// Highest gas any single block consumed, in plain gas units (the block gas limit is
+    /// weighed against the executor's effective gas)
+    pub fn peak_block_gas(&self) -> u64 {
+        self.blocks.iter().map(|block| block.gas_used).max().unwrap_or(0)
+    }
+
+    /// Local state bytes added per successful transaction
+    pub fn state_growth_per_txn(&self) -> f64 {
+        self.state_growth as f64 / (self.txns() - self.failures()).max(1) as f64
+    }
+}
+
+impl fmt::Display for WorkloadReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} transactions from {} accounts in {} blocks ({:.1?})",
+            self.txns(),
+            self.accounts,
+            self.blocks.len(),
+            self.elapsed
+        )?;
+        writeln!(f, "  gas: {} total, {} peak per block", self.total_gas(), self.peak_block_gas())?;
+        writeln!(f, "  deferred by the block gas limit: {}", self.retried())?;
+        writeln!(f, "  state growth: {} bytes ({:.0} per successful transaction)", self.state_growth, self.state_growth_per_txn())?;
+        write!(f, "  failures: {}", self.failures())?;
+        for (status, count) in self.statuses.iter().filter(|(status, _)| status.as_str() != "Success") {
+            write!(f, "\n    {} x {}", count, status)?;
+        }
+        Ok(())
+    }
+}
+
+impl AptosBB {
+    /// Create `workload.accounts` fresh accounts and have them all hammer a target
+    ///
+    /// `payload(account_index, txn_index)` builds each transaction. Transactions are
+    /// interleaved across senders and executed as real blocks (see `execute_block`), so the
+    /// block gas limit set with `set_block_gas_limit` decides what fits; transactions it
+    /// leaves out are offered again at the front of the next block. The aggregate gas,
+    /// failures and state growth show whether spam or capacity-exhaustion claims hold up.
+    pub fn generate_workload(
+        &mut self,
+        workload: Workload,
+        mut payload: impl FnMut(usize, usize) -> TransactionPayload,
+    ) -> Result<WorkloadReport> {
+        let mut accounts = Vec::with_capacity(workload.accounts);
+        for _ in 0..workload.accounts {
+            // Fresh random addresses, so nothing on the fork is overwritten
+            let account = Account::new();
+            self.write_account_resource(&account)?;
+            self.fund(account.address(), &apt(), WORKLOAD_GAS_BALANCE)?;
+            self.sequence_numbers.insert(*account.address(), 0);
+            accounts.push(account);
+        }
+
+        let mut pending: VecDeque<(usize, TransactionPayload)> = (0..workload.txns_per_account)
+            .flat_map(|txn_index| (0..accounts.len()).map(move |account_index| (account_index, txn_index)))
+            .map(|(account_index, txn_index)| (account_index, payload(account_index, txn_index)))
+            .collect();
+
+        let state_before = self.local_state_bytes();
+        let started = Instant::now();
+        let mut blocks = vec![];
+        let mut statuses = BTreeMap::new();
+        while !pending.is_empty() {
+            let offered: Vec<(usize, TransactionPayload)> =
+                pending.drain(..workload.block_size.max(1).min(pending.len())).collect();
+            let txns: Vec<(Account, TransactionPayload)> = offered
+                .iter()
+                .map(|(account_index, payload)| (accounts[*account_index].clone(), payload.clone()))
+                .collect();
+            let report = self.run_block(&txns)?;
+
+            let mut block = WorkloadBlock::default();
+            for (outcome, offered) in report.txns.iter().zip(offered).rev() {
+                if outcome.retried() {
+                    block.retried += 1;
+                    pending.push_front(offered);
+                    continue;
+                }
+                block.txns += 1;
+                block.gas_used += outcome.gas_used;
+                if !outcome.succeeded() {
+                    block.failures += 1;
+                }
+                *statuses.entry(status_name(&outcome.status)).or_insert(0) += 1;
+            }
+            blocks.push(block);
+        }
+
+        let report = WorkloadReport {
+            accounts: accounts.len(),
+            blocks,
+            statuses,
+            state_growth: self.local_state_bytes().saturating_sub(state_before),
+            elapsed: started.elapsed(),
+        };
+        println!("{}", report);
+        Ok(report)
+    }
+}